@@ -0,0 +1,133 @@
+//! Compare the recall and latency of the HNSW index of the in-memory vector store with the
+//! brute-force search.
+//!
+//! Run with:
+//! ```bash
+//! cargo bench -p rig-core --features hnsw --bench hnsw_recall -- [documents] [dims]
+//! ```
+use std::{
+    collections::HashSet,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use rig::{
+    embeddings::{Embedding, EmbeddingError, EmbeddingModel},
+    vector_store::{hnsw::HnswConfig, in_memory_store::InMemoryVectorStore, VectorStoreIndex},
+    OneOrMany,
+};
+
+const QUERIES: usize = 200;
+const TOP_N: usize = 10;
+
+/// Embedding model returning precomputed query vectors, queries being their index (e.g. "42")
+#[derive(Clone)]
+struct QueryModel {
+    queries: Arc<Vec<Vec<f64>>>,
+}
+
+impl EmbeddingModel for QueryModel {
+    const MAX_DOCUMENTS: usize = 1;
+
+    fn ndims(&self) -> usize {
+        self.queries[0].len()
+    }
+
+    async fn embed_texts(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<Embedding>, EmbeddingError> {
+        texts
+            .into_iter()
+            .map(|text| {
+                let i = text
+                    .parse::<usize>()
+                    .map_err(|e| EmbeddingError::DocumentError(e.into()))?;
+                Ok(Embedding {
+                    document: text,
+                    vec: self.queries[i].clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Deterministic pseudo-random vectors
+fn vectors(n: usize, dims: usize, mut state: u64) -> Vec<Vec<f64>> {
+    (0..n)
+        .map(|_| {
+            (0..dims)
+                .map(|_| {
+                    state = state
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    (state >> 33) as f64 / (1u64 << 31) as f64 - 0.5
+                })
+                .collect()
+        })
+        .collect()
+}
+
+async fn run(
+    index: &impl VectorStoreIndex,
+) -> Result<(Vec<HashSet<String>>, Duration), anyhow::Error> {
+    let mut results = Vec::with_capacity(QUERIES);
+    let start = Instant::now();
+    for i in 0..QUERIES {
+        let ids = index.top_n_ids(&i.to_string(), TOP_N).await?;
+        results.push(ids.into_iter().map(|(_, id)| id).collect());
+    }
+    Ok((results, start.elapsed()))
+}
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    // `cargo bench` passes `--bench` to the benchmark
+    let args = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .map(|arg| arg.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let documents = args.first().copied().unwrap_or(20_000);
+    let dims = args.get(1).copied().unwrap_or(64);
+
+    let model = QueryModel {
+        queries: Arc::new(vectors(QUERIES, dims, 7)),
+    };
+    let embeddings = vectors(documents, dims, 42)
+        .into_iter()
+        .enumerate()
+        .map(|(i, vec)| {
+            let embedding = Embedding {
+                document: i.to_string(),
+                vec,
+            };
+            (i.to_string(), i, OneOrMany::one(embedding))
+        });
+    let store = InMemoryVectorStore::from_documents_with_ids(embeddings);
+
+    println!("{documents} documents, {dims} dimensions, {QUERIES} queries, top {TOP_N}");
+    let (expected, elapsed) = run(&store.clone().index(model.clone())).await?;
+    println!("brute force: {:>10.3?}/query", elapsed / QUERIES as u32);
+
+    for (m, ef_search) in [(8, 32), (16, 64), (16, 128), (32, 128)] {
+        let config = HnswConfig::new().m(m).ef_search(ef_search);
+        let start = Instant::now();
+        let index = store.clone().with_hnsw(config).index(model.clone());
+        let build = start.elapsed();
+
+        let (results, elapsed) = run(&index).await?;
+        let found = results
+            .iter()
+            .zip(&expected)
+            .map(|(results, expected)| results.intersection(expected).count())
+            .sum::<usize>();
+        println!(
+            "hnsw m={m:<2} ef_search={ef_search:<3}: {:>10.3?}/query, recall@{TOP_N} {:.3}, built in {build:.2?}",
+            elapsed / QUERIES as u32,
+            found as f64 / (QUERIES * TOP_N) as f64,
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use std::env;
+
+use rig::{
+    completion::Prompt,
+    providers::openai::{self, Client},
+    tool::{AgentTool, SharedChatHistory},
+};
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    tracing_subscriber::fmt().init();
+
+    // Create OpenAI client
+    let openai_api_key = env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY not set");
+    let openai_client = Client::new(&openai_api_key);
+
+    // Create the specialized agents
+    let market_analyst = openai_client
+        .agent(openai::GPT_4O)
+        .preamble(
+            "You are a market analyst. Given a token, describe its recent market trend \
+            and sentiment in a few sentences.",
+        )
+        .build();
+
+    let risk_assessor = openai_client
+        .agent(openai::GPT_4O)
+        .preamble(
+            "You are a risk assessor. Given a proposed trade and the conversation so far, \
+            list the main risks of the trade and rate its overall risk from 0 to 1.",
+        )
+        .build();
+
+    // Handle used to forward the orchestrator's conversation to the risk assessor
+    let history = SharedChatHistory::new();
+
+    // Create the orchestrator agent, which delegates to the specialized agents
+    let trader = openai_client
+        .agent(openai::GPT_4O)
+        .preamble(
+            "You are a trader. Use your tools to analyze the market and assess the risks \
+            of a trade before making a recommendation.",
+        )
+        .tool(AgentTool::new(
+            "analyze_market",
+            "Analyze the market trend and sentiment of a token",
+            market_analyst,
+        ))
+        .tool(
+            AgentTool::new(
+                "assess_risk",
+                "Assess the risks of a proposed trade",
+                risk_assessor,
+            )
+            .parameters(serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "symbol": {
+                        "type": "string",
+                        "description": "The symbol of the token to trade"
+                    },
+                    "side": {
+                        "type": "string",
+                        "enum": ["buy", "sell"]
+                    },
+                    "size": {
+                        "type": "number",
+                        "description": "The size of the trade in USD"
+                    }
+                },
+                "required": ["symbol", "side", "size"]
+            }))
+            .forward_history(history.clone()),
+        )
+        .hook(history)
+        .build();
+
+    let response = trader
+        .prompt("What are the risks of buying $500 of SOL right now?")
+        .await?;
+
+    println!("Trader: {response}");
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use anyhow::Result;
+use rig::{
+    completion::Prompt,
+    embeddings::EmbeddingsBuilder,
+    providers::openai::{Client, TEXT_EMBEDDING_ADA_002},
+    tool::ToolSet,
+    tools::{Calculator, DateTime, FileList, FileRead, HttpFetch, JsonQuery},
+    vector_store::in_memory_store::InMemoryVectorStore,
+};
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    // Create OpenAI client
+    let openai_client = Client::from_env();
+
+    // Create dynamic tools embeddings from the standard tool library
+    let toolset = ToolSet::builder()
+        .dynamic_tool(Calculator)
+        .dynamic_tool(DateTime)
+        .dynamic_tool(JsonQuery)
+        .dynamic_tool(FileRead::new("rig-core/examples"))
+        .dynamic_tool(FileList::new("rig-core/examples"))
+        .dynamic_tool(HttpFetch::new(["api.coingecko.com"]))
+        .build();
+
+    let embedding_model = openai_client.embedding_model(TEXT_EMBEDDING_ADA_002);
+    let embeddings = EmbeddingsBuilder::new(embedding_model.clone())
+        .documents(toolset.schemas()?)?
+        .build()
+        .await?;
+
+    let vector_store =
+        InMemoryVectorStore::from_documents_with_id_f(embeddings, |tool| tool.name.clone());
+    let index = vector_store.index(embedding_model);
+
+    // Create agent with a dynamic tool source (i.e.: only the 2 most relevant
+    // tools will be added to prompts)
+    let agent = openai_client
+        .agent("gpt-4o")
+        .preamble(
+            "You are a helpful assistant. Use the tools provided to answer the user's question.",
+        )
+        .dynamic_tools(2, index, toolset)
+        .build();
+
+    for prompt in [
+        "What is (2 + 3) * sqrt(16)?",
+        "What day of the week is it today?",
+        "Which files are in the examples directory?",
+    ] {
+        println!("{prompt}");
+        println!("Agent: {}", agent.prompt(prompt).await?);
+    }
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use rig::loaders::FileLoader;
+use rig::loaders::{splitters::MarkdownSplitter, FileLoader};
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
@@ -10,5 +10,18 @@ async fn main() -> Result<(), anyhow::Error> {
             Err(e) => eprintln!("Error reading file: {}", e),
         });
 
+    // Split markdown files into chunks small enough to be embedded
+    FileLoader::with_glob("*.md")?
+        .read_with_metadata()
+        .split(MarkdownSplitter::new(1000).chunk_overlap(100))
+        .into_iter()
+        .for_each(|result| match result {
+            Ok(chunk) => println!(
+                "{:?} #{:?} [{}..{}] {:?}",
+                chunk.metadata.path, chunk.metadata.chunk, chunk.start, chunk.end, chunk.headings
+            ),
+            Err(e) => eprintln!("Error reading file: {}", e),
+        });
+
     Ok(())
 }
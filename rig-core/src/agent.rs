@@ -10,12 +10,20 @@
 //!
 //! The [Agent] struct implements the [Completion] and [Prompt] traits, allowing it to be used for generating
 //! completions responses and prompts. The [Agent] struct also implements the [Chat] trait, which allows it to
-//! be used for generating chat completions.
+//! be used for generating chat completions, and the [Cite] trait, which returns answers along with the
+//! citations of the context documents supporting them.
 //!
 //! The [AgentBuilder] implements the builder pattern for creating instances of [Agent].
 //! It allows configuring the model, preamble, context documents, tools, temperature, and additional parameters
 //! before building the agent.
 //!
+//! Requests that would exceed the model's context window can be truncated automatically by
+//! setting a [ContextWindow] with the [AgentBuilder::context_window] method.
+//!
+//! The [AgentHook] trait can be implemented to observe the lifecycle of an agent's prompts
+//! (e.g.: for audit logging, progress reporting or metrics). Hooks are attached to the agent
+//! using the [AgentBuilder::hook] method.
+//!
 //! # Example
 //! ```rust
 //! use rig::{
@@ -106,24 +114,147 @@
 //! let response = agent.prompt("What does \"glarb-glarb\" mean?").await
 //!     .expect("Failed to prompt the agent");
 //! ```
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use futures::{stream, StreamExt, TryStreamExt};
 
 use crate::{
     completion::{
-        Chat, Completion, CompletionError, CompletionModel, CompletionRequestBuilder, Document,
-        Message, Prompt, PromptError,
+        Chat, Cite, CitedResponse, CitedText, Completion, CompletionError, CompletionModel,
+        CompletionRequest, CompletionRequestBuilder, CompletionResponse, ContextWindow, Document,
+        Message, Prompt, PromptError, CITATION_INSTRUCTIONS,
     },
     message::AssistantContent,
+    rerank::{RerankError, Reranker, RerankerDyn},
     streaming::{
         StreamingChat, StreamingCompletion, StreamingCompletionModel, StreamingPrompt,
         StreamingResult,
     },
-    tool::{Tool, ToolSet},
-    vector_store::{VectorStoreError, VectorStoreIndexDyn},
+    tool::{qualified_name, Tool, ToolSet, ToolSetError, ToolSwitches},
+    vector_store::{SearchOptions, VectorStoreError, VectorStoreIndexDyn},
 };
 
+/// Trait for observing the lifecycle of an [Agent] prompt.
+///
+/// All methods have a default no-op implementation, so implementors only need to
+/// override the callbacks they are interested in. Callbacks are invoked synchronously
+/// and in the order in which the hooks were added to the agent, so they should not block.
+///
+/// Note: hooks are invoked when the agent is used through the [Prompt], [Chat] and [Cite] traits.
+/// Requests built with [Completion::completion] and sent manually only trigger
+/// [AgentHook::on_dynamic_context].
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+///
+/// use rig::{
+///     agent::AgentHook,
+///     completion::{CompletionModel, CompletionRequest, CompletionResponse},
+///     providers::openai,
+/// };
+///
+/// struct AuditLog;
+///
+/// impl<M: CompletionModel> AgentHook<M> for AuditLog {
+///     fn on_completion_request(&self, request: &CompletionRequest) {
+///         println!("Sending request: {:?}", request.prompt);
+///     }
+///
+///     fn on_completion_response(
+///         &self,
+///         _request: &CompletionRequest,
+///         response: &CompletionResponse<M::Response>,
+///         elapsed: Duration,
+///     ) {
+///         println!("Received {:?} in {:?}", response.choice, elapsed);
+///     }
+/// }
+///
+/// let openai = openai::Client::from_env();
+///
+/// let agent = openai.agent(openai::GPT_4O)
+///     .preamble("You are a helpful assistant.")
+///     .hook(AuditLog)
+///     .build();
+/// ```
+pub trait AgentHook<M: CompletionModel>: Send + Sync {
+    /// Called once the completion request has been assembled, right before it is sent
+    /// to the completion model.
+    fn on_completion_request(&self, _request: &CompletionRequest) {}
+
+    /// Called when the completion model returns a response. `elapsed` is the time spent
+    /// waiting for the model.
+    fn on_completion_response(
+        &self,
+        _request: &CompletionRequest,
+        _response: &CompletionResponse<M::Response>,
+        _elapsed: Duration,
+    ) {
+    }
+
+    /// Called before a tool requested by the model is executed.
+    fn on_tool_call(&self, _toolname: &str, _args: &str) {}
+
+    /// Called once a tool requested by the model has been executed, whether it succeeded or not.
+    fn on_tool_result(
+        &self,
+        _toolname: &str,
+        _args: &str,
+        _result: &Result<String, ToolSetError>,
+        _elapsed: Duration,
+    ) {
+    }
+
+    /// Called once the dynamic context documents have been retrieved from the agent's
+    /// vector store indices. `query` is the text used for the lookup.
+    fn on_dynamic_context(&self, _query: &str, _documents: &[Document], _elapsed: Duration) {}
+
+    /// Called when prompting the agent fails.
+    fn on_error(&self, _error: &PromptError) {}
+}
+
+impl<M: CompletionModel, H: AgentHook<M>> AgentHook<M> for Arc<H> {
+    fn on_completion_request(&self, request: &CompletionRequest) {
+        (**self).on_completion_request(request)
+    }
+
+    fn on_completion_response(
+        &self,
+        request: &CompletionRequest,
+        response: &CompletionResponse<M::Response>,
+        elapsed: Duration,
+    ) {
+        (**self).on_completion_response(request, response, elapsed)
+    }
+
+    fn on_tool_call(&self, toolname: &str, args: &str) {
+        (**self).on_tool_call(toolname, args)
+    }
+
+    fn on_tool_result(
+        &self,
+        toolname: &str,
+        args: &str,
+        result: &Result<String, ToolSetError>,
+        elapsed: Duration,
+    ) {
+        (**self).on_tool_result(toolname, args, result, elapsed)
+    }
+
+    fn on_dynamic_context(&self, query: &str, documents: &[Document], elapsed: Duration) {
+        (**self).on_dynamic_context(query, documents, elapsed)
+    }
+
+    fn on_error(&self, error: &PromptError) {
+        (**self).on_error(error)
+    }
+}
+
 /// Struct representing an LLM agent. An agent is an LLM model combined with a preamble
 /// (i.e.: system prompt) and a static set of context documents and tools.
 /// All context documents and tools are always provided to the agent when prompted.
@@ -159,12 +290,206 @@ pub struct Agent<M: CompletionModel> {
     max_tokens: Option<u64>,
     /// Additional parameters to be passed to the model
     additional_params: Option<serde_json::Value>,
-    /// List of vector store, with the sample number
-    dynamic_context: Vec<(usize, Box<dyn VectorStoreIndexDyn>)>,
+    /// List of vector store, with the sample number and the search options
+    dynamic_context: Vec<(usize, Box<dyn VectorStoreIndexDyn>, SearchOptions)>,
+    /// Reranker of the dynamic context, with the number of candidates to fetch
+    reranker: Option<(Box<dyn RerankerDyn>, usize)>,
+    /// Truncation of the requests exceeding the model's context window
+    context_window: Option<ContextWindow>,
     /// Dynamic tools
     dynamic_tools: Vec<(usize, Box<dyn VectorStoreIndexDyn>)>,
     /// Actual tool implementations
     pub tools: ToolSet,
+    /// Hooks notified of each step of a prompt
+    hooks: Vec<Box<dyn AgentHook<M>>>,
+}
+
+impl<M: CompletionModel> Agent<M> {
+    /// Send the completion request to the model, notifying the agent's hooks. If `citations`
+    /// is set, the model is asked to cite the documents of the request natively (see
+    /// [CompletionModel::cited_completion]).
+    async fn send_completion(
+        &self,
+        request: CompletionRequest,
+        citations: bool,
+    ) -> Result<CompletionResponse<M::Response>, CompletionError> {
+        let complete = |request| async move {
+            if citations {
+                self.model.cited_completion(request).await
+            } else {
+                self.model.completion(request).await
+            }
+        };
+
+        if self.hooks.is_empty() {
+            return complete(request).await;
+        }
+
+        self.hooks
+            .iter()
+            .for_each(|hook| hook.on_completion_request(&request));
+
+        let start = Instant::now();
+        let response = complete(request.clone()).await?;
+        let elapsed = start.elapsed();
+
+        self.hooks
+            .iter()
+            .for_each(|hook| hook.on_completion_response(&request, &response, elapsed));
+
+        Ok(response)
+    }
+
+    /// Call a tool from the agent's toolset, unless disabled by `switches`, notifying the
+    /// agent's hooks.
+    async fn call_tool(
+        &self,
+        toolname: &str,
+        args: String,
+        switches: &ToolSwitches,
+    ) -> Result<String, ToolSetError> {
+        self.hooks
+            .iter()
+            .for_each(|hook| hook.on_tool_call(toolname, &args));
+
+        let start = Instant::now();
+        let result = self.tools.call_with(toolname, args.clone(), switches).await;
+        let elapsed = start.elapsed();
+
+        self.hooks
+            .iter()
+            .for_each(|hook| hook.on_tool_result(toolname, &args, &result, elapsed));
+
+        result
+    }
+
+    async fn chat_with_hooks(
+        &self,
+        prompt: Message,
+        chat_history: Vec<Message>,
+        switches: &ToolSwitches,
+    ) -> Result<String, PromptError> {
+        let request = self
+            .completion_with(prompt, chat_history, switches)
+            .await?
+            .build();
+        let resp = self.send_completion(request, false).await?;
+
+        // TODO: consider returning a `Message` instead of `String` for parallel responses / tool calls
+        match resp.choice.first() {
+            AssistantContent::Text(text) => Ok(text.text.clone()),
+            AssistantContent::ToolCall(tool_call) => Ok(self
+                .call_tool(
+                    &tool_call.function.name,
+                    tool_call.function.arguments.to_string(),
+                    switches,
+                )
+                .await?),
+        }
+    }
+
+    async fn cite_with_hooks(
+        &self,
+        prompt: Message,
+        switches: &ToolSwitches,
+    ) -> Result<CitedResponse, PromptError> {
+        let mut request = self
+            .completion_with(prompt, vec![], switches)
+            .await?
+            .build();
+
+        if !request.documents.is_empty() && !self.model.supports_citations() {
+            request.preamble = Some(match request.preamble.take() {
+                Some(preamble) if !preamble.is_empty() => {
+                    format!("{preamble}\n\n{CITATION_INSTRUCTIONS}")
+                }
+                _ => CITATION_INSTRUCTIONS.to_string(),
+            });
+        }
+
+        let documents = request.documents.clone();
+        let resp = self
+            .send_completion(request, self.model.supports_citations())
+            .await?;
+
+        if let AssistantContent::ToolCall(tool_call) = resp.choice.first() {
+            let text = self
+                .call_tool(
+                    &tool_call.function.name,
+                    tool_call.function.arguments.to_string(),
+                    switches,
+                )
+                .await?;
+            return Ok(CitedResponse {
+                text,
+                citations: vec![],
+                documents,
+            });
+        }
+
+        let CitedText { text, citations } = self.model.citations(&resp, &documents);
+        Ok(CitedResponse {
+            text,
+            citations,
+            documents,
+        })
+    }
+
+    /// Fetch the `n` documents of `index` most relevant to `query` using the search `options`,
+    /// over-fetching candidates and reranking them if the agent has a reranker.
+    async fn dynamic_context_documents(
+        &self,
+        index: &dyn VectorStoreIndexDyn,
+        query: &str,
+        n: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<Document>, RerankError> {
+        let Some((reranker, candidates)) = &self.reranker else {
+            return Ok(index
+                .top_n_with_options(query, n, options)
+                .await?
+                .into_iter()
+                .map(|(_, id, doc)| Document::from_json(id, doc))
+                .collect());
+        };
+
+        let documents = index
+            .top_n_with_options(query, (*candidates).max(n), options)
+            .await?
+            .into_iter()
+            .map(|(_, id, doc)| Document::from_json(id, doc))
+            .collect::<Vec<_>>();
+        let texts = documents
+            .iter()
+            .map(|document| document.text.clone())
+            .collect::<Vec<_>>();
+
+        let results = reranker.rerank(query, &texts, n).await?;
+
+        let mut documents = documents.into_iter().map(Some).collect::<Vec<_>>();
+        Ok(results
+            .into_iter()
+            .filter_map(|result| documents.get_mut(result.index).and_then(Option::take))
+            .take(n)
+            .collect())
+    }
+}
+
+/// Interleave lists of documents ranked by relevance: the first document of each list, then
+/// the second of each list, etc.
+fn interleave(lists: Vec<Vec<Document>>) -> Vec<Document> {
+    let mut lists = lists.into_iter().map(Vec::into_iter).collect::<Vec<_>>();
+    let mut documents = vec![];
+    loop {
+        let round = lists
+            .iter_mut()
+            .filter_map(Iterator::next)
+            .collect::<Vec<_>>();
+        if round.is_empty() {
+            return documents;
+        }
+        documents.extend(round);
+    }
 }
 
 impl<M: CompletionModel> Completion<M> for Agent<M> {
@@ -173,7 +498,43 @@ impl<M: CompletionModel> Completion<M> for Agent<M> {
         prompt: impl Into<Message> + Send,
         chat_history: Vec<Message>,
     ) -> Result<CompletionRequestBuilder<M>, CompletionError> {
-        let prompt = prompt.into();
+        self.completion_with(prompt.into(), chat_history, &ToolSwitches::new())
+            .await
+    }
+}
+
+impl<M: CompletionModel> Agent<M> {
+    /// Restrict the tools available to the requests made through the returned [ScopedAgent]:
+    /// tools disabled by `switches` are neither advertised to the model nor callable, on top
+    /// of the tools disabled in the agent's toolset. This allows enabling tools per request
+    /// without affecting concurrent requests to the agent.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rig::{completion::Prompt, tool::ToolSwitches};
+    ///
+    /// // Answer without trading for this prompt only
+    /// let switches = ToolSwitches::new();
+    /// switches.disable_namespace("solana");
+    /// let answer = agent
+    ///     .with_tool_switches(switches)
+    ///     .prompt("What is the price of SOL?")
+    ///     .await?;
+    /// ```
+    pub fn with_tool_switches(&self, switches: ToolSwitches) -> ScopedAgent<'_, M> {
+        ScopedAgent {
+            agent: self,
+            switches,
+        }
+    }
+
+    /// Build the completion request of `prompt`, with the tools not disabled by `switches`
+    async fn completion_with(
+        &self,
+        prompt: Message,
+        chat_history: Vec<Message>,
+        switches: &ToolSwitches,
+    ) -> Result<CompletionRequestBuilder<M>, CompletionError> {
         let rag_text = prompt.rag_text().clone();
 
         let completion_request = self
@@ -188,33 +549,25 @@ impl<M: CompletionModel> Completion<M> for Agent<M> {
 
         let agent = match &rag_text {
             Some(text) => {
+                let start = Instant::now();
                 let dynamic_context = stream::iter(self.dynamic_context.iter())
-                    .then(|(num_sample, index)| async {
-                        Ok::<_, VectorStoreError>(
-                            index
-                                .top_n(text, *num_sample)
-                                .await?
-                                .into_iter()
-                                .map(|(_, id, doc)| {
-                                    // Pretty print the document if possible for better readability
-                                    let text = serde_json::to_string_pretty(&doc)
-                                        .unwrap_or_else(|_| doc.to_string());
-
-                                    Document {
-                                        id,
-                                        text,
-                                        additional_props: HashMap::new(),
-                                    }
-                                })
-                                .collect::<Vec<_>>(),
-                        )
-                    })
-                    .try_fold(vec![], |mut acc, docs| async {
-                        acc.extend(docs);
-                        Ok(acc)
+                    .then(|(num_sample, index, options)| {
+                        self.dynamic_context_documents(index.as_ref(), text, *num_sample, options)
                     })
+                    .try_collect::<Vec<_>>()
                     .await
                     .map_err(|e| CompletionError::RequestError(Box::new(e)))?;
+                // Interleave the documents of the indexes by rank, so that truncating the
+                // documents to fit in the context window drops the lowest ranked ones first
+                // (see [TruncationPolicy::TrimDocuments](crate::completion::context_window::TruncationPolicy::TrimDocuments))
+                let dynamic_context = interleave(dynamic_context);
+
+                if !self.dynamic_context.is_empty() {
+                    let elapsed = start.elapsed();
+                    self.hooks
+                        .iter()
+                        .for_each(|hook| hook.on_dynamic_context(text, &dynamic_context, elapsed));
+                }
 
                 let dynamic_tools = stream::iter(self.dynamic_tools.iter())
                     .then(|(num_sample, index)| async {
@@ -229,9 +582,13 @@ impl<M: CompletionModel> Completion<M> for Agent<M> {
                     })
                     .try_fold(vec![], |mut acc, docs| async {
                         for doc in docs {
-                            if let Some(tool) = self.tools.get(&doc) {
-                                acc.push(tool.definition(text.into()).await)
-                            } else {
+                            if let Some(definition) = self
+                                .tools
+                                .definition_with(&doc, text.into(), switches)
+                                .await
+                            {
+                                acc.push(definition)
+                            } else if !self.tools.contains(&doc) {
                                 tracing::warn!("Tool implementation not found in toolset: {}", doc);
                             }
                         }
@@ -242,15 +599,17 @@ impl<M: CompletionModel> Completion<M> for Agent<M> {
 
                 let static_tools = stream::iter(self.static_tools.iter())
                     .filter_map(|toolname| async move {
-                        if let Some(tool) = self.tools.get(toolname) {
-                            Some(tool.definition(text.into()).await)
-                        } else {
+                        let definition = self
+                            .tools
+                            .definition_with(toolname, text.into(), switches)
+                            .await;
+                        if definition.is_none() && !self.tools.contains(toolname) {
                             tracing::warn!(
                                 "Tool implementation not found in toolset: {}",
                                 toolname
                             );
-                            None
                         }
+                        definition
                     })
                     .collect::<Vec<_>>()
                     .await;
@@ -262,16 +621,18 @@ impl<M: CompletionModel> Completion<M> for Agent<M> {
             None => {
                 let static_tools = stream::iter(self.static_tools.iter())
                     .filter_map(|toolname| async move {
-                        if let Some(tool) = self.tools.get(toolname) {
-                            // TODO: tool definitions should likely take an `Option<String>`
-                            Some(tool.definition("".into()).await)
-                        } else {
+                        // TODO: tool definitions should likely take an `Option<String>`
+                        let definition = self
+                            .tools
+                            .definition_with(toolname, "".into(), switches)
+                            .await;
+                        if definition.is_none() && !self.tools.contains(toolname) {
                             tracing::warn!(
                                 "Tool implementation not found in toolset: {}",
                                 toolname
                             );
-                            None
                         }
+                        definition
                     })
                     .collect::<Vec<_>>()
                     .await;
@@ -280,7 +641,16 @@ impl<M: CompletionModel> Completion<M> for Agent<M> {
             }
         };
 
-        Ok(agent)
+        match &self.context_window {
+            Some(context_window) => {
+                let request = context_window.fit(&self.model, agent.build()).await?;
+                Ok(CompletionRequestBuilder::from_request(
+                    self.model.clone(),
+                    request,
+                ))
+            }
+            None => Ok(agent),
+        }
     }
 }
 
@@ -302,19 +672,94 @@ impl<M: CompletionModel> Chat for Agent<M> {
         prompt: impl Into<Message> + Send,
         chat_history: Vec<Message>,
     ) -> Result<String, PromptError> {
-        let resp = self.completion(prompt, chat_history).await?.send().await?;
+        let result = self
+            .chat_with_hooks(prompt.into(), chat_history, &ToolSwitches::new())
+            .await;
 
-        // TODO: consider returning a `Message` instead of `String` for parallel responses / tool calls
-        match resp.choice.first() {
-            AssistantContent::Text(text) => Ok(text.text.clone()),
-            AssistantContent::ToolCall(tool_call) => Ok(self
-                .tools
-                .call(
-                    &tool_call.function.name,
-                    tool_call.function.arguments.to_string(),
-                )
-                .await?),
+        if let Err(error) = &result {
+            self.hooks.iter().for_each(|hook| hook.on_error(error));
+        }
+
+        result
+    }
+}
+
+impl<M: CompletionModel> Cite for Agent<M> {
+    async fn cite(&self, prompt: impl Into<Message> + Send) -> Result<CitedResponse, PromptError> {
+        let result = self
+            .cite_with_hooks(prompt.into(), &ToolSwitches::new())
+            .await;
+
+        if let Err(error) = &result {
+            self.hooks.iter().for_each(|hook| hook.on_error(error));
+        }
+
+        result
+    }
+}
+
+/// An [Agent] whose tools are restricted by per-request [ToolSwitches]
+/// (see [Agent::with_tool_switches]).
+pub struct ScopedAgent<'a, M: CompletionModel> {
+    agent: &'a Agent<M>,
+    switches: ToolSwitches,
+}
+
+impl<M: CompletionModel> Completion<M> for ScopedAgent<'_, M> {
+    async fn completion(
+        &self,
+        prompt: impl Into<Message> + Send,
+        chat_history: Vec<Message>,
+    ) -> Result<CompletionRequestBuilder<M>, CompletionError> {
+        self.agent
+            .completion_with(prompt.into(), chat_history, &self.switches)
+            .await
+    }
+}
+
+impl<M: CompletionModel> Prompt for ScopedAgent<'_, M> {
+    async fn prompt(&self, prompt: impl Into<Message> + Send) -> Result<String, PromptError> {
+        self.chat(prompt, vec![]).await
+    }
+}
+
+impl<M: CompletionModel> Chat for ScopedAgent<'_, M> {
+    async fn chat(
+        &self,
+        prompt: impl Into<Message> + Send,
+        chat_history: Vec<Message>,
+    ) -> Result<String, PromptError> {
+        let result = self
+            .agent
+            .chat_with_hooks(prompt.into(), chat_history, &self.switches)
+            .await;
+
+        if let Err(error) = &result {
+            self.agent
+                .hooks
+                .iter()
+                .for_each(|hook| hook.on_error(error));
+        }
+
+        result
+    }
+}
+
+impl<M: CompletionModel> Cite for ScopedAgent<'_, M> {
+    async fn cite(&self, prompt: impl Into<Message> + Send) -> Result<CitedResponse, PromptError> {
+        let result = self
+            .agent
+            .cite_with_hooks(prompt.into(), &self.switches)
+            .await;
+
+        if let Err(error) = &result {
+            self.agent
+                .hooks
+                .iter()
+                .for_each(|hook| hook.on_error(error));
         }
+
+        result
     }
 }
 
@@ -352,14 +797,20 @@ pub struct AgentBuilder<M: CompletionModel> {
     additional_params: Option<serde_json::Value>,
     /// Maximum number of tokens for the completion
     max_tokens: Option<u64>,
-    /// List of vector store, with the sample number
-    dynamic_context: Vec<(usize, Box<dyn VectorStoreIndexDyn>)>,
+    /// List of vector store, with the sample number and the search options
+    dynamic_context: Vec<(usize, Box<dyn VectorStoreIndexDyn>, SearchOptions)>,
+    /// Reranker of the dynamic context, with the number of candidates to fetch
+    reranker: Option<(Box<dyn RerankerDyn>, usize)>,
+    /// Truncation of the requests exceeding the model's context window
+    context_window: Option<ContextWindow>,
     /// Dynamic tools
     dynamic_tools: Vec<(usize, Box<dyn VectorStoreIndexDyn>)>,
     /// Temperature of the model
     temperature: Option<f64>,
     /// Actual tool implementations
     tools: ToolSet,
+    /// Hooks notified of each step of a prompt
+    hooks: Vec<Box<dyn AgentHook<M>>>,
 }
 
 impl<M: CompletionModel> AgentBuilder<M> {
@@ -373,8 +824,11 @@ impl<M: CompletionModel> AgentBuilder<M> {
             max_tokens: None,
             additional_params: None,
             dynamic_context: vec![],
+            reranker: None,
+            context_window: None,
             dynamic_tools: vec![],
             tools: ToolSet::default(),
+            hooks: vec![],
         }
     }
 
@@ -412,15 +866,60 @@ impl<M: CompletionModel> AgentBuilder<M> {
         self
     }
 
+    /// Add a static tool to the given namespace of the agent's toolset. The tool is exposed
+    /// to the model under its qualified name (see [qualified_name]).
+    pub fn namespaced_tool(mut self, namespace: &str, tool: impl Tool + 'static) -> Self {
+        let toolname = qualified_name(namespace, &tool.name());
+        self.tools.add_namespaced_tool(namespace, tool);
+        self.static_tools.push(toolname);
+        self
+    }
+
     /// Add some dynamic context to the agent. On each prompt, `sample` documents from the
     /// dynamic context will be inserted in the request.
     pub fn dynamic_context(
+        self,
+        sample: usize,
+        dynamic_context: impl VectorStoreIndexDyn + 'static,
+    ) -> Self {
+        self.dynamic_context_with_options(sample, dynamic_context, SearchOptions::default())
+    }
+
+    /// Add some dynamic context to the agent, searched using the given `options`. On each
+    /// prompt, at most `sample` documents from the dynamic context will be inserted in the
+    /// request (fewer if some documents are below the minimum score of the options).
+    ///
+    /// # Example
+    /// ```rust
+    /// use rig::vector_store::{Mmr, SearchOptions};
+    ///
+    /// let agent = openai.agent("gpt-4o")
+    ///     .dynamic_context_with_options(5, index, SearchOptions::new().min_score(0.8).mmr(Mmr::new(0.5)))
+    ///     .build();
+    /// ```
+    pub fn dynamic_context_with_options(
         mut self,
         sample: usize,
         dynamic_context: impl VectorStoreIndexDyn + 'static,
+        options: SearchOptions,
     ) -> Self {
         self.dynamic_context
-            .push((sample, Box::new(dynamic_context)));
+            .push((sample, Box::new(dynamic_context), options));
+        self
+    }
+
+    /// Rerank the dynamic context of the agent. On each prompt, `candidates` documents are
+    /// fetched from each dynamic context, reranked against the prompt by `reranker`, and
+    /// only the most relevant `sample` documents are inserted in the request.
+    pub fn reranker(mut self, reranker: impl Reranker + 'static, candidates: usize) -> Self {
+        self.reranker = Some((Box::new(reranker), candidates));
+        self
+    }
+
+    /// Make the agent's requests fit in the model's context window by applying the truncation
+    /// policies of `context_window` (e.g.: dropping the oldest chat history) when needed.
+    pub fn context_window(mut self, context_window: ContextWindow) -> Self {
+        self.context_window = Some(context_window);
         self
     }
 
@@ -455,6 +954,13 @@ impl<M: CompletionModel> AgentBuilder<M> {
         self
     }
 
+    /// Add a hook to the agent. The hook will be notified of each step of the agent's
+    /// prompts (e.g.: request sent, response received, tool called).
+    pub fn hook(mut self, hook: impl AgentHook<M> + 'static) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
     /// Build the agent
     pub fn build(self) -> Agent<M> {
         Agent {
@@ -466,8 +972,11 @@ impl<M: CompletionModel> AgentBuilder<M> {
             max_tokens: self.max_tokens,
             additional_params: self.additional_params,
             dynamic_context: self.dynamic_context,
+            reranker: self.reranker,
+            context_window: self.context_window,
             dynamic_tools: self.dynamic_tools,
             tools: self.tools,
+            hooks: self.hooks,
         }
     }
 }
@@ -502,3 +1011,465 @@ impl<M: StreamingCompletionModel> StreamingChat for Agent<M> {
             .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        completion::{CompletionResponse, ToolDefinition},
+        loaders::metadata::SOURCE_METADATA_MARKER,
+        rerank::RerankResult,
+        tokenizer,
+        vector_store::VectorStoreIndex,
+        OneOrMany,
+    };
+
+    /// Completion model that calls the first tool advertised in the request (with `echo`
+    /// arguments) if any, and answers with the number of documents in the request otherwise.
+    #[derive(Clone)]
+    struct MockModel;
+
+    impl CompletionModel for MockModel {
+        type Response = ();
+
+        async fn completion(
+            &self,
+            request: CompletionRequest,
+        ) -> Result<CompletionResponse<()>, CompletionError> {
+            let choice = if let Some(tool) = request.tools.first() {
+                AssistantContent::tool_call("call_0", &tool.name, json!({"text": "hello"}))
+            } else {
+                AssistantContent::text(format!("{} documents", request.documents.len()))
+            };
+
+            Ok(CompletionResponse {
+                choice: OneOrMany::one(choice),
+                raw_response: (),
+            })
+        }
+    }
+
+    #[derive(serde::Deserialize)]
+    struct EchoArgs {
+        text: String,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("Echo error")]
+    struct EchoError;
+
+    struct Echo;
+
+    impl Tool for Echo {
+        const NAME: &'static str = "echo";
+
+        type Error = EchoError;
+        type Args = EchoArgs;
+        type Output = String;
+
+        async fn definition(&self, _prompt: String) -> ToolDefinition {
+            ToolDefinition {
+                name: Self::NAME.to_string(),
+                description: "Echo the given text".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "text": {"type": "string", "description": "The text to echo"}
+                    }
+                }),
+            }
+        }
+
+        async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+            Ok(args.text)
+        }
+    }
+
+    /// Same as [Echo], but always fails.
+    struct BrokenEcho;
+
+    impl Tool for BrokenEcho {
+        const NAME: &'static str = "echo";
+
+        type Error = EchoError;
+        type Args = EchoArgs;
+        type Output = String;
+
+        async fn definition(&self, prompt: String) -> ToolDefinition {
+            Echo.definition(prompt).await
+        }
+
+        async fn call(&self, _args: Self::Args) -> Result<Self::Output, Self::Error> {
+            Err(EchoError)
+        }
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl Recorder {
+        fn record(&self, event: String) {
+            self.events.lock().unwrap().push(event);
+        }
+
+        fn events(&self) -> Vec<String> {
+            self.events.lock().unwrap().clone()
+        }
+    }
+
+    impl<M: CompletionModel> AgentHook<M> for Recorder {
+        fn on_completion_request(&self, request: &CompletionRequest) {
+            self.record(format!("request: {} tools", request.tools.len()));
+        }
+
+        fn on_completion_response(
+            &self,
+            _request: &CompletionRequest,
+            response: &CompletionResponse<M::Response>,
+            _elapsed: Duration,
+        ) {
+            let event = match response.choice.first() {
+                AssistantContent::Text(text) => format!("response: {}", text.text),
+                AssistantContent::ToolCall(call) => {
+                    format!("response: call {}", call.function.name)
+                }
+            };
+            self.record(event);
+        }
+
+        fn on_tool_call(&self, toolname: &str, args: &str) {
+            self.record(format!("tool call: {toolname} {args}"));
+        }
+
+        fn on_tool_result(
+            &self,
+            toolname: &str,
+            _args: &str,
+            result: &Result<String, ToolSetError>,
+            _elapsed: Duration,
+        ) {
+            self.record(format!("tool result: {toolname} {}", result.is_ok()));
+        }
+
+        fn on_error(&self, error: &PromptError) {
+            self.record(format!("error: {error}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hooks_text_response() {
+        let recorder = Arc::new(Recorder::default());
+
+        let agent = AgentBuilder::new(MockModel)
+            .context("Context document")
+            .hook(recorder.clone())
+            .build();
+
+        let response = agent.prompt("Hello").await.unwrap();
+
+        assert_eq!(response, "1 documents");
+        assert_eq!(
+            recorder.events(),
+            vec!["request: 0 tools", "response: 1 documents"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hooks_tool_call() {
+        let recorder = Arc::new(Recorder::default());
+
+        let agent = AgentBuilder::new(MockModel)
+            .tool(Echo)
+            .hook(recorder.clone())
+            .build();
+
+        let response = agent.prompt("Hello").await.unwrap();
+
+        assert_eq!(response, "\"hello\"");
+        assert_eq!(
+            recorder.events(),
+            vec![
+                "request: 1 tools",
+                "response: call echo",
+                r#"tool call: echo {"text":"hello"}"#,
+                "tool result: echo true",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hooks_error() {
+        let recorder = Arc::new(Recorder::default());
+
+        let agent = AgentBuilder::new(MockModel)
+            .tool(BrokenEcho)
+            .hook(recorder.clone())
+            .build();
+
+        let result = agent.prompt("Hello").await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            recorder.events(),
+            vec![
+                "request: 1 tools",
+                "response: call echo",
+                r#"tool call: echo {"text":"hello"}"#,
+                "tool result: echo false",
+                "error: ToolCallError: ToolCallError: ToolCallError: Echo error",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disabled_tools_are_not_advertised() {
+        let recorder = Arc::new(Recorder::default());
+        let agent = AgentBuilder::new(MockModel)
+            .namespaced_tool("util", Echo)
+            .hook(recorder.clone())
+            .build();
+
+        assert_eq!(agent.prompt("Echo hello").await.unwrap(), "\"hello\"");
+
+        let switches = agent.tools.switches();
+        switches.disable_namespace("util");
+        assert_eq!(agent.prompt("Echo hello").await.unwrap(), "0 documents");
+
+        switches.enable_namespace("util");
+        agent.tools.disable("util_echo");
+        assert_eq!(agent.prompt("Echo hello").await.unwrap(), "0 documents");
+
+        agent.tools.enable("util_echo");
+        assert_eq!(agent.prompt("Echo hello").await.unwrap(), "\"hello\"");
+
+        assert_eq!(
+            recorder.events(),
+            vec![
+                "request: 1 tools",
+                "response: call util_echo",
+                "tool call: util_echo {\"text\":\"hello\"}",
+                "tool result: util_echo true",
+                "request: 0 tools",
+                "response: 0 documents",
+                "request: 0 tools",
+                "response: 0 documents",
+                "request: 1 tools",
+                "response: call util_echo",
+                "tool call: util_echo {\"text\":\"hello\"}",
+                "tool result: util_echo true",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_per_request_tool_switches() {
+        let agent = AgentBuilder::new(MockModel)
+            .namespaced_tool("util", Echo)
+            .build();
+
+        let switches = ToolSwitches::new();
+        switches.disable_namespace("util");
+        let scoped = agent.with_tool_switches(switches.clone());
+        assert_eq!(scoped.prompt("Echo hello").await.unwrap(), "0 documents");
+
+        // Other requests to the agent are unaffected
+        assert_eq!(agent.prompt("Echo hello").await.unwrap(), "\"hello\"");
+        assert!(agent.tools.is_enabled("util_echo"));
+
+        // Tools disabled for a request cannot be called during it
+        let result = agent
+            .tools
+            .call_with("util_echo", r#"{"text":"hello"}"#.to_string(), &switches)
+            .await;
+        assert!(matches!(result, Err(ToolSetError::ToolDisabledError(_))));
+    }
+
+    /// Completion model that cites every document of the request when asked to
+    #[derive(Clone)]
+    struct CitingModel;
+
+    impl CompletionModel for CitingModel {
+        type Response = ();
+
+        async fn completion(
+            &self,
+            request: CompletionRequest,
+        ) -> Result<CompletionResponse<()>, CompletionError> {
+            let markers = match request.preamble {
+                Some(preamble) if preamble.ends_with(CITATION_INSTRUCTIONS) => request
+                    .documents
+                    .iter()
+                    .map(|document| format!("[{}]", document.id))
+                    .collect::<String>(),
+                _ => String::new(),
+            };
+
+            Ok(CompletionResponse {
+                choice: OneOrMany::one(AssistantContent::text(format!(
+                    "SOL is up {markers}. Nothing else."
+                ))),
+                raw_response: (),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cite() {
+        let agent = AgentBuilder::new(CitingModel)
+            .preamble("You are a market analyst.")
+            .context("SOL is up 12%")
+            .context("SOL volume doubled")
+            .build();
+
+        let response = agent.cite("How is SOL doing?").await.unwrap();
+
+        assert_eq!(response.text, "SOL is up. Nothing else.");
+        assert_eq!(response.citations.len(), 1);
+        assert_eq!(response.citations[0].text, "SOL is up.");
+        assert_eq!(
+            response.citations[0].document_ids,
+            vec!["static_doc_0", "static_doc_1"]
+        );
+        assert_eq!(response.documents.len(), 2);
+    }
+
+    /// Index returning up to 5 documents, in order
+    struct MockIndex;
+
+    impl VectorStoreIndex for MockIndex {
+        async fn top_n<T: for<'a> serde::Deserialize<'a> + Send>(
+            &self,
+            _query: &str,
+            n: usize,
+        ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+            (0..n.min(5))
+                .map(|i| {
+                    let doc = serde_json::from_value(json!({
+                        "text": format!("Document {i}"),
+                        SOURCE_METADATA_MARKER: true,
+                    }))?;
+                    Ok((1.0 - i as f64 / 10.0, format!("doc{i}"), doc))
+                })
+                .collect()
+        }
+
+        async fn top_n_ids(
+            &self,
+            _query: &str,
+            n: usize,
+        ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+            Ok((0..n.min(5))
+                .map(|i| (1.0 - i as f64 / 10.0, format!("doc{i}")))
+                .collect())
+        }
+    }
+
+    /// Reranker reversing the order of the candidates
+    struct ReverseReranker;
+
+    impl Reranker for ReverseReranker {
+        async fn rerank(
+            &self,
+            _query: &str,
+            documents: &[String],
+            top_k: usize,
+        ) -> Result<Vec<RerankResult>, RerankError> {
+            Ok((0..documents.len())
+                .rev()
+                .map(|index| RerankResult {
+                    index,
+                    score: index as f64,
+                })
+                .take(top_k)
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dynamic_context_min_score() {
+        let agent = AgentBuilder::new(MockModel)
+            .dynamic_context_with_options(5, MockIndex, SearchOptions::new().min_score(0.75))
+            .build();
+
+        assert_eq!(agent.prompt("Hello").await.unwrap(), "3 documents");
+    }
+
+    #[tokio::test]
+    async fn test_reranked_dynamic_context() {
+        let agent = AgentBuilder::new(MockModel)
+            .dynamic_context(2, MockIndex)
+            .reranker(ReverseReranker, 10)
+            .build();
+
+        let request = agent.completion("Hello", vec![]).await.unwrap().build();
+
+        assert_eq!(
+            request
+                .documents
+                .iter()
+                .map(|document| (document.id.as_str(), document.text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("doc4", "Document 4"), ("doc3", "Document 3")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_context_window_truncation() {
+        let context_window =
+            ContextWindow::new()
+                .limit(30)
+                .tokenizer(tokenizer::from_fn(|text: &str| {
+                    text.split_whitespace().count()
+                }));
+        let agent = AgentBuilder::new(MockModel)
+            .dynamic_context(5, MockIndex)
+            .context_window(context_window.clone())
+            .build();
+
+        let chat_history = (0..5)
+            .map(|i| Message::user(format!("old message {i}")))
+            .collect();
+        let request = agent
+            .completion("Hello", chat_history)
+            .await
+            .unwrap()
+            .build();
+
+        assert!(request.chat_history.is_empty());
+        assert!(!request.documents.is_empty() && request.documents.len() < 5);
+        assert!(context_window.count_request_tokens(&request) <= 30);
+    }
+
+    #[test]
+    fn test_interleave() {
+        let documents = |ids: &[&str]| {
+            ids.iter()
+                .map(|id| Document {
+                    id: id.to_string(),
+                    text: String::new(),
+                    additional_props: HashMap::new(),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let interleaved = interleave(vec![
+            documents(&["a0", "a1", "a2"]),
+            documents(&["b0"]),
+            documents(&["c0", "c1"]),
+        ]);
+        assert_eq!(
+            interleaved
+                .iter()
+                .map(|document| document.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a0", "b0", "c0", "a1", "c1", "a2"]
+        );
+    }
+}
@@ -0,0 +1,368 @@
+//! This module provides citation-aware responses, i.e.: answers whose sentences are mapped to
+//! the ids of the context documents supporting them.
+//!
+//! The [Cite] trait is the citation counterpart of the [Prompt](super::Prompt) trait: instead of a
+//! plain string, it returns a [CitedResponse] containing the answer, its [Citation]s and the
+//! documents that were attached to the request (e.g.: the dynamic context of an agent).
+//!
+//! Completion models whose provider cites documents natively (e.g.: Cohere, Anthropic) override
+//! [CompletionModel::supports_citations](super::CompletionModel::supports_citations),
+//! [CompletionModel::cited_completion](super::CompletionModel::cited_completion) and
+//! [CompletionModel::citations](super::CompletionModel::citations). For other models, the
+//! [CITATION_INSTRUCTIONS] are added to the preamble and the citation markers written by the
+//! model are parsed out of its answer (see [CitedText::parse]).
+//!
+//! # Example
+//! ```rust
+//! use rig::{completion::Cite, providers::anthropic};
+//!
+//! let agent = anthropic::Client::from_env()
+//!     .agent(anthropic::CLAUDE_3_5_SONNET)
+//!     .preamble("You are a market research assistant.")
+//!     .dynamic_context(4, index)
+//!     .build();
+//!
+//! let response = agent.cite("How did SOL perform last week?").await?;
+//!
+//! for citation in &response.citations {
+//!     println!("{:?} <- {:?}", citation.text, citation.document_ids);
+//! }
+//! ```
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Document, Message, PromptError};
+use crate::{message::AssistantContent, OneOrMany};
+
+/// Instructions added to the preamble of models that do not support citations natively
+pub const CITATION_INSTRUCTIONS: &str = "\
+    Cite the attachments supporting each sentence of your answer by writing their ids in square \
+    brackets at the end of the sentence, e.g.: \"SOL closed the week up 12% [doc1][doc2].\". \
+    Only cite the ids of the attached files, and do not cite sentences that are not supported \
+    by any attachment.";
+
+/// A span of an answer along with the ids of the documents supporting it
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Citation {
+    /// Byte offset of the start of the span in the answer
+    pub start: usize,
+    /// Byte offset of the end (exclusive) of the span in the answer
+    pub end: usize,
+    /// Text of the span
+    pub text: String,
+    /// Ids of the documents supporting the span
+    pub document_ids: Vec<String>,
+}
+
+/// An answer along with its citations
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CitedText {
+    pub text: String,
+    pub citations: Vec<Citation>,
+}
+
+impl CitedText {
+    /// Parse the citation markers of `text`, i.e.: document ids written in square brackets after
+    /// the sentence they support (as requested by [CITATION_INSTRUCTIONS]). `resolve` maps the
+    /// content of a marker to a document id, markers containing anything else (e.g.: Markdown
+    /// links) are left untouched. Markers are removed from the returned text, and each citation
+    /// spans the whole sentence preceding its markers.
+    pub fn parse(text: &str, resolve: impl Fn(&str) -> Option<String>) -> Self {
+        let mut parser = Parser::default();
+
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            if c == '[' {
+                if let Some((document_ids, length)) = parse_marker(rest, &resolve) {
+                    parser.cite(document_ids);
+                    rest = &rest[length..];
+                    continue;
+                }
+            }
+
+            rest = &rest[c.len_utf8()..];
+            parser.text.push(c);
+
+            let next = rest.chars().next();
+            if c == '\n'
+                || (matches!(c, '.' | '!' | '?')
+                    && next.is_none_or(|next| next.is_whitespace() || next == '['))
+            {
+                parser.end_sentence();
+            }
+        }
+        parser.end_sentence();
+
+        Self {
+            text: parser.text,
+            citations: parser.citations,
+        }
+    }
+}
+
+/// Parser state of [CitedText::parse]
+#[derive(Default)]
+struct Parser {
+    text: String,
+    citations: Vec<Citation>,
+    /// Start of the current sentence in `text`
+    sentence_start: usize,
+    /// Span of the last complete sentence in `text`
+    last_sentence: Option<Range<usize>>,
+    /// Document ids cited for the current sentence
+    pending: Vec<String>,
+}
+
+impl Parser {
+    fn cite(&mut self, document_ids: Vec<String>) {
+        // Markers are separated from the sentence they cite by spaces, which are removed with them
+        self.text
+            .truncate(self.text.trim_end_matches([' ', '\t']).len());
+        self.sentence_start = self.sentence_start.min(self.text.len());
+
+        if !self.text[self.sentence_start..].trim().is_empty() {
+            extend_unique(&mut self.pending, document_ids);
+        } else if let Some(sentence) = self.last_sentence.clone() {
+            // Marker written after the end of the sentence it cites
+            self.add(sentence, document_ids);
+        }
+    }
+
+    fn end_sentence(&mut self) {
+        let sentence = &self.text[self.sentence_start..];
+        if !sentence.trim().is_empty() {
+            let start = self.sentence_start + (sentence.len() - sentence.trim_start().len());
+            let range = start..self.sentence_start + sentence.trim_end().len();
+
+            let document_ids = std::mem::take(&mut self.pending);
+            if !document_ids.is_empty() {
+                self.add(range.clone(), document_ids);
+            }
+            self.last_sentence = Some(range);
+        }
+        self.sentence_start = self.text.len();
+    }
+
+    fn add(&mut self, range: Range<usize>, document_ids: Vec<String>) {
+        match self.citations.last_mut() {
+            Some(citation) if citation.start == range.start && citation.end == range.end => {
+                extend_unique(&mut citation.document_ids, document_ids)
+            }
+            _ => self.citations.push(Citation {
+                start: range.start,
+                end: range.end,
+                text: self.text[range].to_string(),
+                document_ids,
+            }),
+        }
+    }
+}
+
+fn extend_unique(ids: &mut Vec<String>, new_ids: Vec<String>) {
+    for id in new_ids {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+}
+
+/// Parse the citation marker at the start of `text` (e.g.: `[doc1, doc2]`), returning the cited
+/// document ids and the length of the marker
+fn parse_marker(
+    text: &str,
+    resolve: &impl Fn(&str) -> Option<String>,
+) -> Option<(Vec<String>, usize)> {
+    let end = text.find(']')?;
+    let content = &text[1..end];
+    if content.is_empty() || content.contains(['[', '\n']) {
+        return None;
+    }
+
+    let document_ids = content
+        .split([',', ';'])
+        .map(|id| resolve(id.trim()))
+        .collect::<Option<Vec<_>>>()?;
+
+    Some((document_ids, end + 1))
+}
+
+/// Id of the document of `documents` referenced by a citation `marker` (e.g.: `doc1` or
+/// `id: doc1`)
+pub(crate) fn document_id(documents: &[Document], marker: &str) -> Option<String> {
+    let marker = marker.trim();
+    let marker = marker.strip_prefix("id:").map(str::trim).unwrap_or(marker);
+
+    documents
+        .iter()
+        .find(|document| document.id == marker)
+        .map(|document| document.id.clone())
+}
+
+/// Text of an answer, i.e.: the concatenation of its text contents
+pub(crate) fn answer_text(choice: &OneOrMany<AssistantContent>) -> String {
+    choice
+        .iter()
+        .filter_map(|content| match content {
+            AssistantContent::Text(text) => Some(text.text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Convert a character offset of `text` (as returned by some providers) into a byte offset
+pub(crate) fn byte_offset(text: &str, char_offset: usize) -> usize {
+    text.char_indices()
+        .nth(char_offset)
+        .map(|(i, _)| i)
+        .unwrap_or(text.len())
+}
+
+/// A response along with the citations of the documents supporting it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CitedResponse {
+    /// Text of the answer
+    pub text: String,
+    /// Citations of the documents supporting the answer
+    pub citations: Vec<Citation>,
+    /// Documents attached to the request (e.g.: the dynamic context of an agent)
+    pub documents: Vec<Document>,
+}
+
+impl CitedResponse {
+    /// Documents supporting `citation`
+    pub fn sources<'a>(&'a self, citation: &'a Citation) -> impl Iterator<Item = &'a Document> {
+        citation
+            .document_ids
+            .iter()
+            .filter_map(|id| self.documents.iter().find(|document| &document.id == id))
+    }
+
+    /// Documents cited at least once in the answer, in order of first citation
+    pub fn cited_documents(&self) -> Vec<&Document> {
+        let mut ids = vec![];
+        for citation in &self.citations {
+            extend_unique(&mut ids, citation.document_ids.clone());
+        }
+
+        ids.iter()
+            .filter_map(|id| self.documents.iter().find(|document| &document.id == id))
+            .collect()
+    }
+}
+
+/// Trait defining a high-level LLM prompt interface returning cited answers (i.e.: prompt in,
+/// answer and citations of the context documents out).
+pub trait Cite: Send + Sync {
+    /// Send a prompt to the underlying completion model and return its answer along with the
+    /// citations of the documents supporting it.
+    ///
+    /// If the completion model's response is a tool call, then the tool is called and its result
+    /// is returned without citations.
+    fn cite(
+        &self,
+        prompt: impl Into<Message> + Send,
+    ) -> impl std::future::Future<Output = Result<CitedResponse, PromptError>> + Send;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn documents() -> Vec<Document> {
+        ["doc1", "doc2"]
+            .into_iter()
+            .map(|id| Document {
+                id: id.to_string(),
+                text: String::new(),
+                additional_props: HashMap::new(),
+            })
+            .collect()
+    }
+
+    fn parse(text: &str) -> CitedText {
+        let documents = documents();
+        CitedText::parse(text, |marker| document_id(&documents, marker))
+    }
+
+    #[test]
+    fn test_parse_citations() {
+        let cited =
+            parse("SOL closed at $101.5 [doc1][doc2]. Volume was flat. ETH fell [id: doc2, doc1]!");
+
+        assert_eq!(
+            cited.text,
+            "SOL closed at $101.5. Volume was flat. ETH fell!"
+        );
+        assert_eq!(
+            cited.citations,
+            vec![
+                Citation {
+                    start: 0,
+                    end: 21,
+                    text: "SOL closed at $101.5.".to_string(),
+                    document_ids: vec!["doc1".to_string(), "doc2".to_string()],
+                },
+                Citation {
+                    start: 39,
+                    end: 48,
+                    text: "ETH fell!".to_string(),
+                    document_ids: vec!["doc2".to_string(), "doc1".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_citations_after_sentence() {
+        let cited = parse("SOL is up. [doc1]\nSee [the docs](https://solana.com) [doc3].");
+
+        assert_eq!(
+            cited.text,
+            "SOL is up.\nSee [the docs](https://solana.com) [doc3]."
+        );
+        assert_eq!(
+            cited.citations,
+            vec![Citation {
+                start: 0,
+                end: 10,
+                text: "SOL is up.".to_string(),
+                document_ids: vec!["doc1".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_cited_documents() {
+        let cited = parse("A [doc2]. B [doc1]. C [doc2].");
+        let response = CitedResponse {
+            text: cited.text,
+            citations: cited.citations,
+            documents: documents(),
+        };
+
+        assert_eq!(
+            response
+                .cited_documents()
+                .iter()
+                .map(|document| document.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["doc2", "doc1"]
+        );
+        assert_eq!(
+            response
+                .sources(&response.citations[1])
+                .map(|document| document.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["doc1"]
+        );
+    }
+
+    #[test]
+    fn test_byte_offset() {
+        assert_eq!(byte_offset("épargne", 1), 2);
+        assert_eq!(byte_offset("abc", 5), 3);
+    }
+}
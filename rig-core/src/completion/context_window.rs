@@ -0,0 +1,821 @@
+//! Context window management for completion requests.
+//!
+//! The preamble, context documents, tool definitions, chat history and prompt of a request
+//! all count towards the context window of the model, and providers reject requests exceeding
+//! it. A [ContextWindow] counts the tokens of a request with the model's tokenizer (see
+//! [CompletionModel::tokenizer]) and applies [TruncationPolicy]s until it fits in the model's
+//! context window (see [CompletionModel::context_window]), minus the tokens reserved for the
+//! completion.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     completion::context_window::{ContextWindow, TruncationPolicy},
+//!     providers::openai,
+//! };
+//!
+//! let openai = openai::Client::from_env();
+//!
+//! // Summarize all but the last 4 messages of the chat history, then drop the lowest
+//! // ranked context documents if the request still does not fit
+//! let agent = openai.agent(openai::GPT_4O)
+//!     .dynamic_context(10, index)
+//!     .max_tokens(1024)
+//!     .context_window(ContextWindow::new().policies(vec![
+//!         TruncationPolicy::Summarize { keep_recent: 4 },
+//!         TruncationPolicy::TrimDocuments,
+//!     ]))
+//!     .build();
+//! ```
+use std::{
+    borrow::Cow,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    citation::answer_text,
+    message::{AssistantContent, Message, ToolResultContent, UserContent},
+    CompletionError, CompletionModel, CompletionRequest,
+};
+use crate::tokenizer::{HeuristicTokenizer, Tokenizer};
+
+/// Tokens added by providers to each message (role, delimiters, etc.)
+pub const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+/// Estimated tokens of an image, audio or document attachment
+pub const MEDIA_TOKENS: usize = 1_000;
+
+/// Context windows of known models, matched by prefix of the model name (the first matching
+/// prefix wins, so more specific prefixes come first).
+const CONTEXT_WINDOWS: &[(&str, u64)] = &[
+    // OpenAI
+    ("gpt-4o", 128_000),
+    ("chatgpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4-1106", 128_000),
+    ("gpt-4-0125", 128_000),
+    ("gpt-4-32k", 32_768),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo-instruct", 4_096),
+    ("gpt-3.5-turbo", 16_385),
+    ("o1-mini", 128_000),
+    ("o1-preview", 128_000),
+    ("o1", 200_000),
+    ("o3-mini", 200_000),
+    // Anthropic
+    ("claude-3", 200_000),
+    ("claude-2.1", 200_000),
+    ("claude-2", 100_000),
+    ("claude-instant", 100_000),
+    // Cohere
+    ("command-r", 128_000),
+    ("command-light", 4_096),
+    ("command", 4_096),
+    // Gemini
+    ("gemini-1.5-pro", 2_097_152),
+    ("gemini-1.5-flash", 1_048_576),
+    ("gemini-2.0-flash", 1_048_576),
+    ("gemini-1.0-pro", 32_760),
+    // DeepSeek
+    ("deepseek-chat", 64_000),
+    ("deepseek-reasoner", 64_000),
+    // xAI
+    ("grok-2", 131_072),
+    ("grok-beta", 131_072),
+    // Perplexity
+    ("sonar", 127_072),
+    ("llama-3.1-sonar", 127_072),
+    // Moonshot
+    ("moonshot-v1-8k", 8_192),
+    ("moonshot-v1-32k", 32_768),
+    ("moonshot-v1-128k", 131_072),
+    // Llama models served by open model providers
+    ("llama-3.1", 131_072),
+    ("llama-3.2", 131_072),
+    ("llama-3.3", 131_072),
+    ("meta-llama-3.1", 131_072),
+];
+
+/// Context window (in tokens) of a model given its name, if known. Organization prefixes
+/// (e.g.: `meta-llama/`) are ignored and names are matched case-insensitively.
+pub fn model_context_window(model: &str) -> Option<u64> {
+    let model = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+
+    CONTEXT_WINDOWS
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|(_, context_window)| *context_window)
+}
+
+/// Instructions given to the model to summarize the chat history (see [TruncationPolicy::Summarize])
+pub const SUMMARIZE_PREAMBLE: &str = "\
+    Summarize the following conversation between a user and an assistant. Keep the facts, \
+    decisions and open questions needed to continue the conversation. Respond only with the \
+    summary.";
+
+/// Maximum number of tokens of a summary of the chat history (see [TruncationPolicy::Summarize]),
+/// further limited to a quarter of the context window of the model
+pub const SUMMARY_MAX_TOKENS: u64 = 1_024;
+
+/// Number of summaries cached by a [ContextWindow]
+const SUMMARY_CACHE_SIZE: usize = 16;
+
+/// Strategy used by a [ContextWindow] to shrink a request that does not fit
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TruncationPolicy {
+    /// Drop the oldest messages of the chat history until the request fits
+    DropOldestHistory,
+    /// Drop context documents from the end of the list until the request fits. Agents add the
+    /// dynamic context documents after the static ones, interleaved by rank across their
+    /// vector indexes (the best document of each index first), so the lowest ranked documents
+    /// of all the indexes are dropped first.
+    TrimDocuments,
+    /// Replace all but the `keep_recent` most recent messages of the chat history with a
+    /// summary written by the model (at the cost of extra completion calls, see
+    /// [ContextWindow::summarize])
+    Summarize { keep_recent: usize },
+}
+
+/// Context window settings, used to make completion requests fit in the model's context window.
+#[derive(Clone)]
+pub struct ContextWindow {
+    limit: Option<u64>,
+    reserved: Option<u64>,
+    policies: Vec<TruncationPolicy>,
+    /// Tokenizer counting the tokens of the request (defaults to the model's tokenizer)
+    tokenizer: Option<Arc<dyn Tokenizer>>,
+    /// Most recent summaries of chat histories, shared by the clones of the context window
+    summaries: Arc<Mutex<Vec<Summary>>>,
+}
+
+/// Summary of the first `messages` messages of a chat history
+#[derive(Clone)]
+struct Summary {
+    messages: usize,
+    /// Hash of the summarized messages
+    hash: u64,
+    text: String,
+}
+
+impl Default for ContextWindow {
+    fn default() -> Self {
+        Self {
+            limit: None,
+            reserved: None,
+            policies: vec![
+                TruncationPolicy::DropOldestHistory,
+                TruncationPolicy::TrimDocuments,
+            ],
+            tokenizer: None,
+            summaries: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+impl ContextWindow {
+    /// Create new context window settings using the model's context window, dropping the
+    /// oldest chat history then the lowest ranked documents of requests that do not fit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the context window size in tokens, overriding the one of the model
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Set the number of tokens reserved for the completion (defaults to the `max_tokens` of
+    /// the request, if any)
+    pub fn reserve(mut self, reserved: u64) -> Self {
+        self.reserved = Some(reserved);
+        self
+    }
+
+    /// Set the truncation policies, applied in order until the request fits
+    pub fn policies(mut self, policies: Vec<TruncationPolicy>) -> Self {
+        self.policies = policies;
+        self
+    }
+
+    /// Set the tokenizer used to count the tokens of requests (defaults to the tokenizer of the
+    /// model, see [CompletionModel::tokenizer])
+    pub fn tokenizer(mut self, tokenizer: impl Tokenizer + 'static) -> Self {
+        self.tokenizer = Some(Arc::new(tokenizer));
+        self
+    }
+
+    /// Number of tokens of `text`. A [HeuristicTokenizer] is used if no tokenizer was set.
+    fn count_tokens(&self, text: &str) -> usize {
+        match &self.tokenizer {
+            Some(tokenizer) => tokenizer.count_tokens(text),
+            None => HeuristicTokenizer::default().count_tokens(text),
+        }
+    }
+
+    /// Estimate the number of tokens of `message`
+    pub fn count_message_tokens(&self, message: &Message) -> usize {
+        let count = |text: &str| self.count_tokens(text);
+
+        let content = match message {
+            Message::User { content } => content
+                .iter()
+                .map(|content| match content {
+                    UserContent::Text(text) => count(&text.text),
+                    UserContent::ToolResult(result) => result
+                        .content
+                        .iter()
+                        .map(|content| match content {
+                            ToolResultContent::Text(text) => count(&text.text),
+                            ToolResultContent::Image(_) => MEDIA_TOKENS,
+                        })
+                        .sum(),
+                    UserContent::Image(_) | UserContent::Audio(_) | UserContent::Document(_) => {
+                        MEDIA_TOKENS
+                    }
+                })
+                .sum::<usize>(),
+            Message::Assistant { content } => content
+                .iter()
+                .map(|content| match content {
+                    AssistantContent::Text(text) => count(&text.text),
+                    AssistantContent::ToolCall(tool_call) => {
+                        count(&tool_call.function.name)
+                            + count(&tool_call.function.arguments.to_string())
+                    }
+                })
+                .sum::<usize>(),
+        };
+
+        content + MESSAGE_OVERHEAD_TOKENS
+    }
+
+    /// Estimate the number of tokens of `request`, excluding the tokens of the completion
+    pub fn count_request_tokens(&self, request: &CompletionRequest) -> usize {
+        let count = |text: &str| self.count_tokens(text);
+
+        let preamble = request.preamble.as_deref().map(count).unwrap_or(0);
+        let documents = request
+            .documents
+            .iter()
+            .map(|document| count(&document.to_string()))
+            .sum::<usize>();
+        let tools = request
+            .tools
+            .iter()
+            .map(|tool| {
+                count(&tool.name) + count(&tool.description) + count(&tool.parameters.to_string())
+            })
+            .sum::<usize>();
+        let messages = request
+            .chat_history
+            .iter()
+            .chain(std::iter::once(&request.prompt))
+            .map(|message| self.count_message_tokens(message))
+            .sum::<usize>();
+
+        preamble + documents + tools + messages
+    }
+
+    /// Number of tokens available for `request` in the context window of `model`, if known
+    fn budget<M: CompletionModel>(&self, model: &M, request: &CompletionRequest) -> Option<usize> {
+        let limit = self.limit.or_else(|| model.context_window())?;
+        let reserved = self.reserved.or(request.max_tokens).unwrap_or(0);
+        Some(limit.saturating_sub(reserved) as usize)
+    }
+
+    /// These settings, counting tokens with the tokenizer of `model` if no tokenizer was set
+    fn with_tokenizer_of<M: CompletionModel>(&self, model: &M) -> Cow<'_, Self> {
+        match &self.tokenizer {
+            Some(_) => Cow::Borrowed(self),
+            None => Cow::Owned(Self {
+                tokenizer: Some(model.tokenizer()),
+                ..self.clone()
+            }),
+        }
+    }
+
+    /// Apply the truncation policies to `request` until it fits in the context window of
+    /// `model`. Requests for models with an unknown context window are returned unchanged.
+    ///
+    /// Returns a [CompletionError::ContextWindowError] if the request still does not fit once
+    /// all the policies have been applied (e.g.: if the prompt alone is too long).
+    pub async fn fit<M: CompletionModel>(
+        &self,
+        model: &M,
+        mut request: CompletionRequest,
+    ) -> Result<CompletionRequest, CompletionError> {
+        let Some(budget) = self.budget(model, &request) else {
+            return Ok(request);
+        };
+
+        let window = self.with_tokenizer_of(model);
+
+        for policy in &window.policies {
+            let tokens = window.count_request_tokens(&request);
+            if tokens <= budget {
+                return Ok(request);
+            }
+
+            tracing::debug!(target: "rig",
+                "Request of {} tokens exceeds the context window budget of {} tokens, applying {:?}",
+                tokens, budget, policy
+            );
+
+            match policy {
+                TruncationPolicy::DropOldestHistory => {
+                    window.drop_oldest_history(&mut request, tokens - budget)
+                }
+                TruncationPolicy::TrimDocuments => {
+                    window.trim_documents(&mut request, tokens - budget)
+                }
+                TruncationPolicy::Summarize { keep_recent } => {
+                    request.chat_history = window
+                        .compact_history(model, &request.chat_history, *keep_recent)
+                        .await?
+                }
+            }
+        }
+
+        let tokens = window.count_request_tokens(&request);
+        if tokens > budget {
+            return Err(CompletionError::ContextWindowError { tokens, budget });
+        }
+
+        Ok(request)
+    }
+
+    /// Drop the oldest messages of the chat history until `excess` tokens have been removed.
+    /// Tool results left without their tool call are dropped too.
+    fn drop_oldest_history(&self, request: &mut CompletionRequest, excess: usize) {
+        let mut removed = 0;
+        let mut dropped = 0;
+        for message in &request.chat_history {
+            // Tool results left without their tool call are dropped too
+            if removed >= excess && !is_tool_result(message) {
+                break;
+            }
+            removed += self.count_message_tokens(message);
+            dropped += 1;
+        }
+
+        request.chat_history.drain(..dropped);
+    }
+
+    /// Drop documents from the end of the list until `excess` tokens have been removed
+    fn trim_documents(&self, request: &mut CompletionRequest, excess: usize) {
+        let mut removed = 0;
+        while removed < excess {
+            let Some(document) = request.documents.pop() else {
+                break;
+            };
+            removed += self.count_tokens(&document.to_string());
+        }
+    }
+
+    /// Replace all but the `keep_recent` most recent messages of `history` with a summary
+    /// written by `model` (see [ContextWindow::summarize]). Tool results are kept along with
+    /// their tool call, so more than `keep_recent` messages may be kept.
+    ///
+    /// This is the [TruncationPolicy::Summarize] policy. Applications storing the chat history
+    /// can also call it to replace their history with the compacted one.
+    pub async fn compact_history<M: CompletionModel>(
+        &self,
+        model: &M,
+        history: &[Message],
+        keep_recent: usize,
+    ) -> Result<Vec<Message>, CompletionError> {
+        let mut split = history.len().saturating_sub(keep_recent);
+        while split > 0 && split < history.len() && is_tool_result(&history[split]) {
+            split -= 1;
+        }
+        if split == 0 {
+            return Ok(history.to_vec());
+        }
+
+        let summary = self.summarize(model, &history[..split]).await?;
+
+        Ok(std::iter::once(summary_message(&summary))
+            .chain(history[split..].iter().cloned())
+            .collect())
+    }
+
+    /// Summarize `messages` with `model`.
+    ///
+    /// The messages are summarized incrementally, in batches fitting in the context window of
+    /// the model: each batch is summarized along with the summary of the previous ones.
+    /// Summaries are cached (and shared by the clones of these settings), so summarizing a
+    /// chat history that extends a previously summarized one only summarizes the new messages.
+    pub async fn summarize<M: CompletionModel>(
+        &self,
+        model: &M,
+        messages: &[Message],
+    ) -> Result<String, CompletionError> {
+        let window = self.with_tokenizer_of(model);
+
+        // Hashes of the prefixes of `messages`, `hashes[i]` being the hash of `messages[..=i]`
+        let mut hasher = DefaultHasher::new();
+        let hashes = messages
+            .iter()
+            .map(|message| {
+                serde_json::to_string(message)
+                    .unwrap_or_default()
+                    .hash(&mut hasher);
+                hasher.finish()
+            })
+            .collect::<Vec<_>>();
+
+        // Resume from the summary of the longest summarized prefix of `messages`, if any
+        let cached = self.summaries.lock().ok().and_then(|summaries| {
+            summaries
+                .iter()
+                .filter(|summary| hashes.get(summary.messages - 1) == Some(&summary.hash))
+                .max_by_key(|summary| summary.messages)
+                .cloned()
+        });
+        let (mut summarized, mut summary) = match cached {
+            Some(summary) => (summary.messages, Some(summary.text)),
+            None => (0, None),
+        };
+
+        // Number of tokens of the summaries, and available for the transcript in each
+        // summarization request
+        let limit = self.limit.or_else(|| model.context_window());
+        let max_tokens = limit.map_or(SUMMARY_MAX_TOKENS, |limit| {
+            SUMMARY_MAX_TOKENS.min(limit / 4)
+        });
+        let budget = limit.map(|limit| {
+            ((limit - max_tokens) as usize).saturating_sub(
+                window.count_tokens(SUMMARIZE_PREAMBLE) + 2 * MESSAGE_OVERHEAD_TOKENS,
+            )
+        });
+
+        while summarized < messages.len() {
+            let mut transcript = summary
+                .as_deref()
+                .map(summary_text)
+                .into_iter()
+                .collect::<Vec<_>>();
+            let mut tokens = transcript
+                .iter()
+                .map(|line| window.count_tokens(line))
+                .sum::<usize>();
+
+            let mut end = summarized;
+            while end < messages.len() {
+                let line = transcript_line(&messages[end]);
+                let line_tokens = window.count_tokens(&line);
+                match budget {
+                    Some(budget) if tokens + line_tokens > budget => {
+                        if end > summarized {
+                            break;
+                        }
+                        // The message alone does not fit with the summary: truncate it
+                        let remaining = budget.saturating_sub(tokens);
+                        transcript.push(truncate(&line, line_tokens, remaining));
+                    }
+                    _ => transcript.push(line),
+                }
+                tokens += line_tokens;
+                end += 1;
+            }
+
+            let response = model
+                .completion_request(transcript.join("\n"))
+                .preamble(SUMMARIZE_PREAMBLE.to_string())
+                .max_tokens(max_tokens)
+                .send()
+                .await?;
+            let text = answer_text(&response.choice);
+
+            if let Ok(mut summaries) = self.summaries.lock() {
+                if summaries.len() >= SUMMARY_CACHE_SIZE {
+                    summaries.remove(0);
+                }
+                summaries.push(Summary {
+                    messages: end,
+                    hash: hashes[end - 1],
+                    text: text.clone(),
+                });
+            }
+
+            summary = Some(text);
+            summarized = end;
+        }
+
+        Ok(summary.unwrap_or_default())
+    }
+}
+
+/// Text introducing the summary of the earlier messages of a chat history
+fn summary_text(summary: &str) -> String {
+    format!("Summary of the earlier conversation:\n{summary}")
+}
+
+/// Message replacing the summarized messages of a chat history
+fn summary_message(summary: &str) -> Message {
+    Message::user(summary_text(summary))
+}
+
+/// Whether `message` holds tool results (which must follow their tool call)
+fn is_tool_result(message: &Message) -> bool {
+    matches!(
+        message,
+        Message::User { content } if matches!(content.first(), UserContent::ToolResult(_))
+    )
+}
+
+/// Truncate `text` of `tokens` tokens to about `max_tokens` tokens, keeping its start
+fn truncate(text: &str, tokens: usize, max_tokens: usize) -> String {
+    let chars = text.chars().count() * max_tokens / tokens.max(1);
+    text.chars().take(chars).collect()
+}
+
+/// Text of `message` prefixed by its role, as shown to the model summarizing the chat history
+fn transcript_line(message: &Message) -> String {
+    match message {
+        Message::User { content } => {
+            let text = content
+                .iter()
+                .filter_map(|content| match content {
+                    UserContent::Text(text) => Some(text.text.clone()),
+                    UserContent::ToolResult(result) => Some(
+                        result
+                            .content
+                            .iter()
+                            .filter_map(|content| match content {
+                                ToolResultContent::Text(text) => Some(text.text.as_str()),
+                                ToolResultContent::Image(_) => None,
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    ),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("User: {text}")
+        }
+        Message::Assistant { content } => {
+            let text = content
+                .iter()
+                .map(|content| match content {
+                    AssistantContent::Text(text) => text.text.clone(),
+                    AssistantContent::ToolCall(tool_call) => format!(
+                        "(called {} with {})",
+                        tool_call.function.name, tool_call.function.arguments
+                    ),
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("Assistant: {text}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        completion::{CompletionResponse, Document},
+        tokenizer, OneOrMany,
+    };
+
+    /// Model with a context window of 100 tokens, answering with a fixed summary
+    #[derive(Clone)]
+    struct SmallModel;
+
+    impl CompletionModel for SmallModel {
+        type Response = ();
+
+        async fn completion(
+            &self,
+            _request: CompletionRequest,
+        ) -> Result<CompletionResponse<()>, CompletionError> {
+            Ok(CompletionResponse {
+                choice: OneOrMany::one(AssistantContent::text("they talked")),
+                raw_response: (),
+            })
+        }
+
+        fn context_window(&self) -> Option<u64> {
+            Some(100)
+        }
+    }
+
+    /// Model with a context window of 100 tokens, recording the prompts it summarizes
+    #[derive(Clone, Default)]
+    struct RecordingModel(Arc<Mutex<Vec<String>>>);
+
+    impl RecordingModel {
+        fn prompts(&self) -> Vec<String> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl CompletionModel for RecordingModel {
+        type Response = ();
+
+        async fn completion(
+            &self,
+            request: CompletionRequest,
+        ) -> Result<CompletionResponse<()>, CompletionError> {
+            let mut prompts = self.0.lock().unwrap();
+            prompts.push(request.prompt.rag_text().unwrap_or_default());
+            Ok(CompletionResponse {
+                choice: OneOrMany::one(AssistantContent::text(format!("summary{}", prompts.len()))),
+                raw_response: (),
+            })
+        }
+
+        fn context_window(&self) -> Option<u64> {
+            Some(100)
+        }
+    }
+
+    /// Window counting one token per word and no message overhead
+    fn window() -> ContextWindow {
+        ContextWindow::new().tokenizer(tokenizer::from_fn(|text: &str| {
+            text.split_whitespace().count()
+        }))
+    }
+
+    fn oversized_request(history: usize, documents: usize) -> CompletionRequest {
+        SmallModel
+            .completion_request("the prompt")
+            .messages(
+                (0..history)
+                    .map(|i| Message::user(format!("message {i} {}", "word ".repeat(8))))
+                    .collect(),
+            )
+            .documents(
+                (0..documents)
+                    .map(|i| Document {
+                        id: format!("doc{i}"),
+                        text: "word ".repeat(10),
+                        additional_props: HashMap::new(),
+                    })
+                    .collect(),
+            )
+            .build()
+    }
+
+    #[test]
+    fn test_model_context_window() {
+        assert_eq!(model_context_window("gpt-4o-mini"), Some(128_000));
+        assert_eq!(model_context_window("gpt-4"), Some(8_192));
+        assert_eq!(
+            model_context_window("meta-llama/Llama-3.3-70B-Instruct"),
+            Some(131_072)
+        );
+        assert_eq!(model_context_window("my-local-model"), None);
+    }
+
+    #[tokio::test]
+    async fn test_fitting_request_is_unchanged() {
+        let request = window()
+            .fit(&SmallModel, oversized_request(2, 2))
+            .await
+            .unwrap();
+        assert_eq!(request.chat_history.len(), 2);
+        assert_eq!(request.documents.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest_history_then_trim_documents() {
+        // 10 messages of 14 tokens, 5 documents of 14 tokens and a prompt of 6 tokens
+        let request = window()
+            .fit(&SmallModel, oversized_request(10, 5))
+            .await
+            .unwrap();
+        assert_eq!(request.chat_history.len(), 1);
+        assert_eq!(request.documents.len(), 5);
+
+        // Reserving tokens for the completion leaves less room for the documents
+        let request = window()
+            .reserve(50)
+            .fit(&SmallModel, oversized_request(10, 5))
+            .await
+            .unwrap();
+        assert!(request.chat_history.is_empty());
+        assert_eq!(request.documents.len(), 3);
+        assert_eq!(request.documents.last().unwrap().id, "doc2");
+    }
+
+    #[tokio::test]
+    async fn test_summarize() {
+        let request = window()
+            .policies(vec![TruncationPolicy::Summarize { keep_recent: 2 }])
+            .fit(&SmallModel, oversized_request(10, 0))
+            .await
+            .unwrap();
+
+        assert_eq!(request.chat_history.len(), 3);
+        assert_eq!(
+            request.chat_history[0],
+            Message::user("Summary of the earlier conversation:\nthey talked")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_summaries_are_cached() {
+        let model = RecordingModel::default();
+        let window = window();
+        let history = (0..6)
+            .map(|i| Message::user(format!("message {i}")))
+            .collect::<Vec<_>>();
+
+        let compacted = window.compact_history(&model, &history, 2).await.unwrap();
+        assert_eq!(compacted.len(), 3);
+        assert_eq!(compacted[0], summary_message("summary1"));
+        assert_eq!(
+            model.prompts(),
+            vec!["User: message 0\nUser: message 1\nUser: message 2\nUser: message 3"]
+        );
+
+        // Extending the history only summarizes the new messages, along with the summary
+        let extended = [history, vec![Message::user("message 6")]].concat();
+        let compacted = window
+            .clone()
+            .compact_history(&model, &extended, 2)
+            .await
+            .unwrap();
+        assert_eq!(compacted[0], summary_message("summary2"));
+        assert_eq!(
+            model.prompts()[1],
+            "Summary of the earlier conversation:\nsummary1\nUser: message 4"
+        );
+
+        // Summarizing the same history again uses the cached summary
+        window.compact_history(&model, &extended, 2).await.unwrap();
+        assert_eq!(model.prompts().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_summarize_in_batches() {
+        let model = RecordingModel::default();
+        // 10 messages of 14 tokens, while the transcripts are limited to 100 tokens minus 25
+        // for the summary and 35 for the preamble and message overhead
+        let history = (0..10)
+            .map(|i| Message::user(format!("message {i} {}", "word ".repeat(11))))
+            .collect::<Vec<_>>();
+
+        let summary = window().summarize(&model, &history).await.unwrap();
+
+        let prompts = model.prompts();
+        assert_eq!(summary, format!("summary{}", prompts.len()));
+        assert!(prompts.len() > 1);
+        assert!(prompts
+            .iter()
+            .all(|prompt| prompt.split_whitespace().count() <= 40));
+        assert!(prompts[1].starts_with("Summary of the earlier conversation:\nsummary1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_compact_history_keeps_tool_calls() {
+        let model = RecordingModel::default();
+        let tool_call = Message::Assistant {
+            content: OneOrMany::one(AssistantContent::tool_call(
+                "call_0",
+                "price",
+                serde_json::json!({ "token": "SOL" }),
+            )),
+        };
+        let tool_result = Message::User {
+            content: OneOrMany::one(UserContent::tool_result(
+                "call_0",
+                OneOrMany::one(ToolResultContent::text("180")),
+            )),
+        };
+        let history = vec![
+            Message::user("hello"),
+            Message::assistant("hi"),
+            Message::user("what is the price of SOL?"),
+            tool_call.clone(),
+            tool_result.clone(),
+        ];
+
+        let compacted = window().compact_history(&model, &history, 1).await.unwrap();
+        assert_eq!(
+            compacted,
+            vec![summary_message("summary1"), tool_call, tool_result]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_context_window_error() {
+        let result = window()
+            .policies(vec![TruncationPolicy::TrimDocuments])
+            .fit(&SmallModel, oversized_request(10, 0))
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(CompletionError::ContextWindowError { budget: 100, .. })
+        ));
+    }
+}
@@ -1,5 +1,9 @@
+pub mod citation;
+pub mod context_window;
 pub mod message;
 pub mod request;
 
+pub use citation::{Citation, Cite, CitedResponse, CitedText, CITATION_INSTRUCTIONS};
+pub use context_window::{ContextWindow, TruncationPolicy};
 pub use message::{AssistantContent, Message, MessageError};
 pub use request::*;
@@ -62,12 +62,14 @@
 //!
 //! For more information on how to use the completion functionality, refer to the documentation of
 //! the individual traits, structs, and enums defined in this module.
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::loaders::metadata::SOURCE_METADATA_MARKER;
 use crate::streaming::{StreamingCompletionModel, StreamingResult};
+use crate::tokenizer::{HeuristicTokenizer, Tokenizer};
 use crate::OneOrMany;
 use crate::{
     json_utils,
@@ -75,6 +77,7 @@ use crate::{
     tool::ToolSetError,
 };
 
+use super::citation::{self, CitedText};
 use super::message::AssistantContent;
 
 // Errors
@@ -99,6 +102,11 @@ pub enum CompletionError {
     /// Error returned by the completion model provider
     #[error("ProviderError: {0}")]
     ProviderError(String),
+
+    /// The request does not fit in the context window of the model, even after truncation
+    /// (see [ContextWindow](super::context_window::ContextWindow))
+    #[error("ContextWindowError: request of {tokens} tokens exceeds the context window budget of {budget} tokens")]
+    ContextWindowError { tokens: usize, budget: usize },
 }
 
 #[derive(Debug, Error)]
@@ -118,6 +126,79 @@ pub struct Document {
     pub additional_props: HashMap<String, String>,
 }
 
+impl Document {
+    /// Build a document from a JSON value returned by a vector store.
+    ///
+    /// By default, `value` is pretty printed as the text of the document, without metadata.
+    ///
+    /// Values serialized with [SourceMetadata](crate::loaders::SourceMetadata) (e.g.: a
+    /// [Chunk](crate::loaders::splitters::Chunk)) are objects with a string `text` field and the
+    /// [SOURCE_METADATA_MARKER] field. For those, the `text` field becomes the text of the
+    /// document and the other fields become its metadata: the fields of a nested `metadata`
+    /// object are inlined, `null` fields and fields starting with `_` (e.g.: MongoDB's `_id`)
+    /// are skipped, and non-string values are JSON encoded.
+    pub fn from_json(id: impl Into<String>, value: serde_json::Value) -> Self {
+        let id = id.into();
+
+        let marked = |fields: &serde_json::Map<String, serde_json::Value>| {
+            fields.get(SOURCE_METADATA_MARKER) == Some(&serde_json::Value::Bool(true))
+        };
+        let has_source_metadata = |fields: &serde_json::Map<String, serde_json::Value>| {
+            let nested_metadata = match fields.get("metadata") {
+                Some(serde_json::Value::Object(metadata)) => marked(metadata),
+                _ => false,
+            };
+            matches!(fields.get("text"), Some(serde_json::Value::String(_)))
+                && (marked(fields) || nested_metadata)
+        };
+
+        let mut fields = match value {
+            serde_json::Value::Object(fields) if has_source_metadata(&fields) => fields,
+            value => {
+                // Pretty print the document if possible for better readability
+                let text =
+                    serde_json::to_string_pretty(&value).unwrap_or_else(|_| value.to_string());
+                return Self {
+                    id,
+                    text,
+                    additional_props: HashMap::new(),
+                };
+            }
+        };
+
+        let text = match fields.remove("text") {
+            Some(serde_json::Value::String(text)) => text,
+            _ => unreachable!("text field is checked above"),
+        };
+
+        let mut additional_props = HashMap::new();
+        let mut insert = |key: String, value: serde_json::Value| match value {
+            serde_json::Value::Null => (),
+            _ if key.starts_with('_') => (),
+            serde_json::Value::String(value) => {
+                additional_props.insert(key, value);
+            }
+            value => {
+                additional_props.insert(key, value.to_string());
+            }
+        };
+        for (key, value) in fields {
+            match value {
+                serde_json::Value::Object(metadata) if key == "metadata" => metadata
+                    .into_iter()
+                    .for_each(|(key, value)| insert(key, value)),
+                value => insert(key, value),
+            }
+        }
+
+        Self {
+            id,
+            text,
+            additional_props,
+        }
+    }
+}
+
 impl std::fmt::Display for Document {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -228,6 +309,50 @@ pub trait CompletionModel: Clone + Send + Sync {
     ) -> impl std::future::Future<Output = Result<CompletionResponse<Self::Response>, CompletionError>>
            + Send;
 
+    /// Generates a completion response for the given completion request, asking the provider to
+    /// natively cite the documents of the request in its response. Only used by agents if
+    /// [CompletionModel::supports_citations] is true. Defaults to [CompletionModel::completion].
+    fn cited_completion(
+        &self,
+        request: CompletionRequest,
+    ) -> impl std::future::Future<Output = Result<CompletionResponse<Self::Response>, CompletionError>>
+           + Send {
+        self.completion(request)
+    }
+
+    /// Whether the provider natively cites the documents of completion requests sent with
+    /// [CompletionModel::cited_completion]. If it does not, agents instead ask the model to cite
+    /// the documents in its answer (see [CITATION_INSTRUCTIONS](citation::CITATION_INSTRUCTIONS)).
+    fn supports_citations(&self) -> bool {
+        false
+    }
+
+    /// Size of the model's context window in tokens, if known. Used by
+    /// [ContextWindow](super::context_window::ContextWindow) to truncate requests that would
+    /// not fit in it.
+    fn context_window(&self) -> Option<u64> {
+        None
+    }
+
+    /// Tokenizer of the model, used to count tokens offline (e.g.: to truncate requests to the
+    /// context window). Defaults to a [HeuristicTokenizer](crate::tokenizer::HeuristicTokenizer).
+    fn tokenizer(&self) -> Arc<dyn Tokenizer> {
+        Arc::new(HeuristicTokenizer::default())
+    }
+
+    /// Extract the answer of `response` along with its citations of `documents` (the documents
+    /// of the request). The default implementation parses the citation markers requested by
+    /// [CITATION_INSTRUCTIONS](citation::CITATION_INSTRUCTIONS) out of the answer.
+    fn citations(
+        &self,
+        response: &CompletionResponse<Self::Response>,
+        documents: &[Document],
+    ) -> CitedText {
+        CitedText::parse(&citation::answer_text(&response.choice), |marker| {
+            citation::document_id(documents, marker)
+        })
+    }
+
     /// Generates a completion request builder for the given `prompt`.
     fn completion_request(&self, prompt: impl Into<Message>) -> CompletionRequestBuilder<Self> {
         CompletionRequestBuilder::new(self.clone(), prompt)
@@ -235,6 +360,7 @@ pub trait CompletionModel: Clone + Send + Sync {
 }
 
 /// Struct representing a general completion request that can be sent to a completion model provider.
+#[derive(Clone, Debug)]
 pub struct CompletionRequest {
     /// The prompt to be sent to the completion model provider
     pub prompt: Message,
@@ -346,6 +472,21 @@ impl<M: CompletionModel> CompletionRequestBuilder<M> {
         }
     }
 
+    /// Create a builder from an existing completion request, e.g.: to modify it before sending it.
+    pub fn from_request(model: M, request: CompletionRequest) -> Self {
+        Self {
+            model,
+            prompt: request.prompt,
+            preamble: request.preamble,
+            chat_history: request.chat_history,
+            documents: request.documents,
+            tools: request.tools,
+            temperature: request.temperature,
+            max_tokens: request.max_tokens,
+            additional_params: request.additional_params,
+        }
+    }
+
     /// Sets the preamble for the completion request.
     pub fn preamble(mut self, preamble: String) -> Self {
         self.preamble = Some(preamble);
@@ -475,6 +616,7 @@ impl<M: StreamingCompletionModel> CompletionRequestBuilder<M> {
 
 #[cfg(test)]
 mod tests {
+    use crate::loaders::{splitters::Chunk, SourceMetadata};
     use crate::OneOrMany;
 
     use super::*;
@@ -512,6 +654,70 @@ mod tests {
         assert_eq!(format!("{}", doc), expected);
     }
 
+    #[test]
+    fn test_document_from_json_with_metadata() {
+        let doc = Document::from_json(
+            "doc0",
+            serde_json::json!({
+                "_id": "6650",
+                "text": "SOL is up.",
+                "start": 0,
+                "headings": ["Report"],
+                "metadata": {
+                    SOURCE_METADATA_MARKER: true,
+                    "path": "reports/sol.md",
+                    "page": 2,
+                    "title": null,
+                },
+            }),
+        );
+
+        let expected = concat!(
+            "<file id: doc0>\n",
+            "<metadata headings: \"[\\\"Report\\\"]\" page: \"2\" path: \"reports/sol.md\" start: \"0\" />\n",
+            "SOL is up.\n",
+            "</file>\n"
+        );
+        assert_eq!(format!("{}", doc), expected);
+    }
+
+    #[test]
+    fn test_document_from_json_with_chunk() {
+        let chunk = Chunk {
+            text: "SOL is up.".to_string(),
+            start: 0,
+            end: 10,
+            headings: vec![],
+            metadata: SourceMetadata::from("reports/sol.md").with_page(2),
+        };
+        let doc = Document::from_json("doc0", serde_json::to_value(&chunk).unwrap());
+
+        let expected = concat!(
+            "<file id: doc0>\n",
+            "<metadata end: \"10\" page: \"2\" path: \"reports/sol.md\" start: \"0\" />\n",
+            "SOL is up.\n",
+            "</file>\n"
+        );
+        assert_eq!(format!("{}", doc), expected);
+    }
+
+    #[test]
+    fn test_document_from_json_without_text() {
+        let doc = Document::from_json("doc0", serde_json::json!({"name": "SOL"}));
+
+        assert_eq!(doc.text, "{\n  \"name\": \"SOL\"\n}");
+        assert!(doc.additional_props.is_empty());
+    }
+
+    #[test]
+    fn test_document_from_json_without_marker() {
+        let value = serde_json::json!({"_id": "6650", "text": "SOL is up.", "page": 2});
+        let doc = Document::from_json("doc0", value.clone());
+
+        assert_eq!(doc.text, serde_json::to_string_pretty(&value).unwrap());
+        assert!(doc.additional_props.is_empty());
+    }
+
     #[test]
     fn test_prompt_with_context_with_documents() {
         let doc1 = Document {
@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 pub trait VectorDistance {
     /// Get dot product of two embedding vectors
     fn dot_product(&self, other: &Self) -> f64;
@@ -19,14 +21,79 @@ pub trait VectorDistance {
     fn chebyshev_distance(&self, other: &Self) -> f64;
 }
 
+/// Metric used to compare embeddings.
+///
+/// Similarities (cosine similarity and dot product) are higher for closer embeddings while
+/// distances are lower. To rank embeddings the same way with every metric, [DistanceMetric::score]
+/// converts distances to scores where higher is closer:
+/// - the angular distance `d`, in `[0, 1]`, gives `1 - d`;
+/// - the other distances `d`, in `[0, +inf)`, give `1 / (1 + d)`, in `(0, 1]`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DistanceMetric {
+    #[default]
+    Cosine,
+    DotProduct,
+    Angular,
+    Euclidean,
+    Manhattan,
+    Chebyshev,
+}
+
+impl DistanceMetric {
+    /// Whether higher values of the metric are closer (as opposed to a distance)
+    pub fn is_similarity(&self) -> bool {
+        matches!(self, Self::Cosine | Self::DotProduct)
+    }
+
+    /// Value of the metric between two embeddings.
+    /// If `normalized` is true, the embeddings are assumed to have a unit norm.
+    pub fn compute<E: VectorDistance>(&self, a: &E, b: &E, normalized: bool) -> f64 {
+        match self {
+            Self::Cosine => a.cosine_similarity(b, normalized),
+            Self::DotProduct => a.dot_product(b),
+            Self::Angular => a.angular_distance(b, normalized),
+            Self::Euclidean => a.euclidean_distance(b),
+            Self::Manhattan => a.manhattan_distance(b),
+            Self::Chebyshev => a.chebyshev_distance(b),
+        }
+    }
+
+    /// Value of the metric between two vectors (of `f32` or `f64`)
+    pub fn compute_slices<T: kernels::Float>(&self, a: &[T], b: &[T]) -> f64 {
+        let cosine = || {
+            kernels::dot(a, b) / (kernels::squared_norm(a).sqrt() * kernels::squared_norm(b).sqrt())
+        };
+
+        match self {
+            Self::Cosine => cosine(),
+            Self::DotProduct => kernels::dot(a, b),
+            Self::Angular => cosine().acos() / std::f64::consts::PI,
+            Self::Euclidean => kernels::squared_euclidean(a, b).sqrt(),
+            Self::Manhattan => kernels::manhattan(a, b),
+            Self::Chebyshev => kernels::chebyshev(a, b),
+        }
+    }
+
+    /// Convert a value of the metric to a score where higher is closer
+    pub fn score_of(&self, value: f64) -> f64 {
+        match self {
+            Self::Cosine | Self::DotProduct => value,
+            Self::Angular => 1.0 - value,
+            Self::Euclidean | Self::Manhattan | Self::Chebyshev => 1.0 / (1.0 + value),
+        }
+    }
+
+    /// Score of two embeddings, higher is closer (see [DistanceMetric::score_of])
+    pub fn score<E: VectorDistance>(&self, a: &E, b: &E, normalized: bool) -> f64 {
+        self.score_of(self.compute(a, b, normalized))
+    }
+}
+
 #[cfg(not(feature = "rayon"))]
 impl VectorDistance for crate::embeddings::Embedding {
     fn dot_product(&self, other: &Self) -> f64 {
-        self.vec
-            .iter()
-            .zip(other.vec.iter())
-            .map(|(x, y)| x * y)
-            .sum()
+        kernels::dot(&self.vec, &other.vec)
     }
 
     fn cosine_similarity(&self, other: &Self, normalized: bool) -> f64 {
@@ -35,8 +102,8 @@ impl VectorDistance for crate::embeddings::Embedding {
         if normalized {
             dot_product
         } else {
-            let magnitude1: f64 = self.vec.iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
-            let magnitude2: f64 = other.vec.iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
+            let magnitude1 = kernels::squared_norm(&self.vec).sqrt();
+            let magnitude2 = kernels::squared_norm(&other.vec).sqrt();
 
             dot_product / (magnitude1 * magnitude2)
         }
@@ -48,43 +115,39 @@ impl VectorDistance for crate::embeddings::Embedding {
     }
 
     fn euclidean_distance(&self, other: &Self) -> f64 {
-        self.vec
-            .iter()
-            .zip(other.vec.iter())
-            .map(|(x, y)| (x - y).powi(2))
-            .sum::<f64>()
-            .sqrt()
+        kernels::squared_euclidean(&self.vec, &other.vec).sqrt()
     }
 
     fn manhattan_distance(&self, other: &Self) -> f64 {
-        self.vec
-            .iter()
-            .zip(other.vec.iter())
-            .map(|(x, y)| (x - y).abs())
-            .sum()
+        kernels::manhattan(&self.vec, &other.vec)
     }
 
     fn chebyshev_distance(&self, other: &Self) -> f64 {
-        self.vec
-            .iter()
-            .zip(other.vec.iter())
-            .map(|(x, y)| (x - y).abs())
-            .fold(0.0, f64::max)
+        kernels::chebyshev(&self.vec, &other.vec)
     }
 }
 
 #[cfg(feature = "rayon")]
 mod rayon {
+    use super::kernels;
     use crate::embeddings::{distance::VectorDistance, Embedding};
     use rayon::prelude::*;
 
+    /// Number of dimensions processed by each task: vectors are split in chunks processed in
+    /// parallel, each with the vectorized kernels.
+    const CHUNK: usize = 1024;
+
+    /// Sum `kernel` over the chunks of `a` and `b`
+    fn par_sum(a: &[f64], b: &[f64], kernel: fn(&[f64], &[f64]) -> f64) -> f64 {
+        a.par_chunks(CHUNK)
+            .zip(b.par_chunks(CHUNK))
+            .map(|(a, b)| kernel(a, b))
+            .sum()
+    }
+
     impl VectorDistance for Embedding {
         fn dot_product(&self, other: &Self) -> f64 {
-            self.vec
-                .par_iter()
-                .zip(other.vec.par_iter())
-                .map(|(x, y)| x * y)
-                .sum()
+            par_sum(&self.vec, &other.vec, kernels::dot)
         }
 
         fn cosine_similarity(&self, other: &Self, normalized: bool) -> f64 {
@@ -93,8 +156,8 @@ mod rayon {
             if normalized {
                 dot_product
             } else {
-                let magnitude1: f64 = self.vec.par_iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
-                let magnitude2: f64 = other.vec.par_iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
+                let magnitude1 = par_sum(&self.vec, &self.vec, kernels::dot).sqrt();
+                let magnitude2 = par_sum(&other.vec, &other.vec, kernels::dot).sqrt();
 
                 dot_product / (magnitude1 * magnitude2)
             }
@@ -106,35 +169,144 @@ mod rayon {
         }
 
         fn euclidean_distance(&self, other: &Self) -> f64 {
-            self.vec
-                .par_iter()
-                .zip(other.vec.par_iter())
-                .map(|(x, y)| (x - y).powi(2))
-                .sum::<f64>()
-                .sqrt()
+            par_sum(&self.vec, &other.vec, kernels::squared_euclidean).sqrt()
         }
 
         fn manhattan_distance(&self, other: &Self) -> f64 {
-            self.vec
-                .par_iter()
-                .zip(other.vec.par_iter())
-                .map(|(x, y)| (x - y).abs())
-                .sum()
+            par_sum(&self.vec, &other.vec, kernels::manhattan)
         }
 
         fn chebyshev_distance(&self, other: &Self) -> f64 {
             self.vec
-                .iter()
-                .zip(other.vec.iter())
-                .map(|(x, y)| (x - y).abs())
-                .fold(0.0, f64::max)
+                .par_chunks(CHUNK)
+                .zip(other.vec.par_chunks(CHUNK))
+                .map(|(a, b)| kernels::chebyshev(a, b))
+                .reduce(|| 0.0, f64::max)
         }
     }
 }
 
+/// Distance kernels on slices of floats (`f32` or `f64`), quantized (`i8`) and binary vectors.
+///
+/// The loops accumulate in [LANES] independent accumulators instead of a single one, so that
+/// the compiler can vectorize them with SIMD instructions (floating point additions are not
+/// associative, so a single accumulator forces them to be sequential). Slices of different
+/// lengths are truncated to the shortest one.
+pub mod kernels {
+    use std::ops::{Add, Mul, Sub};
+
+    /// Number of independent accumulators of the kernels
+    pub const LANES: usize = 8;
+
+    /// Floating point element of a vector
+    pub trait Float:
+        Copy + PartialOrd + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Into<f64>
+    {
+        const ZERO: Self;
+
+        fn abs(self) -> Self;
+    }
+
+    impl Float for f32 {
+        const ZERO: Self = 0.0;
+
+        fn abs(self) -> Self {
+            f32::abs(self)
+        }
+    }
+
+    impl Float for f64 {
+        const ZERO: Self = 0.0;
+
+        fn abs(self) -> Self {
+            f64::abs(self)
+        }
+    }
+
+    /// Combine `map(a[i], b[i])` with `reduce` over `LANES` accumulators
+    #[inline(always)]
+    fn fold<T: Copy, A: Copy>(
+        a: &[T],
+        b: &[T],
+        zero: A,
+        map: impl Fn(T, T) -> A,
+        reduce: impl Fn(A, A) -> A,
+    ) -> A {
+        let len = a.len().min(b.len());
+        let (a, b) = (&a[..len], &b[..len]);
+
+        let mut lanes = [zero; LANES];
+        let (a_chunks, b_chunks) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+        let (a_rest, b_rest) = (a_chunks.remainder(), b_chunks.remainder());
+        for (a, b) in a_chunks.zip(b_chunks) {
+            for i in 0..LANES {
+                lanes[i] = reduce(lanes[i], map(a[i], b[i]));
+            }
+        }
+
+        let result = lanes.into_iter().fold(zero, &reduce);
+        a_rest
+            .iter()
+            .zip(b_rest)
+            .fold(result, |acc, (&a, &b)| reduce(acc, map(a, b)))
+    }
+
+    /// Dot product
+    pub fn dot<T: Float>(a: &[T], b: &[T]) -> f64 {
+        fold(a, b, T::ZERO, |a, b| a * b, |x, y| x + y).into()
+    }
+
+    /// Squared euclidean norm
+    pub fn squared_norm<T: Float>(a: &[T]) -> f64 {
+        dot(a, a)
+    }
+
+    /// Squared euclidean distance
+    pub fn squared_euclidean<T: Float>(a: &[T], b: &[T]) -> f64 {
+        fold(
+            a,
+            b,
+            T::ZERO,
+            |a, b| {
+                let d = a - b;
+                d * d
+            },
+            |x, y| x + y,
+        )
+        .into()
+    }
+
+    /// Manhattan distance
+    pub fn manhattan<T: Float>(a: &[T], b: &[T]) -> f64 {
+        fold(a, b, T::ZERO, |a, b| (a - b).abs(), |x, y| x + y).into()
+    }
+
+    /// Chebyshev distance
+    pub fn chebyshev<T: Float>(a: &[T], b: &[T]) -> f64 {
+        fold(
+            a,
+            b,
+            T::ZERO,
+            |a, b| (a - b).abs(),
+            |x, y| if y > x { y } else { x },
+        )
+        .into()
+    }
+
+    /// Dot product of `i8` vectors, accumulated in `i32`
+    pub fn dot_i8(a: &[i8], b: &[i8]) -> i32 {
+        fold(a, b, 0i32, |a, b| a as i32 * b as i32, |x, y| x + y)
+    }
+
+    /// Number of different bits of bit-packed vectors
+    pub fn hamming(a: &[u64], b: &[u64]) -> u32 {
+        fold(a, b, 0u32, |a, b| (a ^ b).count_ones(), |x, y| x + y)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::VectorDistance;
+    use super::{kernels, DistanceMetric, VectorDistance};
     use crate::embeddings::Embedding;
 
     fn embeddings() -> (Embedding, Embedding) {
@@ -198,4 +370,64 @@ mod tests {
 
         assert_eq!(embedding_1.chebyshev_distance(&embedding_2), 4.0)
     }
+
+    #[test]
+    fn test_metric_scores() {
+        let (embedding_1, embedding_2) = embeddings();
+        let embedding_3 = Embedding {
+            document: "test".to_string(),
+            vec: vec![1.0, 2.5, 3.5],
+        };
+
+        // Embedding 3 is closer to embedding 1 than embedding 2 for every metric (the dot
+        // product also depends on the norms of the embeddings)
+        for metric in [
+            DistanceMetric::Cosine,
+            DistanceMetric::Angular,
+            DistanceMetric::Euclidean,
+            DistanceMetric::Manhattan,
+            DistanceMetric::Chebyshev,
+        ] {
+            let (far, close) = (
+                metric.compute(&embedding_1, &embedding_2, false),
+                metric.compute(&embedding_1, &embedding_3, false),
+            );
+            assert_eq!(metric.is_similarity(), close > far, "{metric:?}");
+            assert!(
+                metric.score(&embedding_1, &embedding_3, false)
+                    > metric.score(&embedding_1, &embedding_2, false),
+                "{metric:?}"
+            );
+            assert!(
+                (metric.compute_slices(&embedding_1.vec, &embedding_2.vec) - far).abs() < 1e-12
+            );
+        }
+        assert_eq!(
+            DistanceMetric::Euclidean.score(&embedding_1, &embedding_2, false),
+            1.0 / 6.0
+        );
+    }
+
+    #[test]
+    fn test_kernels() {
+        // Longer than the lanes, with a remainder
+        let a = (0..19).map(|i| i as f64 / 4.0).collect::<Vec<_>>();
+        let b = (0..19).map(|i| 5.0 - i as f64).collect::<Vec<_>>();
+        let scalar_dot = a.iter().zip(&b).map(|(x, y)| x * y).sum::<f64>();
+
+        assert_eq!(kernels::dot(&a, &b), scalar_dot);
+        assert_eq!(
+            kernels::manhattan(&a, &b),
+            a.iter().zip(&b).map(|(x, y)| (x - y).abs()).sum::<f64>()
+        );
+        assert_eq!(kernels::chebyshev(&a, &b), 17.5);
+
+        let a32 = a.iter().map(|&x| x as f32).collect::<Vec<_>>();
+        let b32 = b.iter().map(|&x| x as f32).collect::<Vec<_>>();
+        assert_eq!(kernels::dot(&a32, &b32), scalar_dot);
+        assert_eq!(kernels::squared_norm(&a32[..2]), 0.0625);
+
+        assert_eq!(kernels::dot_i8(&[127; 20], &[-128; 20]), 20 * 127 * -128);
+        assert_eq!(kernels::hamming(&[u64::MAX, 0b1011], &[0, 0b0001]), 66);
+    }
 }
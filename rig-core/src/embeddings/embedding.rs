@@ -6,8 +6,12 @@
 //! Finally, the module defines the [EmbeddingError] enum, which represents various errors that
 //! can occur during embedding generation or processing.
 
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
 
+use crate::tokenizer::{HeuristicTokenizer, Tokenizer};
+
 #[derive(Debug, thiserror::Error)]
 pub enum EmbeddingError {
     /// Http error (e.g.: connection error, timeout, etc.)
@@ -39,6 +43,12 @@ pub trait EmbeddingModel: Clone + Sync + Send {
     /// The number of dimensions in the embedding vector.
     fn ndims(&self) -> usize;
 
+    /// Tokenizer of the model, used to count tokens offline (e.g.: to split documents to the
+    /// input limit of the model). Defaults to a [HeuristicTokenizer].
+    fn tokenizer(&self) -> Arc<dyn Tokenizer> {
+        Arc::new(HeuristicTokenizer::default())
+    }
+
     /// Embed multiple text documents in a single request
     fn embed_texts(
         &self,
@@ -55,6 +55,11 @@
 //! trait, which can be implemented to define vector stores and indices respectively.
 //! Those can then be used as the knowledge base for a RAG enabled [Agent](crate::agent::Agent), or
 //! as a source of context documents in a custom architecture that use multiple LLMs or agents.
+//! The documents retrieved from an index can be reordered by a [Reranker](crate::rerank::Reranker)
+//! (e.g.: Cohere's rerank models) before being added to the context, and the query can be rewritten
+//! by a completion model before searching the index (see [query_transform](crate::vector_store::query_transform)).
+//! Searches can be restricted to the documents matching a [Filter](crate::vector_store::filter::Filter)
+//! on their fields, which each vector store translates to its native query language.
 //!
 //! # Integrations
 //! ## Model Providers
@@ -90,8 +95,13 @@ pub mod loaders;
 pub mod one_or_many;
 pub mod pipeline;
 pub mod providers;
+pub mod rerank;
 pub mod streaming;
+pub mod telemetry;
+pub mod tokenizer;
 pub mod tool;
+#[cfg(feature = "tools")]
+pub mod tools;
 pub mod vector_store;
 
 // Re-export commonly used types and traits
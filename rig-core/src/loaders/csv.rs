@@ -0,0 +1,302 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Number, Value};
+use thiserror::Error;
+
+use super::{
+    file::{FileLoader, FileLoaderError},
+    record::{Record, TextMapping},
+};
+
+#[derive(Error, Debug)]
+pub enum CsvLoaderError {
+    #[error("{0}")]
+    FileLoaderError(#[from] FileLoaderError),
+
+    #[error("CSV error: {0}")]
+    CsvError(#[from] csv::Error),
+}
+
+#[derive(Clone, Debug)]
+struct CsvOptions {
+    delimiter: u8,
+    text_mapping: TextMapping,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            text_mapping: TextMapping::default(),
+        }
+    }
+}
+
+/// Parse a CSV value into a JSON number if it looks like one, or a JSON string otherwise.
+/// Values with leading zeros (e.g.: zip codes) are kept as strings.
+fn parse_value(value: &str) -> Value {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    if digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.") {
+        return Value::String(value.to_string());
+    }
+
+    if let Ok(integer) = value.parse::<i64>() {
+        Value::Number(integer.into())
+    } else if let Some(number) = value.parse::<f64>().ok().and_then(Number::from_f64) {
+        Value::Number(number)
+    } else {
+        Value::String(value.to_string())
+    }
+}
+
+fn load_csv(path: &Path, options: &CsvOptions) -> Vec<Result<Record, CsvLoaderError>> {
+    let mut reader = match csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .flexible(true)
+        .from_path(path)
+    {
+        Ok(reader) => reader,
+        Err(e) => return vec![Err(e.into())],
+    };
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => return vec![Err(e.into())],
+    };
+
+    reader
+        .records()
+        .enumerate()
+        .map(|(row, record)| {
+            let fields = headers
+                .iter()
+                .zip(record?.iter())
+                .map(|(header, value)| (header.to_string(), parse_value(value)))
+                .collect();
+            Ok(options.text_mapping.record(path.to_path_buf(), row, fields))
+        })
+        .collect()
+}
+
+// ================================================================
+// CsvFileLoader definitions and implementations
+// ================================================================
+
+/// [CsvFileLoader] is a utility for loading CSV files from the filesystem using glob patterns or
+///  directory paths. Every row of the files is loaded as a [Record], whose text is built from
+///  the columns selected with [CsvFileLoader::text_columns] (all columns by default) and whose
+///  fields contain every column of the row. The first row of each file must be a header.
+///
+/// # Example Usage
+///
+/// ```rust
+/// use rig::loaders::CsvFileLoader;
+///
+/// let records = CsvFileLoader::with_glob("reports/*.csv")?
+///     .text_columns(["title", "summary"])
+///     .load()
+///     .ignore_errors()
+///     .into_iter()
+///     .collect::<Vec<_>>();
+/// ```
+///
+/// Note: The [CsvFileLoader] requires the `csv` feature to be enabled in the `Cargo.toml` file.
+pub struct CsvFileLoader<'a, T> {
+    iterator: Box<dyn Iterator<Item = T> + 'a>,
+    options: CsvOptions,
+}
+
+impl<'a> CsvFileLoader<'a, Result<PathBuf, CsvLoaderError>> {
+    /// Sets the delimiter of the CSV files (defaults to `,`).
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.options.delimiter = delimiter;
+        self
+    }
+
+    /// Sets the columns used to build the text of the records. If a single column is given, the
+    ///  text is the value of that column, otherwise the text is made of `column: value` lines.
+    pub fn text_columns<S: Into<String>>(mut self, columns: impl IntoIterator<Item = S>) -> Self {
+        self.options.text_mapping =
+            TextMapping::Fields(columns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets a function building the text of the records from their fields.
+    pub fn text_with(
+        mut self,
+        f: impl Fn(&Map<String, Value>) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.options.text_mapping = TextMapping::custom(f);
+        self
+    }
+
+    /// Loads the rows of the CSV files within the iterator returned by [CsvFileLoader::with_glob]
+    ///  or [CsvFileLoader::with_dir] as [Record]s.
+    ///
+    /// # Example
+    /// Load the rows of the files in directory "files/*.csv" and print their text.
+    ///
+    /// ```rust
+    /// let records = CsvFileLoader::with_glob("files/*.csv")?.load();
+    /// for result in records {
+    ///     match result {
+    ///         Ok(record) => println!("{:?} row {}: {}", record.path, record.row, record.text),
+    ///         Err(e) => eprintln!("Error reading csv: {}", e),
+    ///     }
+    /// }
+    /// ```
+    pub fn load(self) -> CsvFileLoader<'a, Result<Record, CsvLoaderError>> {
+        let options = self.options;
+        CsvFileLoader {
+            iterator: Box::new(self.iterator.flat_map({
+                let options = options.clone();
+                move |res| match res {
+                    Ok(path) => load_csv(&path, &options),
+                    Err(e) => vec![Err(e)],
+                }
+            })),
+            options,
+        }
+    }
+}
+
+impl<'a, T: 'a> CsvFileLoader<'a, Result<T, CsvLoaderError>> {
+    /// Ignores errors in the iterator, returning only successful results. This can be used on any
+    ///  [CsvFileLoader] state of iterator whose items are results.
+    pub fn ignore_errors(self) -> CsvFileLoader<'a, T> {
+        CsvFileLoader {
+            iterator: Box::new(self.iterator.filter_map(|res| res.ok())),
+            options: self.options,
+        }
+    }
+}
+
+impl CsvFileLoader<'_, Result<PathBuf, CsvLoaderError>> {
+    /// Creates a new [CsvFileLoader] using a glob pattern to match files.
+    ///
+    /// # Example
+    /// Create a [CsvFileLoader] for all `.csv` files that match the glob "files/*.csv".
+    ///
+    /// ```rust
+    /// let loader = CsvFileLoader::with_glob("files/*.csv")?;
+    /// ```
+    pub fn with_glob(
+        pattern: &str,
+    ) -> Result<CsvFileLoader<'_, Result<PathBuf, CsvLoaderError>>, CsvLoaderError> {
+        Ok(CsvFileLoader {
+            iterator: Box::new(
+                FileLoader::with_glob(pattern)?
+                    .into_iter()
+                    .map(|res| res.map_err(CsvLoaderError::FileLoaderError)),
+            ),
+            options: CsvOptions::default(),
+        })
+    }
+
+    /// Creates a new [CsvFileLoader] on all files within a directory.
+    ///
+    /// # Example
+    /// Create a [CsvFileLoader] for all files that are in the directory "files" (ignores subdirectories).
+    ///
+    /// ```rust
+    /// let loader = CsvFileLoader::with_dir("files")?;
+    /// ```
+    pub fn with_dir(
+        directory: &str,
+    ) -> Result<CsvFileLoader<'_, Result<PathBuf, CsvLoaderError>>, CsvLoaderError> {
+        Ok(CsvFileLoader {
+            iterator: Box::new(
+                FileLoader::with_dir(directory)?
+                    .into_iter()
+                    .map(|res| res.map_err(CsvLoaderError::FileLoaderError)),
+            ),
+            options: CsvOptions::default(),
+        })
+    }
+}
+
+// ================================================================
+// CsvFileLoader iterator implementations
+// ================================================================
+
+pub struct IntoIter<'a, T> {
+    iterator: Box<dyn Iterator<Item = T> + 'a>,
+}
+
+impl<'a, T> IntoIterator for CsvFileLoader<'a, T> {
+    type Item = T;
+    type IntoIter = IntoIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            iterator: self.iterator,
+        }
+    }
+}
+
+impl<T> Iterator for IntoIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iterator.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::prelude::{FileWriteStr, PathChild};
+    use serde_json::json;
+
+    use super::{parse_value, CsvFileLoader};
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("42"), json!(42));
+        assert_eq!(parse_value("-101.5"), json!(-101.5));
+        assert_eq!(parse_value("0.5"), json!(0.5));
+        assert_eq!(parse_value("02134"), json!("02134"));
+        assert_eq!(parse_value("NaN"), json!("NaN"));
+        assert_eq!(parse_value("SOL"), json!("SOL"));
+    }
+
+    #[test]
+    fn test_csv_loader() {
+        let temp = assert_fs::TempDir::new().expect("Failed to create temp dir");
+        temp.child("prices.csv")
+            .write_str("symbol;price;summary\nSOL;101.5;Up 5%\nETH;2300;\"Down; 2%\"\n")
+            .unwrap();
+
+        let glob = temp.path().to_string_lossy().to_string() + "/*.csv";
+        let records = CsvFileLoader::with_glob(&glob)
+            .unwrap()
+            .delimiter(b';')
+            .text_columns(["symbol", "summary"])
+            .load()
+            .ignore_errors()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].row, 1);
+        assert_eq!(records[1].text, "symbol: ETH\nsummary: Down; 2%");
+        assert_eq!(records[1].fields["price"], json!(2300));
+        assert_eq!(records[0].path, temp.path().join("prices.csv"));
+    }
+
+    #[test]
+    fn test_csv_loader_custom_text() {
+        let temp = assert_fs::TempDir::new().expect("Failed to create temp dir");
+        temp.child("prices.csv")
+            .write_str("symbol,price\nSOL,101.5\n")
+            .unwrap();
+
+        let records = CsvFileLoader::with_dir(&temp.path().to_string_lossy())
+            .unwrap()
+            .text_with(|fields| format!("{} trades at ${}", fields["symbol"], fields["price"]))
+            .load()
+            .ignore_errors()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        assert_eq!(records[0].text, "\"SOL\" trades at $101.5");
+    }
+}
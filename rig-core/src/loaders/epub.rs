@@ -0,0 +1,445 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use roxmltree::{Document, ParsingOptions};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zip::ZipArchive;
+
+use super::{
+    file::{FileLoader, FileLoaderError},
+    html::HtmlDocument,
+    metadata::SourceMetadata,
+};
+use crate::embeddings::{embed::EmbedError, Embed, TextEmbedder};
+
+#[derive(Error, Debug)]
+pub enum EpubLoaderError {
+    #[error("{0}")]
+    FileLoaderError(#[from] FileLoaderError),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Zip error: {0}")]
+    ZipError(#[from] zip::result::ZipError),
+
+    #[error("XML error: {0}")]
+    XmlError(#[from] roxmltree::Error),
+
+    #[error("Invalid EPUB: {0}")]
+    InvalidEpub(String),
+}
+
+/// A chapter of an EPUB book
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chapter {
+    /// Index of the chapter in the reading order of the book (0-based)
+    pub index: usize,
+    /// Title of the chapter's document, if any
+    pub title: Option<String>,
+    /// Text of the chapter
+    pub text: String,
+}
+
+impl Embed for Chapter {
+    fn embed(&self, embedder: &mut TextEmbedder) -> Result<(), EmbedError> {
+        embedder.embed(self.text.clone());
+        Ok(())
+    }
+}
+
+fn parse_xml(xml: &str) -> Result<Document<'_>, roxmltree::Error> {
+    Document::parse_with_options(
+        xml,
+        ParsingOptions {
+            allow_dtd: true,
+            ..Default::default()
+        },
+    )
+}
+
+fn read_entry(archive: &mut ZipArchive<fs::File>, name: &str) -> Result<String, EpubLoaderError> {
+    let mut content = String::new();
+    archive.by_name(name)?.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Resolve the (URL encoded) `href` of a manifest item relative to the directory of the package
+///  document
+fn resolve_href(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or(href);
+
+    let mut bytes = vec![];
+    let mut chars = href.bytes();
+    while let Some(byte) = chars.next() {
+        let decoded = (byte == b'%')
+            .then(|| {
+                let hex = [chars.clone().next()?, chars.clone().nth(1)?];
+                u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()
+            })
+            .flatten();
+        match decoded {
+            Some(decoded) => {
+                bytes.push(decoded);
+                chars.nth(1);
+            }
+            None => bytes.push(byte),
+        }
+    }
+    let href = String::from_utf8_lossy(&bytes);
+
+    let mut parts: Vec<&str> = base.split('/').filter(|part| !part.is_empty()).collect();
+    for part in href.split('/') {
+        match part {
+            "" | "." => (),
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+/// Load the chapters of an EPUB book, in reading order. Chapters without text are skipped.
+fn load_chapters(path: &Path) -> Result<Vec<Chapter>, EpubLoaderError> {
+    let mut archive = ZipArchive::new(fs::File::open(path)?)?;
+
+    let container = read_entry(&mut archive, "META-INF/container.xml")?;
+    let package_path = parse_xml(&container)?
+        .descendants()
+        .find(|node| node.tag_name().name() == "rootfile")
+        .and_then(|node| node.attribute("full-path"))
+        .map(str::to_string)
+        .ok_or_else(|| EpubLoaderError::InvalidEpub("missing package document".to_string()))?;
+    let base = package_path
+        .rsplit_once('/')
+        .map(|(dir, _)| dir)
+        .unwrap_or_default();
+
+    let package = read_entry(&mut archive, &package_path)?;
+    let package = parse_xml(&package)?;
+    let manifest = package
+        .descendants()
+        .filter(|node| node.tag_name().name() == "item")
+        .filter_map(|node| Some((node.attribute("id")?, node.attribute("href")?)))
+        .collect::<HashMap<_, _>>();
+
+    let mut chapters = vec![];
+    for idref in package
+        .descendants()
+        .filter(|node| node.tag_name().name() == "itemref")
+        .filter_map(|node| node.attribute("idref"))
+    {
+        let href = manifest.get(idref).ok_or_else(|| {
+            EpubLoaderError::InvalidEpub(format!("missing manifest item {idref}"))
+        })?;
+        let document =
+            HtmlDocument::from_html(&read_entry(&mut archive, &resolve_href(base, href))?);
+        if !document.text.is_empty() {
+            chapters.push(Chapter {
+                index: chapters.len(),
+                title: document.title,
+                text: document.text,
+            });
+        }
+    }
+
+    Ok(chapters)
+}
+
+fn join_chapters(chapters: Vec<Chapter>) -> String {
+    chapters
+        .into_iter()
+        .map(|chapter| chapter.text)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+// ================================================================
+// EpubFileLoader definitions and implementations
+// ================================================================
+
+/// [EpubFileLoader] is a utility for loading EPUB books from the filesystem using glob patterns
+///  or directory paths. Books can be read as a whole, or split into their chapters.
+///
+/// # Example Usage
+///
+/// ```rust
+/// use rig::loaders::EpubFileLoader;
+///
+/// let books = EpubFileLoader::with_glob("books/*.epub")?
+///     .by_chapter()
+///     .ignore_errors()
+///     .into_iter();
+///
+/// for (path, chapters) in books {
+///     for chapter in chapters {
+///         println!("{:?} chapter {}: {:?}", path, chapter.index, chapter.title);
+///     }
+/// }
+/// ```
+///
+/// Note: The [EpubFileLoader] requires the `epub` feature to be enabled in the `Cargo.toml` file.
+pub struct EpubFileLoader<'a, T> {
+    iterator: Box<dyn Iterator<Item = T> + 'a>,
+}
+
+impl<'a> EpubFileLoader<'a, Result<PathBuf, EpubLoaderError>> {
+    /// Reads the text of the books within the iterator returned by [EpubFileLoader::with_glob]
+    ///  or [EpubFileLoader::with_dir]. Chapters are separated by empty lines.
+    pub fn read(self) -> EpubFileLoader<'a, Result<String, EpubLoaderError>> {
+        EpubFileLoader {
+            iterator: Box::new(
+                self.iterator
+                    .map(|res| Ok(join_chapters(load_chapters(&res?)?))),
+            ),
+        }
+    }
+
+    /// Reads the text of the books within the iterator returned by [EpubFileLoader::with_glob]
+    ///  or [EpubFileLoader::with_dir], along with their paths.
+    pub fn read_with_path(self) -> EpubFileLoader<'a, Result<(PathBuf, String), EpubLoaderError>> {
+        EpubFileLoader {
+            iterator: Box::new(self.iterator.map(|res| {
+                let path = res?;
+                let text = join_chapters(load_chapters(&path)?);
+                Ok((path, text))
+            })),
+        }
+    }
+
+    /// Loads the chapters of the books within the iterator returned by
+    ///  [EpubFileLoader::with_glob] or [EpubFileLoader::with_dir], in reading order, along
+    ///  with the paths of the books.
+    pub fn by_chapter(
+        self,
+    ) -> EpubFileLoader<'a, Result<(PathBuf, Vec<Chapter>), EpubLoaderError>> {
+        EpubFileLoader {
+            iterator: Box::new(self.iterator.map(|res| {
+                let path = res?;
+                let chapters = load_chapters(&path)?;
+                Ok((path, chapters))
+            })),
+        }
+    }
+
+    /// Reads the chapters of the books within the iterator returned by
+    ///  [EpubFileLoader::with_glob] or [EpubFileLoader::with_dir], flattened as a single
+    ///  iterator. Each chapter is returned along with its [SourceMetadata] (path and timestamps
+    ///  of its book, `chapter` index and `title` of the chapter if it has one).
+    pub fn read_chapters_with_metadata(
+        self,
+    ) -> EpubFileLoader<'a, Result<(SourceMetadata, String), EpubLoaderError>> {
+        EpubFileLoader {
+            iterator: Box::new(self.by_chapter().iterator.flat_map(|res| match res {
+                Ok((path, chapters)) => {
+                    let metadata = SourceMetadata::from_path(path);
+                    chapters
+                        .into_iter()
+                        .map(|chapter| {
+                            let metadata = metadata.clone().with_field("chapter", chapter.index);
+                            let metadata = match chapter.title {
+                                Some(title) => metadata.with_field("title", title),
+                                None => metadata,
+                            };
+                            Ok((metadata, chapter.text))
+                        })
+                        .collect::<Vec<_>>()
+                }
+                Err(e) => vec![Err(e)],
+            })),
+        }
+    }
+}
+
+impl<'a, T: 'a> EpubFileLoader<'a, Result<T, EpubLoaderError>> {
+    /// Ignores errors in the iterator, returning only successful results. This can be used on any
+    ///  [EpubFileLoader] state of iterator whose items are results.
+    pub fn ignore_errors(self) -> EpubFileLoader<'a, T> {
+        EpubFileLoader {
+            iterator: Box::new(self.iterator.filter_map(|res| res.ok())),
+        }
+    }
+}
+
+impl EpubFileLoader<'_, Result<PathBuf, EpubLoaderError>> {
+    /// Creates a new [EpubFileLoader] using a glob pattern to match files.
+    ///
+    /// # Example
+    /// Create a [EpubFileLoader] for all `.epub` files that match the glob "files/*.epub".
+    ///
+    /// ```rust
+    /// let loader = EpubFileLoader::with_glob("files/*.epub")?;
+    /// ```
+    pub fn with_glob(
+        pattern: &str,
+    ) -> Result<EpubFileLoader<'_, Result<PathBuf, EpubLoaderError>>, EpubLoaderError> {
+        Ok(EpubFileLoader {
+            iterator: Box::new(
+                FileLoader::with_glob(pattern)?
+                    .into_iter()
+                    .map(|res| res.map_err(EpubLoaderError::FileLoaderError)),
+            ),
+        })
+    }
+
+    /// Creates a new [EpubFileLoader] on all files within a directory.
+    ///
+    /// # Example
+    /// Create a [EpubFileLoader] for all files that are in the directory "files" (ignores subdirectories).
+    ///
+    /// ```rust
+    /// let loader = EpubFileLoader::with_dir("files")?;
+    /// ```
+    pub fn with_dir(
+        directory: &str,
+    ) -> Result<EpubFileLoader<'_, Result<PathBuf, EpubLoaderError>>, EpubLoaderError> {
+        Ok(EpubFileLoader {
+            iterator: Box::new(
+                FileLoader::with_dir(directory)?
+                    .into_iter()
+                    .map(|res| res.map_err(EpubLoaderError::FileLoaderError)),
+            ),
+        })
+    }
+}
+
+// ================================================================
+// EpubFileLoader iterator implementations
+// ================================================================
+
+pub struct IntoIter<'a, T> {
+    iterator: Box<dyn Iterator<Item = T> + 'a>,
+}
+
+impl<'a, T> IntoIterator for EpubFileLoader<'a, T> {
+    type Item = T;
+    type IntoIter = IntoIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            iterator: self.iterator,
+        }
+    }
+}
+
+impl<T> Iterator for IntoIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iterator.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write, path::Path};
+
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use super::{resolve_href, EpubFileLoader};
+
+    fn write_epub(path: &Path) {
+        let mut zip = ZipWriter::new(fs::File::create(path).unwrap());
+        let files = [
+            ("mimetype", "application/epub+zip"),
+            (
+                "META-INF/container.xml",
+                r#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#,
+            ),
+            (
+                "OEBPS/content.opf",
+                r#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <manifest>
+    <item id="cover" href="cover.xhtml" media-type="application/xhtml+xml"/>
+    <item id="ch2" href="text/chapter%202.xhtml" media-type="application/xhtml+xml"/>
+    <item id="ch1" href="text/chapter1.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine>
+    <itemref idref="cover"/>
+    <itemref idref="ch1"/>
+    <itemref idref="ch2"/>
+  </spine>
+</package>"#,
+            ),
+            (
+                "OEBPS/cover.xhtml",
+                "<html><body><img src=\"cover.png\"/></body></html>",
+            ),
+            (
+                "OEBPS/text/chapter1.xhtml",
+                "<html><head><title>Genesis</title></head><body><p>In the beginning.</p></body></html>",
+            ),
+            (
+                "OEBPS/text/chapter 2.xhtml",
+                "<html><head><title>Exodus</title></head><body><p>Then.</p></body></html>",
+            ),
+        ];
+        for (name, content) in files {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_resolve_href() {
+        assert_eq!(
+            resolve_href("OEBPS", "text/a%20b.xhtml#top"),
+            "OEBPS/text/a b.xhtml"
+        );
+        assert_eq!(
+            resolve_href("OEBPS/text", "../images/c.png"),
+            "OEBPS/images/c.png"
+        );
+        assert_eq!(resolve_href("", "100%.xhtml"), "100%.xhtml");
+    }
+
+    #[test]
+    fn test_epub_loader() {
+        let temp = assert_fs::TempDir::new().expect("Failed to create temp dir");
+        write_epub(&temp.path().join("book.epub"));
+
+        let glob = temp.path().to_string_lossy().to_string() + "/*.epub";
+        let books = EpubFileLoader::with_glob(&glob)
+            .unwrap()
+            .by_chapter()
+            .ignore_errors()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        assert_eq!(books.len(), 1);
+        let (path, chapters) = &books[0];
+        assert_eq!(path, &temp.path().join("book.epub"));
+        assert_eq!(
+            chapters
+                .iter()
+                .map(|c| (c.index, c.title.as_deref(), c.text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, Some("Genesis"), "In the beginning."),
+                (1, Some("Exodus"), "Then.")
+            ]
+        );
+
+        let text = EpubFileLoader::with_glob(&glob)
+            .unwrap()
+            .read()
+            .ignore_errors()
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(text, vec!["In the beginning.\n\nThen.".to_string()]);
+    }
+}
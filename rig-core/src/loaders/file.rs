@@ -3,6 +3,11 @@ use std::{fs, path::PathBuf};
 use glob::glob;
 use thiserror::Error;
 
+use super::{
+    metadata::SourceMetadata,
+    splitters::{Chunk, TextSplitter},
+};
+
 #[derive(Error, Debug)]
 pub enum FileLoaderError {
     #[error("Invalid glob pattern: {0}")]
@@ -140,6 +145,28 @@ impl<'a> FileLoader<'a, Result<PathBuf, FileLoaderError>> {
             iterator: Box::new(self.iterator.map(|res| res.read_with_path())),
         }
     }
+
+    /// Reads the contents of the files within the iterator returned by [FileLoader::with_glob] or
+    ///  [FileLoader::with_dir] and returns the [SourceMetadata] of each file (path and timestamps)
+    ///  along with its content.
+    ///
+    /// # Example
+    /// ```rust
+    /// let chunks = FileLoader::with_glob("files/*.txt")?
+    ///     .read_with_metadata()
+    ///     .ignore_errors()
+    ///     .split(RecursiveCharacterSplitter::new(1000));
+    /// ```
+    pub fn read_with_metadata(
+        self,
+    ) -> FileLoader<'a, Result<(SourceMetadata, String), FileLoaderError>> {
+        FileLoader {
+            iterator: Box::new(self.iterator.map(|res| {
+                let (path, content) = res.read_with_path()?;
+                Ok((SourceMetadata::from_path(path), content))
+            })),
+        }
+    }
 }
 
 impl<'a, T: 'a> FileLoader<'a, Result<T, FileLoaderError>> {
@@ -162,6 +189,65 @@ impl<'a, T: 'a> FileLoader<'a, Result<T, FileLoaderError>> {
     }
 }
 
+impl<'a, S: Into<SourceMetadata> + 'a> FileLoader<'a, Result<(S, String), FileLoaderError>> {
+    /// Splits the contents of the files read with [FileLoader::read_with_path] or
+    ///  [FileLoader::read_with_metadata] into chunks using `splitter`. The metadata of each chunk
+    ///  is set to the metadata (or path) of its file, along with the index of the chunk.
+    ///
+    /// # Example
+    /// Read files in directory "files/*.txt" and split them into chunks of 1000 characters.
+    ///
+    /// ```rust
+    /// let chunks = FileLoader::with_glob("files/*.txt")?
+    ///     .read_with_path()
+    ///     .split(RecursiveCharacterSplitter::new(1000));
+    /// for result in chunks {
+    ///     match result {
+    ///         Ok(chunk) => println!("{:?} {}", chunk.metadata.path, chunk.text),
+    ///         Err(e) => eprintln!("Error reading file: {}", e),
+    ///     }
+    /// }
+    /// ```
+    pub fn split(
+        self,
+        splitter: impl TextSplitter + 'a,
+    ) -> FileLoader<'a, Result<Chunk, FileLoaderError>> {
+        FileLoader {
+            iterator: Box::new(self.iterator.flat_map(move |res| {
+                match res {
+                    Ok((source, content)) => splitter
+                        .split_with_metadata(&source.into(), &content)
+                        .into_iter()
+                        .map(Ok)
+                        .collect::<Vec<_>>(),
+                    Err(e) => vec![Err(e)],
+                }
+            })),
+        }
+    }
+}
+
+impl<'a, S: Into<SourceMetadata> + 'a> FileLoader<'a, (S, String)> {
+    /// Splits the contents of the files read with [FileLoader::read_with_path] into chunks using
+    ///  `splitter`, after errors were ignored. The metadata of each chunk is set to the metadata
+    ///  (or path) of its file, along with the index of the chunk.
+    ///
+    /// # Example
+    /// ```rust
+    /// let chunks = FileLoader::with_glob("files/*.txt")?
+    ///     .read_with_path()
+    ///     .ignore_errors()
+    ///     .split(RecursiveCharacterSplitter::new(1000));
+    /// ```
+    pub fn split(self, splitter: impl TextSplitter + 'a) -> FileLoader<'a, Chunk> {
+        FileLoader {
+            iterator: Box::new(self.iterator.flat_map(move |(source, content)| {
+                splitter.split_with_metadata(&source.into(), &content)
+            })),
+        }
+    }
+}
+
 impl FileLoader<'_, Result<PathBuf, FileLoaderError>> {
     /// Creates a new [FileLoader] using a glob pattern to match files.
     ///
@@ -240,6 +326,7 @@ mod tests {
     use assert_fs::prelude::{FileTouch, FileWriteStr, PathChild};
 
     use super::FileLoader;
+    use crate::loaders::splitters::RecursiveCharacterSplitter;
 
     #[test]
     fn test_file_loader() {
@@ -270,4 +357,60 @@ mod tests {
         assert!(!actual.is_empty());
         assert!(expected == actual)
     }
+
+    #[test]
+    fn test_file_loader_split() {
+        let temp = assert_fs::TempDir::new().expect("Failed to create temp dir");
+        let foo_file = temp.child("foo.txt");
+        foo_file
+            .write_str("foo bar baz")
+            .expect("Failed to write to foo");
+
+        let glob = temp.path().to_string_lossy().to_string() + "/*.txt";
+
+        let chunks = FileLoader::with_glob(&glob)
+            .unwrap()
+            .read_with_path()
+            .split(RecursiveCharacterSplitter::new(7))
+            .ignore_errors()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            chunks.iter().map(|c| c.text.as_str()).collect::<Vec<_>>(),
+            vec!["foo", "bar baz"]
+        );
+        assert_eq!(chunks[1].start, 4);
+        assert_eq!(
+            chunks[0].metadata.path.as_deref(),
+            Some(foo_file.path().to_string_lossy().as_ref())
+        );
+        assert_eq!(chunks[1].metadata.chunk, Some(1));
+    }
+
+    #[test]
+    fn test_file_loader_read_with_metadata() {
+        let temp = assert_fs::TempDir::new().expect("Failed to create temp dir");
+        let foo_file = temp.child("foo.txt");
+        foo_file
+            .write_str("foo bar baz")
+            .expect("Failed to write to foo");
+
+        let glob = temp.path().to_string_lossy().to_string() + "/*.txt";
+
+        let documents = FileLoader::with_glob(&glob)
+            .unwrap()
+            .read_with_metadata()
+            .ignore_errors()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        let (metadata, content) = &documents[0];
+        assert_eq!(content, "foo bar baz");
+        assert_eq!(
+            metadata.path.as_deref(),
+            Some(foo_file.path().to_string_lossy().as_ref())
+        );
+        assert!(metadata.modified.is_some());
+    }
 }
@@ -0,0 +1,413 @@
+use std::{fs, path::PathBuf};
+
+use scraper::{ElementRef, Html, Node, Selector};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{
+    file::{FileLoader, FileLoaderError},
+    metadata::SourceMetadata,
+};
+use crate::embeddings::{embed::EmbedError, Embed, TextEmbedder};
+
+#[derive(Error, Debug)]
+pub enum HtmlLoaderError {
+    #[error("{0}")]
+    FileLoaderError(#[from] FileLoaderError),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Elements whose content is never part of the text of a page
+const IGNORED_ELEMENTS: [&str; 12] = [
+    "head", "script", "style", "noscript", "template", "iframe", "svg", "canvas", "form", "button",
+    "select", "object",
+];
+
+/// Elements considered as boilerplate (navigation, headers, footers, etc.) when the page has no
+/// `<main>` or `<article>` element
+const BOILERPLATE_ELEMENTS: [&str; 4] = ["nav", "header", "footer", "aside"];
+
+/// Elements separating paragraphs of text
+const BLOCK_ELEMENTS: [&str; 31] = [
+    "address",
+    "article",
+    "blockquote",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+
+/// Text content of an HTML page, stripped of its markup and boilerplate
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HtmlDocument {
+    /// Content of the `<title>` element of the page, if any
+    pub title: Option<String>,
+    /// Text of the page. Paragraphs are separated by empty lines.
+    pub text: String,
+}
+
+impl HtmlDocument {
+    /// Extract the text of an HTML page. If the page has a `<main>` or `<article>` element, only
+    ///  its content is kept. Otherwise, the content of the `<body>` element is kept, minus the
+    ///  navigation, header, footer and aside elements.
+    /// Scripts, styles, forms and hidden elements are always stripped.
+    pub fn from_html(html: &str) -> Self {
+        let html = Html::parse_document(html);
+
+        let title = select_first(&html, "title")
+            .map(|title| collapse_whitespace(&title.text().collect::<String>()))
+            .filter(|title| !title.is_empty());
+
+        let (root, strip_boilerplate) = match select_first(&html, "main")
+            .or_else(|| select_first(&html, "article"))
+            .or_else(|| select_first(&html, "[role=main]"))
+        {
+            Some(main) => (main, false),
+            None => (
+                select_first(&html, "body").unwrap_or_else(|| html.root_element()),
+                true,
+            ),
+        };
+
+        let mut text = String::new();
+        extract_text(root, strip_boilerplate, &mut text);
+
+        Self {
+            title,
+            text: clean_lines(&text),
+        }
+    }
+}
+
+impl Embed for HtmlDocument {
+    fn embed(&self, embedder: &mut TextEmbedder) -> Result<(), EmbedError> {
+        embedder.embed(self.text.clone());
+        Ok(())
+    }
+}
+
+fn select_first<'a>(html: &'a Html, selector: &str) -> Option<ElementRef<'a>> {
+    let selector = Selector::parse(selector).expect("Selector should be valid");
+    html.select(&selector).next()
+}
+
+fn extract_text(element: ElementRef, strip_boilerplate: bool, out: &mut String) {
+    let name = element.value().name();
+    if IGNORED_ELEMENTS.contains(&name)
+        || (strip_boilerplate && BOILERPLATE_ELEMENTS.contains(&name))
+        || element.value().attr("hidden").is_some()
+        || element.value().attr("aria-hidden") == Some("true")
+    {
+        return;
+    }
+    if name == "br" {
+        out.push('\n');
+        return;
+    }
+
+    let is_block = BLOCK_ELEMENTS.contains(&name);
+    if is_block {
+        out.push_str("\n\n");
+    }
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => out.push_str(&collapse_whitespace(text)),
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    extract_text(child, strip_boilerplate, out);
+                }
+            }
+            _ => (),
+        }
+    }
+    if is_block {
+        out.push_str("\n\n");
+    }
+}
+
+/// Replace runs of whitespace with a single space, keeping leading and trailing spaces so that
+///  words of consecutive inline elements stay separated
+fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut previous_whitespace = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !previous_whitespace {
+                collapsed.push(' ');
+            }
+            previous_whitespace = true;
+        } else {
+            collapsed.push(c);
+            previous_whitespace = false;
+        }
+    }
+    collapsed
+}
+
+/// Trim lines and collapse consecutive empty lines into a single one
+fn clean_lines(text: &str) -> String {
+    let mut lines: Vec<&str> = vec![];
+    for line in text.lines().map(str::trim) {
+        if !line.is_empty() || lines.last().is_some_and(|last| !last.is_empty()) {
+            lines.push(line);
+        }
+    }
+    lines.join("\n").trim().to_string()
+}
+
+// ================================================================
+// HtmlFileLoader definitions and implementations
+// ================================================================
+
+/// [HtmlFileLoader] is a utility for loading HTML files from the filesystem using glob patterns
+///  or directory paths, and extracting their text stripped of markup and boilerplate (see
+///  [HtmlDocument::from_html]).
+///
+/// # Example Usage
+///
+/// ```rust
+/// use rig::loaders::HtmlFileLoader;
+///
+/// let pages = HtmlFileLoader::with_glob("reports/*.html")?
+///     .read_with_path()
+///     .ignore_errors()
+///     .into_iter()
+///     .collect::<Vec<_>>();
+/// ```
+///
+/// Note: The [HtmlFileLoader] requires the `html` feature to be enabled in the `Cargo.toml` file.
+pub struct HtmlFileLoader<'a, T> {
+    iterator: Box<dyn Iterator<Item = T> + 'a>,
+}
+
+impl<'a> HtmlFileLoader<'a, Result<PathBuf, HtmlLoaderError>> {
+    /// Loads the HTML files within the iterator returned by [HtmlFileLoader::with_glob] or
+    ///  [HtmlFileLoader::with_dir] as [HtmlDocument]s.
+    pub fn load(self) -> HtmlFileLoader<'a, Result<HtmlDocument, HtmlLoaderError>> {
+        HtmlFileLoader {
+            iterator: Box::new(
+                self.iterator
+                    .map(|res| Ok(HtmlDocument::from_html(&fs::read_to_string(res?)?))),
+            ),
+        }
+    }
+
+    /// Loads the HTML files within the iterator returned by [HtmlFileLoader::with_glob] or
+    ///  [HtmlFileLoader::with_dir] as [HtmlDocument]s, along with their paths.
+    pub fn load_with_path(
+        self,
+    ) -> HtmlFileLoader<'a, Result<(PathBuf, HtmlDocument), HtmlLoaderError>> {
+        HtmlFileLoader {
+            iterator: Box::new(self.iterator.map(|res| {
+                let path = res?;
+                let document = HtmlDocument::from_html(&fs::read_to_string(&path)?);
+                Ok((path, document))
+            })),
+        }
+    }
+
+    /// Reads the text of the HTML files within the iterator returned by
+    ///  [HtmlFileLoader::with_glob] or [HtmlFileLoader::with_dir].
+    pub fn read(self) -> HtmlFileLoader<'a, Result<String, HtmlLoaderError>> {
+        HtmlFileLoader {
+            iterator: Box::new(self.load().iterator.map(|res| res.map(|doc| doc.text))),
+        }
+    }
+
+    /// Reads the text of the HTML files within the iterator returned by
+    ///  [HtmlFileLoader::with_glob] or [HtmlFileLoader::with_dir], along with their paths.
+    pub fn read_with_path(self) -> HtmlFileLoader<'a, Result<(PathBuf, String), HtmlLoaderError>> {
+        HtmlFileLoader {
+            iterator: Box::new(
+                self.load_with_path()
+                    .iterator
+                    .map(|res| res.map(|(path, doc)| (path, doc.text))),
+            ),
+        }
+    }
+
+    /// Reads the text of the HTML files within the iterator returned by
+    ///  [HtmlFileLoader::with_glob] or [HtmlFileLoader::with_dir], along with their
+    ///  [SourceMetadata] (path, timestamps and `title` of the page if it has one).
+    pub fn read_with_metadata(
+        self,
+    ) -> HtmlFileLoader<'a, Result<(SourceMetadata, String), HtmlLoaderError>> {
+        HtmlFileLoader {
+            iterator: Box::new(self.load_with_path().iterator.map(|res| {
+                let (path, doc) = res?;
+                let metadata = SourceMetadata::from_path(path);
+                let metadata = match doc.title {
+                    Some(title) => metadata.with_field("title", title),
+                    None => metadata,
+                };
+                Ok((metadata, doc.text))
+            })),
+        }
+    }
+}
+
+impl<'a, T: 'a> HtmlFileLoader<'a, Result<T, HtmlLoaderError>> {
+    /// Ignores errors in the iterator, returning only successful results. This can be used on any
+    ///  [HtmlFileLoader] state of iterator whose items are results.
+    pub fn ignore_errors(self) -> HtmlFileLoader<'a, T> {
+        HtmlFileLoader {
+            iterator: Box::new(self.iterator.filter_map(|res| res.ok())),
+        }
+    }
+}
+
+impl HtmlFileLoader<'_, Result<PathBuf, HtmlLoaderError>> {
+    /// Creates a new [HtmlFileLoader] using a glob pattern to match files.
+    ///
+    /// # Example
+    /// Create a [HtmlFileLoader] for all `.html` files that match the glob "files/*.html".
+    ///
+    /// ```rust
+    /// let loader = HtmlFileLoader::with_glob("files/*.html")?;
+    /// ```
+    pub fn with_glob(
+        pattern: &str,
+    ) -> Result<HtmlFileLoader<'_, Result<PathBuf, HtmlLoaderError>>, HtmlLoaderError> {
+        Ok(HtmlFileLoader {
+            iterator: Box::new(
+                FileLoader::with_glob(pattern)?
+                    .into_iter()
+                    .map(|res| res.map_err(HtmlLoaderError::FileLoaderError)),
+            ),
+        })
+    }
+
+    /// Creates a new [HtmlFileLoader] on all files within a directory.
+    ///
+    /// # Example
+    /// Create a [HtmlFileLoader] for all files that are in the directory "files" (ignores subdirectories).
+    ///
+    /// ```rust
+    /// let loader = HtmlFileLoader::with_dir("files")?;
+    /// ```
+    pub fn with_dir(
+        directory: &str,
+    ) -> Result<HtmlFileLoader<'_, Result<PathBuf, HtmlLoaderError>>, HtmlLoaderError> {
+        Ok(HtmlFileLoader {
+            iterator: Box::new(
+                FileLoader::with_dir(directory)?
+                    .into_iter()
+                    .map(|res| res.map_err(HtmlLoaderError::FileLoaderError)),
+            ),
+        })
+    }
+}
+
+// ================================================================
+// HtmlFileLoader iterator implementations
+// ================================================================
+
+pub struct IntoIter<'a, T> {
+    iterator: Box<dyn Iterator<Item = T> + 'a>,
+}
+
+impl<'a, T> IntoIterator for HtmlFileLoader<'a, T> {
+    type Item = T;
+    type IntoIter = IntoIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            iterator: self.iterator,
+        }
+    }
+}
+
+impl<T> Iterator for IntoIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iterator.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::prelude::{FileWriteStr, PathChild};
+
+    use super::{HtmlDocument, HtmlFileLoader};
+
+    const PAGE: &str = r#"<html>
+<head><title>SOL  weekly report</title><style>p { color: red; }</style></head>
+<body>
+  <nav><a href="/">Home</a> <a href="/reports">Reports</a></nav>
+  <h1>Weekly <em>report</em></h1>
+  <p>SOL closed the week
+     at <b>$101.5</b>.<br>Volume was up.</p>
+  <script>track();</script>
+  <div hidden>Hidden text</div>
+  <ul><li>DEX volume: $1.2B</li><li>Fees: $3M</li></ul>
+  <footer>Copyright 2024</footer>
+</body>
+</html>"#;
+
+    #[test]
+    fn test_html_to_text() {
+        let document = HtmlDocument::from_html(PAGE);
+
+        assert_eq!(document.title.as_deref(), Some("SOL weekly report"));
+        assert_eq!(
+            document.text,
+            "Weekly report\n\nSOL closed the week at $101.5.\nVolume was up.\n\nDEX volume: $1.2B\n\nFees: $3M"
+        );
+    }
+
+    #[test]
+    fn test_html_main_content() {
+        let document = HtmlDocument::from_html(
+            "<body><header>Site</header><main><header><h1>Title</h1></header><p>Text</p></main></body>",
+        );
+
+        assert_eq!(document.title, None);
+        assert_eq!(document.text, "Title\n\nText");
+    }
+
+    #[test]
+    fn test_html_loader() {
+        let temp = assert_fs::TempDir::new().expect("Failed to create temp dir");
+        temp.child("report.html").write_str(PAGE).unwrap();
+
+        let glob = temp.path().to_string_lossy().to_string() + "/*.html";
+        let pages = HtmlFileLoader::with_glob(&glob)
+            .unwrap()
+            .read_with_path()
+            .ignore_errors()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].0, temp.path().join("report.html"));
+        assert!(pages[0].1.starts_with("Weekly report"));
+    }
+}
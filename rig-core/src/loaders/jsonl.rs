@@ -0,0 +1,242 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use super::{
+    file::{FileLoader, FileLoaderError},
+    record::{Record, TextMapping},
+};
+
+#[derive(Error, Debug)]
+pub enum JsonlLoaderError {
+    #[error("{0}")]
+    FileLoaderError(#[from] FileLoaderError),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Invalid JSON in {0:?} at line {1}: {2}")]
+    JsonError(PathBuf, usize, serde_json::Error),
+}
+
+fn load_jsonl(path: &Path, text_mapping: &TextMapping) -> Vec<Result<Record, JsonlLoaderError>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => return vec![Err(e.into())],
+    };
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(row, line)| {
+            let fields = match serde_json::from_str(line) {
+                Ok(Value::Object(fields)) => fields.into_iter().collect(),
+                Ok(value) => vec![("value".to_string(), value)],
+                Err(e) => return Err(JsonlLoaderError::JsonError(path.to_path_buf(), row + 1, e)),
+            };
+            Ok(text_mapping.record(path.to_path_buf(), row, fields))
+        })
+        .collect()
+}
+
+// ================================================================
+// JsonlFileLoader definitions and implementations
+// ================================================================
+
+/// [JsonlFileLoader] is a utility for loading JSON Lines files from the filesystem using glob
+///  patterns or directory paths. Every line of the files is loaded as a [Record], whose text is
+///  built from the fields selected with [JsonlFileLoader::text_fields] (all fields by default)
+///  and whose fields contain every top-level field of the line. Lines that are not JSON objects
+///  are loaded as a record with a single `value` field, and empty lines are skipped.
+///
+/// # Example Usage
+///
+/// ```rust
+/// use rig::loaders::JsonlFileLoader;
+///
+/// let records = JsonlFileLoader::with_glob("reports/*.jsonl")?
+///     .text_fields(["body"])
+///     .load()
+///     .ignore_errors()
+///     .into_iter()
+///     .collect::<Vec<_>>();
+/// ```
+pub struct JsonlFileLoader<'a, T> {
+    iterator: Box<dyn Iterator<Item = T> + 'a>,
+    text_mapping: TextMapping,
+}
+
+impl<'a> JsonlFileLoader<'a, Result<PathBuf, JsonlLoaderError>> {
+    /// Sets the fields used to build the text of the records. If a single field is given, the
+    ///  text is the value of that field, otherwise the text is made of `field: value` lines.
+    pub fn text_fields<S: Into<String>>(mut self, fields: impl IntoIterator<Item = S>) -> Self {
+        self.text_mapping = TextMapping::Fields(fields.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets a function building the text of the records from their fields.
+    pub fn text_with(
+        mut self,
+        f: impl Fn(&Map<String, Value>) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.text_mapping = TextMapping::custom(f);
+        self
+    }
+
+    /// Loads the lines of the JSON Lines files within the iterator returned by
+    ///  [JsonlFileLoader::with_glob] or [JsonlFileLoader::with_dir] as [Record]s.
+    ///
+    /// # Example
+    /// Load the lines of the files in directory "files/*.jsonl" and print their text.
+    ///
+    /// ```rust
+    /// let records = JsonlFileLoader::with_glob("files/*.jsonl")?.load();
+    /// for result in records {
+    ///     match result {
+    ///         Ok(record) => println!("{:?} line {}: {}", record.path, record.row, record.text),
+    ///         Err(e) => eprintln!("Error reading jsonl: {}", e),
+    ///     }
+    /// }
+    /// ```
+    pub fn load(self) -> JsonlFileLoader<'a, Result<Record, JsonlLoaderError>> {
+        let text_mapping = self.text_mapping;
+        JsonlFileLoader {
+            iterator: Box::new(self.iterator.flat_map({
+                let text_mapping = text_mapping.clone();
+                move |res| match res {
+                    Ok(path) => load_jsonl(&path, &text_mapping),
+                    Err(e) => vec![Err(e)],
+                }
+            })),
+            text_mapping,
+        }
+    }
+}
+
+impl<'a, T: 'a> JsonlFileLoader<'a, Result<T, JsonlLoaderError>> {
+    /// Ignores errors in the iterator, returning only successful results. This can be used on any
+    ///  [JsonlFileLoader] state of iterator whose items are results.
+    pub fn ignore_errors(self) -> JsonlFileLoader<'a, T> {
+        JsonlFileLoader {
+            iterator: Box::new(self.iterator.filter_map(|res| res.ok())),
+            text_mapping: self.text_mapping,
+        }
+    }
+}
+
+impl JsonlFileLoader<'_, Result<PathBuf, JsonlLoaderError>> {
+    /// Creates a new [JsonlFileLoader] using a glob pattern to match files.
+    ///
+    /// # Example
+    /// Create a [JsonlFileLoader] for all `.jsonl` files that match the glob "files/*.jsonl".
+    ///
+    /// ```rust
+    /// let loader = JsonlFileLoader::with_glob("files/*.jsonl")?;
+    /// ```
+    pub fn with_glob(
+        pattern: &str,
+    ) -> Result<JsonlFileLoader<'_, Result<PathBuf, JsonlLoaderError>>, JsonlLoaderError> {
+        Ok(JsonlFileLoader {
+            iterator: Box::new(
+                FileLoader::with_glob(pattern)?
+                    .into_iter()
+                    .map(|res| res.map_err(JsonlLoaderError::FileLoaderError)),
+            ),
+            text_mapping: TextMapping::default(),
+        })
+    }
+
+    /// Creates a new [JsonlFileLoader] on all files within a directory.
+    ///
+    /// # Example
+    /// Create a [JsonlFileLoader] for all files that are in the directory "files" (ignores subdirectories).
+    ///
+    /// ```rust
+    /// let loader = JsonlFileLoader::with_dir("files")?;
+    /// ```
+    pub fn with_dir(
+        directory: &str,
+    ) -> Result<JsonlFileLoader<'_, Result<PathBuf, JsonlLoaderError>>, JsonlLoaderError> {
+        Ok(JsonlFileLoader {
+            iterator: Box::new(
+                FileLoader::with_dir(directory)?
+                    .into_iter()
+                    .map(|res| res.map_err(JsonlLoaderError::FileLoaderError)),
+            ),
+            text_mapping: TextMapping::default(),
+        })
+    }
+}
+
+// ================================================================
+// JsonlFileLoader iterator implementations
+// ================================================================
+
+pub struct IntoIter<'a, T> {
+    iterator: Box<dyn Iterator<Item = T> + 'a>,
+}
+
+impl<'a, T> IntoIterator for JsonlFileLoader<'a, T> {
+    type Item = T;
+    type IntoIter = IntoIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            iterator: self.iterator,
+        }
+    }
+}
+
+impl<T> Iterator for IntoIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iterator.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::prelude::{FileWriteStr, PathChild};
+    use serde_json::json;
+
+    use super::{JsonlFileLoader, JsonlLoaderError};
+
+    #[test]
+    fn test_jsonl_loader() {
+        let temp = assert_fs::TempDir::new().expect("Failed to create temp dir");
+        temp.child("reports.jsonl")
+            .write_str(concat!(
+                "{\"title\": \"SOL report\", \"body\": \"SOL is up\", \"score\": 0.9}\n",
+                "\n",
+                "{invalid\n",
+                "\"just a string\"\n",
+            ))
+            .unwrap();
+
+        let glob = temp.path().to_string_lossy().to_string() + "/*.jsonl";
+        let results = JsonlFileLoader::with_glob(&glob)
+            .unwrap()
+            .text_fields(["body"])
+            .load()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        assert_eq!(results.len(), 3);
+        let record = results[0].as_ref().unwrap();
+        assert_eq!(record.text, "SOL is up");
+        assert_eq!(record.fields["score"], json!(0.9));
+        assert!(matches!(
+            results[1],
+            Err(JsonlLoaderError::JsonError(_, 3, _))
+        ));
+        let record = results[2].as_ref().unwrap();
+        assert_eq!(record.row, 3);
+        assert_eq!(record.fields["value"], json!("just a string"));
+    }
+}
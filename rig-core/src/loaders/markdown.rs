@@ -0,0 +1,325 @@
+use std::{fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use super::{
+    file::{FileLoader, FileLoaderError},
+    metadata::SourceMetadata,
+};
+use crate::embeddings::{embed::EmbedError, Embed, TextEmbedder};
+
+#[derive(Error, Debug)]
+pub enum MarkdownLoaderError {
+    #[error("{0}")]
+    FileLoaderError(#[from] FileLoaderError),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Front matter error: {0}")]
+    FrontMatterError(#[from] serde_yaml::Error),
+}
+
+/// A Markdown document, split into its YAML front matter and its content
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarkdownDocument {
+    /// Fields of the YAML front matter of the document (empty if the document has none)
+    pub front_matter: Map<String, Value>,
+    /// Content of the document, without its front matter
+    pub content: String,
+}
+
+impl MarkdownDocument {
+    /// Parse a Markdown document, extracting its YAML front matter (delimited by `---` lines at the
+    ///  very start of the document) if it has one.
+    pub fn parse(markdown: &str) -> Result<Self, MarkdownLoaderError> {
+        let markdown = markdown.strip_prefix('\u{feff}').unwrap_or(markdown);
+
+        let Some((front_matter, content)) = split_front_matter(markdown) else {
+            return Ok(Self {
+                front_matter: Map::new(),
+                content: markdown.to_string(),
+            });
+        };
+
+        let front_matter = match serde_yaml::from_str::<Value>(front_matter)? {
+            Value::Object(fields) => fields,
+            Value::Null => Map::new(),
+            value => Map::from_iter([("front_matter".to_string(), value)]),
+        };
+
+        Ok(Self {
+            front_matter,
+            content: content.to_string(),
+        })
+    }
+}
+
+/// Split `markdown` into its front matter and its content
+fn split_front_matter(markdown: &str) -> Option<(&str, &str)> {
+    let rest = markdown
+        .strip_prefix("---\r\n")
+        .or_else(|| markdown.strip_prefix("---\n"))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if matches!(line.trim_end(), "---" | "...") {
+            let content = &rest[offset + line.len()..];
+            return Some((&rest[..offset], content));
+        }
+        offset += line.len();
+    }
+
+    None
+}
+
+impl Embed for MarkdownDocument {
+    fn embed(&self, embedder: &mut TextEmbedder) -> Result<(), EmbedError> {
+        embedder.embed(self.content.clone());
+        Ok(())
+    }
+}
+
+// ================================================================
+// MarkdownFileLoader definitions and implementations
+// ================================================================
+
+/// [MarkdownFileLoader] is a utility for loading Markdown files from the filesystem using glob
+///  patterns or directory paths, extracting their front matter.
+///
+/// # Example Usage
+///
+/// ```rust
+/// use rig::loaders::MarkdownFileLoader;
+///
+/// let documents = MarkdownFileLoader::with_glob("notes/*.md")?
+///     .load_with_path()
+///     .ignore_errors()
+///     .into_iter()
+///     .collect::<Vec<_>>();
+///
+/// for (path, document) in documents {
+///     println!("{:?} {:?}", path, document.front_matter.get("title"));
+/// }
+/// ```
+///
+/// Note: The [MarkdownFileLoader] requires the `markdown` feature to be enabled in the `Cargo.toml` file.
+pub struct MarkdownFileLoader<'a, T> {
+    iterator: Box<dyn Iterator<Item = T> + 'a>,
+}
+
+impl<'a> MarkdownFileLoader<'a, Result<PathBuf, MarkdownLoaderError>> {
+    /// Loads the Markdown files within the iterator returned by [MarkdownFileLoader::with_glob]
+    ///  or [MarkdownFileLoader::with_dir] as [MarkdownDocument]s.
+    pub fn load(self) -> MarkdownFileLoader<'a, Result<MarkdownDocument, MarkdownLoaderError>> {
+        MarkdownFileLoader {
+            iterator: Box::new(
+                self.iterator
+                    .map(|res| MarkdownDocument::parse(&fs::read_to_string(res?)?)),
+            ),
+        }
+    }
+
+    /// Loads the Markdown files within the iterator returned by [MarkdownFileLoader::with_glob]
+    ///  or [MarkdownFileLoader::with_dir] as [MarkdownDocument]s, along with their paths.
+    pub fn load_with_path(
+        self,
+    ) -> MarkdownFileLoader<'a, Result<(PathBuf, MarkdownDocument), MarkdownLoaderError>> {
+        MarkdownFileLoader {
+            iterator: Box::new(self.iterator.map(|res| {
+                let path = res?;
+                let document = MarkdownDocument::parse(&fs::read_to_string(&path)?)?;
+                Ok((path, document))
+            })),
+        }
+    }
+
+    /// Reads the content (without front matter) of the Markdown files within the iterator
+    ///  returned by [MarkdownFileLoader::with_glob] or [MarkdownFileLoader::with_dir].
+    pub fn read(self) -> MarkdownFileLoader<'a, Result<String, MarkdownLoaderError>> {
+        MarkdownFileLoader {
+            iterator: Box::new(self.load().iterator.map(|res| res.map(|doc| doc.content))),
+        }
+    }
+
+    /// Reads the content (without front matter) of the Markdown files within the iterator
+    ///  returned by [MarkdownFileLoader::with_glob] or [MarkdownFileLoader::with_dir], along
+    ///  with their paths.
+    pub fn read_with_path(
+        self,
+    ) -> MarkdownFileLoader<'a, Result<(PathBuf, String), MarkdownLoaderError>> {
+        MarkdownFileLoader {
+            iterator: Box::new(
+                self.load_with_path()
+                    .iterator
+                    .map(|res| res.map(|(path, doc)| (path, doc.content))),
+            ),
+        }
+    }
+
+    /// Reads the content (without front matter) of the Markdown files within the iterator
+    ///  returned by [MarkdownFileLoader::with_glob] or [MarkdownFileLoader::with_dir], along
+    ///  with their [SourceMetadata] (path, timestamps and fields of the front matter).
+    pub fn read_with_metadata(
+        self,
+    ) -> MarkdownFileLoader<'a, Result<(SourceMetadata, String), MarkdownLoaderError>> {
+        MarkdownFileLoader {
+            iterator: Box::new(self.load_with_path().iterator.map(|res| {
+                let (path, doc) = res?;
+                let metadata = doc
+                    .front_matter
+                    .into_iter()
+                    .fold(SourceMetadata::from_path(path), |metadata, (key, value)| {
+                        metadata.with_field(key, value)
+                    });
+                Ok((metadata, doc.content))
+            })),
+        }
+    }
+}
+
+impl<'a, T: 'a> MarkdownFileLoader<'a, Result<T, MarkdownLoaderError>> {
+    /// Ignores errors in the iterator, returning only successful results. This can be used on any
+    ///  [MarkdownFileLoader] state of iterator whose items are results.
+    pub fn ignore_errors(self) -> MarkdownFileLoader<'a, T> {
+        MarkdownFileLoader {
+            iterator: Box::new(self.iterator.filter_map(|res| res.ok())),
+        }
+    }
+}
+
+impl MarkdownFileLoader<'_, Result<PathBuf, MarkdownLoaderError>> {
+    /// Creates a new [MarkdownFileLoader] using a glob pattern to match files.
+    ///
+    /// # Example
+    /// Create a [MarkdownFileLoader] for all `.md` files that match the glob "files/*.md".
+    ///
+    /// ```rust
+    /// let loader = MarkdownFileLoader::with_glob("files/*.md")?;
+    /// ```
+    pub fn with_glob(
+        pattern: &str,
+    ) -> Result<MarkdownFileLoader<'_, Result<PathBuf, MarkdownLoaderError>>, MarkdownLoaderError>
+    {
+        Ok(MarkdownFileLoader {
+            iterator: Box::new(
+                FileLoader::with_glob(pattern)?
+                    .into_iter()
+                    .map(|res| res.map_err(MarkdownLoaderError::FileLoaderError)),
+            ),
+        })
+    }
+
+    /// Creates a new [MarkdownFileLoader] on all files within a directory.
+    ///
+    /// # Example
+    /// Create a [MarkdownFileLoader] for all files that are in the directory "files" (ignores subdirectories).
+    ///
+    /// ```rust
+    /// let loader = MarkdownFileLoader::with_dir("files")?;
+    /// ```
+    pub fn with_dir(
+        directory: &str,
+    ) -> Result<MarkdownFileLoader<'_, Result<PathBuf, MarkdownLoaderError>>, MarkdownLoaderError>
+    {
+        Ok(MarkdownFileLoader {
+            iterator: Box::new(
+                FileLoader::with_dir(directory)?
+                    .into_iter()
+                    .map(|res| res.map_err(MarkdownLoaderError::FileLoaderError)),
+            ),
+        })
+    }
+}
+
+// ================================================================
+// MarkdownFileLoader iterator implementations
+// ================================================================
+
+pub struct IntoIter<'a, T> {
+    iterator: Box<dyn Iterator<Item = T> + 'a>,
+}
+
+impl<'a, T> IntoIterator for MarkdownFileLoader<'a, T> {
+    type Item = T;
+    type IntoIter = IntoIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            iterator: self.iterator,
+        }
+    }
+}
+
+impl<T> Iterator for IntoIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iterator.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::prelude::{FileWriteStr, PathChild};
+    use serde_json::json;
+
+    use super::{MarkdownDocument, MarkdownFileLoader};
+
+    #[test]
+    fn test_front_matter() {
+        let document = MarkdownDocument::parse(
+            "---\ntitle: Weekly report\ntags: [sol, defi]\n---\n# Report\n\nSOL is up.\n",
+        )
+        .unwrap();
+
+        assert_eq!(document.front_matter["title"], json!("Weekly report"));
+        assert_eq!(document.front_matter["tags"], json!(["sol", "defi"]));
+        assert_eq!(document.content, "# Report\n\nSOL is up.\n");
+    }
+
+    #[test]
+    fn test_without_front_matter() {
+        let document = MarkdownDocument::parse("# Report\n\n---\n\nSOL is up.\n").unwrap();
+
+        assert!(document.front_matter.is_empty());
+        assert_eq!(document.content, "# Report\n\n---\n\nSOL is up.\n");
+    }
+
+    #[test]
+    fn test_markdown_loader() {
+        let temp = assert_fs::TempDir::new().expect("Failed to create temp dir");
+        temp.child("good.md")
+            .write_str("---\ndate: 2024-03-01\n---\nGood")
+            .unwrap();
+        temp.child("bad.md")
+            .write_str("---\n: [invalid\n---\nBad")
+            .unwrap();
+
+        let results = MarkdownFileLoader::with_dir(&temp.path().to_string_lossy())
+            .unwrap()
+            .load()
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results.iter().filter(|res| res.is_err()).count(), 1);
+
+        let documents = results.into_iter().flatten().collect::<Vec<_>>();
+        assert_eq!(documents[0].front_matter["date"], json!("2024-03-01"));
+        assert_eq!(documents[0].content, "Good");
+
+        let documents =
+            MarkdownFileLoader::with_glob(&format!("{}/good.md", temp.path().display()))
+                .unwrap()
+                .read_with_metadata()
+                .ignore_errors()
+                .into_iter()
+                .collect::<Vec<_>>();
+        let (metadata, content) = &documents[0];
+        assert!(metadata.path.as_deref().unwrap().ends_with("good.md"));
+        assert_eq!(metadata.extra["date"], json!("2024-03-01"));
+        assert_eq!(content, "Good");
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{ser::SerializeMap, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+/// Field set to `true` in serialized [SourceMetadata]. Agents only render the fields of their
+/// context documents as metadata if the documents carry this marker (see
+/// [Document::from_json](crate::completion::Document::from_json)).
+pub const SOURCE_METADATA_MARKER: &str = "_source_metadata";
+
+/// Metadata describing where a loaded text comes from (file, page, chunk, ...).
+///
+/// Loaders attach it to the texts they produce (e.g.: [FileLoader::read_with_metadata](super::FileLoader::read_with_metadata))
+/// and [Chunk](super::splitters::Chunk)s carry it along. When chunks are stored in a vector
+/// store, the metadata is returned with them and rendered by agents as the `<metadata>` of
+/// their context documents (see [Document::from_json](crate::completion::Document::from_json)),
+/// so that answers can cite their sources.
+///
+/// Fields are flattened when serialized and unset fields are omitted, so the metadata can be
+/// stored as plain properties by backends that do not support nested objects (e.g.: Neo4j).
+/// The serialized metadata also contains the [SOURCE_METADATA_MARKER] field, so that types
+/// flattening a [SourceMetadata] next to a `text` field (e.g.: [Chunk](super::splitters::Chunk))
+/// are rendered with their metadata by agents.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct SourceMetadata {
+    /// Path of the file the text was loaded from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Page of the text in its file (1-based)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<usize>,
+    /// Index of the chunk in the text it was split from (0-based)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk: Option<usize>,
+    /// Last modification time of the file, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+    /// Creation time of the file, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<u64>,
+    /// Loader specific metadata (e.g.: the title of an HTML page)
+    #[serde(flatten, deserialize_with = "deserialize_extra")]
+    pub extra: Map<String, Value>,
+}
+
+impl Serialize for SourceMetadata {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry(SOURCE_METADATA_MARKER, &true)?;
+        if let Some(path) = &self.path {
+            map.serialize_entry("path", path)?;
+        }
+        if let Some(page) = &self.page {
+            map.serialize_entry("page", page)?;
+        }
+        if let Some(chunk) = &self.chunk {
+            map.serialize_entry("chunk", chunk)?;
+        }
+        if let Some(modified) = &self.modified {
+            map.serialize_entry("modified", modified)?;
+        }
+        if let Some(created) = &self.created {
+            map.serialize_entry("created", created)?;
+        }
+        for (key, value) in &self.extra {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+/// Deserialize the loader specific metadata, without the [SOURCE_METADATA_MARKER]
+fn deserialize_extra<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Map<String, Value>, D::Error> {
+    let mut extra = Map::deserialize(deserializer)?;
+    extra.remove(SOURCE_METADATA_MARKER);
+    Ok(extra)
+}
+
+impl SourceMetadata {
+    /// Metadata of the file at `path`, including its timestamps when the filesystem provides them
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let metadata = fs::metadata(path).ok();
+        let timestamp = |time: std::io::Result<SystemTime>| {
+            time.ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs())
+        };
+
+        Self {
+            path: Some(path.to_string_lossy().to_string()),
+            modified: metadata.as_ref().and_then(|m| timestamp(m.modified())),
+            created: metadata.as_ref().and_then(|m| timestamp(m.created())),
+            ..Default::default()
+        }
+    }
+
+    /// Set the page of the text
+    pub fn with_page(mut self, page: usize) -> Self {
+        self.page = Some(page);
+        self
+    }
+
+    /// Set the chunk index of the text
+    pub fn with_chunk(mut self, chunk: usize) -> Self {
+        self.chunk = Some(chunk);
+        self
+    }
+
+    /// Add a loader specific field
+    pub fn with_field(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.extra.insert(key.into(), value.into());
+        self
+    }
+}
+
+impl From<PathBuf> for SourceMetadata {
+    fn from(path: PathBuf) -> Self {
+        Self {
+            path: Some(path.to_string_lossy().to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&Path> for SourceMetadata {
+    fn from(path: &Path) -> Self {
+        path.to_path_buf().into()
+    }
+}
+
+impl From<&str> for SourceMetadata {
+    fn from(path: &str) -> Self {
+        Self {
+            path: Some(path.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::prelude::{FileWriteStr, PathChild};
+    use serde_json::json;
+
+    use super::{SourceMetadata, SOURCE_METADATA_MARKER};
+
+    #[test]
+    fn test_from_path() {
+        let temp = assert_fs::TempDir::new().expect("Failed to create temp dir");
+        let file = temp.child("a.txt");
+        file.write_str("Hello").unwrap();
+
+        let metadata = SourceMetadata::from_path(file.path());
+        assert_eq!(
+            metadata.path,
+            Some(file.path().to_string_lossy().to_string())
+        );
+        assert!(metadata.modified.is_some());
+    }
+
+    #[test]
+    fn test_serialization_is_flat() {
+        let metadata = SourceMetadata::from("docs/a.pdf")
+            .with_page(3)
+            .with_chunk(0)
+            .with_field("title", "Report");
+
+        assert_eq!(
+            serde_json::to_value(&metadata).unwrap(),
+            json!({
+                SOURCE_METADATA_MARKER: true,
+                "path": "docs/a.pdf",
+                "page": 3,
+                "chunk": 0,
+                "title": "Report",
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<SourceMetadata>(serde_json::to_value(&metadata).unwrap())
+                .unwrap(),
+            metadata
+        );
+    }
+}
@@ -8,14 +8,70 @@
 //! files. This loader also provides PDF-specific preprocessing methods for splitting the PDF into pages
 //! and keeping track of the page numbers along with their contents.
 //!
+//! The [splitters] module provides text splitters to split the loaded files into chunks small enough
+//! to be embedded, e.g.: using [FileLoader::split] after [FileLoader::read_with_path].
+//!
+//! Loaders can also return the [SourceMetadata] of their texts (path, page, timestamps, ...), e.g.:
+//! using [FileLoader::read_with_metadata]. Splitters keep it on every [Chunk](splitters::Chunk)
+//! along with the index of the chunk, so that it is stored in vector stores with the chunks and
+//! rendered by agents as the metadata of their context documents, allowing answers to cite sources.
+//!
+//! The [HtmlFileLoader], [MarkdownFileLoader], [CsvFileLoader], [JsonlFileLoader] and
+//! [EpubFileLoader] work the same way for other document formats: HTML pages are stripped of their
+//! markup and boilerplate, Markdown documents have their front matter extracted, CSV rows and JSON
+//! Lines are loaded as one [Record] per row, and EPUB books can be split into their chapters.
+//!
 //! Note: The [PdfFileLoader] requires the `pdf` feature to be enabled in the `Cargo.toml` file.
+//! Similarly, the [HtmlFileLoader], [MarkdownFileLoader], [CsvFileLoader], [JsonlFileLoader] and
+//! [EpubFileLoader] require the `html`, `markdown`, `csv`, `jsonl` and `epub` features respectively.
 
 pub mod file;
 
 pub use file::FileLoader;
 
+pub mod metadata;
+pub mod splitters;
+
+pub use metadata::SourceMetadata;
+
+#[cfg(any(feature = "csv", feature = "jsonl"))]
+pub mod record;
+
+#[cfg(any(feature = "csv", feature = "jsonl"))]
+pub use record::Record;
+
 #[cfg(feature = "pdf")]
 pub mod pdf;
 
 #[cfg(feature = "pdf")]
 pub use pdf::PdfFileLoader;
+
+#[cfg(feature = "html")]
+pub mod html;
+
+#[cfg(feature = "html")]
+pub use html::HtmlFileLoader;
+
+#[cfg(feature = "markdown")]
+pub mod markdown;
+
+#[cfg(feature = "markdown")]
+pub use markdown::MarkdownFileLoader;
+
+#[cfg(feature = "csv")]
+pub mod csv;
+
+#[cfg(feature = "csv")]
+pub use self::csv::CsvFileLoader;
+
+#[cfg(feature = "jsonl")]
+pub mod jsonl;
+
+#[cfg(feature = "jsonl")]
+pub use jsonl::JsonlFileLoader;
+
+#[cfg(feature = "epub")]
+pub mod epub;
+
+#[cfg(feature = "epub")]
+pub use epub::EpubFileLoader;
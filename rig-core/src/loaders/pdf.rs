@@ -4,7 +4,11 @@ use glob::glob;
 use lopdf::{Document, Error as LopdfError};
 use thiserror::Error;
 
-use super::file::FileLoaderError;
+use super::{
+    file::FileLoaderError,
+    metadata::SourceMetadata,
+    splitters::{Chunk, TextSplitter},
+};
 
 #[derive(Error, Debug)]
 pub enum PdfLoaderError {
@@ -208,6 +212,41 @@ impl<'a> PdfFileLoader<'a, Result<PathBuf, PdfLoaderError>> {
             })),
         }
     }
+
+    /// Reads the pages of the pdfs within the iterator returned by [PdfFileLoader::with_glob] or
+    ///  [PdfFileLoader::with_dir], flattened as a single iterator. Each page is returned along
+    ///  with its [SourceMetadata] (path and timestamps of its pdf, and page number starting at 1).
+    ///
+    /// # Example
+    /// ```rust
+    /// let chunks = PdfFileLoader::with_glob("tests/data/*.pdf")?
+    ///     .read_pages_with_metadata()
+    ///     .ignore_errors()
+    ///     .split(RecursiveCharacterSplitter::new(1000));
+    /// ```
+    pub fn read_pages_with_metadata(
+        self,
+    ) -> PdfFileLoader<'a, Result<(SourceMetadata, String), PdfLoaderError>> {
+        PdfFileLoader {
+            iterator: Box::new(self.iterator.flat_map(|res| {
+                let (path, doc) = match res.load_with_path() {
+                    Ok(loaded) => loaded,
+                    Err(e) => return vec![Err(e)],
+                };
+                let metadata = SourceMetadata::from_path(&path);
+
+                doc.page_iter()
+                    .enumerate()
+                    .map(|(page_no, _)| {
+                        let content = doc
+                            .extract_text(&[page_no as u32 + 1])
+                            .map_err(PdfLoaderError::PdfError)?;
+                        Ok((metadata.clone().with_page(page_no + 1), content))
+                    })
+                    .collect::<Vec<_>>()
+            })),
+        }
+    }
 }
 
 impl<'a> PdfFileLoader<'a, Document> {
@@ -335,6 +374,53 @@ impl<'a, T: 'a> PdfFileLoader<'a, Result<T, PdfLoaderError>> {
     }
 }
 
+impl<'a, S: Into<SourceMetadata> + 'a> PdfFileLoader<'a, Result<(S, String), PdfLoaderError>> {
+    /// Splits the contents of the pdfs read with [PdfFileLoader::read_with_path] into chunks
+    ///  or [PdfFileLoader::read_pages_with_metadata] using `splitter`. The metadata of each chunk
+    ///  is set to the metadata (or path) of its pdf or page, along with the index of the chunk.
+    ///
+    /// # Example
+    /// Read pdfs in directory "tests/data/*.pdf" and split them into chunks of 1000 characters.
+    ///
+    /// ```rust
+    /// let chunks = PdfFileLoader::with_glob("tests/data/*.pdf")?
+    ///     .read_with_path()
+    ///     .split(RecursiveCharacterSplitter::new(1000))
+    ///     .ignore_errors();
+    /// ```
+    pub fn split(
+        self,
+        splitter: impl TextSplitter + 'a,
+    ) -> PdfFileLoader<'a, Result<Chunk, PdfLoaderError>> {
+        PdfFileLoader {
+            iterator: Box::new(self.iterator.flat_map(move |res| {
+                match res {
+                    Ok((source, content)) => splitter
+                        .split_with_metadata(&source.into(), &content)
+                        .into_iter()
+                        .map(Ok)
+                        .collect::<Vec<_>>(),
+                    Err(e) => vec![Err(e)],
+                }
+            })),
+        }
+    }
+}
+
+impl<'a, S: Into<SourceMetadata> + 'a> PdfFileLoader<'a, (S, String)> {
+    /// Splits the contents of the pdfs read with [PdfFileLoader::read_with_path] into chunks
+    ///  or [PdfFileLoader::read_pages_with_metadata] using `splitter`, after errors were ignored.
+    ///  The metadata of each chunk is set to the metadata (or path) of its pdf or page, along with
+    ///  the index of the chunk.
+    pub fn split(self, splitter: impl TextSplitter + 'a) -> PdfFileLoader<'a, Chunk> {
+        PdfFileLoader {
+            iterator: Box::new(self.iterator.flat_map(move |(source, content)| {
+                splitter.split_with_metadata(&source.into(), &content)
+            })),
+        }
+    }
+}
+
 impl PdfFileLoader<'_, Result<PathBuf, FileLoaderError>> {
     /// Creates a new [PdfFileLoader] using a glob pattern to match files.
     ///
@@ -453,4 +539,37 @@ mod tests {
         assert!(!actual.is_empty());
         assert!(expected == actual)
     }
+
+    #[test]
+    fn test_pdf_loader_read_pages_with_metadata() {
+        let mut pages = PdfFileLoader::with_glob("tests/data/pages.pdf")
+            .unwrap()
+            .read_pages_with_metadata()
+            .ignore_errors()
+            .into_iter()
+            .map(|(metadata, content)| (metadata.path, metadata.page, content))
+            .collect::<Vec<_>>();
+        pages.sort();
+
+        assert_eq!(
+            pages,
+            vec![
+                (
+                    Some("tests/data/pages.pdf".to_string()),
+                    Some(1),
+                    "Page\n1\n".to_string()
+                ),
+                (
+                    Some("tests/data/pages.pdf".to_string()),
+                    Some(2),
+                    "Page\n2\n".to_string()
+                ),
+                (
+                    Some("tests/data/pages.pdf".to_string()),
+                    Some(3),
+                    "Page\n3\n".to_string()
+                ),
+            ]
+        );
+    }
 }
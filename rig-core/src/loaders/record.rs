@@ -0,0 +1,99 @@
+use std::{fmt, path::PathBuf, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::embeddings::{embed::EmbedError, Embed, TextEmbedder};
+
+/// A document loaded from a row of a CSV file or a line of a JSON Lines file.
+/// Only the [Record::text] field is embedded.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    /// Path of the file the record was loaded from
+    pub path: PathBuf,
+    /// Index of the record in the file (0-based, excluding the CSV header)
+    pub row: usize,
+    /// Text of the record, built from its fields (see the `text_columns` and `text_fields`
+    ///  methods of the CSV and JSON Lines loaders)
+    pub text: String,
+    /// All the fields of the record
+    pub fields: Map<String, Value>,
+}
+
+impl Embed for Record {
+    fn embed(&self, embedder: &mut TextEmbedder) -> Result<(), EmbedError> {
+        embedder.embed(self.text.clone());
+        Ok(())
+    }
+}
+
+type TextFn = Arc<dyn Fn(&Map<String, Value>) -> String + Send + Sync>;
+
+/// Mapping from the fields of a record to its text
+#[derive(Clone, Default)]
+pub(crate) enum TextMapping {
+    /// `name: value` lines for every field
+    #[default]
+    AllFields,
+    /// The value of the field if there is a single one, `name: value` lines otherwise
+    Fields(Vec<String>),
+    Custom(TextFn),
+}
+
+impl fmt::Debug for TextMapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AllFields => write!(f, "AllFields"),
+            Self::Fields(fields) => f.debug_tuple("Fields").field(fields).finish(),
+            Self::Custom(_) => write!(f, "Custom(<fn>)"),
+        }
+    }
+}
+
+impl TextMapping {
+    pub fn custom(f: impl Fn(&Map<String, Value>) -> String + Send + Sync + 'static) -> Self {
+        Self::Custom(Arc::new(f))
+    }
+
+    /// Build a record from its fields, in the order they appear in the source file
+    pub fn record(&self, path: PathBuf, row: usize, fields: Vec<(String, Value)>) -> Record {
+        let text = match self {
+            Self::AllFields => fields
+                .iter()
+                .map(|(name, value)| format!("{name}: {}", value_to_text(value)))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Self::Fields(names) if names.len() == 1 => fields
+                .iter()
+                .find(|(name, _)| *name == names[0])
+                .map(|(_, value)| value_to_text(value))
+                .unwrap_or_default(),
+            Self::Fields(names) => names
+                .iter()
+                .filter_map(|name| {
+                    fields
+                        .iter()
+                        .find(|(field, _)| field == name)
+                        .map(|(_, value)| format!("{name}: {}", value_to_text(value)))
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Self::Custom(f) => f(&fields.iter().cloned().collect()),
+        };
+
+        Record {
+            path,
+            row,
+            text,
+            fields: fields.into_iter().collect(),
+        }
+    }
+}
+
+fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,100 @@
+//! Recursive character text splitter.
+use super::{to_chunks, Chunk, Chunker, TextSplitter, DEFAULT_SEPARATORS};
+
+/// Splitter recursively splitting texts on a list of separators (by default: paragraphs, lines,
+/// words and characters) until chunks are smaller than the chunk size.
+/// Chunk sizes and overlaps are measured in characters.
+///
+/// # Example
+/// ```rust
+/// use rig::loaders::splitters::{RecursiveCharacterSplitter, TextSplitter};
+///
+/// let splitter = RecursiveCharacterSplitter::new(500).chunk_overlap(50);
+/// let chunks = splitter.split("A very long text...");
+/// ```
+#[derive(Clone, Debug)]
+pub struct RecursiveCharacterSplitter {
+    chunk_size: usize,
+    chunk_overlap: usize,
+    separators: Vec<String>,
+}
+
+impl RecursiveCharacterSplitter {
+    /// Create a new splitter producing chunks of at most `chunk_size` characters
+    pub fn new(chunk_size: usize) -> Self {
+        Self {
+            chunk_size: chunk_size.max(1),
+            chunk_overlap: 0,
+            separators: DEFAULT_SEPARATORS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Set the maximum number of characters shared by consecutive chunks
+    pub fn chunk_overlap(mut self, chunk_overlap: usize) -> Self {
+        self.chunk_overlap = chunk_overlap;
+        self
+    }
+
+    /// Set the separators to split texts on, from the coarsest to the finest.
+    /// Texts are split into characters when none of the separators can be used.
+    pub fn separators<S: Into<String>>(mut self, separators: impl IntoIterator<Item = S>) -> Self {
+        self.separators = separators.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+impl TextSplitter for RecursiveCharacterSplitter {
+    fn split(&self, text: &str) -> Vec<Chunk> {
+        let separators = self
+            .separators
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let chunker = Chunker {
+            chunk_size: self.chunk_size,
+            chunk_overlap: self.chunk_overlap,
+            length: &|text: &str| text.chars().count(),
+        };
+
+        to_chunks(text, chunker.split(text, 0..text.len(), &separators), &[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_paragraphs() {
+        let text = "First paragraph.\n\nSecond paragraph, which is a bit longer.\n\nThird.";
+        let chunks = RecursiveCharacterSplitter::new(30).split(text);
+
+        assert_eq!(
+            chunks.iter().map(|c| c.text.as_str()).collect::<Vec<_>>(),
+            vec![
+                "First paragraph.",
+                "Second paragraph, which is a",
+                "bit longer.",
+                "Third."
+            ]
+        );
+        for chunk in &chunks {
+            assert!(chunk.text.chars().count() <= 30);
+            assert_eq!(&text[chunk.start..chunk.end], chunk.text);
+        }
+    }
+
+    #[test]
+    fn test_split_multibyte_characters() {
+        let text = "ééééé ààààà";
+        let chunks = RecursiveCharacterSplitter::new(3)
+            .separators(["|"])
+            .split(text);
+
+        assert_eq!(chunks.len(), 4);
+        for chunk in &chunks {
+            assert!(chunk.text.chars().count() <= 3);
+            assert_eq!(&text[chunk.start..chunk.end], chunk.text);
+        }
+    }
+}
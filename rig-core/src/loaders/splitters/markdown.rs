@@ -0,0 +1,177 @@
+//! Markdown text splitter.
+use std::ops::Range;
+
+use super::{to_chunks, Chunk, Chunker, TextSplitter};
+
+/// Separators used to split Markdown sections larger than the chunk size
+const MARKDOWN_SEPARATORS: [&str; 5] = ["\n```\n", "\n\n", "\n", " ", ""];
+
+/// Splitter splitting Markdown documents on headings, so that chunks never span two sections.
+/// Sections larger than the chunk size are split on code blocks, paragraphs, lines, words and
+/// characters. Chunk sizes and overlaps are measured in characters.
+///
+/// Every chunk keeps track of the headings of its section in [Chunk::headings] (e.g.:
+/// `["Installation", "From source"]` for a chunk of the `## From source` subsection of the
+/// `# Installation` section).
+///
+/// # Example
+/// ```rust
+/// use rig::loaders::splitters::{MarkdownSplitter, TextSplitter};
+///
+/// let splitter = MarkdownSplitter::new(1000).chunk_overlap(100);
+/// let chunks = splitter.split("# Title\n\nSome text...");
+/// ```
+#[derive(Clone, Debug)]
+pub struct MarkdownSplitter {
+    chunk_size: usize,
+    chunk_overlap: usize,
+}
+
+impl MarkdownSplitter {
+    /// Create a new splitter producing chunks of at most `chunk_size` characters
+    pub fn new(chunk_size: usize) -> Self {
+        Self {
+            chunk_size: chunk_size.max(1),
+            chunk_overlap: 0,
+        }
+    }
+
+    /// Set the maximum number of characters shared by consecutive chunks of a section
+    pub fn chunk_overlap(mut self, chunk_overlap: usize) -> Self {
+        self.chunk_overlap = chunk_overlap;
+        self
+    }
+}
+
+/// Parse a heading line (e.g.: `## Title`) into its level and title
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let title = &line[level..];
+    if (1..=6).contains(&level) && (title.is_empty() || title.starts_with([' ', '\t'])) {
+        Some((level, title.trim().trim_end_matches('#').trim_end()))
+    } else {
+        None
+    }
+}
+
+/// Split `text` into sections starting at headings, along with the headings of each section.
+/// Headings inside code blocks are ignored.
+fn sections(text: &str) -> Vec<(Range<usize>, Vec<String>)> {
+    let mut sections = vec![];
+    let mut headings: Vec<(usize, String)> = vec![];
+    let mut start = 0;
+    let mut in_code_block = false;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+        } else if let Some((level, title)) = heading(trimmed.trim_end()).filter(|_| !in_code_block)
+        {
+            if offset > start {
+                sections.push((start..offset, titles(&headings)));
+            }
+            headings.retain(|(parent_level, _)| *parent_level < level);
+            headings.push((level, title.to_string()));
+            start = offset;
+        }
+        offset += line.len();
+    }
+    if text.len() > start {
+        sections.push((start..text.len(), titles(&headings)));
+    }
+
+    sections
+}
+
+fn titles(headings: &[(usize, String)]) -> Vec<String> {
+    headings.iter().map(|(_, title)| title.clone()).collect()
+}
+
+impl TextSplitter for MarkdownSplitter {
+    fn split(&self, text: &str) -> Vec<Chunk> {
+        let chunker = Chunker {
+            chunk_size: self.chunk_size,
+            chunk_overlap: self.chunk_overlap,
+            length: &|text: &str| text.chars().count(),
+        };
+
+        sections(text)
+            .into_iter()
+            .flat_map(|(section, headings)| {
+                to_chunks(
+                    text,
+                    chunker.split(text, section, &MARKDOWN_SEPARATORS),
+                    &headings,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = "Intro text.
+
+# Installation
+
+Run the installer.
+
+## From source
+
+```sh
+# not a heading
+cargo build
+```
+
+# Usage
+
+Run `rig` with a very long list of arguments.
+";
+
+    #[test]
+    fn test_split_on_headings() {
+        let chunks = MarkdownSplitter::new(1000).split(DOCUMENT);
+
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|c| (c.headings.clone(), c.text.lines().next().unwrap()))
+                .collect::<Vec<_>>(),
+            vec![
+                (vec![], "Intro text."),
+                (vec!["Installation".to_string()], "# Installation"),
+                (
+                    vec!["Installation".to_string(), "From source".to_string()],
+                    "## From source"
+                ),
+                (vec!["Usage".to_string()], "# Usage"),
+            ]
+        );
+        for chunk in &chunks {
+            assert_eq!(&DOCUMENT[chunk.start..chunk.end], chunk.text);
+        }
+    }
+
+    #[test]
+    fn test_split_large_sections() {
+        let chunks = MarkdownSplitter::new(30).split(DOCUMENT);
+
+        let usage = chunks
+            .iter()
+            .filter(|c| c.headings == vec!["Usage".to_string()])
+            .map(|c| c.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            usage,
+            vec![
+                "# Usage",
+                "Run `rig` with a very long",
+                "list of arguments."
+            ]
+        );
+    }
+}
@@ -0,0 +1,300 @@
+//! This module provides text splitters, which split long texts (e.g.: files loaded with a
+//! [FileLoader](crate::loaders::FileLoader)) into smaller chunks that fit in the input limits
+//! of embedding models.
+//!
+//! Every splitter implements the [TextSplitter] trait and has a configurable chunk size and
+//! overlap between consecutive chunks:
+//! - [RecursiveCharacterSplitter]: splits on paragraphs, then lines, then words, then characters
+//!   until chunks are small enough. Chunk sizes are measured in characters.
+//! - [SentenceSplitter]: packs whole sentences into chunks. Chunk sizes are measured in characters.
+//! - [TokenSplitter]: same as [RecursiveCharacterSplitter], but chunk sizes are measured in tokens.
+//! - [MarkdownSplitter]: splits Markdown documents on headings, so that chunks never span two
+//!   sections, and keeps track of the headings of each chunk.
+//!
+//! Every [Chunk] keeps track of its byte offsets in the source text, and implements the
+//! [Embed](crate::embeddings::Embed) trait so chunks can be passed directly to
+//! [EmbeddingsBuilder::documents](crate::embeddings::EmbeddingsBuilder::documents).
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     embeddings::EmbeddingsBuilder,
+//!     loaders::{FileLoader, splitters::RecursiveCharacterSplitter},
+//!     providers::openai::{self, TEXT_EMBEDDING_ADA_002},
+//! };
+//!
+//! let chunks = FileLoader::with_glob("docs/*.txt")?
+//!     .read_with_path()
+//!     .ignore_errors()
+//!     .split(RecursiveCharacterSplitter::new(1000).chunk_overlap(200))
+//!     .into_iter()
+//!     .collect::<Vec<_>>();
+//!
+//! let openai = openai::Client::from_env();
+//! let embeddings = EmbeddingsBuilder::new(openai.embedding_model(TEXT_EMBEDDING_ADA_002))
+//!     .documents(chunks)?
+//!     .build()
+//!     .await?;
+//! ```
+
+use std::{collections::VecDeque, ops::Range};
+
+use serde::{Deserialize, Serialize};
+
+use super::metadata::SourceMetadata;
+use crate::embeddings::{embed::EmbedError, Embed, TextEmbedder};
+
+pub mod character;
+pub mod markdown;
+pub mod sentence;
+pub mod token;
+
+pub use character::RecursiveCharacterSplitter;
+pub use markdown::MarkdownSplitter;
+pub use sentence::SentenceSplitter;
+pub use token::TokenSplitter;
+
+/// Separators used by the recursive splitters, from the coarsest to the finest
+pub const DEFAULT_SEPARATORS: [&str; 4] = ["\n\n", "\n", " ", ""];
+
+/// A chunk of a source text
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chunk {
+    pub text: String,
+    /// Byte offset of the start of the chunk in the source text
+    pub start: usize,
+    /// Byte offset of the end (exclusive) of the chunk in the source text
+    pub end: usize,
+    /// Headings of the section containing the chunk, from the top level heading to the
+    /// innermost one. Only set by the [MarkdownSplitter].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headings: Vec<String>,
+    /// Metadata of the source of the chunk (e.g.: the path of the file the text was loaded
+    /// from and the index of the chunk in that file)
+    #[serde(flatten)]
+    pub metadata: SourceMetadata,
+}
+
+impl Chunk {
+    /// Set the source metadata of the chunk
+    pub fn with_metadata(mut self, metadata: SourceMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+}
+
+impl Embed for Chunk {
+    fn embed(&self, embedder: &mut TextEmbedder) -> Result<(), EmbedError> {
+        embedder.embed(self.text.clone());
+        Ok(())
+    }
+}
+
+/// Trait for splitting texts into chunks
+pub trait TextSplitter {
+    /// Split `text` into chunks
+    fn split(&self, text: &str) -> Vec<Chunk>;
+
+    /// Split `text` into chunks, setting the metadata of every chunk to `metadata` along with
+    /// the index of the chunk
+    fn split_with_metadata(&self, metadata: &SourceMetadata, text: &str) -> Vec<Chunk> {
+        self.split(text)
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunk)| chunk.with_metadata(metadata.clone().with_chunk(i)))
+            .collect()
+    }
+}
+
+impl<T: TextSplitter + ?Sized> TextSplitter for &T {
+    fn split(&self, text: &str) -> Vec<Chunk> {
+        (**self).split(text)
+    }
+}
+
+/// Splitting and merging logic shared by the splitters. Texts are handled as byte ranges of
+/// the source text so that chunks keep track of their offsets.
+pub(crate) struct Chunker<'a> {
+    pub chunk_size: usize,
+    pub chunk_overlap: usize,
+    pub length: &'a dyn Fn(&str) -> usize,
+}
+
+impl Chunker<'_> {
+    /// Recursively split `range` using the first separator of `separators` found in it until
+    /// pieces are smaller than the chunk size, then merge the pieces into chunks
+    pub fn split(&self, text: &str, range: Range<usize>, separators: &[&str]) -> Vec<Range<usize>> {
+        let slice = &text[range.clone()];
+        let (separator, finer_separators) = match separators
+            .iter()
+            .position(|separator| separator.is_empty() || slice.contains(separator))
+        {
+            Some(i) => (separators[i], &separators[i + 1..]),
+            None => ("", &[][..]),
+        };
+
+        self.merge_pieces(text, split_keep(text, range, separator), |piece| {
+            if separator.is_empty() {
+                // Single characters cannot be split any further
+                vec![piece]
+            } else {
+                self.split(text, piece, finer_separators)
+            }
+        })
+    }
+
+    /// Merge consecutive `pieces` into chunks. Pieces larger than the chunk size are split
+    /// separately with `split_large`.
+    pub fn merge_pieces(
+        &self,
+        text: &str,
+        pieces: Vec<Range<usize>>,
+        mut split_large: impl FnMut(Range<usize>) -> Vec<Range<usize>>,
+    ) -> Vec<Range<usize>> {
+        let mut chunks = vec![];
+        let mut small_pieces = vec![];
+
+        for piece in pieces {
+            if (self.length)(&text[piece.clone()]) <= self.chunk_size {
+                small_pieces.push(piece);
+            } else {
+                chunks.extend(self.merge(text, std::mem::take(&mut small_pieces)));
+                chunks.extend(split_large(piece));
+            }
+        }
+        chunks.extend(self.merge(text, small_pieces));
+
+        chunks
+    }
+
+    /// Merge consecutive pieces (all smaller than the chunk size) into chunks, keeping up to
+    /// `chunk_overlap` of the end of each chunk at the start of the next one
+    fn merge(&self, text: &str, pieces: Vec<Range<usize>>) -> Vec<Range<usize>> {
+        let mut chunks = vec![];
+        let mut current = VecDeque::new();
+        let mut current_length = 0;
+
+        for piece in pieces {
+            let length = (self.length)(&text[piece.clone()]);
+
+            if current_length + length > self.chunk_size && !current.is_empty() {
+                chunks.push(span(&current));
+
+                while let Some((_, front_length)) = current.front() {
+                    if current_length > self.chunk_overlap
+                        || current_length + length > self.chunk_size
+                    {
+                        current_length -= front_length;
+                        current.pop_front();
+                    } else {
+                        break;
+                    }
+                }
+            }
+
+            current.push_back((piece, length));
+            current_length += length;
+        }
+
+        if !current.is_empty() {
+            chunks.push(span(&current));
+        }
+
+        chunks
+    }
+}
+
+fn span(pieces: &VecDeque<(Range<usize>, usize)>) -> Range<usize> {
+    let start = pieces.front().map(|(range, _)| range.start).unwrap_or(0);
+    let end = pieces.back().map(|(range, _)| range.end).unwrap_or(0);
+    start..end
+}
+
+/// Split `range` on `separator`, keeping the separator at the end of each piece so that pieces
+/// are contiguous. An empty separator splits the range into characters.
+pub(crate) fn split_keep(text: &str, range: Range<usize>, separator: &str) -> Vec<Range<usize>> {
+    let slice = &text[range.clone()];
+
+    if separator.is_empty() {
+        return slice
+            .char_indices()
+            .map(|(i, c)| range.start + i..range.start + i + c.len_utf8())
+            .collect();
+    }
+
+    let mut pieces = vec![];
+    let mut start = range.start;
+    for (i, _) in slice.match_indices(separator) {
+        let end = range.start + i + separator.len();
+        pieces.push(start..end);
+        start = end;
+    }
+    if start < range.end {
+        pieces.push(start..range.end);
+    }
+
+    pieces
+}
+
+/// Convert byte ranges of `text` into chunks, trimming surrounding whitespace and skipping
+/// empty chunks
+pub(crate) fn to_chunks(
+    text: &str,
+    ranges: impl IntoIterator<Item = Range<usize>>,
+    headings: &[String],
+) -> Vec<Chunk> {
+    ranges
+        .into_iter()
+        .filter_map(|range| {
+            let slice = &text[range.clone()];
+            let trimmed = slice.trim();
+            if trimmed.is_empty() {
+                return None;
+            }
+            let start = range.start + (slice.len() - slice.trim_start().len());
+            Some(Chunk {
+                text: trimmed.to_string(),
+                start,
+                end: start + trimmed.len(),
+                headings: headings.to_vec(),
+                metadata: SourceMetadata::default(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> usize {
+        text.chars().count()
+    }
+
+    #[test]
+    fn test_split_keep() {
+        let text = "a b  c";
+        assert_eq!(split_keep(text, 0..6, " "), vec![0..2, 2..4, 4..5, 5..6]);
+        assert_eq!(split_keep(text, 2..6, ""), vec![2..3, 3..4, 4..5, 5..6]);
+    }
+
+    #[test]
+    fn test_merge_with_overlap() {
+        let chunker = Chunker {
+            chunk_size: 10,
+            chunk_overlap: 4,
+            length: &chars,
+        };
+        let text = "one two three four five";
+        let ranges = chunker.split(text, 0..text.len(), &DEFAULT_SEPARATORS);
+        let chunks = to_chunks(text, ranges, &[]);
+
+        assert_eq!(
+            chunks.iter().map(|c| c.text.as_str()).collect::<Vec<_>>(),
+            vec!["one two", "two three", "four five"]
+        );
+        for chunk in chunks {
+            assert_eq!(&text[chunk.start..chunk.end], chunk.text);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Sentence text splitter.
+use std::ops::Range;
+
+use super::{to_chunks, Chunk, Chunker, TextSplitter, DEFAULT_SEPARATORS};
+
+/// Splitter packing whole sentences into chunks. Sentences longer than the chunk size are split
+/// on words (or characters) like the [RecursiveCharacterSplitter](super::RecursiveCharacterSplitter).
+/// Chunk sizes and overlaps are measured in characters, and overlaps contain whole sentences.
+///
+/// Sentences end with `.`, `!` or `?` followed by whitespace, or with an empty line.
+///
+/// # Example
+/// ```rust
+/// use rig::loaders::splitters::{SentenceSplitter, TextSplitter};
+///
+/// let splitter = SentenceSplitter::new(500).chunk_overlap(100);
+/// let chunks = splitter.split("A very long text...");
+/// ```
+#[derive(Clone, Debug)]
+pub struct SentenceSplitter {
+    chunk_size: usize,
+    chunk_overlap: usize,
+}
+
+impl SentenceSplitter {
+    /// Create a new splitter producing chunks of at most `chunk_size` characters
+    pub fn new(chunk_size: usize) -> Self {
+        Self {
+            chunk_size: chunk_size.max(1),
+            chunk_overlap: 0,
+        }
+    }
+
+    /// Set the maximum number of characters shared by consecutive chunks
+    pub fn chunk_overlap(mut self, chunk_overlap: usize) -> Self {
+        self.chunk_overlap = chunk_overlap;
+        self
+    }
+}
+
+/// Split `text` into sentences. Whitespace following a sentence is part of the sentence so that
+/// sentences are contiguous.
+fn sentences(text: &str) -> Vec<Range<usize>> {
+    let mut sentences = vec![];
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((_, c)) = chars.next() {
+        let is_boundary = match c {
+            '.' | '!' | '?' => {
+                // Include closing punctuation (e.g.: `?!`, `."`)
+                while let Some((_, '.' | '!' | '?' | '"' | '\'' | ')' | ']' | '”' | '’')) =
+                    chars.peek()
+                {
+                    chars.next();
+                }
+                chars.peek().is_none_or(|(_, next)| next.is_whitespace())
+            }
+            '\n' => chars.peek().is_some_and(|(_, next)| *next == '\n'),
+            _ => false,
+        };
+
+        if is_boundary {
+            while chars.next_if(|(_, next)| next.is_whitespace()).is_some() {}
+            let end = chars.peek().map_or(text.len(), |(i, _)| *i);
+            sentences.push(start..end);
+            start = end;
+        }
+    }
+    if start < text.len() {
+        sentences.push(start..text.len());
+    }
+
+    sentences
+}
+
+impl TextSplitter for SentenceSplitter {
+    fn split(&self, text: &str) -> Vec<Chunk> {
+        let chunker = Chunker {
+            chunk_size: self.chunk_size,
+            chunk_overlap: self.chunk_overlap,
+            length: &|text: &str| text.chars().count(),
+        };
+
+        let ranges = chunker.merge_pieces(text, sentences(text), |sentence| {
+            chunker.split(text, sentence, &DEFAULT_SEPARATORS[1..])
+        });
+
+        to_chunks(text, ranges, &[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sentences() {
+        let text = "Hello world! Is SOL up?! It is.\n\nNew paragraph";
+        let sentences = sentences(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            sentences,
+            vec![
+                "Hello world! ",
+                "Is SOL up?! ",
+                "It is.\n\n",
+                "New paragraph"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_with_overlap() {
+        let text = "SOL is up. ETH is down. BTC is flat. Fees are low.";
+        let chunks = SentenceSplitter::new(26).chunk_overlap(13).split(text);
+
+        assert_eq!(
+            chunks.iter().map(|c| c.text.as_str()).collect::<Vec<_>>(),
+            vec![
+                "SOL is up. ETH is down.",
+                "ETH is down. BTC is flat.",
+                "BTC is flat. Fees are low."
+            ]
+        );
+        for chunk in &chunks {
+            assert_eq!(&text[chunk.start..chunk.end], chunk.text);
+        }
+    }
+}
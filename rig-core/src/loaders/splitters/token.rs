@@ -0,0 +1,109 @@
+//! Token-count text splitter.
+use super::{to_chunks, Chunk, Chunker, TextSplitter, DEFAULT_SEPARATORS};
+use crate::tokenizer::{HeuristicTokenizer, Tokenizer};
+
+/// Splitter recursively splitting texts on paragraphs, lines, words and characters until chunks
+/// are smaller than the chunk size, like the [RecursiveCharacterSplitter](super::RecursiveCharacterSplitter).
+/// Chunk sizes and overlaps are measured in tokens.
+///
+/// By default, tokens are counted with a [HeuristicTokenizer]. Use [TokenSplitter::tokenizer]
+/// to count tokens with the tokenizer of the embedding model (see
+/// [EmbeddingModel::tokenizer](crate::embeddings::EmbeddingModel::tokenizer)).
+///
+/// # Example
+/// ```rust
+/// use rig::loaders::splitters::{TextSplitter, TokenSplitter};
+///
+/// // Stay below the 8191 tokens input limit of OpenAI embedding models
+/// let splitter = TokenSplitter::new(8000)
+///     .chunk_overlap(200)
+///     .tokenizer(embedding_model.tokenizer());
+/// let chunks = splitter.split("A very long text...");
+/// ```
+#[derive(Clone, Debug)]
+pub struct TokenSplitter<F = fn(&str) -> usize> {
+    chunk_size: usize,
+    chunk_overlap: usize,
+    count_tokens: F,
+}
+
+impl TokenSplitter {
+    /// Create a new splitter producing chunks of at most `chunk_size` tokens
+    pub fn new(chunk_size: usize) -> Self {
+        Self {
+            chunk_size: chunk_size.max(1),
+            chunk_overlap: 0,
+            count_tokens: |text| HeuristicTokenizer::default().count_tokens(text),
+        }
+    }
+}
+
+impl<F: Fn(&str) -> usize> TokenSplitter<F> {
+    /// Set the maximum number of tokens shared by consecutive chunks
+    pub fn chunk_overlap(mut self, chunk_overlap: usize) -> Self {
+        self.chunk_overlap = chunk_overlap;
+        self
+    }
+
+    /// Set the function used to count the tokens of a text
+    pub fn token_counter<G: Fn(&str) -> usize>(self, count_tokens: G) -> TokenSplitter<G> {
+        TokenSplitter {
+            chunk_size: self.chunk_size,
+            chunk_overlap: self.chunk_overlap,
+            count_tokens,
+        }
+    }
+
+    /// Set the tokenizer used to count the tokens of a text
+    pub fn tokenizer(self, tokenizer: impl Tokenizer) -> TokenSplitter<impl Fn(&str) -> usize> {
+        self.token_counter(move |text: &str| tokenizer.count_tokens(text))
+    }
+}
+
+impl<F: Fn(&str) -> usize> TextSplitter for TokenSplitter<F> {
+    fn split(&self, text: &str) -> Vec<Chunk> {
+        let chunker = Chunker {
+            chunk_size: self.chunk_size,
+            chunk_overlap: self.chunk_overlap,
+            length: &self.count_tokens,
+        };
+
+        to_chunks(
+            text,
+            chunker.split(text, 0..text.len(), &DEFAULT_SEPARATORS),
+            &[],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_words_as_tokens() {
+        let text = "the quick brown fox jumps over the lazy dog";
+        let chunks = TokenSplitter::new(3)
+            .chunk_overlap(1)
+            .token_counter(|text: &str| text.split_whitespace().count())
+            .split(text);
+
+        assert_eq!(
+            chunks.iter().map(|c| c.text.as_str()).collect::<Vec<_>>(),
+            vec![
+                "the quick brown",
+                "brown fox jumps",
+                "jumps over the",
+                "the lazy dog"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_default_token_count() {
+        let count_tokens = TokenSplitter::new(1).count_tokens;
+        assert_eq!(count_tokens(""), 0);
+        assert_eq!(count_tokens("abcd"), 1);
+        assert_eq!(count_tokens("abcde"), 2);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
-    completion::{self, CompletionModel},
+    completion::{self, CompletionModel, Document},
     extractor::{ExtractionError, Extractor},
-    vector_store,
+    rerank, vector_store,
 };
 
 use super::Op;
@@ -9,6 +9,7 @@ use super::Op;
 pub struct Lookup<I, In, T> {
     index: I,
     n: usize,
+    options: vector_store::SearchOptions,
     _in: std::marker::PhantomData<In>,
     _t: std::marker::PhantomData<T>,
 }
@@ -21,10 +22,17 @@ where
         Self {
             index,
             n,
+            options: vector_store::SearchOptions::default(),
             _in: std::marker::PhantomData,
             _t: std::marker::PhantomData,
         }
     }
+
+    /// Set the search options of the lookup (e.g.: minimum score, MMR diversification)
+    pub fn options(mut self, options: vector_store::SearchOptions) -> Self {
+        self.options = options;
+        self
+    }
 }
 
 impl<I, In, T> Op for Lookup<I, In, T>
@@ -41,7 +49,7 @@ where
 
         let docs = self
             .index
-            .top_n::<T>(&query, self.n)
+            .top_n_with_options::<T>(&query, self.n, &self.options)
             .await?
             .into_iter()
             .collect();
@@ -63,6 +71,103 @@ where
     Lookup::new(index, n)
 }
 
+pub struct RerankedLookup<I, R, In, T> {
+    index: I,
+    reranker: R,
+    candidates: usize,
+    n: usize,
+    options: vector_store::SearchOptions,
+    _in: std::marker::PhantomData<In>,
+    _t: std::marker::PhantomData<T>,
+}
+
+impl<I, R, In, T> RerankedLookup<I, R, In, T>
+where
+    I: vector_store::VectorStoreIndex,
+    R: rerank::Reranker,
+{
+    pub(crate) fn new(index: I, reranker: R, candidates: usize, n: usize) -> Self {
+        Self {
+            index,
+            reranker,
+            candidates,
+            n,
+            options: vector_store::SearchOptions::default(),
+            _in: std::marker::PhantomData,
+            _t: std::marker::PhantomData,
+        }
+    }
+
+    /// Set the search options used to fetch the candidates (e.g.: minimum score, MMR
+    /// diversification)
+    pub fn options(mut self, options: vector_store::SearchOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl<I, R, In, T> Op for RerankedLookup<I, R, In, T>
+where
+    I: vector_store::VectorStoreIndex,
+    R: rerank::Reranker,
+    In: Into<String> + Send + Sync,
+    T: Send + Sync + for<'a> serde::Deserialize<'a>,
+{
+    type Input = In;
+    type Output = Result<Vec<(f64, String, T)>, rerank::RerankError>;
+
+    async fn call(&self, input: Self::Input) -> Self::Output {
+        let query: String = input.into();
+
+        let candidates = self
+            .index
+            .top_n_with_options::<serde_json::Value>(
+                &query,
+                self.candidates.max(self.n),
+                &self.options,
+            )
+            .await?;
+        let texts = candidates
+            .iter()
+            .map(|(_, id, doc)| Document::from_json(id.clone(), doc.clone()).text)
+            .collect::<Vec<_>>();
+
+        let results = rerank::Reranker::rerank(&self.reranker, &query, &texts, self.n).await?;
+
+        let mut candidates = candidates.into_iter().map(Some).collect::<Vec<_>>();
+        results
+            .into_iter()
+            .filter_map(|result| {
+                candidates
+                    .get_mut(result.index)
+                    .and_then(Option::take)
+                    .map(|(_, id, doc)| Ok((result.score, id, serde_json::from_value(doc)?)))
+            })
+            .take(self.n)
+            .collect()
+    }
+}
+
+/// Create a new reranked lookup operation.
+///
+/// The op will perform semantic search on the provided index to fetch `candidates` documents,
+/// rerank them against the input using `reranker` and return the `n` most relevant ones, along
+/// with their rerank scores.
+pub fn lookup_reranked<I, R, In, T>(
+    index: I,
+    reranker: R,
+    candidates: usize,
+    n: usize,
+) -> RerankedLookup<I, R, In, T>
+where
+    I: vector_store::VectorStoreIndex,
+    R: rerank::Reranker,
+    In: Into<String> + Send + Sync,
+    T: Send + Sync + for<'a> serde::Deserialize<'a>,
+{
+    RerankedLookup::new(index, reranker, candidates, n)
+}
+
 pub struct Prompt<P, In> {
     prompt: P,
     _in: std::marker::PhantomData<In>,
@@ -220,6 +325,87 @@ pub mod tests {
         );
     }
 
+    /// Reranker reversing the order of the candidates
+    pub struct ReverseReranker;
+
+    impl rerank::Reranker for ReverseReranker {
+        async fn rerank(
+            &self,
+            _query: &str,
+            documents: &[String],
+            top_k: usize,
+        ) -> Result<Vec<rerank::RerankResult>, rerank::RerankError> {
+            Ok((0..documents.len())
+                .rev()
+                .map(|index| rerank::RerankResult { index, score: 0.5 })
+                .take(top_k)
+                .collect())
+        }
+    }
+
+    pub struct ListIndex;
+
+    impl VectorStoreIndex for ListIndex {
+        async fn top_n<T: for<'a> serde::Deserialize<'a> + std::marker::Send>(
+            &self,
+            _query: &str,
+            n: usize,
+        ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+            ["bar", "baz", "qux"]
+                .into_iter()
+                .take(n)
+                .enumerate()
+                .map(|(i, foo)| {
+                    let doc = serde_json::from_value(serde_json::json!({ "foo": foo }))?;
+                    Ok((1.0, format!("doc{i}"), doc))
+                })
+                .collect()
+        }
+
+        async fn top_n_ids(
+            &self,
+            _query: &str,
+            n: usize,
+        ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+            Ok((0..n.min(3)).map(|i| (1.0, format!("doc{i}"))).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lookup_reranked() {
+        let lookup = lookup_reranked::<_, _, String, Foo>(ListIndex, ReverseReranker, 3, 2);
+
+        let result = lookup.call("query".to_string()).await.unwrap();
+        assert_eq!(
+            result,
+            vec![
+                (
+                    0.5,
+                    "doc2".to_string(),
+                    Foo {
+                        foo: "qux".to_string()
+                    }
+                ),
+                (
+                    0.5,
+                    "doc1".to_string(),
+                    Foo {
+                        foo: "baz".to_string()
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lookup_min_score() {
+        let lookup = lookup::<_, String, Foo>(MockIndex, 1)
+            .options(vector_store::SearchOptions::new().min_score(1.5));
+
+        let result = lookup.call("query".to_string()).await.unwrap();
+        assert!(result.is_empty());
+    }
+
     #[tokio::test]
     async fn test_prompt() {
         let model = MockModel;
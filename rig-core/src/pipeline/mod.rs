@@ -100,7 +100,7 @@ use std::future::Future;
 pub use op::{map, passthrough, then, Op};
 pub use try_op::TryOp;
 
-use crate::{completion, extractor::Extractor, vector_store};
+use crate::{completion, extractor::Extractor, rerank, vector_store};
 
 pub struct PipelineBuilder<E> {
     _error: std::marker::PhantomData<E>,
@@ -216,6 +216,42 @@ impl<E> PipelineBuilder<E> {
         agent_ops::Lookup::new(index, n)
     }
 
+    /// Add a reranked lookup operation to the current pipeline/op. The lookup operation expects
+    /// the current pipeline to output a query string. It will retrieve `candidates` documents
+    /// from the index, rerank them against the query using `reranker` and return the `n` most
+    /// relevant ones, scored by the reranker.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rig::{pipeline::{self, Op}, providers::cohere};
+    ///
+    /// let reranker = cohere_client.rerank_model(cohere::RERANK_V3_5);
+    ///
+    /// let pipeline = pipeline::new()
+    ///     .lookup_reranked(index, reranker, 20, 2)
+    ///     .pipeline(|(query, docs): (_, Vec<String>)| async move {
+    ///         format!("User query: {}\n\nTop documents:\n{}", query, docs.join("\n"))
+    ///     });
+    ///
+    /// let result = pipeline.call("What is a flurbo?".to_string()).await;
+    /// ```
+    pub fn lookup_reranked<I, R, Input, Output>(
+        self,
+        index: I,
+        reranker: R,
+        candidates: usize,
+        n: usize,
+    ) -> agent_ops::RerankedLookup<I, R, Input, Output>
+    where
+        I: vector_store::VectorStoreIndex,
+        R: rerank::Reranker,
+        Output: Send + Sync + for<'a> serde::Deserialize<'a>,
+        Input: Into<String> + Send + Sync,
+        Self: Sized,
+    {
+        agent_ops::RerankedLookup::new(index, reranker, candidates, n)
+    }
+
     /// Add a prompt operation to the current pipeline/op. The prompt operation expects the
     /// current pipeline to output a string. The prompt operation will use the string to prompt
     /// the given `agent`, which must implements the [Prompt](completion::Prompt) trait and return
@@ -286,6 +322,9 @@ pub enum ChainError {
 
     #[error("Failed to lookup documents: {0}")]
     LookupError(#[from] vector_store::VectorStoreError),
+
+    #[error("Failed to rerank documents: {0}")]
+    RerankError(#[from] rerank::RerankError),
 }
 
 pub fn new() -> PipelineBuilder<ChainError> {
@@ -142,6 +142,39 @@ pub trait Op: Send + Sync {
         Sequential::new(self, Lookup::new(index, n))
     }
 
+    /// Chain a reranked lookup operation to the current chain. The lookup operation expects the
+    /// current chain to output a query string. It will retrieve `candidates` documents from the
+    /// index, rerank them against the query using `reranker` and return the `n` most relevant ones.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rig::chain::{self, Chain};
+    ///
+    /// let chain = chain::new()
+    ///     .lookup_reranked(index, reranker, 20, 2)
+    ///     .chain(|(query, docs): (_, Vec<String>)| async move {
+    ///         format!("User query: {}\n\nTop documents:\n{}", query, docs.join("\n"))
+    ///     });
+    ///
+    /// let result = chain.call("What is a flurbo?".to_string()).await;
+    /// ```
+    fn lookup_reranked<I, R, Input>(
+        self,
+        index: I,
+        reranker: R,
+        candidates: usize,
+        n: usize,
+    ) -> Sequential<Self, RerankedLookup<I, R, Self::Output, Input>>
+    where
+        I: vector_store::VectorStoreIndex,
+        R: rerank::Reranker,
+        Input: Send + Sync + for<'a> serde::Deserialize<'a>,
+        Self::Output: Into<String>,
+        Self: Sized,
+    {
+        Sequential::new(self, RerankedLookup::new(index, reranker, candidates, n))
+    }
+
     /// Chain a prompt operation to the current chain. The prompt operation expects the
     /// current chain to output a string. The prompt operation will use the string to prompt
     /// the given agent (or any other type that implements the `Prompt` trait) and return
@@ -208,9 +241,9 @@ where
     }
 }
 
-use crate::{completion, vector_store};
+use crate::{completion, rerank, vector_store};
 
-use super::agent_ops::{Lookup, Prompt};
+use super::agent_ops::{Lookup, Prompt, RerankedLookup};
 
 // ================================================================
 // Core Op implementations
@@ -7,7 +7,7 @@ use crate::{
     json_utils,
     message::{self, MessageError},
     one_or_many::string_or_one_or_many,
-    OneOrMany,
+    telemetry, OneOrMany,
 };
 
 use serde::{Deserialize, Serialize};
@@ -97,7 +97,7 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
             .iter()
             .map(|content| {
                 Ok(match content {
-                    Content::Text { text } => completion::AssistantContent::text(text),
+                    Content::Text { text, .. } => completion::AssistantContent::text(text),
                     Content::ToolUse { id, name, input } => {
                         completion::AssistantContent::tool_call(id, name, input.clone())
                     }
@@ -142,6 +142,10 @@ pub enum Role {
 pub enum Content {
     Text {
         text: String,
+        /// Citations of the documents of the request supporting the text (only set in responses
+        /// to requests with citations enabled)
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        citations: Vec<TextCitation>,
     },
     Image {
         source: ImageSource,
@@ -160,14 +164,43 @@ pub enum Content {
     },
     Document {
         source: DocumentSource,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        context: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        citations: Option<CitationsConfig>,
     },
 }
 
+/// Citation attached to a text block of a response
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct TextCitation {
+    /// Type of location of the cited text (e.g.: `char_location`, `page_location`,
+    /// `web_search_result_location`)
+    pub r#type: String,
+    #[serde(default)]
+    pub cited_text: String,
+    /// Index of the cited document in the request (only set for citations of documents)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document_index: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document_title: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct CitationsConfig {
+    pub enabled: bool,
+}
+
 impl FromStr for Content {
     type Err = Infallible;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Content::Text { text: s.to_owned() })
+        Ok(Content::Text {
+            text: s.to_owned(),
+            citations: vec![],
+        })
     }
 }
 
@@ -218,17 +251,23 @@ pub enum ImageFormat {
 pub enum DocumentFormat {
     #[serde(rename = "application/pdf")]
     PDF,
+    #[serde(rename = "text/plain")]
+    TXT,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum SourceType {
     BASE64,
+    TEXT,
 }
 
 impl From<String> for Content {
     fn from(text: String) -> Self {
-        Content::Text { text }
+        Content::Text {
+            text,
+            citations: vec![],
+        }
     }
 }
 
@@ -255,6 +294,7 @@ impl From<SourceType> for message::ContentFormat {
     fn from(source_type: SourceType) -> Self {
         match source_type {
             SourceType::BASE64 => message::ContentFormat::Base64,
+            SourceType::TEXT => message::ContentFormat::String,
         }
     }
 }
@@ -291,7 +331,10 @@ impl From<ImageFormat> for message::ImageMediaType {
 impl From<message::AssistantContent> for Content {
     fn from(text: message::AssistantContent) -> Self {
         match text {
-            message::AssistantContent::Text(message::Text { text }) => Content::Text { text },
+            message::AssistantContent::Text(message::Text { text }) => Content::Text {
+                text,
+                citations: vec![],
+            },
             message::AssistantContent::ToolCall(message::ToolCall { id, function }) => {
                 Content::ToolUse {
                     id,
@@ -311,9 +354,10 @@ impl TryFrom<message::Message> for Message {
             message::Message::User { content } => Message {
                 role: Role::User,
                 content: content.try_map(|content| match content {
-                    message::UserContent::Text(message::Text { text }) => {
-                        Ok(Content::Text { text })
-                    }
+                    message::UserContent::Text(message::Text { text }) => Ok(Content::Text {
+                        text,
+                        citations: vec![],
+                    }),
                     message::UserContent::ToolResult(message::ToolResult { id, content }) => {
                         Ok(Content::ToolResult {
                             tool_use_id: id,
@@ -372,7 +416,12 @@ impl TryFrom<message::Message> for Message {
                                 None => SourceType::BASE64,
                             },
                         };
-                        Ok(Content::Document { source })
+                        Ok(Content::Document {
+                            source,
+                            title: None,
+                            context: None,
+                            citations: None,
+                        })
                     }
                     message::UserContent::Audio { .. } => Err(MessageError::ConversionError(
                         "Audio is not supported in Anthropic".to_owned(),
@@ -393,7 +442,7 @@ impl TryFrom<Content> for message::AssistantContent {
 
     fn try_from(content: Content) -> Result<Self, Self::Error> {
         Ok(match content {
-            Content::Text { text } => message::AssistantContent::text(text),
+            Content::Text { text, .. } => message::AssistantContent::text(text),
             Content::ToolUse { id, name, input } => {
                 message::AssistantContent::tool_call(id, name, input)
             }
@@ -433,7 +482,7 @@ impl TryFrom<Message> for message::Message {
             Role::User => message::Message::User {
                 content: message.content.try_map(|content| {
                     Ok(match content {
-                        Content::Text { text } => message::UserContent::text(text),
+                        Content::Text { text, .. } => message::UserContent::text(text),
                         Content::ToolResult {
                             tool_use_id,
                             content,
@@ -448,10 +497,13 @@ impl TryFrom<Message> for message::Message {
                             media_type: Some(source.media_type.into()),
                             detail: None,
                         }),
-                        Content::Document { source } => message::UserContent::document(
+                        Content::Document { source, .. } => message::UserContent::document(
                             source.data,
-                            Some(message::ContentFormat::Base64),
-                            Some(message::DocumentMediaType::PDF),
+                            Some(source.r#type.into()),
+                            Some(match source.media_type {
+                                DocumentFormat::PDF => message::DocumentMediaType::PDF,
+                                DocumentFormat::TXT => message::DocumentMediaType::TXT,
+                            }),
                         ),
                         _ => {
                             return Err(MessageError::ConversionError(
@@ -530,10 +582,90 @@ pub enum ToolChoice {
 impl completion::CompletionModel for CompletionModel {
     type Response = CompletionResponse;
 
+    fn context_window(&self) -> Option<u64> {
+        completion::context_window::model_context_window(&self.model)
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: completion::CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        let span = telemetry::completion_span("anthropic", &self.model, &completion_request);
+        telemetry::instrument_completion(span, self.send_completion(completion_request, false))
+            .await
+    }
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn cited_completion(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        let span = telemetry::completion_span("anthropic", &self.model, &completion_request);
+        telemetry::instrument_completion(span, self.send_completion(completion_request, true)).await
+    }
+
+    /// Anthropic cites the documents of requests sent with citations enabled, which are then
+    /// sent as document blocks titled with their ids
+    fn supports_citations(&self) -> bool {
+        true
+    }
+
+    fn citations(
+        &self,
+        response: &completion::CompletionResponse<CompletionResponse>,
+        documents: &[completion::Document],
+    ) -> completion::CitedText {
+        let mut cited = completion::CitedText::default();
+
+        for content in &response.raw_response.content {
+            let Content::Text { text, citations } = content else {
+                continue;
+            };
+
+            let start = cited.text.len();
+            cited.text.push_str(text);
+
+            let mut document_ids = vec![];
+            for citation in citations {
+                // Citations of other sources than the documents (e.g.: web search results) have
+                // no document index and are skipped
+                let Some(index) = citation.document_index else {
+                    continue;
+                };
+                let id = citation
+                    .document_title
+                    .clone()
+                    .or_else(|| documents.get(index).map(|document| document.id.clone()));
+                if let Some(id) = id.filter(|id| !document_ids.contains(id)) {
+                    document_ids.push(id);
+                }
+            }
+
+            // Text blocks with citations are the sentences (or parts of sentences) they support
+            let trimmed = text.trim();
+            if !document_ids.is_empty() && !trimmed.is_empty() {
+                let start = start + (text.len() - text.trim_start().len());
+                cited.citations.push(completion::Citation {
+                    start,
+                    end: start + trimmed.len(),
+                    text: trimmed.to_string(),
+                    document_ids,
+                });
+            }
+        }
+
+        cited
+    }
+}
+
+impl CompletionModel {
+    /// Send the completion request, with its documents as citable document blocks if
+    /// `citations` is set
+    async fn send_completion(
+        &self,
+        completion_request: completion::CompletionRequest,
+        citations: bool,
     ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
         // Note: Ideally we'd introduce provider-specific Request models to handle the
         // specific requirements of each provider. For now, we just manually check while
@@ -550,10 +682,24 @@ impl completion::CompletionModel for CompletionModel {
             ));
         };
 
-        let prompt_message: Message = completion_request
-            .prompt_with_context()
-            .try_into()
-            .map_err(|e: MessageError| CompletionError::RequestError(e.into()))?;
+        let prompt_message = if citations && !completion_request.documents.is_empty() {
+            // Send the documents as document blocks so that Anthropic can cite them
+            let mut prompt_message: Message = completion_request
+                .prompt
+                .clone()
+                .try_into()
+                .map_err(|e: MessageError| CompletionError::RequestError(e.into()))?;
+            let documents = completion_request.documents.iter().map(citable_document);
+            prompt_message.content =
+                OneOrMany::many(documents.chain(prompt_message.content.iter().cloned()))
+                    .expect("There is at least one document");
+            prompt_message
+        } else {
+            completion_request
+                .prompt_with_context()
+                .try_into()
+                .map_err(|e: MessageError| CompletionError::RequestError(e.into()))?
+        };
 
         let mut messages = completion_request
             .chat_history
@@ -616,6 +762,20 @@ impl completion::CompletionModel for CompletionModel {
                         "Anthropic completion token usage: {}",
                         completion.usage
                     );
+                    telemetry::record_usage(
+                        &tracing::Span::current(),
+                        completion.usage.input_tokens,
+                        Some(completion.usage.output_tokens),
+                    );
+                    telemetry::record_response_meta(
+                        &tracing::Span::current(),
+                        &completion.id,
+                        &completion.model,
+                    );
+                    telemetry::record_finish_reasons(
+                        &tracing::Span::current(),
+                        &completion.stop_reason,
+                    );
                     completion.try_into()
                 }
                 ApiResponse::Error(error) => Err(CompletionError::ProviderError(error.message)),
@@ -626,6 +786,30 @@ impl completion::CompletionModel for CompletionModel {
     }
 }
 
+/// Convert a document of the request into a document block with citations enabled
+fn citable_document(document: &completion::Document) -> Content {
+    let context = (!document.additional_props.is_empty()).then(|| {
+        let mut props = document.additional_props.iter().collect::<Vec<_>>();
+        props.sort();
+        props
+            .into_iter()
+            .map(|(key, value)| format!("{key}: {value}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    });
+
+    Content::Document {
+        source: DocumentSource {
+            data: document.text.clone(),
+            media_type: DocumentFormat::TXT,
+            r#type: SourceType::TEXT,
+        },
+        title: Some(document.id.clone()),
+        context,
+        citations: Some(CitationsConfig { enabled: true }),
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct ApiErrorResponse {
     message: String,
@@ -722,7 +906,8 @@ mod tests {
                 assert_eq!(
                     content.first(),
                     Content::Text {
-                        text: "\n\nHello there, how may I assist you today?".to_owned()
+                        text: "\n\nHello there, how may I assist you today?".to_owned(),
+                        citations: vec![],
                     }
                 );
             }
@@ -736,7 +921,7 @@ mod tests {
                 let mut iter = content.into_iter();
 
                 match iter.next().unwrap() {
-                    Content::Text { text } => {
+                    Content::Text { text, .. } => {
                         assert_eq!(text, "\n\nHello there, how may I assist you today?");
                     }
                     _ => panic!("Expected text content"),
@@ -777,7 +962,7 @@ mod tests {
                 }
 
                 match iter.next().unwrap() {
-                    Content::Text { text } => {
+                    Content::Text { text, .. } => {
                         assert_eq!(text, "What is in this image?");
                     }
                     _ => panic!("Expected text content"),
@@ -950,4 +1135,90 @@ mod tests {
         assert_eq!(assistant_message, original_assistant_message);
         assert_eq!(tool_message, original_tool_message);
     }
+
+    #[test]
+    fn test_citations() {
+        let response: CompletionResponse = serde_json::from_value(json!({
+            "id": "msg_01",
+            "model": "claude-3-5-sonnet-latest",
+            "role": "assistant",
+            "stop_reason": "end_turn",
+            "stop_sequence": null,
+            "usage": {"input_tokens": 10, "output_tokens": 10},
+            "content": [
+                {"type": "text", "text": "According to the reports, "},
+                {
+                    "type": "text",
+                    "text": "SOL rallied 12%",
+                    "citations": [{
+                        "type": "char_location",
+                        "cited_text": "SOL rallied 12% last week.",
+                        "document_index": 1,
+                        "document_title": "doc1",
+                        "start_char_index": 0,
+                        "end_char_index": 26
+                    }]
+                },
+                {"type": "text", "text": "."},
+                {
+                    "type": "text",
+                    "text": " Fees also rose.",
+                    "citations": [{
+                        "type": "web_search_result_location",
+                        "cited_text": "Fees rose 5%.",
+                        "url": "https://example.com/sol",
+                        "title": "SOL weekly",
+                        "encrypted_index": "Eo8BCioIAhgB"
+                    }]
+                }
+            ]
+        }))
+        .unwrap();
+        let response: completion::CompletionResponse<CompletionResponse> =
+            response.try_into().unwrap();
+
+        let model = CompletionModel::new(
+            crate::providers::anthropic::ClientBuilder::new("key").build(),
+            CLAUDE_3_5_SONNET,
+        );
+        let cited = completion::CompletionModel::citations(&model, &response, &[]);
+
+        assert_eq!(
+            cited.text,
+            "According to the reports, SOL rallied 12%. Fees also rose."
+        );
+        assert_eq!(
+            cited.citations,
+            vec![completion::Citation {
+                start: 26,
+                end: 41,
+                text: "SOL rallied 12%".to_string(),
+                document_ids: vec!["doc1".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_citable_document() {
+        let document = completion::Document {
+            id: "doc1".to_string(),
+            text: "SOL rallied 12% last week.".to_string(),
+            additional_props: [("path".to_string(), "reports/sol.md".to_string())].into(),
+        };
+
+        assert_eq!(
+            serde_json::to_value(citable_document(&document)).unwrap(),
+            json!({
+                "type": "document",
+                "source": {
+                    "type": "text",
+                    "media_type": "text/plain",
+                    "data": "SOL rallied 12% last week."
+                },
+                "title": "doc1",
+                "context": "path: reports/sol.md",
+                "citations": {"enabled": true}
+            })
+        );
+    }
 }
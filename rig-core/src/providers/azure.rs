@@ -8,6 +8,8 @@
 //!
 //! let gpt4o = client.completion_model(azure::GPT_4O);
 //! ```
+use std::sync::Arc;
+
 use crate::{
     agent::AgentBuilder,
     completion::{self, CompletionError, CompletionRequest},
@@ -15,6 +17,8 @@ use crate::{
     extractor::ExtractorBuilder,
     json_utils,
     providers::openai,
+    telemetry,
+    tokenizer::{self, Tokenizer},
     Embed,
 };
 use schemars::JsonSchema;
@@ -259,6 +263,10 @@ pub struct EmbeddingModel {
 impl embeddings::EmbeddingModel for EmbeddingModel {
     const MAX_DOCUMENTS: usize = 1024;
 
+    fn tokenizer(&self) -> Arc<dyn Tokenizer> {
+        tokenizer::for_model(&self.model)
+    }
+
     fn ndims(&self) -> usize {
         self.ndims
     }
@@ -269,7 +277,16 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
         documents: impl IntoIterator<Item = String>,
     ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
         let documents = documents.into_iter().collect::<Vec<_>>();
+        let span = telemetry::embeddings_span("az.ai.openai", &self.model, documents.len());
+        telemetry::instrument(span, self.send_embeddings(documents)).await
+    }
+}
 
+impl EmbeddingModel {
+    async fn send_embeddings(
+        &self,
+        documents: Vec<String>,
+    ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
         let response = self
             .client
             .post_embedding(&self.model)
@@ -286,6 +303,11 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
                         "Azure embedding token usage: {}",
                         response.usage
                     );
+                    telemetry::record_usage(
+                        &tracing::Span::current(),
+                        response.usage.prompt_tokens as u64,
+                        None,
+                    );
 
                     if response.data.len() != documents.len() {
                         return Err(EmbeddingError::ResponseError(
@@ -296,7 +318,7 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
                     Ok(response
                         .data
                         .into_iter()
-                        .zip(documents.into_iter())
+                        .zip(documents)
                         .map(|(embedding, document)| embeddings::Embedding {
                             document,
                             vec: embedding.embedding,
@@ -370,10 +392,28 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = openai::CompletionResponse;
 
+    fn context_window(&self) -> Option<u64> {
+        completion::context_window::model_context_window(&self.model)
+    }
+
+    fn tokenizer(&self) -> Arc<dyn Tokenizer> {
+        tokenizer::for_model(&self.model)
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<openai::CompletionResponse>, CompletionError> {
+        let span = telemetry::completion_span("az.ai.openai", &self.model, &completion_request);
+        telemetry::instrument_completion(span, self.send_completion(completion_request)).await
+    }
+}
+
+impl CompletionModel {
+    async fn send_completion(
+        &self,
+        completion_request: CompletionRequest,
     ) -> Result<completion::CompletionResponse<openai::CompletionResponse>, CompletionError> {
         // Add preamble to chat history (if available)
         let mut full_history: Vec<openai::Message> = match &completion_request.preamble {
@@ -437,6 +477,22 @@ impl completion::CompletionModel for CompletionModel {
                         "Azure completion token usage: {:?}",
                         response.usage.clone().map(|usage| format!("{usage}")).unwrap_or("N/A".to_string())
                     );
+                    if let Some(usage) = &response.usage {
+                        telemetry::record_usage(
+                            &tracing::Span::current(),
+                            usage.prompt_tokens as u64,
+                            Some(usage.total_tokens.saturating_sub(usage.prompt_tokens) as u64),
+                        );
+                    }
+                    telemetry::record_response_meta(
+                        &tracing::Span::current(),
+                        &response.id,
+                        &response.model,
+                    );
+                    telemetry::record_finish_reasons(
+                        &tracing::Span::current(),
+                        response.choices.iter().map(|choice| &choice.finish_reason),
+                    );
                     response.try_into()
                 }
                 ApiResponse::Err(err) => Err(CompletionError::ProviderError(err.message)),
@@ -7,6 +7,8 @@
 //! let client = cohere::Client::new("YOUR_API_KEY");
 //!
 //! let command_r = client.completion_model(cohere::COMMAND_R);
+//!
+//! let reranker = client.rerank_model(cohere::RERANK_V3_5);
 //! ```
 use std::collections::HashMap;
 
@@ -15,7 +17,9 @@ use crate::{
     completion::{self, CompletionError},
     embeddings::{self, EmbeddingError, EmbeddingsBuilder},
     extractor::ExtractorBuilder,
-    json_utils, message, Embed, OneOrMany,
+    json_utils, message,
+    rerank::{self, RerankError},
+    telemetry, Embed, OneOrMany,
 };
 
 use schemars::JsonSchema;
@@ -100,6 +104,11 @@ impl Client {
         EmbeddingsBuilder::new(self.embedding_model(model, input_type))
     }
 
+    /// Create a rerank model with the given name (e.g.: [RERANK_V3_5]).
+    pub fn rerank_model(&self, model: &str) -> RerankModel {
+        RerankModel::new(self.clone(), model)
+    }
+
     pub fn completion_model(&self, model: &str) -> CompletionModel {
         CompletionModel::new(self.clone(), model)
     }
@@ -217,7 +226,16 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
         documents: impl IntoIterator<Item = String>,
     ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
         let documents = documents.into_iter().collect::<Vec<_>>();
+        let span = telemetry::embeddings_span("cohere", &self.model, documents.len());
+        telemetry::instrument(span, self.send_embeddings(documents)).await
+    }
+}
 
+impl EmbeddingModel {
+    async fn send_embeddings(
+        &self,
+        documents: Vec<String>,
+    ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
         let response = self
             .client
             .post("/v1/embed")
@@ -233,10 +251,17 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
             match response.json::<ApiResponse<EmbeddingResponse>>().await? {
                 ApiResponse::Ok(response) => {
                     match response.meta {
-                        Some(meta) => tracing::info!(target: "rig",
-                            "Cohere embeddings billed units: {}",
-                            meta.billed_units,
-                        ),
+                        Some(meta) => {
+                            tracing::info!(target: "rig",
+                                "Cohere embeddings billed units: {}",
+                                meta.billed_units,
+                            );
+                            telemetry::record_usage(
+                                &tracing::Span::current(),
+                                meta.billed_units.input_tokens as u64,
+                                None,
+                            );
+                        }
                         None => tracing::info!(target: "rig",
                             "Cohere embeddings billed units: n/a",
                         ),
@@ -256,7 +281,7 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
                     Ok(response
                         .embeddings
                         .into_iter()
-                        .zip(documents.into_iter())
+                        .zip(documents)
                         .map(|(embedding, document)| embeddings::Embedding {
                             document,
                             vec: embedding,
@@ -282,6 +307,123 @@ impl EmbeddingModel {
     }
 }
 
+// ================================================================
+// Cohere Rerank API
+// ================================================================
+/// `rerank-v3.5` rerank model
+pub const RERANK_V3_5: &str = "rerank-v3.5";
+/// `rerank-english-v3.0` rerank model
+pub const RERANK_ENGLISH_V3: &str = "rerank-english-v3.0";
+/// `rerank-multilingual-v3.0` rerank model
+pub const RERANK_MULTILINGUAL_V3: &str = "rerank-multilingual-v3.0";
+
+#[derive(Deserialize)]
+pub struct RerankResponse {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub results: Vec<RerankResponseResult>,
+    #[serde(default)]
+    pub meta: Option<Meta>,
+}
+
+#[derive(Deserialize)]
+pub struct RerankResponseResult {
+    pub index: usize,
+    pub relevance_score: f64,
+}
+
+#[derive(Clone)]
+pub struct RerankModel {
+    client: Client,
+    pub model: String,
+}
+
+impl RerankModel {
+    pub fn new(client: Client, model: &str) -> Self {
+        Self {
+            client,
+            model: model.to_string(),
+        }
+    }
+}
+
+impl rerank::Reranker for RerankModel {
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn rerank(
+        &self,
+        query: &str,
+        documents: &[String],
+        top_k: usize,
+    ) -> Result<Vec<rerank::RerankResult>, RerankError> {
+        if documents.is_empty() || top_k == 0 {
+            return Ok(vec![]);
+        }
+
+        let span = telemetry::rerank_span("cohere", &self.model, documents.len(), top_k);
+        telemetry::instrument(span, self.send_rerank(query, documents, top_k)).await
+    }
+}
+
+impl RerankModel {
+    async fn send_rerank(
+        &self,
+        query: &str,
+        documents: &[String],
+        top_k: usize,
+    ) -> Result<Vec<rerank::RerankResult>, RerankError> {
+        let response = self
+            .client
+            .post("/v1/rerank")
+            .json(&json!({
+                "model": self.model,
+                "query": query,
+                "documents": documents,
+                "top_n": top_k.min(documents.len()),
+            }))
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            match response.json::<ApiResponse<RerankResponse>>().await? {
+                ApiResponse::Ok(response) => {
+                    if let Some(meta) = &response.meta {
+                        tracing::info!(target: "rig",
+                            "Cohere rerank billed units: {}",
+                            meta.billed_units,
+                        );
+                        telemetry::record_usage(
+                            &tracing::Span::current(),
+                            meta.billed_units.search_units as u64,
+                            None,
+                        );
+                    }
+
+                    response
+                        .results
+                        .into_iter()
+                        .map(|result| {
+                            if result.index < documents.len() {
+                                Ok(rerank::RerankResult {
+                                    index: result.index,
+                                    score: result.relevance_score,
+                                })
+                            } else {
+                                Err(RerankError::ResponseError(format!(
+                                    "Document index {} out of range",
+                                    result.index
+                                )))
+                            }
+                        })
+                        .collect()
+                }
+                ApiResponse::Err(error) => Err(RerankError::ProviderError(error.message)),
+            }
+        } else {
+            Err(RerankError::ProviderError(response.text().await?))
+        }
+    }
+}
+
 // ================================================================
 // Cohere Completion API
 // ================================================================
@@ -557,10 +699,56 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = CompletionResponse;
 
+    fn context_window(&self) -> Option<u64> {
+        completion::context_window::model_context_window(&self.model)
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: completion::CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        let span = telemetry::completion_span("cohere", &self.model, &completion_request);
+        telemetry::instrument_completion(span, self.send_completion(completion_request)).await
+    }
+    /// Cohere always cites the documents of the request (identified by their `id` field)
+    fn supports_citations(&self) -> bool {
+        true
+    }
+
+    fn citations(
+        &self,
+        response: &completion::CompletionResponse<CompletionResponse>,
+        _documents: &[completion::Document],
+    ) -> completion::CitedText {
+        let text = &response.raw_response.text;
+
+        completion::CitedText {
+            text: text.clone(),
+            citations: response
+                .raw_response
+                .citations
+                .iter()
+                .map(|citation| {
+                    // Cohere's offsets are character offsets
+                    let start = completion::citation::byte_offset(text, citation.start as usize);
+                    let end = completion::citation::byte_offset(text, citation.end as usize);
+                    completion::Citation {
+                        start,
+                        end,
+                        text: text[start..end].to_string(),
+                        document_ids: citation.document_ids.clone(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl CompletionModel {
+    async fn send_completion(
+        &self,
+        completion_request: completion::CompletionRequest,
     ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
         let chat_history = completion_request
             .chat_history
@@ -613,7 +801,18 @@ impl completion::CompletionModel for CompletionModel {
 
         if response.status().is_success() {
             match response.json::<ApiResponse<CompletionResponse>>().await? {
-                ApiResponse::Ok(completion) => Ok(completion.into()),
+                ApiResponse::Ok(completion) => {
+                    telemetry::record_response_meta(
+                        &tracing::Span::current(),
+                        &completion.generation_id,
+                        &self.model,
+                    );
+                    telemetry::record_finish_reasons(
+                        &tracing::Span::current(),
+                        [&completion.finish_reason],
+                    );
+                    Ok(completion.into())
+                }
                 ApiResponse::Err(error) => Err(CompletionError::ProviderError(error.message)),
             }
         } else {
@@ -13,7 +13,7 @@ use crate::{
     extractor::ExtractorBuilder,
     json_utils,
     providers::openai::Message,
-    OneOrMany,
+    telemetry, OneOrMany,
 };
 use reqwest::Client as HttpClient;
 use schemars::JsonSchema;
@@ -204,6 +204,10 @@ pub struct DeepSeekCompletionModel {
 impl CompletionModel for DeepSeekCompletionModel {
     type Response = CompletionResponse;
 
+    fn context_window(&self) -> Option<u64> {
+        completion::context_window::model_context_window(&self.model)
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
@@ -211,6 +215,19 @@ impl CompletionModel for DeepSeekCompletionModel {
     ) -> Result<
         completion::CompletionResponse<CompletionResponse>,
         crate::completion::CompletionError,
+    > {
+        let span = telemetry::completion_span("deepseek", &self.model, &completion_request);
+        telemetry::instrument_completion(span, self.send_completion(completion_request)).await
+    }
+}
+
+impl DeepSeekCompletionModel {
+    async fn send_completion(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<
+        completion::CompletionResponse<CompletionResponse>,
+        crate::completion::CompletionError,
     > {
         // Add preamble to chat history (if available)
         let mut full_history: Vec<Message> = match &completion_request.preamble {
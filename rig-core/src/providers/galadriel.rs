@@ -14,7 +14,7 @@ use crate::{
     agent::AgentBuilder,
     completion::{self, CompletionError, CompletionRequest},
     extractor::ExtractorBuilder,
-    json_utils, message, OneOrMany,
+    json_utils, message, telemetry, OneOrMany,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -403,10 +403,24 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = CompletionResponse;
 
+    fn context_window(&self) -> Option<u64> {
+        completion::context_window::model_context_window(&self.model)
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        let span = telemetry::completion_span("galadriel", &self.model, &completion_request);
+        telemetry::instrument_completion(span, self.send_completion(completion_request)).await
+    }
+}
+
+impl CompletionModel {
+    async fn send_completion(
+        &self,
+        completion_request: CompletionRequest,
     ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
         // Add preamble to chat history (if available)
         let mut full_history: Vec<Message> = match &completion_request.preamble {
@@ -471,6 +485,22 @@ impl completion::CompletionModel for CompletionModel {
                         "Galadriel completion token usage: {:?}",
                         response.usage.clone().map(|usage| format!("{usage}")).unwrap_or("N/A".to_string())
                     );
+                    if let Some(usage) = &response.usage {
+                        telemetry::record_usage(
+                            &tracing::Span::current(),
+                            usage.prompt_tokens as u64,
+                            Some(usage.total_tokens.saturating_sub(usage.prompt_tokens) as u64),
+                        );
+                    }
+                    telemetry::record_response_meta(
+                        &tracing::Span::current(),
+                        &response.id,
+                        &response.model,
+                    );
+                    telemetry::record_finish_reasons(
+                        &tracing::Span::current(),
+                        response.choices.iter().map(|choice| &choice.finish_reason),
+                    );
                     response.try_into()
                 }
                 ApiResponse::Err(err) => Err(CompletionError::ProviderError(err.message)),
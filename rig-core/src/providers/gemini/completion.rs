@@ -21,7 +21,7 @@ use std::convert::TryFrom;
 
 use crate::{
     completion::{self, CompletionError, CompletionRequest},
-    OneOrMany,
+    telemetry, OneOrMany,
 };
 
 use super::Client;
@@ -48,8 +48,22 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = GenerateContentResponse;
 
+    fn context_window(&self) -> Option<u64> {
+        completion::context_window::model_context_window(&self.model)
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<GenerateContentResponse>, CompletionError> {
+        let span = telemetry::completion_span("gcp.gemini", &self.model, &completion_request);
+        telemetry::instrument_completion(span, self.send_completion(completion_request)).await
+    }
+}
+
+impl CompletionModel {
+    async fn send_completion(
         &self,
         mut completion_request: CompletionRequest,
     ) -> Result<completion::CompletionResponse<GenerateContentResponse>, CompletionError> {
@@ -113,15 +127,34 @@ impl completion::CompletionModel for CompletionModel {
             .await?;
 
         match response.usage_metadata {
-            Some(ref usage) => tracing::info!(target: "rig",
-            "Gemini completion token usage: {}",
-            usage
-            ),
+            Some(ref usage) => {
+                tracing::info!(target: "rig",
+                "Gemini completion token usage: {}",
+                usage
+                );
+                telemetry::record_usage(
+                    &tracing::Span::current(),
+                    usage.prompt_token_count as u64,
+                    Some(usage.candidates_token_count as u64),
+                );
+            }
             None => tracing::info!(target: "rig",
                 "Gemini completion token usage: n/a",
             ),
         }
 
+        if let Some(model_version) = &response.model_version {
+            tracing::Span::current().record("gen_ai.response.model", model_version.as_str());
+        }
+        telemetry::record_finish_reasons(
+            &tracing::Span::current(),
+            response
+                .candidates
+                .iter()
+                .filter_map(|candidate| candidate.finish_reason.as_ref())
+                .map(|reason| format!("{reason:?}")),
+        );
+
         tracing::debug!("Received response");
 
         completion::CompletionResponse::try_from(response)
@@ -5,7 +5,10 @@
 
 use serde_json::json;
 
-use crate::embeddings::{self, EmbeddingError};
+use crate::{
+    embeddings::{self, EmbeddingError},
+    telemetry,
+};
 
 use super::{client::ApiResponse, Client};
 
@@ -47,6 +50,16 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
         documents: impl IntoIterator<Item = String> + Send,
     ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
         let documents: Vec<_> = documents.into_iter().collect();
+        let span = telemetry::embeddings_span("gcp.gemini", &self.model, documents.len());
+        telemetry::instrument(span, self.send_embeddings(documents)).await
+    }
+}
+
+impl EmbeddingModel {
+    async fn send_embeddings(
+        &self,
+        documents: Vec<String>,
+    ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
         let mut request_body = json!({
             "model": format!("models/{}", self.model),
             "content": {
@@ -70,7 +83,9 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
 
         match response {
             ApiResponse::Ok(response) => {
-                let chunk_size = self.ndims.unwrap_or_else(|| self.ndims());
+                let chunk_size = self
+                    .ndims
+                    .unwrap_or_else(|| embeddings::EmbeddingModel::ndims(self));
                 Ok(documents
                     .into_iter()
                     .zip(response.embedding.values.chunks(chunk_size))
@@ -15,7 +15,7 @@ use crate::{
     extractor::ExtractorBuilder,
     json_utils,
     providers::openai::Message,
-    OneOrMany,
+    telemetry, OneOrMany,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -278,10 +278,24 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = CompletionResponse;
 
+    fn context_window(&self) -> Option<u64> {
+        completion::context_window::model_context_window(&self.model)
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        let span = telemetry::completion_span("hyperbolic", &self.model, &completion_request);
+        telemetry::instrument_completion(span, self.send_completion(completion_request)).await
+    }
+}
+
+impl CompletionModel {
+    async fn send_completion(
+        &self,
+        completion_request: CompletionRequest,
     ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
         // Add preamble to chat history (if available)
         let mut full_history: Vec<Message> = match &completion_request.preamble {
@@ -332,6 +346,22 @@ impl completion::CompletionModel for CompletionModel {
                         "Hyperbolic completion token usage: {:?}",
                         response.usage.clone().map(|usage| format!("{usage}")).unwrap_or("N/A".to_string())
                     );
+                    if let Some(usage) = &response.usage {
+                        telemetry::record_usage(
+                            &tracing::Span::current(),
+                            usage.prompt_tokens as u64,
+                            Some(usage.total_tokens.saturating_sub(usage.prompt_tokens) as u64),
+                        );
+                    }
+                    telemetry::record_response_meta(
+                        &tracing::Span::current(),
+                        &response.id,
+                        &response.model,
+                    );
+                    telemetry::record_finish_reasons(
+                        &tracing::Span::current(),
+                        response.choices.iter().map(|choice| &choice.finish_reason),
+                    );
 
                     response.try_into()
                 }
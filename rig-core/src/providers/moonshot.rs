@@ -15,6 +15,7 @@ use crate::{
     extractor::ExtractorBuilder,
     json_utils,
     providers::openai,
+    telemetry,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -138,10 +139,24 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = openai::CompletionResponse;
 
+    fn context_window(&self) -> Option<u64> {
+        completion::context_window::model_context_window(&self.model)
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<openai::CompletionResponse>, CompletionError> {
+        let span = telemetry::completion_span("moonshot", &self.model, &completion_request);
+        telemetry::instrument_completion(span, self.send_completion(completion_request)).await
+    }
+}
+
+impl CompletionModel {
+    async fn send_completion(
+        &self,
+        completion_request: CompletionRequest,
     ) -> Result<completion::CompletionResponse<openai::CompletionResponse>, CompletionError> {
         // Add preamble to chat history (if available)
         let mut full_history: Vec<openai::Message> = match &completion_request.preamble {
@@ -205,6 +220,22 @@ impl completion::CompletionModel for CompletionModel {
                         "Azure completion token usage: {:?}",
                         response.usage.clone().map(|usage| format!("{usage}")).unwrap_or("N/A".to_string())
                     );
+                    if let Some(usage) = &response.usage {
+                        telemetry::record_usage(
+                            &tracing::Span::current(),
+                            usage.prompt_tokens as u64,
+                            Some(usage.total_tokens.saturating_sub(usage.prompt_tokens) as u64),
+                        );
+                    }
+                    telemetry::record_response_meta(
+                        &tracing::Span::current(),
+                        &response.id,
+                        &response.model,
+                    );
+                    telemetry::record_finish_reasons(
+                        &tracing::Span::current(),
+                        response.choices.iter().map(|choice| &choice.finish_reason),
+                    );
                     response.try_into()
                 }
                 ApiResponse::Err(err) => Err(CompletionError::ProviderError(err.error.message)),
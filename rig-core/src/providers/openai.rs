@@ -8,7 +8,7 @@
 //!
 //! let gpt4o = client.completion_model(openai::GPT_4O);
 //! ```
-use std::{convert::Infallible, str::FromStr};
+use std::{convert::Infallible, str::FromStr, sync::Arc};
 
 use crate::{
     agent::AgentBuilder,
@@ -18,6 +18,8 @@ use crate::{
     json_utils,
     message::{self, AudioMediaType, ImageDetail},
     one_or_many::string_or_one_or_many,
+    telemetry,
+    tokenizer::{self, Tokenizer},
     Embed, OneOrMany,
 };
 use schemars::JsonSchema;
@@ -250,6 +252,10 @@ pub struct EmbeddingModel {
 impl embeddings::EmbeddingModel for EmbeddingModel {
     const MAX_DOCUMENTS: usize = 1024;
 
+    fn tokenizer(&self) -> Arc<dyn Tokenizer> {
+        tokenizer::for_model(&self.model)
+    }
+
     fn ndims(&self) -> usize {
         self.ndims
     }
@@ -260,7 +266,16 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
         documents: impl IntoIterator<Item = String>,
     ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
         let documents = documents.into_iter().collect::<Vec<_>>();
+        let span = telemetry::embeddings_span("openai", &self.model, documents.len());
+        telemetry::instrument(span, self.send_embeddings(documents)).await
+    }
+}
 
+impl EmbeddingModel {
+    async fn send_embeddings(
+        &self,
+        documents: Vec<String>,
+    ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
         let response = self
             .client
             .post("/embeddings")
@@ -278,6 +293,11 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
                         "OpenAI embedding token usage: {}",
                         response.usage
                     );
+                    telemetry::record_usage(
+                        &tracing::Span::current(),
+                        response.usage.prompt_tokens as u64,
+                        None,
+                    );
 
                     if response.data.len() != documents.len() {
                         return Err(EmbeddingError::ResponseError(
@@ -288,7 +308,7 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
                     Ok(response
                         .data
                         .into_iter()
-                        .zip(documents.into_iter())
+                        .zip(documents)
                         .map(|(embedding, document)| embeddings::Embedding {
                             document,
                             vec: embedding.embedding,
@@ -869,10 +889,28 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = CompletionResponse;
 
+    fn context_window(&self) -> Option<u64> {
+        completion::context_window::model_context_window(&self.model)
+    }
+
+    fn tokenizer(&self) -> Arc<dyn Tokenizer> {
+        tokenizer::for_model(&self.model)
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        let span = telemetry::completion_span("openai", &self.model, &completion_request);
+        telemetry::instrument_completion(span, self.send_completion(completion_request)).await
+    }
+}
+
+impl CompletionModel {
+    async fn send_completion(
+        &self,
+        completion_request: CompletionRequest,
     ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
         // Add preamble to chat history (if available)
         let mut full_history: Vec<Message> = match &completion_request.preamble {
@@ -936,6 +974,22 @@ impl completion::CompletionModel for CompletionModel {
                         "OpenAI completion token usage: {:?}",
                         response.usage.clone().map(|usage| format!("{usage}")).unwrap_or("N/A".to_string())
                     );
+                    if let Some(usage) = &response.usage {
+                        telemetry::record_usage(
+                            &tracing::Span::current(),
+                            usage.prompt_tokens as u64,
+                            Some(usage.total_tokens.saturating_sub(usage.prompt_tokens) as u64),
+                        );
+                    }
+                    telemetry::record_response_meta(
+                        &tracing::Span::current(),
+                        &response.id,
+                        &response.model,
+                    );
+                    telemetry::record_finish_reasons(
+                        &tracing::Span::current(),
+                        response.choices.iter().map(|choice| &choice.finish_reason),
+                    );
                     response.try_into()
                 }
                 ApiResponse::Err(err) => Err(CompletionError::ProviderError(err.message)),
@@ -13,7 +13,7 @@ use crate::{
     agent::AgentBuilder,
     completion::{self, message, CompletionError, MessageError},
     extractor::ExtractorBuilder,
-    json_utils, OneOrMany,
+    json_utils, telemetry, OneOrMany,
 };
 
 use schemars::JsonSchema;
@@ -112,6 +112,9 @@ pub struct CompletionResponse {
     #[serde(default)]
     pub choices: Vec<Choice>,
     pub usage: Usage,
+    /// URLs of the web sources cited in the answer with numbered markers (e.g.: `[1]`)
+    #[serde(default)]
+    pub citations: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -260,10 +263,47 @@ impl From<Message> for message::Message {
 impl completion::CompletionModel for CompletionModel {
     type Response = CompletionResponse;
 
+    fn context_window(&self) -> Option<u64> {
+        completion::context_window::model_context_window(&self.model)
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: completion::CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        let span = telemetry::completion_span("perplexity", &self.model, &completion_request);
+        telemetry::instrument_completion(span, self.send_completion(completion_request)).await
+    }
+    /// Perplexity cites the web sources of its answers with numbered markers (e.g.: `[1]`), which
+    /// are resolved to the URLs of the sources. The documents of the request are cited using the
+    /// [CITATION_INSTRUCTIONS](completion::CITATION_INSTRUCTIONS) markers.
+    fn citations(
+        &self,
+        response: &completion::CompletionResponse<CompletionResponse>,
+        documents: &[completion::Document],
+    ) -> completion::CitedText {
+        let sources = &response.raw_response.citations;
+
+        completion::CitedText::parse(
+            &completion::citation::answer_text(&response.choice),
+            |marker| {
+                completion::citation::document_id(documents, marker).or_else(|| {
+                    marker
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|n| sources.get(n.checked_sub(1)?))
+                        .cloned()
+                })
+            },
+        )
+    }
+}
+
+impl CompletionModel {
+    async fn send_completion(
+        &self,
+        completion_request: completion::CompletionRequest,
     ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
         // Add context documents to current prompt
         let prompt_with_context = completion_request.prompt_with_context();
@@ -321,6 +361,23 @@ impl completion::CompletionModel for CompletionModel {
                         "Perplexity completion token usage: {}",
                         completion.usage
                     );
+                    telemetry::record_usage(
+                        &tracing::Span::current(),
+                        completion.usage.prompt_tokens as u64,
+                        Some(completion.usage.completion_tokens as u64),
+                    );
+                    telemetry::record_response_meta(
+                        &tracing::Span::current(),
+                        &completion.id,
+                        &completion.model,
+                    );
+                    telemetry::record_finish_reasons(
+                        &tracing::Span::current(),
+                        completion
+                            .choices
+                            .iter()
+                            .map(|choice| &choice.finish_reason),
+                    );
                     Ok(completion.try_into()?)
                 }
                 ApiResponse::Err(error) => Err(CompletionError::ProviderError(error.message)),
@@ -381,4 +438,44 @@ mod tests {
         assert_eq!(user_message, back_to_user_message);
         assert_eq!(assistant_message, back_to_assistant_message);
     }
+
+    #[test]
+    fn test_citations() {
+        let response: CompletionResponse = serde_json::from_value(json!({
+            "id": "cmpl-1",
+            "model": "sonar",
+            "object": "chat.completion",
+            "created": 1735689600,
+            "choices": [{
+                "index": 0,
+                "finish_reason": "stop",
+                "message": {
+                    "role": "assistant",
+                    "content": "SOL rallied [1][2]. Fees hit a record [doc1]."
+                },
+                "delta": {"role": "assistant", "content": ""}
+            }],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 10, "total_tokens": 20},
+            "citations": ["https://a.example", "https://b.example"]
+        }))
+        .unwrap();
+        let response: completion::CompletionResponse<CompletionResponse> =
+            response.try_into().unwrap();
+
+        let model = CompletionModel::new(Client::new("key"), "sonar");
+        let documents = vec![completion::Document {
+            id: "doc1".to_string(),
+            text: "Fees".to_string(),
+            additional_props: Default::default(),
+        }];
+        let cited = completion::CompletionModel::citations(&model, &response, &documents);
+
+        assert_eq!(cited.text, "SOL rallied. Fees hit a record.");
+        assert_eq!(
+            cited.citations[0].document_ids,
+            vec!["https://a.example", "https://b.example"]
+        );
+        assert_eq!(cited.citations[1].text, "Fees hit a record.");
+        assert_eq!(cited.citations[1].document_ids, vec!["doc1"]);
+    }
 }
@@ -7,6 +7,7 @@ use crate::{
     completion::{self, CompletionError},
     json_utils,
     providers::openai::Message,
+    telemetry,
 };
 
 use serde_json::json;
@@ -39,10 +40,24 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = CompletionResponse;
 
+    fn context_window(&self) -> Option<u64> {
+        completion::context_window::model_context_window(&self.model)
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: completion::CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        let span = telemetry::completion_span("xai", &self.model, &completion_request);
+        telemetry::instrument_completion(span, self.send_completion(completion_request)).await
+    }
+}
+
+impl CompletionModel {
+    async fn send_completion(
+        &self,
+        completion_request: completion::CompletionRequest,
     ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
         // Add preamble to chat history (if available)
         let mut full_history: Vec<Message> = match &completion_request.preamble {
@@ -98,7 +113,26 @@ impl completion::CompletionModel for CompletionModel {
 
         if response.status().is_success() {
             match response.json::<ApiResponse<CompletionResponse>>().await? {
-                ApiResponse::Ok(completion) => completion.try_into(),
+                ApiResponse::Ok(completion) => {
+                    telemetry::record_usage(
+                        &tracing::Span::current(),
+                        completion.usage.prompt_tokens as u64,
+                        Some(completion.usage.completion_tokens as u64),
+                    );
+                    telemetry::record_response_meta(
+                        &tracing::Span::current(),
+                        &completion.id,
+                        &completion.model,
+                    );
+                    telemetry::record_finish_reasons(
+                        &tracing::Span::current(),
+                        completion
+                            .choices
+                            .iter()
+                            .map(|choice| &choice.finish_reason),
+                    );
+                    completion.try_into()
+                }
                 ApiResponse::Error(error) => Err(CompletionError::ProviderError(error.message())),
             }
         } else {
@@ -0,0 +1,268 @@
+//! This module provides the [Reranker] trait, which reorders a list of candidate documents by
+//! relevance to a query.
+//!
+//! Vector search ranks documents by embedding similarity, which is fast but coarse. A reranker
+//! scores each (query, document) pair jointly, which is slower but usually more accurate. The
+//! typical flow is therefore to over-fetch candidates from a vector store index, rerank them
+//! against the query and keep the best few (see [AgentBuilder::reranker](crate::agent::AgentBuilder::reranker)
+//! and [PipelineBuilder::lookup_reranked](crate::pipeline::PipelineBuilder::lookup_reranked)).
+//!
+//! Rig provides the following rerankers:
+//! - [cohere::RerankModel](crate::providers::cohere::RerankModel): Cohere's `rerank` endpoint
+//! - [LlmReranker]: a fallback that asks any [CompletionModel] to score the candidates
+//!
+//! # Example
+//! ```rust
+//! use rig::providers::{cohere, openai};
+//!
+//! let reranker = cohere::Client::from_env().rerank_model(cohere::RERANK_V3_5);
+//!
+//! // Fetch 20 candidates from the index and keep the 4 most relevant ones
+//! let agent = openai::Client::from_env()
+//!     .agent(openai::GPT_4O)
+//!     .dynamic_context(4, index)
+//!     .reranker(reranker, 20)
+//!     .build();
+//! ```
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    completion::{citation::answer_text, CompletionError, CompletionModel},
+    vector_store::VectorStoreError,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RerankError {
+    /// Http error (e.g.: connection error, timeout, etc.)
+    #[error("HttpError: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    /// Json error (e.g.: serialization, deserialization)
+    #[error("JsonError: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    /// Error returned by the completion model of an [LlmReranker]
+    #[error("CompletionError: {0}")]
+    CompletionError(#[from] CompletionError),
+
+    /// Error fetching the candidates to rerank
+    #[error("VectorStoreError: {0}")]
+    VectorStoreError(#[from] VectorStoreError),
+
+    /// Error parsing the rerank response
+    #[error("ResponseError: {0}")]
+    ResponseError(String),
+
+    /// Error returned by the rerank model provider
+    #[error("ProviderError: {0}")]
+    ProviderError(String),
+}
+
+/// Relevance of a candidate document to the query
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RerankResult {
+    /// Index of the document in the list of candidates
+    pub index: usize,
+    /// Relevance score of the document (higher is more relevant)
+    pub score: f64,
+}
+
+/// Trait for rerankers, i.e.: models ordering documents by relevance to a query
+pub trait Reranker: Send + Sync {
+    /// Score `documents` against `query` and return the `top_k` most relevant ones, sorted by
+    /// decreasing relevance.
+    fn rerank(
+        &self,
+        query: &str,
+        documents: &[String],
+        top_k: usize,
+    ) -> impl std::future::Future<Output = Result<Vec<RerankResult>, RerankError>> + Send;
+}
+
+pub type RerankResults = Result<Vec<RerankResult>, RerankError>;
+
+/// Object-safe version of [Reranker], used to store rerankers in agents
+pub trait RerankerDyn: Send + Sync {
+    fn rerank<'a>(
+        &'a self,
+        query: &'a str,
+        documents: &'a [String],
+        top_k: usize,
+    ) -> BoxFuture<'a, RerankResults>;
+}
+
+impl<R: Reranker> RerankerDyn for R {
+    fn rerank<'a>(
+        &'a self,
+        query: &'a str,
+        documents: &'a [String],
+        top_k: usize,
+    ) -> BoxFuture<'a, RerankResults> {
+        Box::pin(Reranker::rerank(self, query, documents, top_k))
+    }
+}
+
+/// Default preamble of [LlmReranker]
+pub const LLM_RERANKER_PREAMBLE: &str = "\
+    You are a search relevance judge. Given a query and a numbered list of documents, score \
+    the relevance of each document to the query between 0 (irrelevant) and 1 (answers the \
+    query). Respond only with a JSON array of objects of the form {\"index\": <document number>, \
+    \"score\": <relevance>}, sorted by decreasing score.";
+
+/// Reranker asking a completion model to score the candidate documents. Useful when no
+/// dedicated rerank model is available, at the cost of a completion call per rerank.
+///
+/// # Example
+/// ```rust
+/// use rig::{providers::openai, rerank::{LlmReranker, Reranker}};
+///
+/// let reranker = LlmReranker::new(openai::Client::from_env().completion_model(openai::GPT_4O_MINI));
+///
+/// let results = reranker.rerank("What is a flurbo?", &documents, 3).await?;
+/// ```
+#[derive(Clone)]
+pub struct LlmReranker<M: CompletionModel> {
+    model: M,
+    preamble: String,
+}
+
+impl<M: CompletionModel> LlmReranker<M> {
+    pub fn new(model: M) -> Self {
+        Self {
+            model,
+            preamble: LLM_RERANKER_PREAMBLE.to_string(),
+        }
+    }
+
+    /// Override the instructions given to the model
+    pub fn preamble(mut self, preamble: &str) -> Self {
+        self.preamble = preamble.to_string();
+        self
+    }
+}
+
+impl<M: CompletionModel> Reranker for LlmReranker<M> {
+    async fn rerank(
+        &self,
+        query: &str,
+        documents: &[String],
+        top_k: usize,
+    ) -> Result<Vec<RerankResult>, RerankError> {
+        if documents.is_empty() || top_k == 0 {
+            return Ok(vec![]);
+        }
+
+        let candidates = documents
+            .iter()
+            .enumerate()
+            .map(|(i, document)| format!("<document index=\"{i}\">\n{document}\n</document>"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let response = self
+            .model
+            .completion_request(format!("Query: {query}\n\nDocuments:\n{candidates}"))
+            .preamble(self.preamble.clone())
+            .temperature(0.0)
+            .send()
+            .await?;
+
+        parse_llm_scores(&answer_text(&response.choice), documents.len(), top_k)
+    }
+}
+
+/// Parse the JSON array of scores written by an [LlmReranker]'s model, ignoring any text
+/// around it (e.g.: Markdown code fences) and indices out of range.
+fn parse_llm_scores(
+    text: &str,
+    num_documents: usize,
+    top_k: usize,
+) -> Result<Vec<RerankResult>, RerankError> {
+    let json = match (text.find('['), text.rfind(']')) {
+        (Some(start), Some(end)) if start < end => &text[start..=end],
+        _ => {
+            return Err(RerankError::ResponseError(format!(
+                "Expected a JSON array of scores, got: {text}"
+            )))
+        }
+    };
+
+    let mut results = serde_json::from_str::<Vec<RerankResult>>(json)?
+        .into_iter()
+        .filter(|result| result.index < num_documents)
+        .fold(Vec::<RerankResult>::new(), |mut acc, result| {
+            if !acc.iter().any(|r| r.index == result.index) {
+                acc.push(result);
+            }
+            acc
+        });
+
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+    results.truncate(top_k);
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        completion::{CompletionRequest, CompletionResponse},
+        message::AssistantContent,
+        OneOrMany,
+    };
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct ScoringModel;
+
+    impl CompletionModel for ScoringModel {
+        type Response = ();
+
+        async fn completion(
+            &self,
+            _request: CompletionRequest,
+        ) -> Result<CompletionResponse<()>, CompletionError> {
+            Ok(CompletionResponse {
+                choice: OneOrMany::one(AssistantContent::text(
+                    "```json\n[{\"index\": 1, \"score\": 0.4}, {\"index\": 2, \"score\": 0.9}, \
+                     {\"index\": 7, \"score\": 1.0}, {\"index\": 0, \"score\": 0.1}]\n```",
+                )),
+                raw_response: (),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_llm_reranker() {
+        let documents = ["a", "b", "c"].map(String::from);
+
+        let reranker = LlmReranker::new(ScoringModel);
+        let results = Reranker::rerank(&reranker, "query", &documents, 2)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            results,
+            vec![
+                RerankResult {
+                    index: 2,
+                    score: 0.9
+                },
+                RerankResult {
+                    index: 1,
+                    score: 0.4
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_llm_scores_error() {
+        assert!(matches!(
+            parse_llm_scores("I cannot rank these documents.", 3, 2),
+            Err(RerankError::ResponseError(_))
+        ));
+    }
+}
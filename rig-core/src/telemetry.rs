@@ -0,0 +1,405 @@
+//! Tracing spans following the [OpenTelemetry GenAI semantic conventions](https://opentelemetry.io/docs/specs/semconv/gen-ai/).
+//!
+//! Rig emits a span (under the `rig` target) for every call to
+//! [CompletionModel::completion](crate::completion::CompletionModel::completion),
+//! [EmbeddingModel::embed_texts](crate::embeddings::EmbeddingModel::embed_texts),
+//! [Reranker::rerank](crate::rerank::Reranker::rerank),
+//! [VectorStoreIndex::top_n](crate::vector_store::VectorStoreIndex::top_n) and
+//! [ToolSet::call](crate::tool::ToolSet::call). The span fields use the attribute names of the
+//! semantic conventions (e.g.: `gen_ai.system`, `gen_ai.request.model`, `gen_ai.usage.input_tokens`)
+//! so that, when exported through [`tracing-opentelemetry`](https://docs.rs/tracing-opentelemetry)
+//! and an OTLP exporter, they show up as standard GenAI spans.
+//!
+//! Prompts, completions, tool arguments and tool results are NOT recorded by default since they
+//! may contain sensitive data. Enable the `trace-content` feature to record them.
+//!
+//! These helpers are public so that custom [CompletionModel](crate::completion::CompletionModel)
+//! and [VectorStoreIndex](crate::vector_store::VectorStoreIndex) implementations can emit the same spans.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     completion::{
+//!         message::AssistantContent, CompletionError, CompletionModel, CompletionRequest,
+//!         CompletionResponse,
+//!     },
+//!     telemetry, OneOrMany,
+//! };
+//!
+//! /// A completion model answering with the prompt it received
+//! #[derive(Clone)]
+//! struct EchoModel;
+//!
+//! impl EchoModel {
+//!     async fn send_completion(
+//!         &self,
+//!         request: CompletionRequest,
+//!     ) -> Result<CompletionResponse<()>, CompletionError> {
+//!         let answer = serde_json::to_string(&request.prompt)?;
+//!         telemetry::record_usage(&tracing::Span::current(), answer.len() as u64, Some(0));
+//!         telemetry::record_finish_reasons(&tracing::Span::current(), ["stop"]);
+//!         Ok(CompletionResponse {
+//!             choice: OneOrMany::one(AssistantContent::text(answer)),
+//!             raw_response: (),
+//!         })
+//!     }
+//! }
+//!
+//! impl CompletionModel for EchoModel {
+//!     type Response = ();
+//!
+//!     async fn completion(
+//!         &self,
+//!         request: CompletionRequest,
+//!     ) -> Result<CompletionResponse<()>, CompletionError> {
+//!         // The span is entered while the request is sent, so that the response can be
+//!         // recorded on `Span::current()`
+//!         let span = telemetry::completion_span("echo", "echo-1", &request);
+//!         telemetry::instrument_completion(span, self.send_completion(request)).await
+//!     }
+//! }
+//! ```
+
+use std::{fmt::Display, future::Future};
+
+use tracing::{field::Empty, Instrument, Span};
+
+use crate::completion::{CompletionError, CompletionRequest, CompletionResponse};
+
+/// Create a span for a chat completion request sent to the provider `system` (e.g.: "openai")
+/// using the model `model`.
+pub fn completion_span(system: &str, model: &str, request: &CompletionRequest) -> Span {
+    let span = tracing::info_span!(
+        target: "rig",
+        "chat",
+        otel.name = %format!("chat {model}"),
+        otel.kind = "client",
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+        gen_ai.operation.name = "chat",
+        gen_ai.system = system,
+        gen_ai.request.model = model,
+        gen_ai.request.temperature = request.temperature,
+        gen_ai.request.max_tokens = request.max_tokens,
+        gen_ai.response.id = Empty,
+        gen_ai.response.model = Empty,
+        gen_ai.response.finish_reasons = Empty,
+        gen_ai.usage.input_tokens = Empty,
+        gen_ai.usage.output_tokens = Empty,
+        gen_ai.prompt = Empty,
+        gen_ai.completion = Empty,
+        error.type = Empty,
+    );
+
+    #[cfg(feature = "trace-content")]
+    span.record(
+        "gen_ai.prompt",
+        serde_json::json!({
+            "preamble": request.preamble,
+            "chat_history": request.chat_history,
+            "prompt": request.prompt_with_context(),
+        })
+        .to_string()
+        .as_str(),
+    );
+
+    span
+}
+
+/// Create a span for an embeddings request of `count` documents sent to the provider `system`
+/// using the model `model`.
+pub fn embeddings_span(system: &str, model: &str, count: usize) -> Span {
+    tracing::info_span!(
+        target: "rig",
+        "embeddings",
+        otel.name = %format!("embeddings {model}"),
+        otel.kind = "client",
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+        gen_ai.operation.name = "embeddings",
+        gen_ai.system = system,
+        gen_ai.request.model = model,
+        gen_ai.request.documents = count,
+        gen_ai.usage.input_tokens = Empty,
+        error.type = Empty,
+    )
+}
+
+/// Create a span for a rerank request of `count` documents, keeping the `top_k` most relevant
+/// ones, sent to the provider `system` using the model `model`.
+pub fn rerank_span(system: &str, model: &str, count: usize, top_k: usize) -> Span {
+    tracing::info_span!(
+        target: "rig",
+        "rerank",
+        otel.name = %format!("rerank {model}"),
+        otel.kind = "client",
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+        gen_ai.operation.name = "rerank",
+        gen_ai.system = system,
+        gen_ai.request.model = model,
+        gen_ai.request.documents = count,
+        gen_ai.request.top_k = top_k,
+        gen_ai.usage.input_tokens = Empty,
+        error.type = Empty,
+    )
+}
+
+/// Create a span for a vector search of the `n` closest documents to `query` in the
+/// vector store `system` (e.g.: "in_memory", "mongodb").
+pub fn retrieval_span(system: &str, query: &str, n: usize) -> Span {
+    let span = tracing::info_span!(
+        target: "rig",
+        "top_n",
+        otel.name = %format!("top_n {system}"),
+        otel.kind = "client",
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+        db.system = system,
+        db.operation.name = "top_n",
+        db.vector.query.top_k = n,
+        db.response.returned_rows = Empty,
+        db.query.text = Empty,
+        error.type = Empty,
+    );
+
+    #[cfg(feature = "trace-content")]
+    span.record("db.query.text", query);
+    #[cfg(not(feature = "trace-content"))]
+    let _ = query;
+
+    span
+}
+
+/// Create a span for a vector search of the `n` closest documents to a query embedding of
+/// `ndims` dimensions in the vector store `system` (e.g.: "in_memory", "mongodb").
+pub fn vector_retrieval_span(system: &str, ndims: usize, n: usize) -> Span {
+    tracing::info_span!(
+        target: "rig",
+        "top_n_by_vector",
+        otel.name = %format!("top_n_by_vector {system}"),
+        otel.kind = "client",
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+        db.system = system,
+        db.operation.name = "top_n_by_vector",
+        db.vector.query.top_k = n,
+        db.vector.query.dimensions = ndims,
+        db.response.returned_rows = Empty,
+        error.type = Empty,
+    )
+}
+
+/// Create a span for the execution of the tool `name` with arguments `args`.
+pub fn tool_span(name: &str, args: &str) -> Span {
+    let span = tracing::info_span!(
+        target: "rig",
+        "execute_tool",
+        otel.name = %format!("execute_tool {name}"),
+        otel.kind = "internal",
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+        gen_ai.operation.name = "execute_tool",
+        gen_ai.tool.name = name,
+        gen_ai.tool.call.arguments = Empty,
+        gen_ai.tool.call.result = Empty,
+        error.type = Empty,
+    );
+
+    #[cfg(feature = "trace-content")]
+    span.record("gen_ai.tool.call.arguments", args);
+    #[cfg(not(feature = "trace-content"))]
+    let _ = args;
+
+    span
+}
+
+/// Record the token usage of a completion or embeddings request on `span`.
+pub fn record_usage(span: &Span, input_tokens: u64, output_tokens: Option<u64>) {
+    span.record("gen_ai.usage.input_tokens", input_tokens);
+    if let Some(output_tokens) = output_tokens {
+        span.record("gen_ai.usage.output_tokens", output_tokens);
+    }
+}
+
+/// Record the id and model of a completion response on `span`.
+pub fn record_response_meta(span: &Span, id: &str, model: &str) {
+    span.record("gen_ai.response.id", id);
+    span.record("gen_ai.response.model", model);
+}
+
+/// Record the reason(s) the model stopped generating tokens on `span`.
+pub fn record_finish_reasons<S: AsRef<str>>(span: &Span, reasons: impl IntoIterator<Item = S>) {
+    let reasons = reasons
+        .into_iter()
+        .map(|reason| reason.as_ref().to_string())
+        .collect::<Vec<_>>();
+    if !reasons.is_empty() {
+        span.record("gen_ai.response.finish_reasons", reasons.join(",").as_str());
+    }
+}
+
+/// Record the completion choice on `span` (only if the `trace-content` feature is enabled).
+pub fn record_completion<T>(span: &Span, response: &CompletionResponse<T>) {
+    #[cfg(feature = "trace-content")]
+    span.record(
+        "gen_ai.completion",
+        serde_json::to_string(&response.choice)
+            .unwrap_or_default()
+            .as_str(),
+    );
+    #[cfg(not(feature = "trace-content"))]
+    let _ = (span, response);
+}
+
+/// Record the number of documents returned by a vector search on `span`.
+pub fn record_returned(span: &Span, count: usize) {
+    span.record("db.response.returned_rows", count);
+}
+
+/// Record the result of a tool call on `span` (only if the `trace-content` feature is enabled).
+pub fn record_tool_result(span: &Span, result: &str) {
+    #[cfg(feature = "trace-content")]
+    span.record("gen_ai.tool.call.result", result);
+    #[cfg(not(feature = "trace-content"))]
+    let _ = (span, result);
+}
+
+/// Record an error on `span`: the type of the error as `error.type` (a low-cardinality
+/// attribute, as required by the semantic conventions) and its message as the status message.
+pub fn record_error<E: Display>(span: &Span, error: &E) {
+    span.record("otel.status_code", "ERROR");
+    span.record("otel.status_message", error.to_string().as_str());
+    span.record("error.type", std::any::type_name::<E>());
+}
+
+/// Record the outcome of `result` on `span`, returning `result` unchanged.
+pub fn record_result<T, E: Display>(span: &Span, result: Result<T, E>) -> Result<T, E> {
+    if let Err(e) = &result {
+        record_error(span, e);
+    }
+    result
+}
+
+/// Instrument a completion `future` with `span`, recording the completion choice or the error
+/// on the span once the future resolves.
+pub async fn instrument_completion<T>(
+    span: Span,
+    future: impl Future<Output = Result<CompletionResponse<T>, CompletionError>>,
+) -> Result<CompletionResponse<T>, CompletionError> {
+    let result = future.instrument(span.clone()).await;
+    if let Ok(response) = &result {
+        record_completion(&span, response);
+    }
+    record_result(&span, result)
+}
+
+/// Instrument `future` with `span`, recording the error (if any) on the span once the future resolves.
+pub async fn instrument<T, E: Display>(
+    span: Span,
+    future: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let result = future.instrument(span.clone()).await;
+    record_result(&span, result)
+}
+
+/// Instrument a vector search `future` with `span`, recording the number of returned documents
+/// or the error on the span once the future resolves.
+pub async fn instrument_retrieval<T, E: Display>(
+    span: Span,
+    future: impl Future<Output = Result<Vec<T>, E>>,
+) -> Result<Vec<T>, E> {
+    let result = instrument(span.clone(), future).await;
+    if let Ok(results) = &result {
+        record_returned(&span, results.len());
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+    use super::*;
+
+    /// Layer collecting the fields recorded on every span
+    #[derive(Clone, Default)]
+    struct FieldRecorder(Arc<Mutex<HashMap<String, String>>>);
+
+    impl Visit for FieldRecorder {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for FieldRecorder {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            attrs.record(&mut self.clone());
+        }
+
+        fn on_record(&self, _id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+            values.record(&mut self.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_span() {
+        let recorder = FieldRecorder::default();
+        let _guard = tracing_subscriber::registry()
+            .with(recorder.clone())
+            .set_default();
+
+        let span = tool_span("add", r#"{"x": 1, "y": 2}"#);
+        let result = instrument(span.clone(), async { Err::<String, _>("boom") }).await;
+        assert!(result.is_err());
+
+        let fields = recorder.0.lock().unwrap();
+        assert_eq!(fields["gen_ai.operation.name"], "execute_tool");
+        assert_eq!(fields["gen_ai.tool.name"], "add");
+        assert_eq!(fields["otel.status_code"], "ERROR");
+        assert_eq!(fields["otel.status_message"], "boom");
+        assert_eq!(fields["error.type"], "&str");
+        assert_eq!(
+            fields.contains_key("gen_ai.tool.call.arguments"),
+            cfg!(feature = "trace-content")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retrieval_span() {
+        let recorder = FieldRecorder::default();
+        let _guard = tracing_subscriber::registry()
+            .with(recorder.clone())
+            .set_default();
+
+        let span = retrieval_span("in_memory", "What is a flurbo?", 3);
+        let result = instrument_retrieval(span, async { Ok::<_, String>(vec![1, 2]) }).await;
+        assert_eq!(result, Ok(vec![1, 2]));
+
+        let fields = recorder.0.lock().unwrap();
+        assert_eq!(fields["db.system"], "in_memory");
+        assert_eq!(fields["db.vector.query.top_k"], "3");
+        assert_eq!(fields["db.response.returned_rows"], "2");
+        assert!(!fields.contains_key("error.type"));
+    }
+}
@@ -0,0 +1,357 @@
+//! Offline byte pair encoding (BPE) tokenizer compatible with OpenAI's tiktoken encodings.
+use std::{collections::HashMap, path::Path};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use fancy_regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::{Tokenizer, TokenizerError};
+
+/// Pre-tokenization pattern of the `cl100k_base` encoding
+const CL100K_PATTERN: &str = r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+(?!\S)|\s+";
+
+/// Pre-tokenization pattern of the `o200k_base` encoding
+const O200K_PATTERN: &str = concat!(
+    r"[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]*[\p{Ll}\p{Lm}\p{Lo}\p{M}]+(?i:'s|'t|'re|'ve|'m|'ll|'d)?",
+    r"|[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]+[\p{Ll}\p{Lm}\p{Lo}\p{M}]*(?i:'s|'t|'re|'ve|'m|'ll|'d)?",
+    r"|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n/]*|\s*[\r\n]+|\s+(?!\S)|\s+",
+);
+
+/// OpenAI byte pair encodings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    /// Encoding of GPT-4, GPT-3.5 and the `text-embedding-*` models
+    Cl100kBase,
+    /// Encoding of GPT-4o and the o-series models
+    O200kBase,
+}
+
+impl Encoding {
+    /// Name of the encoding, also used as the name of its vocabulary file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Cl100kBase => "cl100k_base",
+            Self::O200kBase => "o200k_base",
+        }
+    }
+
+    /// Encoding of an OpenAI model given its name, if known
+    pub fn for_model(model: &str) -> Option<Self> {
+        const O200K_PREFIXES: [&str; 6] =
+            ["gpt-4o", "chatgpt-4o", "gpt-4.1", "gpt-4.5", "o1", "o3"];
+        const CL100K_PREFIXES: [&str; 4] =
+            ["gpt-4", "gpt-3.5-turbo", "gpt-35-turbo", "text-embedding-"];
+
+        if O200K_PREFIXES
+            .iter()
+            .any(|prefix| model.starts_with(prefix))
+        {
+            Some(Self::O200kBase)
+        } else if CL100K_PREFIXES
+            .iter()
+            .any(|prefix| model.starts_with(prefix))
+        {
+            Some(Self::Cl100kBase)
+        } else {
+            None
+        }
+    }
+
+    fn pattern(&self) -> &'static str {
+        match self {
+            Self::Cl100kBase => CL100K_PATTERN,
+            Self::O200kBase => O200K_PATTERN,
+        }
+    }
+
+    /// Special tokens of the encoding and their ids
+    pub fn special_tokens(&self) -> &'static [(&'static str, u32)] {
+        match self {
+            Self::Cl100kBase => &[
+                ("<|endoftext|>", 100257),
+                ("<|fim_prefix|>", 100258),
+                ("<|fim_middle|>", 100259),
+                ("<|fim_suffix|>", 100260),
+                ("<|endofprompt|>", 100276),
+            ],
+            Self::O200kBase => &[("<|endoftext|>", 199999), ("<|endofprompt|>", 200018)],
+        }
+    }
+}
+
+/// Byte pair encoding tokenizer of an OpenAI [Encoding], using the vocabulary (i.e.: the
+/// mergeable ranks) of the encoding in the tiktoken format: one base64 encoded token and
+/// its rank per line.
+pub struct BpeTokenizer {
+    encoding: Encoding,
+    encoder: HashMap<Vec<u8>, u32>,
+    decoder: HashMap<u32, Vec<u8>>,
+    special_tokens: HashMap<&'static str, u32>,
+    pattern: Regex,
+    special_pattern: Regex,
+}
+
+impl BpeTokenizer {
+    /// Create a new tokenizer for `encoding` from its mergeable ranks
+    pub fn new(encoding: Encoding, ranks: HashMap<Vec<u8>, u32>) -> Result<Self, TokenizerError> {
+        let decoder = ranks
+            .iter()
+            .map(|(bytes, rank)| (*rank, bytes.clone()))
+            .collect::<HashMap<_, _>>();
+        if decoder.len() != ranks.len() {
+            return Err(TokenizerError::VocabularyError(
+                "Duplicate token ranks".to_string(),
+            ));
+        }
+
+        let special_pattern = encoding
+            .special_tokens()
+            .iter()
+            .map(|(token, _)| fancy_regex::escape(token))
+            .collect::<Vec<_>>()
+            .join("|");
+
+        Ok(Self {
+            encoding,
+            encoder: ranks,
+            decoder,
+            special_tokens: encoding.special_tokens().iter().copied().collect(),
+            pattern: Regex::new(encoding.pattern()).map_err(Box::new)?,
+            special_pattern: Regex::new(&special_pattern).map_err(Box::new)?,
+        })
+    }
+
+    /// Create a new tokenizer for `encoding` from the contents of its tiktoken vocabulary file
+    pub fn from_tiktoken(encoding: Encoding, vocabulary: &str) -> Result<Self, TokenizerError> {
+        let ranks = vocabulary
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let (token, rank) = line.split_once(' ').ok_or_else(|| {
+                    TokenizerError::VocabularyError(format!("Invalid line: {line}"))
+                })?;
+                let token = BASE64_STANDARD
+                    .decode(token)
+                    .map_err(|e| TokenizerError::VocabularyError(e.to_string()))?;
+                let rank = rank
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|e| TokenizerError::VocabularyError(e.to_string()))?;
+                Ok((token, rank))
+            })
+            .collect::<Result<HashMap<_, _>, TokenizerError>>()?;
+
+        Self::new(encoding, ranks)
+    }
+
+    /// Create a new tokenizer for `encoding` from its tiktoken vocabulary file (e.g.:
+    /// `cl100k_base.tiktoken`)
+    pub fn from_file(encoding: Encoding, path: impl AsRef<Path>) -> Result<Self, TokenizerError> {
+        Self::from_tiktoken(encoding, &std::fs::read_to_string(path)?)
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Encode `text` into token ids. Special tokens (e.g.: `<|endoftext|>`) are encoded as
+    /// regular text.
+    pub fn encode(&self, text: &str) -> Vec<u32> {
+        let mut tokens = vec![];
+        self.encode_ordinary(text, &mut tokens);
+        tokens
+    }
+
+    /// Encode `text` into token ids, encoding the special tokens of the encoding (e.g.:
+    /// `<|endoftext|>`) as their special token id.
+    pub fn encode_with_special_tokens(&self, text: &str) -> Vec<u32> {
+        let mut tokens = vec![];
+        let mut start = 0;
+        for special in self.special_pattern.find_iter(text).flatten() {
+            self.encode_ordinary(&text[start..special.start()], &mut tokens);
+            tokens.push(self.special_tokens[special.as_str()]);
+            start = special.end();
+        }
+        self.encode_ordinary(&text[start..], &mut tokens);
+        tokens
+    }
+
+    /// Decode token ids into text. Invalid UTF-8 sequences (e.g.: a multi-byte character
+    /// split across a truncated list of tokens) are replaced by `U+FFFD`.
+    pub fn decode(&self, tokens: &[u32]) -> Result<String, TokenizerError> {
+        let mut bytes = vec![];
+        for token in tokens {
+            match self.decoder.get(token) {
+                Some(token_bytes) => bytes.extend_from_slice(token_bytes),
+                None => match self.special_tokens.iter().find(|(_, id)| *id == token) {
+                    Some((special, _)) => bytes.extend_from_slice(special.as_bytes()),
+                    None => return Err(TokenizerError::UnknownToken(*token)),
+                },
+            }
+        }
+
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    fn encode_ordinary(&self, text: &str, tokens: &mut Vec<u32>) {
+        for piece in self.pattern.find_iter(text) {
+            // The patterns are valid and simple enough that matching cannot fail
+            let Ok(piece) = piece else {
+                continue;
+            };
+
+            let piece = piece.as_str().as_bytes();
+            match self.encoder.get(piece) {
+                Some(token) => tokens.push(*token),
+                None => tokens.extend(
+                    self.byte_pair_merge(piece)
+                        .windows(2)
+                        .filter_map(|part| self.encoder.get(&piece[part[0]..part[1]]).copied()),
+                ),
+            }
+        }
+    }
+
+    /// Split `piece` into tokens by repeatedly merging the adjacent pair of parts with the
+    /// lowest rank, returning the boundaries of the parts.
+    fn byte_pair_merge(&self, piece: &[u8]) -> Vec<usize> {
+        let mut boundaries = (0..=piece.len()).collect::<Vec<_>>();
+        let rank = |boundaries: &[usize], i: usize| {
+            boundaries
+                .get(i + 2)
+                .and_then(|&end| self.encoder.get(&piece[boundaries[i]..end]))
+                .copied()
+        };
+
+        let mut ranks = (0..boundaries.len())
+            .map(|i| rank(&boundaries, i))
+            .collect::<Vec<_>>();
+
+        while let Some((i, _)) = ranks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, rank)| rank.map(|rank| (i, rank)))
+            .min_by_key(|(i, rank)| (*rank, *i))
+        {
+            boundaries.remove(i + 1);
+            ranks.remove(i + 1);
+            ranks[i] = rank(&boundaries, i);
+            if i > 0 {
+                ranks[i - 1] = rank(&boundaries, i - 1);
+            }
+        }
+
+        boundaries
+    }
+}
+
+impl Tokenizer for BpeTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        self.encode(text).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Vocabulary with a token for each byte and a few merges
+    fn vocabulary() -> String {
+        (0..=255u8)
+            .map(|byte| vec![byte])
+            .chain([
+                b"he".to_vec(),
+                b"ll".to_vec(),
+                b"hell".to_vec(),
+                b" w".to_vec(),
+            ])
+            .enumerate()
+            .map(|(rank, token)| format!("{} {rank}", BASE64_STANDARD.encode(token)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn tokenizer() -> BpeTokenizer {
+        BpeTokenizer::from_tiktoken(Encoding::Cl100kBase, &vocabulary()).unwrap()
+    }
+
+    #[test]
+    fn test_encode_merges_by_rank() {
+        let tokenizer = tokenizer();
+
+        // "hello" -> "he" "l" "l" "o" -> "he" "ll" "o" -> "hell" "o"
+        assert_eq!(tokenizer.encode("hello"), vec![258, b'o' as u32]);
+        // " world" is pre-tokenized as a single piece
+        assert_eq!(
+            tokenizer.encode("hello world"),
+            vec![
+                258,
+                b'o' as u32,
+                259,
+                b'o' as u32,
+                b'r' as u32,
+                b'l' as u32,
+                b'd' as u32
+            ]
+        );
+        assert_eq!(tokenizer.count_tokens("hello world"), 7);
+    }
+
+    #[test]
+    fn test_pre_tokenization() {
+        let tokenizer = tokenizer();
+
+        // Trailing spaces before a word are split from the space attached to the word
+        let tokens = tokenizer.encode("a   b");
+        assert_eq!(tokenizer.decode(&tokens[..3]).unwrap(), "a  ");
+
+        // Numbers are split in groups of at most 3 digits
+        assert_eq!(tokenizer.encode("12345").len(), 5);
+    }
+
+    #[test]
+    fn test_special_tokens() {
+        let tokenizer = tokenizer();
+
+        assert_eq!(
+            tokenizer.encode_with_special_tokens("hi<|endoftext|>"),
+            vec![b'h' as u32, b'i' as u32, 100257]
+        );
+        assert_eq!(tokenizer.encode("<|endoftext|>").len(), 13);
+    }
+
+    #[test]
+    fn test_decode_roundtrip() {
+        let tokenizer = tokenizer();
+        let text = "hello wörld 🦀!\n\n  indented";
+
+        assert_eq!(
+            tokenizer
+                .decode(&tokenizer.encode_with_special_tokens(text))
+                .unwrap(),
+            text
+        );
+        assert!(matches!(
+            tokenizer.decode(&[1_000_000]),
+            Err(TokenizerError::UnknownToken(1_000_000))
+        ));
+    }
+
+    #[test]
+    fn test_encoding_for_model() {
+        assert_eq!(
+            Encoding::for_model("gpt-4o-mini"),
+            Some(Encoding::O200kBase)
+        );
+        assert_eq!(
+            Encoding::for_model("gpt-4-turbo"),
+            Some(Encoding::Cl100kBase)
+        );
+        assert_eq!(
+            Encoding::for_model("text-embedding-3-small"),
+            Some(Encoding::Cl100kBase)
+        );
+        assert_eq!(Encoding::for_model("claude-3-5-sonnet"), None);
+    }
+}
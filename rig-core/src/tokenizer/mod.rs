@@ -0,0 +1,230 @@
+//! This module provides the [Tokenizer] trait, used to count the tokens of texts without
+//! calling the model provider (e.g.: to size chunks with a
+//! [TokenSplitter](crate::loaders::splitters::TokenSplitter) or to truncate requests with a
+//! [ContextWindow](crate::completion::ContextWindow)).
+//!
+//! Rig provides the following tokenizers:
+//! - [BpeTokenizer]: an offline implementation of OpenAI's `cl100k_base` and `o200k_base`
+//!   byte pair encodings, using the vocabulary files of the [tiktoken](https://github.com/openai/tiktoken)
+//!   library loaded from disk
+//! - [HeuristicTokenizer]: an approximation based on the number of characters, used for models
+//!   whose tokenizer is not available
+//! - [FnTokenizer]: any function counting the tokens of a text (see [from_fn])
+//!
+//! Completion and embedding models report their tokenizer with
+//! [CompletionModel::tokenizer](crate::completion::CompletionModel::tokenizer) and
+//! [EmbeddingModel::tokenizer](crate::embeddings::EmbeddingModel::tokenizer). OpenAI models use
+//! the BPE tokenizer of their encoding when its vocabulary file is found in the directory set by
+//! the [TOKENIZERS_DIR_ENV] environment variable, or was [register]ed manually.
+//!
+//! # Example
+//! ```rust
+//! use rig::tokenizer::{self, BpeTokenizer, Encoding, Tokenizer};
+//!
+//! // Load the vocabulary file from disk (e.g.: https://openaipublic.blob.core.windows.net/encodings/o200k_base.tiktoken)
+//! let o200k = BpeTokenizer::from_file(Encoding::O200kBase, "tokenizers/o200k_base.tiktoken")?;
+//! assert_eq!(o200k.count_tokens("Hello world!"), 3);
+//!
+//! // Use it for all the models of the encoding
+//! tokenizer::register(o200k);
+//! ```
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, OnceLock, RwLock},
+};
+
+pub mod bpe;
+
+pub use bpe::{BpeTokenizer, Encoding};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TokenizerError {
+    /// Error reading a vocabulary file
+    #[error("IoError: {0}")]
+    IoError(#[from] std::io::Error),
+
+    /// Invalid vocabulary (e.g.: malformed vocabulary file)
+    #[error("VocabularyError: {0}")]
+    VocabularyError(String),
+
+    /// Error compiling the pre-tokenization pattern of an encoding
+    #[error("PatternError: {0}")]
+    PatternError(#[from] Box<fancy_regex::Error>),
+
+    /// Token absent from the vocabulary
+    #[error("Unknown token: {0}")]
+    UnknownToken(u32),
+}
+
+/// Trait for tokenizers, i.e.: types splitting texts into the tokens seen by a model
+pub trait Tokenizer: Send + Sync {
+    /// Number of tokens of `text`
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+/// Tokenizer counting the tokens of texts with a function, see [from_fn]
+#[derive(Clone, Copy, Debug)]
+pub struct FnTokenizer<F>(F);
+
+/// Use a function counting the tokens of a text as a tokenizer
+///
+/// # Example
+/// ```rust
+/// use rig::tokenizer::{self, Tokenizer};
+///
+/// let words = tokenizer::from_fn(|text: &str| text.split_whitespace().count());
+/// assert_eq!(words.count_tokens("Hello world!"), 2);
+/// ```
+pub fn from_fn<F: Fn(&str) -> usize + Send + Sync>(count_tokens: F) -> FnTokenizer<F> {
+    FnTokenizer(count_tokens)
+}
+
+impl<F: Fn(&str) -> usize + Send + Sync> Tokenizer for FnTokenizer<F> {
+    fn count_tokens(&self, text: &str) -> usize {
+        (self.0)(text)
+    }
+}
+
+impl<T: Tokenizer + ?Sized> Tokenizer for Arc<T> {
+    fn count_tokens(&self, text: &str) -> usize {
+        (**self).count_tokens(text)
+    }
+}
+
+impl<T: Tokenizer + ?Sized> Tokenizer for Box<T> {
+    fn count_tokens(&self, text: &str) -> usize {
+        (**self).count_tokens(text)
+    }
+}
+
+/// Default number of characters per token of a [HeuristicTokenizer] (about right for English text)
+pub const DEFAULT_CHARS_PER_TOKEN: f64 = 4.0;
+
+/// Tokenizer approximating the number of tokens from the number of characters of the text.
+/// Used for models whose tokenizer is not available offline.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeuristicTokenizer {
+    chars_per_token: f64,
+}
+
+impl Default for HeuristicTokenizer {
+    fn default() -> Self {
+        Self::new(DEFAULT_CHARS_PER_TOKEN)
+    }
+}
+
+impl HeuristicTokenizer {
+    pub fn new(chars_per_token: f64) -> Self {
+        Self { chars_per_token }
+    }
+}
+
+impl Tokenizer for HeuristicTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        (text.chars().count() as f64 / self.chars_per_token).ceil() as usize
+    }
+}
+
+/// Environment variable holding the directory of the tiktoken vocabulary files, named after
+/// their encoding (e.g.: `cl100k_base.tiktoken`)
+pub const TOKENIZERS_DIR_ENV: &str = "RIG_TOKENIZERS_DIR";
+
+/// BPE tokenizers of each encoding, `None` if the vocabulary file could not be loaded
+type Registry = RwLock<HashMap<Encoding, Option<Arc<BpeTokenizer>>>>;
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Register the BPE tokenizer used for the models of its encoding (see [for_model])
+pub fn register(tokenizer: BpeTokenizer) {
+    registry()
+        .write()
+        .expect("Tokenizer registry poisoned")
+        .insert(tokenizer.encoding(), Some(Arc::new(tokenizer)));
+}
+
+/// Get the BPE tokenizer of `encoding`, either registered with [register] or loaded from the
+/// directory set by the [TOKENIZERS_DIR_ENV] environment variable.
+pub fn encoding_tokenizer(encoding: Encoding) -> Option<Arc<BpeTokenizer>> {
+    if let Some(tokenizer) = registry()
+        .read()
+        .expect("Tokenizer registry poisoned")
+        .get(&encoding)
+    {
+        return tokenizer.clone();
+    }
+
+    let tokenizer = std::env::var(TOKENIZERS_DIR_ENV).ok().and_then(|dir| {
+        let path = Path::new(&dir).join(format!("{}.tiktoken", encoding.name()));
+        BpeTokenizer::from_file(encoding, &path)
+            .map_err(|e| {
+                tracing::warn!(target: "rig",
+                    "Failed to load the {} tokenizer from {}: {}",
+                    encoding.name(), path.display(), e
+                )
+            })
+            .ok()
+            .map(Arc::new)
+    });
+
+    registry()
+        .write()
+        .expect("Tokenizer registry poisoned")
+        .entry(encoding)
+        .or_insert(tokenizer)
+        .clone()
+}
+
+/// Get the tokenizer of `model`: the BPE tokenizer of its encoding if the model is known and
+/// the tokenizer is available (see [encoding_tokenizer]), a [HeuristicTokenizer] otherwise.
+pub fn for_model(model: &str) -> Arc<dyn Tokenizer> {
+    match Encoding::for_model(model).and_then(encoding_tokenizer) {
+        Some(tokenizer) => tokenizer,
+        None => Arc::new(HeuristicTokenizer::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        completion::ContextWindow,
+        loaders::splitters::{TextSplitter, TokenSplitter},
+    };
+
+    #[test]
+    fn test_heuristic_tokenizer() {
+        let tokenizer = HeuristicTokenizer::default();
+        assert_eq!(tokenizer.count_tokens(""), 0);
+        assert_eq!(tokenizer.count_tokens("abcd"), 1);
+        assert_eq!(tokenizer.count_tokens("abcde"), 2);
+
+        assert_eq!(HeuristicTokenizer::new(2.5).count_tokens("abcde"), 2);
+    }
+
+    #[test]
+    fn test_pointer_tokenizers() {
+        let arc: Arc<dyn Tokenizer> = Arc::new(HeuristicTokenizer::default());
+        assert_eq!(arc.count_tokens("abcde"), 2);
+
+        let boxed: Box<dyn Tokenizer> = Box::new(from_fn(|text: &str| text.len()));
+        assert_eq!(boxed.count_tokens("abcde"), 5);
+
+        // The tokenizers of models can be used by splitters and context windows
+        let splitter = TokenSplitter::new(2).tokenizer(arc.clone());
+        let chunks = splitter.split("abcdefgh abcdefgh");
+        assert!(chunks.len() > 1);
+        assert!(chunks
+            .iter()
+            .all(|chunk| arc.count_tokens(&chunk.text) <= 2));
+        let _ = ContextWindow::new().tokenizer(arc);
+    }
+
+    #[test]
+    fn test_unknown_model_uses_heuristic() {
+        assert_eq!(for_model("my-local-model").count_tokens("abcdefgh"), 2);
+    }
+}
@@ -7,16 +7,32 @@
 //! stored in a vector store and RAGged.
 //!
 //! The [ToolSet] struct is a collection of tools that can be used by an [Agent](crate::agent::Agent)
-//! and optionally RAGged.
+//! and optionally RAGged. Tools can be grouped in namespaces and enabled or disabled at runtime
+//! (see [ToolSwitches]).
+//!
+//! The [AgentTool] struct wraps an agent (or anything implementing [Chat] or [Prompt]) as a [Tool],
+//! allowing agents to delegate subtasks to other specialized agents.
 
-use std::{collections::HashMap, pin::Pin};
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    pin::Pin,
+    sync::{Arc, Mutex, RwLock},
+    task::{Context, Poll},
+};
 
 use futures::Future;
 use serde::{Deserialize, Serialize};
+use tracing::Instrument;
 
 use crate::{
-    completion::{self, ToolDefinition},
+    agent::AgentHook,
+    completion::{
+        self, Chat, CompletionModel, CompletionRequest, Message, Prompt, PromptError,
+        ToolDefinition,
+    },
     embeddings::{embed::EmbedError, tool::ToolSchema},
+    telemetry,
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -239,15 +255,145 @@ pub enum ToolSetError {
     #[error("ToolNotFoundError: {0}")]
     ToolNotFoundError(String),
 
+    /// The tool exists but is currently disabled
+    #[error("ToolDisabledError: {0}")]
+    ToolDisabledError(String),
+
+    /// A tool with the same (qualified) name already exists in the toolset
+    #[error("ToolNameCollisionError: {0}")]
+    ToolNameCollisionError(String),
+
     // TODO: Revisit this
     #[error("JsonError: {0}")]
     JsonError(#[from] serde_json::Error),
 }
 
-/// A struct that holds a set of tools
+/// Separator placed between a tool's namespace and its name to form the tool's qualified name
+/// (e.g.: the tool `swap` in the namespace `solana` is exposed to the model as `solana_swap`).
+/// Most providers only accept alphanumeric characters, `_` and `-` in tool names.
+pub const NAMESPACE_SEPARATOR: &str = "_";
+
+/// Get the qualified name of the tool `name` in the namespace `namespace`
+pub fn qualified_name(namespace: &str, name: &str) -> String {
+    format!("{namespace}{NAMESPACE_SEPARATOR}{name}")
+}
+
+#[derive(Debug, Default)]
+struct DisabledTools {
+    tools: HashSet<String>,
+    namespaces: HashSet<String>,
+}
+
+/// A cloneable handle used to enable and disable the tools of a [ToolSet] at runtime.
+///
+/// All clones of a handle (and the toolset it was obtained from) share the same state, so a
+/// handle can be kept around after the toolset has been moved into an [Agent](crate::agent::Agent)
+/// to turn tools or whole namespaces on and off without rebuilding the agent.
+///
+/// Switches created with [ToolSwitches::new] are independent of any toolset, and can be used to
+/// disable tools for some requests only (see [Agent::with_tool_switches](crate::agent::Agent::with_tool_switches)).
+///
+/// # Example
+/// ```
+/// use rig::tool::ToolSet;
+///
+/// let toolset = ToolSet::builder()
+///     .static_tool_in("solana", swap_tool)
+///     .static_tool_in("twitter", post_tool)
+///     .build();
+///
+/// let switches = toolset.switches();
+///
+/// // Halt trading
+/// switches.disable_namespace("solana");
+/// assert!(!toolset.is_enabled("solana_swap"));
+/// assert!(toolset.is_enabled("twitter_post"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ToolSwitches(Arc<RwLock<DisabledTools>>);
+
+impl ToolSwitches {
+    /// Create new switches with all tools enabled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable the tool with the given (qualified) name
+    pub fn enable(&self, toolname: &str) {
+        if let Ok(mut disabled) = self.0.write() {
+            disabled.tools.remove(toolname);
+        }
+    }
+
+    /// Disable the tool with the given (qualified) name
+    pub fn disable(&self, toolname: &str) {
+        if let Ok(mut disabled) = self.0.write() {
+            disabled.tools.insert(toolname.to_string());
+        }
+    }
+
+    /// Enable all tools in the given namespace (tools disabled individually stay disabled)
+    pub fn enable_namespace(&self, namespace: &str) {
+        if let Ok(mut disabled) = self.0.write() {
+            disabled.namespaces.remove(namespace);
+        }
+    }
+
+    /// Disable all tools in the given namespace
+    pub fn disable_namespace(&self, namespace: &str) {
+        if let Ok(mut disabled) = self.0.write() {
+            disabled.namespaces.insert(namespace.to_string());
+        }
+    }
+
+    /// Check if the tool with the given (qualified) name was disabled individually
+    pub fn is_tool_disabled(&self, toolname: &str) -> bool {
+        self.0
+            .read()
+            .map(|disabled| disabled.tools.contains(toolname))
+            .unwrap_or(false)
+    }
+
+    /// Check if the given namespace was disabled
+    pub fn is_namespace_disabled(&self, namespace: &str) -> bool {
+        self.0
+            .read()
+            .map(|disabled| disabled.namespaces.contains(namespace))
+            .unwrap_or(false)
+    }
+
+    /// Check if the tool with the given (qualified) name, in the given namespace, is disabled
+    /// individually or through its namespace
+    fn disables(&self, toolname: &str, namespace: Option<&str>) -> bool {
+        self.is_tool_disabled(toolname)
+            || namespace.is_some_and(|namespace| self.is_namespace_disabled(namespace))
+    }
+
+    /// Copy the disabled tools and namespaces of `other` into this handle
+    fn merge(&self, other: &ToolSwitches) {
+        if Arc::ptr_eq(&self.0, &other.0) {
+            return;
+        }
+        let (Ok(mut disabled), Ok(other)) = (self.0.write(), other.0.read()) else {
+            return;
+        };
+        disabled.tools.extend(other.tools.iter().cloned());
+        disabled.namespaces.extend(other.namespaces.iter().cloned());
+    }
+}
+
+/// A struct that holds a set of tools.
+///
+/// Tools can optionally be added to a namespace (e.g.: `solana`, `twitter`), in which case they
+/// are registered (and exposed to the model) under their qualified name (see [qualified_name]).
+/// Tools and namespaces can be enabled and disabled at runtime (see [ToolSet::switches]):
+/// disabled tools are not advertised to the model by agents and cannot be called.
 #[derive(Default)]
 pub struct ToolSet {
     pub(crate) tools: HashMap<String, ToolType>,
+    /// Namespace of the namespaced tools, keyed by qualified name
+    namespaces: HashMap<String, String>,
+    switches: ToolSwitches,
 }
 
 impl ToolSet {
@@ -265,34 +411,196 @@ impl ToolSet {
         ToolSetBuilder::default()
     }
 
-    /// Check if the toolset contains a tool with the given name
+    /// Check if the toolset contains a tool with the given (qualified) name
     pub fn contains(&self, toolname: &str) -> bool {
         self.tools.contains_key(toolname)
     }
 
-    /// Add a tool to the toolset
+    /// Add a tool to the toolset. If a tool with the same name already exists, it is replaced
+    /// (use [ToolSet::try_add_tool] to detect name collisions).
     pub fn add_tool(&mut self, tool: impl ToolDyn + 'static) {
-        self.tools
-            .insert(tool.name(), ToolType::Simple(Box::new(tool)));
+        self.insert(None, ToolType::Simple(Box::new(tool)));
+    }
+
+    /// Add a tool to the toolset, failing if a tool with the same name already exists
+    pub fn try_add_tool(&mut self, tool: impl ToolDyn + 'static) -> Result<(), ToolSetError> {
+        self.try_insert(None, ToolType::Simple(Box::new(tool)))
     }
 
-    /// Merge another toolset into this one
+    /// Add a tool to the given namespace of the toolset. If a tool with the same qualified name
+    /// already exists, it is replaced (use [ToolSet::try_add_namespaced_tool] to detect name collisions).
+    pub fn add_namespaced_tool(&mut self, namespace: &str, tool: impl ToolDyn + 'static) {
+        self.insert(
+            Some(namespace.to_string()),
+            ToolType::Simple(Box::new(tool)),
+        );
+    }
+
+    /// Add a tool to the given namespace of the toolset, failing if a tool with the same
+    /// qualified name already exists
+    pub fn try_add_namespaced_tool(
+        &mut self,
+        namespace: &str,
+        tool: impl ToolDyn + 'static,
+    ) -> Result<(), ToolSetError> {
+        self.try_insert(
+            Some(namespace.to_string()),
+            ToolType::Simple(Box::new(tool)),
+        )
+    }
+
+    /// Merge another toolset into this one. Tools of `toolset` replace tools of `self` with
+    /// the same name (use [ToolSet::try_add_tools] to detect name collisions).
     pub fn add_tools(&mut self, toolset: ToolSet) {
-        self.tools.extend(toolset.tools);
+        self.switches.merge(&toolset.switches);
+        for (toolname, tool) in toolset.tools {
+            if self.tools.contains_key(&toolname) {
+                tracing::warn!(target: "rig", "Tool {toolname} already exists in toolset, replacing it");
+            }
+            self.namespaces.remove(&toolname);
+            self.tools.insert(toolname, tool);
+        }
+        self.namespaces.extend(toolset.namespaces);
+    }
+
+    /// Merge another toolset into this one, failing (without adding any tool) if both
+    /// toolsets contain a tool with the same name
+    pub fn try_add_tools(&mut self, toolset: ToolSet) -> Result<(), ToolSetError> {
+        if let Some(toolname) = toolset
+            .tools
+            .keys()
+            .find(|toolname| self.tools.contains_key(*toolname))
+        {
+            return Err(ToolSetError::ToolNameCollisionError(toolname.clone()));
+        }
+        self.add_tools(toolset);
+        Ok(())
     }
 
-    pub(crate) fn get(&self, toolname: &str) -> Option<&ToolType> {
-        self.tools.get(toolname)
+    fn key(namespace: Option<&str>, tool: &ToolType) -> String {
+        match namespace {
+            Some(namespace) => qualified_name(namespace, &tool.name()),
+            None => tool.name(),
+        }
+    }
+
+    fn insert(&mut self, namespace: Option<String>, tool: ToolType) {
+        let toolname = Self::key(namespace.as_deref(), &tool);
+        if self.tools.contains_key(&toolname) {
+            tracing::warn!(target: "rig", "Tool {toolname} already exists in toolset, replacing it");
+        }
+        match namespace {
+            Some(namespace) => self.namespaces.insert(toolname.clone(), namespace),
+            None => self.namespaces.remove(&toolname),
+        };
+        self.tools.insert(toolname, tool);
+    }
+
+    fn try_insert(
+        &mut self,
+        namespace: Option<String>,
+        tool: ToolType,
+    ) -> Result<(), ToolSetError> {
+        let toolname = Self::key(namespace.as_deref(), &tool);
+        if self.tools.contains_key(&toolname) {
+            return Err(ToolSetError::ToolNameCollisionError(toolname));
+        }
+        self.insert(namespace, tool);
+        Ok(())
     }
 
-    /// Call a tool with the given name and arguments
+    /// Get the namespace of the tool with the given qualified name, if any
+    pub fn namespace(&self, toolname: &str) -> Option<&str> {
+        self.namespaces.get(toolname).map(String::as_str)
+    }
+
+    /// Get a handle to enable and disable the toolset's tools at runtime
+    pub fn switches(&self) -> ToolSwitches {
+        self.switches.clone()
+    }
+
+    /// Enable the tool with the given (qualified) name
+    pub fn enable(&self, toolname: &str) {
+        self.switches.enable(toolname)
+    }
+
+    /// Disable the tool with the given (qualified) name
+    pub fn disable(&self, toolname: &str) {
+        self.switches.disable(toolname)
+    }
+
+    /// Enable all tools in the given namespace
+    pub fn enable_namespace(&self, namespace: &str) {
+        self.switches.enable_namespace(namespace)
+    }
+
+    /// Disable all tools in the given namespace
+    pub fn disable_namespace(&self, namespace: &str) {
+        self.switches.disable_namespace(namespace)
+    }
+
+    /// Check if the toolset contains an enabled tool with the given (qualified) name
+    pub fn is_enabled(&self, toolname: &str) -> bool {
+        self.contains(toolname) && !self.switches.disables(toolname, self.namespace(toolname))
+    }
+
+    /// Check if the toolset contains an enabled tool with the given (qualified) name, which is
+    /// not disabled by the `overrides` either
+    pub fn is_enabled_with(&self, toolname: &str, overrides: &ToolSwitches) -> bool {
+        self.is_enabled(toolname) && !overrides.disables(toolname, self.namespace(toolname))
+    }
+
+    /// Get the definition of the tool with the given (qualified) name, as it should be
+    /// advertised to the model. Returns `None` if the tool does not exist or is disabled.
+    pub async fn definition(&self, toolname: &str, prompt: String) -> Option<ToolDefinition> {
+        self.definition_with(toolname, prompt, &ToolSwitches::new())
+            .await
+    }
+
+    /// Get the definition of the tool with the given (qualified) name, as it should be
+    /// advertised to the model. Returns `None` if the tool does not exist, or is disabled in
+    /// the toolset or by the `overrides`.
+    pub async fn definition_with(
+        &self,
+        toolname: &str,
+        prompt: String,
+        overrides: &ToolSwitches,
+    ) -> Option<ToolDefinition> {
+        if !self.is_enabled_with(toolname, overrides) {
+            return None;
+        }
+        let mut definition = self.tools.get(toolname)?.definition(prompt).await;
+        definition.name = toolname.to_string();
+        Some(definition)
+    }
+
+    /// Call a tool with the given (qualified) name and arguments
     pub async fn call(&self, toolname: &str, args: String) -> Result<String, ToolSetError> {
+        self.call_with(toolname, args, &ToolSwitches::new()).await
+    }
+
+    /// Call a tool with the given (qualified) name and arguments, failing if the tool is
+    /// disabled in the toolset or by the `overrides`
+    pub async fn call_with(
+        &self,
+        toolname: &str,
+        args: String,
+        overrides: &ToolSwitches,
+    ) -> Result<String, ToolSetError> {
         if let Some(tool) = self.tools.get(toolname) {
+            if !self.is_enabled_with(toolname, overrides) {
+                return Err(ToolSetError::ToolDisabledError(toolname.to_string()));
+            }
             tracing::info!(target: "rig",
                 "Calling tool {toolname} with args:\n{}",
                 serde_json::to_string_pretty(&args).unwrap_or_else(|_| args.clone())
             );
-            Ok(tool.call(args).await?)
+            let span = telemetry::tool_span(toolname, &args);
+            let result = tool.call(args).instrument(span.clone()).await;
+            if let Ok(output) = &result {
+                telemetry::record_tool_result(&span, output);
+            }
+            Ok(telemetry::record_result(&span, result)?)
         } else {
             Err(ToolSetError::ToolNotFoundError(toolname.to_string()))
         }
@@ -301,39 +609,22 @@ impl ToolSet {
     /// Get the documents of all the tools in the toolset
     pub async fn documents(&self) -> Result<Vec<completion::Document>, ToolSetError> {
         let mut docs = Vec::new();
-        for tool in self.tools.values() {
-            match tool {
-                ToolType::Simple(tool) => {
-                    docs.push(completion::Document {
-                        id: tool.name(),
-                        text: format!(
-                            "\
-                            Tool: {}\n\
-                            Definition: \n\
-                            {}\
-                        ",
-                            tool.name(),
-                            serde_json::to_string_pretty(&tool.definition("".to_string()).await)?
-                        ),
-                        additional_props: HashMap::new(),
-                    });
-                }
-                ToolType::Embedding(tool) => {
-                    docs.push(completion::Document {
-                        id: tool.name(),
-                        text: format!(
-                            "\
-                            Tool: {}\n\
-                            Definition: \n\
-                            {}\
-                        ",
-                            tool.name(),
-                            serde_json::to_string_pretty(&tool.definition("".to_string()).await)?
-                        ),
-                        additional_props: HashMap::new(),
-                    });
-                }
-            }
+        for (toolname, tool) in &self.tools {
+            let mut definition = tool.definition("".to_string()).await;
+            definition.name = toolname.clone();
+            docs.push(completion::Document {
+                id: toolname.clone(),
+                text: format!(
+                    "\
+                    Tool: {}\n\
+                    Definition: \n\
+                    {}\
+                ",
+                    toolname,
+                    serde_json::to_string_pretty(&definition)?
+                ),
+                additional_props: HashMap::new(),
+            });
         }
         Ok(docs)
     }
@@ -343,10 +634,13 @@ impl ToolSet {
     /// documents added to the builder must all be of the same type.
     pub fn schemas(&self) -> Result<Vec<ToolSchema>, EmbedError> {
         self.tools
-            .values()
-            .filter_map(|tool_type| {
+            .iter()
+            .filter_map(|(toolname, tool_type)| {
                 if let ToolType::Embedding(tool) = tool_type {
-                    Some(ToolSchema::try_from(&**tool))
+                    Some(ToolSchema::try_from(&**tool).map(|schema| ToolSchema {
+                        name: toolname.clone(),
+                        ..schema
+                    }))
                 } else {
                     None
                 }
@@ -357,27 +651,574 @@ impl ToolSet {
 
 #[derive(Default)]
 pub struct ToolSetBuilder {
-    tools: Vec<ToolType>,
+    tools: Vec<(Option<String>, ToolType)>,
 }
 
 impl ToolSetBuilder {
     pub fn static_tool(mut self, tool: impl ToolDyn + 'static) -> Self {
-        self.tools.push(ToolType::Simple(Box::new(tool)));
+        self.tools.push((None, ToolType::Simple(Box::new(tool))));
+        self
+    }
+
+    /// Add a static tool to the given namespace
+    pub fn static_tool_in(mut self, namespace: &str, tool: impl ToolDyn + 'static) -> Self {
+        self.tools.push((
+            Some(namespace.to_string()),
+            ToolType::Simple(Box::new(tool)),
+        ));
         self
     }
 
     pub fn dynamic_tool(mut self, tool: impl ToolEmbeddingDyn + 'static) -> Self {
-        self.tools.push(ToolType::Embedding(Box::new(tool)));
+        self.tools.push((None, ToolType::Embedding(Box::new(tool))));
         self
     }
 
+    /// Add a dynamic tool to the given namespace
+    pub fn dynamic_tool_in(
+        mut self,
+        namespace: &str,
+        tool: impl ToolEmbeddingDyn + 'static,
+    ) -> Self {
+        self.tools.push((
+            Some(namespace.to_string()),
+            ToolType::Embedding(Box::new(tool)),
+        ));
+        self
+    }
+
+    /// Build the toolset. Tools with the same (qualified) name replace the ones added
+    /// before them (use [ToolSetBuilder::try_build] to detect name collisions).
     pub fn build(self) -> ToolSet {
-        ToolSet {
-            tools: self
-                .tools
-                .into_iter()
-                .map(|tool| (tool.name(), tool))
-                .collect(),
+        let mut toolset = ToolSet::default();
+        for (namespace, tool) in self.tools {
+            toolset.insert(namespace, tool);
+        }
+        toolset
+    }
+
+    /// Build the toolset, failing if two tools have the same (qualified) name
+    pub fn try_build(self) -> Result<ToolSet, ToolSetError> {
+        let mut toolset = ToolSet::default();
+        for (namespace, tool) in self.tools {
+            toolset.try_insert(namespace, tool)?;
         }
+        Ok(toolset)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AgentToolError {
+    /// Error returned by the wrapped agent
+    #[error("PromptError: {0}")]
+    PromptError(#[from] PromptError),
+
+    #[error("MaxDepthError: agent tool `{0}` exceeded its maximum call depth of {1}")]
+    MaxDepthError(String, usize),
+}
+
+/// A handle to the chat history of a parent agent, used by [AgentTool] to forward the
+/// parent's conversation to the wrapped agent.
+///
+/// The handle is kept up to date by attaching it to the parent agent as a hook (see
+/// [AgentBuilder::hook](crate::agent::AgentBuilder::hook)): each time the parent agent sends a
+/// completion request, the handle records the request's chat history and prompt.
+///
+/// Note: the handle only stores the latest conversation, so a parent agent sharing a handle
+/// should not be prompted concurrently.
+#[derive(Clone, Default)]
+pub struct SharedChatHistory(Arc<RwLock<Vec<Message>>>);
+
+impl SharedChatHistory {
+    /// Create a new, empty, chat history handle
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a copy of the recorded chat history
+    pub fn get(&self) -> Vec<Message> {
+        self.0
+            .read()
+            .map(|history| history.clone())
+            .unwrap_or_default()
+    }
+
+    /// Overwrite the recorded chat history
+    pub fn set(&self, history: Vec<Message>) {
+        if let Ok(mut current) = self.0.write() {
+            *current = history;
+        }
+    }
+}
+
+impl<M: CompletionModel> AgentHook<M> for SharedChatHistory {
+    fn on_completion_request(&self, request: &CompletionRequest) {
+        let mut history = request.chat_history.clone();
+        history.push(request.prompt.clone());
+        self.set(history);
+    }
+}
+
+/// Adapter allowing types that only implement [Prompt] to be wrapped in an [AgentTool].
+/// The chat history passed to [Chat::chat] is ignored.
+pub struct PromptAdapter<P: Prompt>(pub P);
+
+impl<P: Prompt> Chat for PromptAdapter<P> {
+    async fn chat(
+        &self,
+        prompt: impl Into<Message> + Send,
+        _chat_history: Vec<Message>,
+    ) -> Result<String, PromptError> {
+        self.0.prompt(prompt).await
+    }
+}
+
+/// Struct wrapping an agent (or anything implementing [Chat]) as a [Tool], so that it can be
+/// called by another agent. This makes it possible to build hierarchical multi-agent systems
+/// in which an orchestrator agent delegates subtasks to specialized agents.
+///
+/// By default, the tool takes a single `prompt` string argument which is sent to the wrapped
+/// agent. A custom input schema can be provided with [AgentTool::parameters], in which case the
+/// arguments generated by the model are sent to the wrapped agent as a JSON string.
+///
+/// To prevent runaway recursion (e.g.: agents delegating to each other in a loop), the nesting
+/// depth of agent tool calls is limited (see [AgentTool::max_depth]). The depth is tracked per
+/// call chain: independent prompts running concurrently do not count towards each other's depth.
+///
+/// # Example
+/// ```
+/// use rig::{
+///     completion::Prompt,
+///     providers::openai,
+///     tool::{AgentTool, SharedChatHistory},
+/// };
+///
+/// let openai = openai::Client::from_env();
+///
+/// let risk_assessor = openai.agent(openai::GPT_4O)
+///     .preamble("You assess the risks of a trade.")
+///     .build();
+///
+/// let history = SharedChatHistory::new();
+///
+/// let trader = openai.agent(openai::GPT_4O)
+///     .preamble("You are a trader. Always assess the risks of a trade before executing it.")
+///     .tool(
+///         AgentTool::new("assess_risk", "Assess the risks of a trade", risk_assessor)
+///             .forward_history(history.clone())
+///             .max_depth(2),
+///     )
+///     .hook(history)
+///     .build();
+///
+/// let response = trader.prompt("Should I buy 10 SOL?").await
+///     .expect("Failed to prompt the agent");
+/// ```
+pub struct AgentTool<A: Chat> {
+    agent: A,
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+    history: Option<SharedChatHistory>,
+    max_depth: usize,
+}
+
+impl<A: Chat> AgentTool<A> {
+    /// Default maximum number of nested calls to an [AgentTool]
+    pub const DEFAULT_MAX_DEPTH: usize = 3;
+
+    /// Create a new agent tool with the given name and description
+    pub fn new(name: &str, description: &str, agent: A) -> Self {
+        Self {
+            agent,
+            name: name.to_string(),
+            description: description.to_string(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "prompt": {
+                        "type": "string",
+                        "description": "The prompt to send to the agent"
+                    }
+                },
+                "required": ["prompt"]
+            }),
+            history: None,
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+        }
+    }
+
+    /// Set the JSON schema of the tool's arguments
+    pub fn parameters(mut self, parameters: serde_json::Value) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    /// Forward the chat history recorded by `history` to the wrapped agent on each call
+    pub fn forward_history(mut self, history: SharedChatHistory) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Set the maximum nesting depth of agent tool calls at which the tool can be called. Calls
+    /// made from within `max_depth` nested agent tool calls return
+    /// [AgentToolError::MaxDepthError].
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Convert the arguments generated by the model into the prompt sent to the wrapped agent
+    fn to_prompt(args: serde_json::Value) -> String {
+        match args {
+            serde_json::Value::String(prompt) => prompt,
+            serde_json::Value::Object(ref map) if map.len() == 1 => match map.get("prompt") {
+                Some(serde_json::Value::String(prompt)) => prompt.clone(),
+                _ => args.to_string(),
+            },
+            args => args.to_string(),
+        }
+    }
+}
+
+impl<P: Prompt> AgentTool<PromptAdapter<P>> {
+    /// Create a new agent tool from a type only implementing [Prompt]. Forwarded chat history
+    /// is ignored by such tools.
+    pub fn from_prompt(name: &str, description: &str, agent: P) -> Self {
+        Self::new(name, description, PromptAdapter(agent))
+    }
+}
+
+impl<A: Chat + Clone> Clone for AgentTool<A> {
+    fn clone(&self) -> Self {
+        Self {
+            agent: self.agent.clone(),
+            name: self.name.clone(),
+            description: self.description.clone(),
+            parameters: self.parameters.clone(),
+            history: self.history.clone(),
+            max_depth: self.max_depth,
+        }
+    }
+}
+
+thread_local! {
+    /// Nesting depth of the agent tool call being polled on the current thread
+    static AGENT_TOOL_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Future polling the wrapped agent call with [AGENT_TOOL_DEPTH] set to `depth`, so that the
+/// agent tools it calls (in the same task) see the depth of their call chain.
+struct DepthScope<F> {
+    depth: usize,
+    future: Pin<Box<F>>,
+}
+
+/// Guard restoring the previous [AGENT_TOOL_DEPTH] once a poll returns (or panics)
+struct RestoreDepth(usize);
+
+impl Drop for RestoreDepth {
+    fn drop(&mut self) {
+        AGENT_TOOL_DEPTH.set(self.0);
+    }
+}
+
+impl<F: Future> Future for DepthScope<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let _restore = RestoreDepth(AGENT_TOOL_DEPTH.replace(this.depth));
+        this.future.as_mut().poll(cx)
+    }
+}
+
+/// Future wrapper which is `Sync` as long as the wrapped future is `Send`.
+/// Required since [Tool::call] must return a `Sync` future, which agent futures are not.
+struct SyncFuture<F>(Mutex<Pin<Box<F>>>);
+
+impl<F: Future> Future for SyncFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.get_mut()
+            .0
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .as_mut()
+            .poll(cx)
+    }
+}
+
+impl<A: Chat> Tool for AgentTool<A> {
+    const NAME: &'static str = "agent";
+
+    type Error = AgentToolError;
+    type Args = serde_json::Value;
+    type Output = String;
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            parameters: self.parameters.clone(),
+        }
+    }
+
+    fn call(
+        &self,
+        args: Self::Args,
+    ) -> impl Future<Output = Result<Self::Output, Self::Error>> + Send + Sync {
+        SyncFuture(Mutex::new(Box::pin(async move {
+            let depth = AGENT_TOOL_DEPTH.get();
+            if depth >= self.max_depth {
+                return Err(AgentToolError::MaxDepthError(
+                    self.name.clone(),
+                    self.max_depth,
+                ));
+            }
+
+            let history = self
+                .history
+                .as_ref()
+                .map(|history| history.get())
+                .unwrap_or_default();
+
+            let chat = self.agent.chat(Self::to_prompt(args), history);
+            Ok(DepthScope {
+                depth: depth + 1,
+                future: Box::pin(chat),
+            }
+            .await?)
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use super::*;
+
+    /// Agent echoing its prompt along with the length of its chat history
+    struct EchoAgent;
+
+    impl Chat for EchoAgent {
+        async fn chat(
+            &self,
+            prompt: impl Into<Message> + Send,
+            chat_history: Vec<Message>,
+        ) -> Result<String, PromptError> {
+            let prompt = prompt.into().rag_text().unwrap_or_default();
+            Ok(format!("{prompt} ({} messages)", chat_history.len()))
+        }
+    }
+
+    /// Agent delegating every prompt back to the tool wrapping it
+    #[derive(Clone)]
+    struct RecursiveAgent(Arc<OnceLock<AgentTool<RecursiveAgent>>>);
+
+    impl Chat for RecursiveAgent {
+        async fn chat(
+            &self,
+            prompt: impl Into<Message> + Send,
+            _chat_history: Vec<Message>,
+        ) -> Result<String, PromptError> {
+            let prompt = prompt.into().rag_text().unwrap_or_default();
+            let tool = self.0.get().expect("tool should be set");
+            let output = Tool::call(tool, serde_json::json!({ "prompt": prompt }))
+                .await
+                .map_err(|e| ToolSetError::ToolCallError(ToolError::ToolCallError(Box::new(e))))?;
+            Ok(output)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_tool_prompt() {
+        let tool = AgentTool::new("echo", "Echo the prompt", EchoAgent);
+
+        let definition = Tool::definition(&tool, "".to_string()).await;
+        assert_eq!(definition.name, "echo");
+        assert_eq!(
+            definition.parameters["required"],
+            serde_json::json!(["prompt"])
+        );
+
+        let output = Tool::call(&tool, serde_json::json!({ "prompt": "Hello" }))
+            .await
+            .unwrap();
+        assert_eq!(output, "Hello (0 messages)");
+
+        let tool = tool.parameters(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "symbol": { "type": "string" }
+            }
+        }));
+        let output = Tool::call(&tool, serde_json::json!({ "symbol": "SOL" }))
+            .await
+            .unwrap();
+        assert_eq!(output, r#"{"symbol":"SOL"} (0 messages)"#);
+    }
+
+    #[tokio::test]
+    async fn test_agent_tool_forward_history() {
+        let history = SharedChatHistory::new();
+        let tool =
+            AgentTool::new("echo", "Echo the prompt", EchoAgent).forward_history(history.clone());
+
+        let request = CompletionRequest {
+            prompt: Message::user("Should I buy SOL?"),
+            preamble: None,
+            chat_history: vec![Message::user("Hi"), Message::assistant("Hello!")],
+            documents: vec![],
+            tools: vec![],
+            temperature: None,
+            max_tokens: None,
+            additional_params: None,
+        };
+        AgentHook::<crate::providers::openai::CompletionModel>::on_completion_request(
+            &history, &request,
+        );
+
+        let output = Tool::call(&tool, serde_json::json!({ "prompt": "Assess risk" }))
+            .await
+            .unwrap();
+        assert_eq!(output, "Assess risk (3 messages)");
+    }
+
+    #[tokio::test]
+    async fn test_agent_tool_max_depth() {
+        let cell = Arc::new(OnceLock::new());
+        let tool = AgentTool::new("recurse", "Recurse", RecursiveAgent(cell.clone())).max_depth(2);
+        assert!(cell.set(tool.clone()).is_ok());
+
+        let err = Tool::call(&tool, serde_json::json!({ "prompt": "Loop" }))
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("agent tool `recurse` exceeded its maximum call depth of 2"));
+
+        // The depth is restored once the calls return
+        assert_eq!(AGENT_TOOL_DEPTH.get(), 0);
+    }
+
+    /// Agent waiting for `n` concurrent prompts before answering
+    struct BarrierAgent(Arc<tokio::sync::Barrier>);
+
+    impl Chat for BarrierAgent {
+        async fn chat(
+            &self,
+            prompt: impl Into<Message> + Send,
+            _chat_history: Vec<Message>,
+        ) -> Result<String, PromptError> {
+            let prompt = prompt.into().rag_text().unwrap_or_default();
+            self.0.wait().await;
+            Ok(prompt)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_tool_concurrent_calls() {
+        // More concurrent calls than the maximum depth, none of them nested
+        let n = AgentTool::<BarrierAgent>::DEFAULT_MAX_DEPTH * 2;
+        let tool = Arc::new(AgentTool::new(
+            "wait",
+            "Wait",
+            BarrierAgent(Arc::new(tokio::sync::Barrier::new(n))),
+        ));
+
+        let calls = (0..n).map(|i| {
+            let tool = tool.clone();
+            tokio::spawn(async move {
+                Tool::call(
+                    tool.as_ref(),
+                    serde_json::json!({ "prompt": format!("{i}") }),
+                )
+                .await
+            })
+        });
+        for (i, result) in futures::future::join_all(calls)
+            .await
+            .into_iter()
+            .enumerate()
+        {
+            assert_eq!(result.unwrap().unwrap(), format!("{i}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_toolset_namespaces() {
+        let mut toolset = ToolSet::builder()
+            .static_tool_in("solana", AgentTool::new("swap", "Swap tokens", EchoAgent))
+            .static_tool_in("twitter", AgentTool::new("post", "Post a tweet", EchoAgent))
+            .static_tool(AgentTool::new("swap", "Swap tokens", EchoAgent))
+            .try_build()
+            .unwrap();
+
+        assert!(toolset.contains("solana_swap"));
+        assert!(toolset.contains("swap"));
+        assert_eq!(toolset.namespace("solana_swap"), Some("solana"));
+        assert_eq!(toolset.namespace("swap"), None);
+
+        let definition = toolset
+            .definition("solana_swap", "".to_string())
+            .await
+            .unwrap();
+        assert_eq!(definition.name, "solana_swap");
+
+        let err = toolset
+            .try_add_namespaced_tool("twitter", AgentTool::new("post", "Post", EchoAgent))
+            .unwrap_err();
+        assert!(
+            matches!(err, ToolSetError::ToolNameCollisionError(name) if name == "twitter_post")
+        );
+
+        let err = ToolSet::builder()
+            .static_tool(AgentTool::new("swap", "Swap tokens", EchoAgent))
+            .static_tool(AgentTool::new("swap", "Swap tokens", EchoAgent))
+            .try_build()
+            .err()
+            .unwrap();
+        assert!(matches!(err, ToolSetError::ToolNameCollisionError(name) if name == "swap"));
+    }
+
+    #[tokio::test]
+    async fn test_toolset_switches() {
+        let toolset = ToolSet::builder()
+            .static_tool_in("solana", AgentTool::new("swap", "Swap tokens", EchoAgent))
+            .static_tool_in("twitter", AgentTool::new("post", "Post a tweet", EchoAgent))
+            .build();
+        let switches = toolset.switches();
+
+        switches.disable_namespace("solana");
+        assert!(!toolset.is_enabled("solana_swap"));
+        assert!(toolset.is_enabled("twitter_post"));
+        assert!(toolset
+            .definition("solana_swap", "".to_string())
+            .await
+            .is_none());
+
+        let err = toolset
+            .call("solana_swap", r#"{"prompt": "Buy SOL"}"#.to_string())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolSetError::ToolDisabledError(name) if name == "solana_swap"));
+
+        switches.enable_namespace("solana");
+        toolset.disable("twitter_post");
+        assert!(toolset.is_enabled("solana_swap"));
+        assert!(!toolset.is_enabled("twitter_post"));
+
+        let output = toolset
+            .call("solana_swap", r#"{"prompt": "Buy SOL"}"#.to_string())
+            .await
+            .unwrap();
+        assert_eq!(output, r#""Buy SOL (0 messages)""#);
     }
 }
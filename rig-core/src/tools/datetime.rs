@@ -0,0 +1,384 @@
+//! Date and time tool.
+//!
+//! The [DateTime] tool gives models access to the current date and time (which they otherwise
+//! do not know), and converts between unix timestamps and RFC 3339 dates (e.g.: `2024-03-01T12:30:00Z`).
+//! Dates are computed in UTC, or in a fixed UTC offset if `utc_offset_minutes` is provided.
+use std::{
+    convert::Infallible,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolEmbedding},
+};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum DateTimeError {
+    #[error("Invalid date: {0}")]
+    InvalidDate(String),
+
+    #[error("Missing argument `{0}` for operation `{1}`")]
+    MissingArgument(&'static str, &'static str),
+
+    #[error("System clock error: {0}")]
+    ClockError(String),
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DateTimeOperation {
+    /// Get the current date and time
+    Now,
+    /// Convert a unix timestamp to a date
+    FromTimestamp,
+    /// Convert a date to a unix timestamp
+    ToTimestamp,
+    /// Add a number of seconds (possibly negative) to a date
+    Add,
+}
+
+#[derive(Deserialize)]
+pub struct DateTimeArgs {
+    pub operation: DateTimeOperation,
+    /// Unix timestamp (in seconds), used by `from_timestamp`
+    pub timestamp: Option<i64>,
+    /// RFC 3339 date, used by `to_timestamp` and `add`
+    pub datetime: Option<String>,
+    /// Number of seconds to add, used by `add`
+    pub seconds: Option<i64>,
+    /// Offset from UTC (in minutes) of the returned date. Defaults to 0 (UTC).
+    pub utc_offset_minutes: Option<i32>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DateTimeOutput {
+    /// Unix timestamp (in seconds)
+    pub timestamp: i64,
+    /// RFC 3339 date
+    pub datetime: String,
+    /// Day of the week (e.g.: "Monday")
+    pub weekday: String,
+}
+
+/// Tool returning the current date and time and converting between unix timestamps and dates
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct DateTime;
+
+impl DateTime {
+    /// Format the unix timestamp `timestamp` as an RFC 3339 date in the given UTC offset
+    pub fn format(timestamp: i64, utc_offset_minutes: i32) -> DateTimeOutput {
+        let local = timestamp + i64::from(utc_offset_minutes) * 60;
+        let days = local.div_euclid(SECONDS_PER_DAY);
+        let seconds = local.rem_euclid(SECONDS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+
+        let offset = if utc_offset_minutes == 0 {
+            "Z".to_string()
+        } else {
+            let sign = if utc_offset_minutes < 0 { '-' } else { '+' };
+            let minutes = utc_offset_minutes.unsigned_abs();
+            format!("{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+        };
+
+        DateTimeOutput {
+            timestamp,
+            datetime: format!(
+                "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}{offset}",
+                seconds / 3600,
+                seconds % 3600 / 60,
+                seconds % 60
+            ),
+            // 1970-01-01 was a Thursday
+            weekday: WEEKDAYS[(days + 3).rem_euclid(7) as usize].to_string(),
+        }
+    }
+
+    /// Parse an RFC 3339 date (e.g.: `2024-03-01T12:30:00+02:00`) into a unix timestamp.
+    /// Dates without a time (e.g.: `2024-03-01`) or without an offset are interpreted as UTC.
+    pub fn parse(datetime: &str) -> Result<i64, DateTimeError> {
+        let invalid = || DateTimeError::InvalidDate(datetime.to_string());
+        let datetime = datetime.trim();
+
+        let (date, time) = match datetime.find(['T', 't', ' ']) {
+            Some(i) => (&datetime[..i], Some(&datetime[i + 1..])),
+            None => (datetime, None),
+        };
+
+        let mut date_parts = date.splitn(3, '-');
+        let mut next_date_part = || -> Result<i64, DateTimeError> {
+            date_parts
+                .next()
+                .and_then(|part| part.parse().ok())
+                .ok_or_else(invalid)
+        };
+        let (year, month, day) = (next_date_part()?, next_date_part()?, next_date_part()?);
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return Err(invalid());
+        }
+
+        let (seconds, offset) = match time {
+            None => (0, 0),
+            Some(time) => {
+                let (time, offset) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+                    (time, 0)
+                } else if let Some(i) = time.rfind(['+', '-']) {
+                    let sign = if &time[i..=i] == "-" { -1 } else { 1 };
+                    let (hours, minutes) = time[i + 1..].split_once(':').ok_or_else(invalid)?;
+                    let hours: i64 = hours.parse().map_err(|_| invalid())?;
+                    let minutes: i64 = minutes.parse().map_err(|_| invalid())?;
+                    (&time[..i], sign * (hours * 3600 + minutes * 60))
+                } else {
+                    (time, 0)
+                };
+
+                // Ignore fractional seconds
+                let time = time.split('.').next().unwrap_or(time);
+                let mut time_parts = time.splitn(3, ':');
+                let mut next_time_part = || -> Result<i64, DateTimeError> {
+                    time_parts
+                        .next()
+                        .map(|part| part.parse().map_err(|_| invalid()))
+                        .unwrap_or(Ok(0))
+                };
+                let (hours, minutes, seconds) =
+                    (next_time_part()?, next_time_part()?, next_time_part()?);
+                if hours > 23 || minutes > 59 || seconds > 60 {
+                    return Err(invalid());
+                }
+                (hours * 3600 + minutes * 60 + seconds, offset)
+            }
+        };
+
+        Ok(days_from_civil(year, month, day) * SECONDS_PER_DAY + seconds - offset)
+    }
+
+    fn now() -> Result<i64, DateTimeError> {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .map_err(|e| DateTimeError::ClockError(e.to_string()))
+    }
+}
+
+impl Tool for DateTime {
+    const NAME: &'static str = "datetime";
+
+    type Error = DateTimeError;
+    type Args = DateTimeArgs;
+    type Output = DateTimeOutput;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Get the current date and time, convert between unix timestamps and \
+                RFC 3339 dates, or add a duration to a date."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "operation": {
+                        "type": "string",
+                        "enum": ["now", "from_timestamp", "to_timestamp", "add"],
+                        "description": "now: get the current date; from_timestamp: convert `timestamp` to a date; \
+                            to_timestamp: convert `datetime` to a timestamp; add: add `seconds` to `datetime`"
+                    },
+                    "timestamp": {
+                        "type": "integer",
+                        "description": "Unix timestamp in seconds (for from_timestamp)"
+                    },
+                    "datetime": {
+                        "type": "string",
+                        "description": "RFC 3339 date, e.g. 2024-03-01T12:30:00Z (for to_timestamp and add)"
+                    },
+                    "seconds": {
+                        "type": "integer",
+                        "description": "Number of seconds to add, can be negative (for add)"
+                    },
+                    "utc_offset_minutes": {
+                        "type": "integer",
+                        "description": "Offset from UTC in minutes of the returned date (default: 0)"
+                    }
+                },
+                "required": ["operation"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let timestamp = match args.operation {
+            DateTimeOperation::Now => Self::now()?,
+            DateTimeOperation::FromTimestamp => args.timestamp.ok_or(
+                DateTimeError::MissingArgument("timestamp", "from_timestamp"),
+            )?,
+            DateTimeOperation::ToTimestamp => Self::parse(
+                args.datetime
+                    .as_deref()
+                    .ok_or(DateTimeError::MissingArgument("datetime", "to_timestamp"))?,
+            )?,
+            DateTimeOperation::Add => {
+                let datetime = args
+                    .datetime
+                    .as_deref()
+                    .ok_or(DateTimeError::MissingArgument("datetime", "add"))?;
+                let seconds = args
+                    .seconds
+                    .ok_or(DateTimeError::MissingArgument("seconds", "add"))?;
+                Self::parse(datetime)? + seconds
+            }
+        };
+
+        Ok(Self::format(
+            timestamp,
+            args.utc_offset_minutes.unwrap_or_default(),
+        ))
+    }
+}
+
+impl ToolEmbedding for DateTime {
+    type InitError = Infallible;
+    type Context = ();
+    type State = ();
+
+    fn embedding_docs(&self) -> Vec<String> {
+        vec![
+            "Get the current date and time".into(),
+            "What day is it today?".into(),
+            "Convert a unix timestamp to a date or a date to a unix timestamp".into(),
+        ]
+    }
+
+    fn context(&self) -> Self::Context {}
+
+    fn init(_state: Self::State, _context: Self::Context) -> Result<Self, Self::InitError> {
+        Ok(DateTime)
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Number of days since 1970-01-01 of the given date (proleptic Gregorian calendar).
+/// See <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Date of the given number of days since 1970-01-01 (proleptic Gregorian calendar).
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            DateTime::format(0, 0),
+            DateTimeOutput {
+                timestamp: 0,
+                datetime: "1970-01-01T00:00:00Z".to_string(),
+                weekday: "Thursday".to_string(),
+            }
+        );
+        assert_eq!(
+            DateTime::format(1_709_296_200, 0).datetime,
+            "2024-03-01T12:30:00Z"
+        );
+        assert_eq!(
+            DateTime::format(1_709_296_200, -330).datetime,
+            "2024-03-01T07:00:00-05:30"
+        );
+        assert_eq!(
+            DateTime::format(-86_400, 0).datetime,
+            "1969-12-31T00:00:00Z"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(DateTime::parse("2024-03-01T12:30:00Z"), Ok(1_709_296_200));
+        assert_eq!(
+            DateTime::parse("2024-03-01T14:30:00.123+02:00"),
+            Ok(1_709_296_200)
+        );
+        assert_eq!(DateTime::parse("2024-03-01"), Ok(1_709_251_200));
+        assert_eq!(DateTime::parse("2024-02-29 00:00"), Ok(1_709_164_800));
+        assert!(DateTime::parse("2023-02-29").is_err());
+        assert!(DateTime::parse("2024-13-01").is_err());
+        assert!(DateTime::parse("yesterday").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_call() {
+        let output = DateTime
+            .call(DateTimeArgs {
+                operation: DateTimeOperation::Add,
+                timestamp: None,
+                datetime: Some("2024-02-28T12:00:00Z".to_string()),
+                seconds: Some(2 * SECONDS_PER_DAY),
+                utc_offset_minutes: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(output.datetime, "2024-03-01T12:00:00Z");
+        assert_eq!(output.weekday, "Friday");
+
+        let err = DateTime
+            .call(DateTimeArgs {
+                operation: DateTimeOperation::FromTimestamp,
+                timestamp: None,
+                datetime: None,
+                seconds: None,
+                utc_offset_minutes: None,
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            DateTimeError::MissingArgument("timestamp", "from_timestamp")
+        );
+    }
+}
@@ -0,0 +1,479 @@
+//! Sandboxed filesystem tools.
+//!
+//! The [FileRead], [FileList] and [FileWrite] tools give models access to the files of a
+//! single root directory. Paths provided by the model are always interpreted relative to the
+//! root directory, and paths escaping it (e.g.: `../secret`, or symlinks pointing outside of
+//! the root directory) are rejected.
+//!
+//! The tools are independent so that, for example, an agent can be given read-only access to
+//! a directory by only adding the [FileRead] and [FileList] tools.
+use std::{
+    convert::Infallible,
+    fs,
+    io::Write,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolEmbedding},
+};
+
+/// Default maximum size (in bytes) of the files read by [FileRead]
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FsError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Path is outside of the sandbox: {0}")]
+    OutsideSandbox(String),
+
+    #[error("File {0} is too large ({1} bytes, maximum is {2} bytes)")]
+    FileTooLarge(String, u64, u64),
+}
+
+/// Resolve `path` relative to `root`, making sure the resolved path stays inside `root`
+fn resolve(root: &Path, path: &str) -> Result<PathBuf, FsError> {
+    let outside = || FsError::OutsideSandbox(path.to_string());
+
+    let mut relative = PathBuf::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::ParentDir => {
+                if !relative.pop() {
+                    return Err(outside());
+                }
+            }
+            // Absolute paths are interpreted relative to the root directory
+            Component::CurDir | Component::RootDir => (),
+            Component::Prefix(_) => return Err(outside()),
+        }
+    }
+
+    let resolved = root.join(relative);
+
+    // Resolve symlinks of the deepest existing ancestor to make sure they do not point
+    // outside of the root directory. Dangling symlinks (which do not "exist" but would be
+    // followed when creating files) cannot be resolved, and are rejected.
+    let root = root.canonicalize()?;
+    let existing = resolved
+        .ancestors()
+        .find(|ancestor| ancestor.symlink_metadata().is_ok())
+        .unwrap_or(&root)
+        .canonicalize()
+        .map_err(|_| outside())?;
+    if !existing.starts_with(&root) {
+        return Err(outside());
+    }
+
+    Ok(resolved)
+}
+
+#[derive(Deserialize)]
+pub struct FileReadArgs {
+    /// Path of the file to read, relative to the root directory
+    pub path: String,
+}
+
+/// Tool reading text files inside a root directory
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FileRead {
+    root: PathBuf,
+    max_file_size: u64,
+}
+
+impl FileRead {
+    /// Create a new tool reading files inside `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+        }
+    }
+
+    /// Set the maximum size (in bytes) of the files that can be read
+    pub fn max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+}
+
+impl Tool for FileRead {
+    const NAME: &'static str = "read_file";
+
+    type Error = FsError;
+    type Args = FileReadArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Read the content of a text file".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path of the file to read"
+                    }
+                },
+                "required": ["path"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let path = resolve(&self.root, &args.path)?;
+        let size = fs::metadata(&path)?.len();
+        if size > self.max_file_size {
+            return Err(FsError::FileTooLarge(args.path, size, self.max_file_size));
+        }
+        Ok(fs::read_to_string(path)?)
+    }
+}
+
+impl ToolEmbedding for FileRead {
+    type InitError = Infallible;
+    type Context = Self;
+    type State = ();
+
+    fn embedding_docs(&self) -> Vec<String> {
+        vec![
+            "Read the content of a file".into(),
+            "Open a text file and return its content".into(),
+        ]
+    }
+
+    fn context(&self) -> Self::Context {
+        self.clone()
+    }
+
+    fn init(_state: Self::State, context: Self::Context) -> Result<Self, Self::InitError> {
+        Ok(context)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct FileListArgs {
+    /// Path of the directory to list, relative to the root directory. Defaults to the root directory.
+    pub path: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FileEntry {
+    /// Path of the entry, relative to the root directory
+    pub path: String,
+    pub is_dir: bool,
+    /// Size of the file in bytes (0 for directories)
+    pub size: u64,
+}
+
+/// Tool listing the content of directories inside a root directory
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FileList {
+    root: PathBuf,
+}
+
+impl FileList {
+    /// Create a new tool listing directories inside `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl Tool for FileList {
+    const NAME: &'static str = "list_files";
+
+    type Error = FsError;
+    type Args = FileListArgs;
+    type Output = Vec<FileEntry>;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "List the files and directories in a directory".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path of the directory to list (defaults to the root directory)"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let dir = resolve(&self.root, args.path.as_deref().unwrap_or("."))?;
+
+        let mut entries = fs::read_dir(&dir)?
+            .map(|entry| {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+                let path = entry
+                    .path()
+                    .strip_prefix(&self.root)
+                    .unwrap_or(&entry.path())
+                    .to_string_lossy()
+                    .to_string();
+                Ok(FileEntry {
+                    path,
+                    is_dir: metadata.is_dir(),
+                    size: if metadata.is_dir() { 0 } else { metadata.len() },
+                })
+            })
+            .collect::<Result<Vec<_>, std::io::Error>>()?;
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Ok(entries)
+    }
+}
+
+impl ToolEmbedding for FileList {
+    type InitError = Infallible;
+    type Context = Self;
+    type State = ();
+
+    fn embedding_docs(&self) -> Vec<String> {
+        vec![
+            "List the files in a directory".into(),
+            "Show the content of a folder".into(),
+        ]
+    }
+
+    fn context(&self) -> Self::Context {
+        self.clone()
+    }
+
+    fn init(_state: Self::State, context: Self::Context) -> Result<Self, Self::InitError> {
+        Ok(context)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct FileWriteArgs {
+    /// Path of the file to write, relative to the root directory
+    pub path: String,
+    /// Content to write
+    pub content: String,
+    /// Append the content to the file instead of overwriting it
+    #[serde(default)]
+    pub append: bool,
+}
+
+/// Tool writing text files inside a root directory. Missing parent directories are created.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FileWrite {
+    root: PathBuf,
+}
+
+impl FileWrite {
+    /// Create a new tool writing files inside `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl Tool for FileWrite {
+    const NAME: &'static str = "write_file";
+
+    type Error = FsError;
+    type Args = FileWriteArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Write text to a file, overwriting it unless `append` is true".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path of the file to write"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "The text to write"
+                    },
+                    "append": {
+                        "type": "boolean",
+                        "description": "Append to the file instead of overwriting it (default: false)"
+                    }
+                },
+                "required": ["path", "content"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let path = resolve(&self.root, &args.path)?;
+        // Never write through a symlink, which could be replaced after being resolved
+        if path
+            .symlink_metadata()
+            .is_ok_and(|metadata| metadata.file_type().is_symlink())
+        {
+            return Err(FsError::OutsideSandbox(args.path));
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(args.append)
+            .truncate(!args.append)
+            .open(&path)?;
+        file.write_all(args.content.as_bytes())?;
+
+        Ok(format!(
+            "Wrote {} bytes to {}",
+            args.content.len(),
+            args.path
+        ))
+    }
+}
+
+impl ToolEmbedding for FileWrite {
+    type InitError = Infallible;
+    type Context = Self;
+    type State = ();
+
+    fn embedding_docs(&self) -> Vec<String> {
+        vec![
+            "Write text to a file".into(),
+            "Save content to a file or append to an existing file".into(),
+        ]
+    }
+
+    fn context(&self) -> Self::Context {
+        self.clone()
+    }
+
+    fn init(_state: Self::State, context: Self::Context) -> Result<Self, Self::InitError> {
+        Ok(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::prelude::{FileWriteStr, PathChild};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_and_list() {
+        let temp = assert_fs::TempDir::new().expect("Failed to create temp dir");
+        temp.child("notes.txt").write_str("Buy SOL").unwrap();
+        temp.child("logs/today.txt").write_str("Sold SOL").unwrap();
+
+        let read = FileRead::new(temp.path());
+        let content = read
+            .call(FileReadArgs {
+                path: "logs/../notes.txt".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(content, "Buy SOL");
+
+        let list = FileList::new(temp.path());
+        let entries = list.call(FileListArgs { path: None }).await.unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                FileEntry {
+                    path: "logs".to_string(),
+                    is_dir: true,
+                    size: 0
+                },
+                FileEntry {
+                    path: "notes.txt".to_string(),
+                    is_dir: false,
+                    size: 7
+                },
+            ]
+        );
+
+        let err = read
+            .clone()
+            .max_file_size(4)
+            .call(FileReadArgs {
+                path: "notes.txt".to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::FileTooLarge(_, 7, 4)));
+    }
+
+    #[tokio::test]
+    async fn test_write() {
+        let temp = assert_fs::TempDir::new().expect("Failed to create temp dir");
+        let write = FileWrite::new(temp.path());
+
+        for append in [false, true] {
+            write
+                .call(FileWriteArgs {
+                    path: "/journal/trades.txt".to_string(),
+                    content: "BUY SOL\n".to_string(),
+                    append,
+                })
+                .await
+                .unwrap();
+        }
+
+        let content = fs::read_to_string(temp.path().join("journal/trades.txt")).unwrap();
+        assert_eq!(content, "BUY SOL\nBUY SOL\n");
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_escape() {
+        let temp = assert_fs::TempDir::new().expect("Failed to create temp dir");
+        let root = temp.path().join("root");
+        fs::create_dir(&root).unwrap();
+        temp.child("secret.txt").write_str("private key").unwrap();
+
+        let err = FileRead::new(&root)
+            .call(FileReadArgs {
+                path: "../secret.txt".to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::OutsideSandbox(_)));
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(temp.path(), root.join("link")).unwrap();
+            let err = FileRead::new(&root)
+                .call(FileReadArgs {
+                    path: "link/secret.txt".to_string(),
+                })
+                .await
+                .unwrap_err();
+            assert!(matches!(err, FsError::OutsideSandbox(_)));
+
+            // Dangling symlinks are not followed when creating files
+            let target = temp.path().join("created.txt");
+            std::os::unix::fs::symlink(&target, root.join("dangling")).unwrap();
+            for path in ["dangling", "dangling/file.txt"] {
+                let err = FileWrite::new(&root)
+                    .call(FileWriteArgs {
+                        path: path.to_string(),
+                        content: "pwned".to_string(),
+                        append: false,
+                    })
+                    .await
+                    .unwrap_err();
+                assert!(matches!(err, FsError::OutsideSandbox(_)));
+            }
+            assert!(!target.exists());
+        }
+    }
+}
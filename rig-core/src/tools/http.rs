@@ -0,0 +1,280 @@
+//! HTTP fetch tool.
+//!
+//! The [HttpFetch] tool lets models fetch the content of web pages and APIs using `GET` requests.
+//! Requests are restricted to an allow-list of hosts (redirects to other hosts are not followed)
+//! and the size of the responses returned to the model is capped.
+use std::convert::Infallible;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolEmbedding},
+};
+
+/// Default maximum size (in bytes) of the responses returned by [HttpFetch]
+pub const DEFAULT_MAX_RESPONSE_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum HttpFetchError {
+    #[error("HttpError: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("Host is not allowed: {0}")]
+    HostNotAllowed(String),
+}
+
+#[derive(Deserialize)]
+pub struct HttpFetchArgs {
+    /// The URL to fetch
+    pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HttpFetchOutput {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: String,
+    /// Whether the body was truncated because it exceeded the maximum response size
+    pub truncated: bool,
+}
+
+/// Configuration of an [HttpFetch] tool
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct HttpFetchConfig {
+    /// Hosts that can be fetched. `*.example.com` allows all subdomains of `example.com`.
+    pub allowed_hosts: Vec<String>,
+    /// Maximum size (in bytes) of the responses returned to the model
+    pub max_response_size: usize,
+}
+
+impl HttpFetchConfig {
+    fn is_allowed(&self, url: &reqwest::Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host.to_lowercase();
+
+        matches!(url.scheme(), "http" | "https")
+            && self.allowed_hosts.iter().any(|allowed| {
+                let allowed = allowed.to_lowercase();
+                match allowed.strip_prefix("*.") {
+                    Some(domain) => host == domain || host.ends_with(&format!(".{domain}")),
+                    None => host == allowed,
+                }
+            })
+    }
+}
+
+/// Tool fetching the content of URLs whose host is in an allow-list
+///
+/// # Example
+/// ```
+/// use rig::tools::HttpFetch;
+///
+/// let fetch = HttpFetch::new(["api.coingecko.com", "*.solana.com"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct HttpFetch {
+    config: HttpFetchConfig,
+    client: reqwest::Client,
+}
+
+impl HttpFetch {
+    /// Create a new tool allowed to fetch URLs from the given hosts
+    pub fn new<S: Into<String>>(allowed_hosts: impl IntoIterator<Item = S>) -> Self {
+        Self::from_config(HttpFetchConfig {
+            allowed_hosts: allowed_hosts.into_iter().map(Into::into).collect(),
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+        })
+    }
+
+    /// Create a new tool from its configuration
+    pub fn from_config(config: HttpFetchConfig) -> Self {
+        let redirect_config = config.clone();
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() >= 10 {
+                    attempt.error("too many redirects")
+                } else if redirect_config.is_allowed(attempt.url()) {
+                    attempt.follow()
+                } else {
+                    attempt.stop()
+                }
+            }))
+            .build()
+            .expect("HTTP client should build");
+
+        Self { config, client }
+    }
+
+    /// Set the maximum size (in bytes) of the responses returned to the model
+    pub fn max_response_size(mut self, max_response_size: usize) -> Self {
+        self.config.max_response_size = max_response_size;
+        self
+    }
+}
+
+impl Tool for HttpFetch {
+    const NAME: &'static str = "http_fetch";
+
+    type Error = HttpFetchError;
+    type Args = HttpFetchArgs;
+    type Output = HttpFetchOutput;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: format!(
+                "Fetch the content of a URL with a GET request. Allowed hosts: {}",
+                self.config.allowed_hosts.join(", ")
+            ),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "url": {
+                        "type": "string",
+                        "description": "The URL to fetch"
+                    }
+                },
+                "required": ["url"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let url = reqwest::Url::parse(&args.url)
+            .map_err(|e| HttpFetchError::InvalidUrl(format!("{}: {e}", args.url)))?;
+        if !self.config.is_allowed(&url) {
+            return Err(HttpFetchError::HostNotAllowed(
+                url.host_str().unwrap_or_default().to_string(),
+            ));
+        }
+
+        let mut response = self.client.get(url).send().await?;
+
+        let status = response.status().as_u16();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        let mut body = Vec::new();
+        let mut truncated = false;
+        while let Some(chunk) = response.chunk().await? {
+            body.extend_from_slice(&chunk);
+            if body.len() > self.config.max_response_size {
+                body.truncate(self.config.max_response_size);
+                truncated = true;
+                break;
+            }
+        }
+
+        Ok(HttpFetchOutput {
+            status,
+            content_type,
+            body: String::from_utf8_lossy(&body).to_string(),
+            truncated,
+        })
+    }
+}
+
+impl ToolEmbedding for HttpFetch {
+    type InitError = Infallible;
+    type Context = HttpFetchConfig;
+    type State = ();
+
+    fn embedding_docs(&self) -> Vec<String> {
+        vec![
+            "Fetch the content of a web page".into(),
+            "Make an HTTP GET request to an API".into(),
+            "Download data from a URL".into(),
+        ]
+    }
+
+    fn context(&self) -> Self::Context {
+        self.config.clone()
+    }
+
+    fn init(_state: Self::State, context: Self::Context) -> Result<Self, Self::InitError> {
+        Ok(Self::from_config(context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// Serve a single HTTP response with the given body on a local port
+    async fn serve_once(body: &'static str) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = [0; 1024];
+            let _ = socket.read(&mut buffer).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        port
+    }
+
+    #[test]
+    fn test_allow_list() {
+        let config = HttpFetch::new(["api.example.com", "*.solana.com"]).config;
+        let allowed = |url: &str| config.is_allowed(&reqwest::Url::parse(url).unwrap());
+
+        assert!(allowed("https://api.example.com/v1/prices"));
+        assert!(allowed("http://API.example.com"));
+        assert!(allowed("https://solana.com"));
+        assert!(allowed("https://docs.solana.com/rpc"));
+        assert!(!allowed("https://example.com"));
+        assert!(!allowed("https://api.example.com.evil.com"));
+        assert!(!allowed("https://evilsolana.com"));
+        assert!(!allowed("ftp://api.example.com"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch() {
+        let port = serve_once("SOL: 101.5").await;
+        let fetch = HttpFetch::new(["127.0.0.1"]).max_response_size(8);
+
+        let output = fetch
+            .call(HttpFetchArgs {
+                url: format!("http://127.0.0.1:{port}/price"),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(output.status, 200);
+        assert_eq!(output.content_type.as_deref(), Some("text/plain"));
+        assert_eq!(output.body, "SOL: 101");
+        assert!(output.truncated);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_not_allowed() {
+        let fetch = HttpFetch::new(["api.example.com"]);
+
+        let err = fetch
+            .call(HttpFetchArgs {
+                url: "http://127.0.0.1/admin".to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, HttpFetchError::HostNotAllowed(host) if host == "127.0.0.1"));
+    }
+}
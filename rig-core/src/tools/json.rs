@@ -0,0 +1,265 @@
+//! JSON query tool.
+//!
+//! The [JsonQuery] tool extracts values from a JSON document using a path expression.
+//! Two path syntaxes are supported:
+//! - [JSON pointers](https://datatracker.ietf.org/doc/html/rfc6901) (e.g.: `/data/0/price`)
+//! - dot paths (e.g.: `data[0].price`), in which `*` selects every element of an array or
+//!   object (e.g.: `data[*].price` returns the prices of all elements of `data`)
+use std::convert::Infallible;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolEmbedding},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum JsonQueryError {
+    #[error("Invalid JSON document: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Invalid path: {0}")]
+    InvalidPath(String),
+
+    #[error("No value found at path: {0}")]
+    NotFound(String),
+}
+
+#[derive(Deserialize)]
+pub struct JsonQueryArgs {
+    /// The JSON document to query. Strings are parsed as JSON documents.
+    pub json: Value,
+    /// The path of the value(s) to extract
+    pub path: String,
+}
+
+/// Tool extracting values from JSON documents (see the [module documentation](self) for the
+/// supported path syntaxes)
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct JsonQuery;
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+impl JsonQuery {
+    /// Extract the value(s) at `path` from `document`. Paths containing wildcards return an
+    /// array of all matching values.
+    pub fn query(document: &Value, path: &str) -> Result<Value, JsonQueryError> {
+        let path = path.trim();
+
+        if path.is_empty() || path == "." || path == "$" {
+            return Ok(document.clone());
+        }
+
+        if path.starts_with('/') {
+            return document
+                .pointer(path)
+                .cloned()
+                .ok_or_else(|| JsonQueryError::NotFound(path.to_string()));
+        }
+
+        let segments = parse_path(path)?;
+        let has_wildcard = segments.contains(&Segment::Wildcard);
+
+        let mut values = vec![document];
+        for segment in &segments {
+            values = values
+                .into_iter()
+                .flat_map(|value| -> Vec<&Value> {
+                    match (segment, value) {
+                        (Segment::Key(key), Value::Object(map)) => {
+                            map.get(key).into_iter().collect()
+                        }
+                        (Segment::Index(i), Value::Array(array)) => {
+                            array.get(*i).into_iter().collect()
+                        }
+                        (Segment::Wildcard, Value::Array(array)) => array.iter().collect(),
+                        (Segment::Wildcard, Value::Object(map)) => map.values().collect(),
+                        _ => vec![],
+                    }
+                })
+                .collect();
+        }
+
+        match (has_wildcard, values.as_slice()) {
+            (true, values) => Ok(Value::Array(values.iter().map(|v| (*v).clone()).collect())),
+            (false, [value]) => Ok((*value).clone()),
+            (false, _) => Err(JsonQueryError::NotFound(path.to_string())),
+        }
+    }
+}
+
+fn parse_path(path: &str) -> Result<Vec<Segment>, JsonQueryError> {
+    let invalid = || JsonQueryError::InvalidPath(path.to_string());
+    let path = path.strip_prefix("$").unwrap_or(path);
+    let path = path.strip_prefix('.').unwrap_or(path);
+
+    let mut segments = Vec::new();
+    for part in path.split('.') {
+        let (key, mut indices) = match part.find('[') {
+            Some(i) => (&part[..i], &part[i..]),
+            None => (part, ""),
+        };
+
+        match key {
+            "" if indices.is_empty() => return Err(invalid()),
+            "" => (),
+            "*" => segments.push(Segment::Wildcard),
+            key => segments.push(Segment::Key(key.to_string())),
+        }
+
+        while !indices.is_empty() {
+            let end = indices.find(']').ok_or_else(invalid)?;
+            let index = indices[1..end].trim();
+            segments.push(match index {
+                "*" => Segment::Wildcard,
+                index => match index.parse() {
+                    Ok(index) => Segment::Index(index),
+                    // Bracket notation for keys (e.g.: `["my key"]`)
+                    Err(_) => Segment::Key(index.trim_matches(['"', '\'']).to_string()),
+                },
+            });
+            indices = &indices[end + 1..];
+            if !indices.is_empty() && !indices.starts_with('[') {
+                return Err(invalid());
+            }
+        }
+    }
+
+    Ok(segments)
+}
+
+impl Tool for JsonQuery {
+    const NAME: &'static str = "json_query";
+
+    type Error = JsonQueryError;
+    type Args = JsonQueryArgs;
+    type Output = Value;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Extract values from a JSON document using a path, either a JSON \
+                pointer (e.g. /data/0/price) or a dot path (e.g. data[0].price). In dot paths, \
+                * selects all elements (e.g. data[*].price)."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "json": {
+                        "description": "The JSON document to query (as JSON or as a string)"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "The path of the value(s) to extract"
+                    }
+                },
+                "required": ["json", "path"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let document = match args.json {
+            Value::String(text) => serde_json::from_str(&text)?,
+            document => document,
+        };
+        Self::query(&document, &args.path)
+    }
+}
+
+impl ToolEmbedding for JsonQuery {
+    type InitError = Infallible;
+    type Context = ();
+    type State = ();
+
+    fn embedding_docs(&self) -> Vec<String> {
+        vec![
+            "Extract a value from a JSON document".into(),
+            "Query a field of a JSON object or an element of a JSON array".into(),
+        ]
+    }
+
+    fn context(&self) -> Self::Context {}
+
+    fn init(_state: Self::State, _context: Self::Context) -> Result<Self, Self::InitError> {
+        Ok(JsonQuery)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document() -> Value {
+        json!({
+            "token": {"symbol": "SOL", "decimals": 9},
+            "prices": [
+                {"source": "dex", "price": 101.5},
+                {"source": "cex", "price": 102.0}
+            ],
+            "my key": true
+        })
+    }
+
+    #[test]
+    fn test_query() {
+        let document = document();
+        assert_eq!(
+            JsonQuery::query(&document, "token.symbol").unwrap(),
+            json!("SOL")
+        );
+        assert_eq!(
+            JsonQuery::query(&document, "$.prices[1].price").unwrap(),
+            json!(102.0)
+        );
+        assert_eq!(
+            JsonQuery::query(&document, "prices[*].source").unwrap(),
+            json!(["dex", "cex"])
+        );
+        assert_eq!(
+            JsonQuery::query(&document, "/prices/0/source").unwrap(),
+            json!("dex")
+        );
+        assert_eq!(
+            JsonQuery::query(&document, r#"["my key"]"#).unwrap(),
+            json!(true)
+        );
+        assert_eq!(JsonQuery::query(&document, "").unwrap(), document);
+    }
+
+    #[test]
+    fn test_query_errors() {
+        let document = document();
+        assert!(matches!(
+            JsonQuery::query(&document, "token.name"),
+            Err(JsonQueryError::NotFound(_))
+        ));
+        assert!(matches!(
+            JsonQuery::query(&document, "prices[0"),
+            Err(JsonQueryError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            JsonQuery::query(&document, "token..symbol"),
+            Err(JsonQueryError::InvalidPath(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_call_with_string_document() {
+        let output = JsonQuery
+            .call(JsonQueryArgs {
+                json: Value::String(r#"{"a": {"b": [1, 2, 3]}}"#.to_string()),
+                path: "a.b[2]".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(output, json!(3));
+    }
+}
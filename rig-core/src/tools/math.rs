@@ -0,0 +1,429 @@
+//! Math expression evaluator tool.
+//!
+//! The [Calculator] tool evaluates arithmetic expressions such as `(2 + 3) * sqrt(16) / 2^3`.
+//! Supported syntax:
+//! - numbers (e.g.: `42`, `3.14`, `1e-3`)
+//! - the binary operators `+`, `-`, `*`, `/`, `%` and `^` (exponentiation, right associative)
+//! - unary `-` and `+`
+//! - parentheses
+//! - the constants `pi` and `e`
+//! - the functions `sqrt`, `abs`, `ln`, `log` (base 10), `log2`, `exp`, `sin`, `cos`, `tan`,
+//!   `floor`, `ceil`, `round`, `min` and `max`
+use std::convert::Infallible;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolEmbedding},
+};
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum MathError {
+    #[error("Invalid expression: {0}")]
+    ParseError(String),
+
+    #[error("Unknown function or constant: {0}")]
+    UnknownIdentifier(String),
+
+    #[error("Function {0} expects {1} argument(s)")]
+    ArityError(String, usize),
+
+    #[error("Result is not a finite number")]
+    NotFinite,
+
+    #[error("Expression is nested more than {0} levels deep")]
+    TooDeeplyNested(usize),
+}
+
+/// Maximum nesting depth of parentheses, function calls, signs and exponents in an expression,
+/// preventing model-provided expressions from overflowing the stack of the parser
+pub const MAX_NESTING_DEPTH: usize = 64;
+
+#[derive(Deserialize)]
+pub struct CalculatorArgs {
+    /// The expression to evaluate
+    pub expression: String,
+}
+
+/// Tool evaluating math expressions (see the [module documentation](self) for the supported syntax)
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct Calculator;
+
+impl Calculator {
+    /// Evaluate the given math expression
+    pub fn evaluate(expression: &str) -> Result<f64, MathError> {
+        let mut parser = Parser {
+            tokens: tokenize(expression)?,
+            pos: 0,
+            depth: 0,
+        };
+        let value = parser.expression()?;
+        if let Some(token) = parser.peek() {
+            return Err(MathError::ParseError(format!("unexpected token {token:?}")));
+        }
+        if value.is_finite() {
+            Ok(value)
+        } else {
+            Err(MathError::NotFinite)
+        }
+    }
+}
+
+impl Tool for Calculator {
+    const NAME: &'static str = "calculator";
+
+    type Error = MathError;
+    type Args = CalculatorArgs;
+    type Output = f64;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Evaluate a math expression. Supports + - * / % ^, parentheses, the \
+                constants pi and e, and the functions sqrt, abs, ln, log, log2, exp, sin, cos, \
+                tan, floor, ceil, round, min and max."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "expression": {
+                        "type": "string",
+                        "description": "The math expression to evaluate (e.g.: \"(2 + 3) * sqrt(16)\")"
+                    }
+                },
+                "required": ["expression"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        Self::evaluate(&args.expression)
+    }
+}
+
+impl ToolEmbedding for Calculator {
+    type InitError = Infallible;
+    type Context = ();
+    type State = ();
+
+    fn embedding_docs(&self) -> Vec<String> {
+        vec![
+            "Evaluate a math expression".into(),
+            "Calculate the result of an arithmetic operation".into(),
+            "Add, subtract, multiply, divide numbers or compute powers and square roots".into(),
+        ]
+    }
+
+    fn context(&self) -> Self::Context {}
+
+    fn init(_state: Self::State, _context: Self::Context) -> Result<Self, Self::InitError> {
+        Ok(Calculator)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Operator(char),
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, MathError> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '0'..='9' | '.' => {
+                let mut end = start;
+                let mut previous = c;
+                while let Some(&(i, c)) = chars.peek() {
+                    let is_exponent_sign = (c == '-' || c == '+') && matches!(previous, 'e' | 'E');
+                    if c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || is_exponent_sign {
+                        end = i + c.len_utf8();
+                        previous = c;
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let number = &expression[start..end];
+                tokens.push(Token::Number(number.parse().map_err(|_| {
+                    MathError::ParseError(format!("invalid number {number}"))
+                })?));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = start;
+                while let Some(&(i, c)) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' {
+                        end = i + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Identifier(expression[start..end].to_lowercase()));
+            }
+            '+' | '-' | '*' | '/' | '%' | '^' => {
+                tokens.push(Token::Operator(c));
+                chars.next();
+            }
+            '(' => {
+                tokens.push(Token::LeftParen);
+                chars.next();
+            }
+            ')' => {
+                tokens.push(Token::RightParen);
+                chars.next();
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                chars.next();
+            }
+            c => return Err(MathError::ParseError(format!("unexpected character {c}"))),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser evaluating the expression as it is parsed
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Current nesting depth (see [MAX_NESTING_DEPTH])
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), MathError> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(MathError::ParseError(format!(
+                "expected {expected:?}, found {token:?}"
+            ))),
+            None => Err(MathError::ParseError(format!(
+                "expected {expected:?}, found end of expression"
+            ))),
+        }
+    }
+
+    /// expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<f64, MathError> {
+        let mut value = self.term()?;
+        while let Some(Token::Operator(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.next();
+            let rhs = self.term()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Ok(value)
+    }
+
+    /// term := unary (('*' | '/' | '%') unary)*
+    fn term(&mut self) -> Result<f64, MathError> {
+        let mut value = self.unary()?;
+        while let Some(Token::Operator(op @ ('*' | '/' | '%'))) = self.peek().cloned() {
+            self.next();
+            let rhs = self.unary()?;
+            value = match op {
+                '*' => value * rhs,
+                '/' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+        Ok(value)
+    }
+
+    /// unary := ('-' | '+') unary | power
+    ///
+    /// Every level of nesting goes through `unary`, which limits the depth of the recursion.
+    fn unary(&mut self) -> Result<f64, MathError> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(MathError::TooDeeplyNested(MAX_NESTING_DEPTH));
+        }
+        self.depth += 1;
+        let value = match self.peek() {
+            Some(Token::Operator('-')) => {
+                self.next();
+                self.unary().map(|value| -value)
+            }
+            Some(Token::Operator('+')) => {
+                self.next();
+                self.unary()
+            }
+            _ => self.power(),
+        };
+        self.depth -= 1;
+        value
+    }
+
+    /// power := primary ('^' unary)?
+    fn power(&mut self) -> Result<f64, MathError> {
+        let base = self.primary()?;
+        if let Some(Token::Operator('^')) = self.peek() {
+            self.next();
+            let exponent = self.unary()?;
+            return Ok(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    /// primary := number | '(' expression ')' | identifier ('(' arguments ')')?
+    fn primary(&mut self) -> Result<f64, MathError> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(value),
+            Some(Token::LeftParen) => {
+                let value = self.expression()?;
+                self.expect(Token::RightParen)?;
+                Ok(value)
+            }
+            Some(Token::Identifier(name)) => {
+                if let Some(Token::LeftParen) = self.peek() {
+                    self.next();
+                    let args = self.arguments()?;
+                    call_function(&name, &args)
+                } else {
+                    match name.as_str() {
+                        "pi" => Ok(std::f64::consts::PI),
+                        "e" => Ok(std::f64::consts::E),
+                        _ => Err(MathError::UnknownIdentifier(name)),
+                    }
+                }
+            }
+            Some(token) => Err(MathError::ParseError(format!("unexpected token {token:?}"))),
+            None => Err(MathError::ParseError(
+                "unexpected end of expression".to_string(),
+            )),
+        }
+    }
+
+    /// arguments := (expression (',' expression)*)? ')'
+    fn arguments(&mut self) -> Result<Vec<f64>, MathError> {
+        let mut args = Vec::new();
+        if let Some(Token::RightParen) = self.peek() {
+            self.next();
+            return Ok(args);
+        }
+        loop {
+            args.push(self.expression()?);
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::RightParen) => return Ok(args),
+                _ => {
+                    return Err(MathError::ParseError(
+                        "expected , or ) in function call".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+fn call_function(name: &str, args: &[f64]) -> Result<f64, MathError> {
+    let unary = |f: fn(f64) -> f64| match args {
+        [x] => Ok(f(*x)),
+        _ => Err(MathError::ArityError(name.to_string(), 1)),
+    };
+
+    match name {
+        "sqrt" => unary(f64::sqrt),
+        "abs" => unary(f64::abs),
+        "ln" => unary(f64::ln),
+        "log" => unary(f64::log10),
+        "log2" => unary(f64::log2),
+        "exp" => unary(f64::exp),
+        "sin" => unary(f64::sin),
+        "cos" => unary(f64::cos),
+        "tan" => unary(f64::tan),
+        "floor" => unary(f64::floor),
+        "ceil" => unary(f64::ceil),
+        "round" => unary(f64::round),
+        "min" | "max" if args.is_empty() => Err(MathError::ArityError(name.to_string(), 1)),
+        "min" => Ok(args.iter().copied().fold(f64::INFINITY, f64::min)),
+        "max" => Ok(args.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+        _ => Err(MathError::UnknownIdentifier(name.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(Calculator::evaluate("1 + 2 * 3"), Ok(7.0));
+        assert_eq!(Calculator::evaluate("(1 + 2) * 3"), Ok(9.0));
+        assert_eq!(Calculator::evaluate("2 ^ 3 ^ 2"), Ok(512.0));
+        assert_eq!(Calculator::evaluate("-2 ^ 2"), Ok(-4.0));
+        assert_eq!(Calculator::evaluate("10 % 4 - -1"), Ok(3.0));
+        assert_eq!(Calculator::evaluate("1.5e3 / 3"), Ok(500.0));
+        assert_eq!(Calculator::evaluate("sqrt(16) + abs(-2)"), Ok(6.0));
+        assert_eq!(Calculator::evaluate("max(1, 5, 3) - min(4, 2)"), Ok(3.0));
+        assert_eq!(Calculator::evaluate("round(PI * 100)"), Ok(314.0));
+    }
+
+    #[test]
+    fn test_evaluate_errors() {
+        assert!(matches!(
+            Calculator::evaluate("1 +"),
+            Err(MathError::ParseError(_))
+        ));
+        assert!(matches!(
+            Calculator::evaluate("(1 + 2"),
+            Err(MathError::ParseError(_))
+        ));
+        assert_eq!(
+            Calculator::evaluate("foo(1)"),
+            Err(MathError::UnknownIdentifier("foo".to_string()))
+        );
+        assert_eq!(
+            Calculator::evaluate("sqrt(1, 2)"),
+            Err(MathError::ArityError("sqrt".to_string(), 1))
+        );
+        assert_eq!(Calculator::evaluate("1 / 0"), Err(MathError::NotFinite));
+
+        // Deeply nested expressions are rejected instead of overflowing the stack
+        let nested = format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000));
+        assert_eq!(
+            Calculator::evaluate(&nested),
+            Err(MathError::TooDeeplyNested(MAX_NESTING_DEPTH))
+        );
+        let negated = format!("{}1", "-".repeat(100_000));
+        assert_eq!(
+            Calculator::evaluate(&negated),
+            Err(MathError::TooDeeplyNested(MAX_NESTING_DEPTH))
+        );
+        assert_eq!(
+            Calculator::evaluate(&format!("{}1", "-".repeat(10))),
+            Ok(1.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_call() {
+        let result = Calculator
+            .call(CalculatorArgs {
+                expression: "2 * (3 + 4)".to_string(),
+            })
+            .await;
+        assert_eq!(result, Ok(14.0));
+    }
+}
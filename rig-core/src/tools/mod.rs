@@ -0,0 +1,41 @@
+//! This module provides a library of commonly needed tools that can be given to an
+//! [Agent](crate::agent::Agent), either as static tools or as dynamic (RAGged) tools.
+//!
+//! Every tool implements both the [Tool](crate::tool::Tool) and [ToolEmbedding](crate::tool::ToolEmbedding)
+//! traits:
+//! - [FileRead], [FileList] and [FileWrite]: read, list and write files, sandboxed to a root directory
+//! - [HttpFetch]: fetch the content of web pages, restricted to an allow-list of hosts
+//! - [Calculator]: evaluate math expressions
+//! - [DateTime]: get the current date and time and convert between timestamps and dates
+//! - [JsonQuery]: extract values from JSON documents
+//!
+//! Note: This module requires the `tools` feature to be enabled in the `Cargo.toml` file.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     providers::openai,
+//!     tools::{Calculator, DateTime, FileRead},
+//! };
+//!
+//! let openai = openai::Client::from_env();
+//!
+//! let agent = openai.agent(openai::GPT_4O)
+//!     .preamble("You are a helpful assistant.")
+//!     .tool(Calculator)
+//!     .tool(DateTime)
+//!     .tool(FileRead::new("./notes"))
+//!     .build();
+//! ```
+
+pub mod datetime;
+pub mod fs;
+pub mod http;
+pub mod json;
+pub mod math;
+
+pub use datetime::DateTime;
+pub use fs::{FileList, FileRead, FileWrite};
+pub use http::HttpFetch;
+pub use json::JsonQuery;
+pub use math::Calculator;
@@ -0,0 +1,313 @@
+//! In-memory [BM25](https://en.wikipedia.org/wiki/Okapi_BM25) keyword index.
+//!
+//! Embeddings capture the meaning of a text but are poor at matching exact tokens such as
+//! tickers, contract addresses or identifiers. A [Bm25Index] ranks documents by the query terms
+//! they contain instead, and can be combined with a vector index using a
+//! [HybridIndex](super::hybrid::HybridIndex).
+//!
+//! # Example
+//! ```rust
+//! use rig::vector_store::{bm25::Bm25Index, VectorStoreIndex};
+//!
+//! // Index the texts that were embedded for the documents of the store
+//! let keyword_index = vector_store.keyword_index();
+//!
+//! let results = keyword_index
+//!     .top_n::<TokenReport>("So11111111111111111111111111111111111111112", 3)
+//!     .await?;
+//! ```
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{in_memory_store::InMemoryVectorStore, VectorStoreError, VectorStoreIndex};
+use crate::telemetry;
+
+/// Default term frequency saturation parameter
+pub const DEFAULT_K1: f64 = 1.2;
+/// Default document length normalization parameter
+pub const DEFAULT_B: f64 = 0.75;
+
+/// Split `text` into lowercase terms made of alphanumeric characters (so that e.g.: `$SOL`,
+/// `sol` and `SOL,` all match).
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+}
+
+struct IndexedDocument<D> {
+    id: String,
+    document: D,
+    term_frequencies: HashMap<String, usize>,
+    length: usize,
+}
+
+/// BM25 keyword index over a set of documents and their texts.
+pub struct Bm25Index<D: Serialize> {
+    documents: Vec<IndexedDocument<D>>,
+    /// Number of documents containing each term
+    document_frequencies: HashMap<String, usize>,
+    total_length: usize,
+    k1: f64,
+    b: f64,
+}
+
+impl<D: Serialize> Default for Bm25Index<D> {
+    fn default() -> Self {
+        Self {
+            documents: vec![],
+            document_frequencies: HashMap::new(),
+            total_length: 0,
+            k1: DEFAULT_K1,
+            b: DEFAULT_B,
+        }
+    }
+}
+
+impl<D: Serialize> Bm25Index<D> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new [Bm25Index] from documents, their ids and the texts to index.
+    pub fn from_documents_with_ids(
+        documents: impl IntoIterator<Item = (impl ToString, impl AsRef<str>, D)>,
+    ) -> Self {
+        let mut index = Self::new();
+        index.add_documents_with_ids(documents);
+        index
+    }
+
+    /// Set the term frequency saturation parameter (default: [DEFAULT_K1])
+    pub fn k1(mut self, k1: f64) -> Self {
+        self.k1 = k1;
+        self
+    }
+
+    /// Set the document length normalization parameter (default: [DEFAULT_B])
+    pub fn b(mut self, b: f64) -> Self {
+        self.b = b;
+        self
+    }
+
+    /// Add documents, their ids and the texts to index.
+    pub fn add_documents_with_ids(
+        &mut self,
+        documents: impl IntoIterator<Item = (impl ToString, impl AsRef<str>, D)>,
+    ) {
+        for (id, text, document) in documents {
+            let mut term_frequencies = HashMap::new();
+            let mut length = 0;
+            for term in tokenize(text.as_ref()) {
+                *term_frequencies.entry(term).or_insert(0) += 1;
+                length += 1;
+            }
+
+            for term in term_frequencies.keys() {
+                *self.document_frequencies.entry(term.clone()).or_insert(0) += 1;
+            }
+            self.total_length += length;
+
+            self.documents.push(IndexedDocument {
+                id: id.to_string(),
+                document,
+                term_frequencies,
+                length,
+            });
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Inverse document frequency of `term`
+    fn idf(&self, term: &str) -> f64 {
+        let n = self.documents.len() as f64;
+        let df = self.document_frequencies.get(term).copied().unwrap_or(0) as f64;
+        ((n - df + 0.5) / (df + 0.5) + 1.0).ln()
+    }
+
+    /// Get the `n` documents with the highest BM25 score for `query`, sorted by decreasing
+    /// score. Documents containing none of the query terms are not returned.
+    fn search(&self, query: &str, n: usize) -> Vec<(f64, &String, &D)> {
+        let mut terms = tokenize(query).collect::<Vec<_>>();
+        terms.sort();
+        terms.dedup();
+
+        let idfs = terms
+            .iter()
+            .map(|term| (term, self.idf(term)))
+            .collect::<Vec<_>>();
+        let avg_length = self.total_length as f64 / self.documents.len().max(1) as f64;
+
+        let mut results = self
+            .documents
+            .iter()
+            .filter_map(|doc| {
+                let normalization =
+                    self.k1 * (1.0 - self.b + self.b * doc.length as f64 / avg_length.max(1.0));
+                let score = idfs
+                    .iter()
+                    .filter_map(|(term, idf)| {
+                        doc.term_frequencies.get(*term).map(|&tf| {
+                            let tf = tf as f64;
+                            idf * tf * (self.k1 + 1.0) / (tf + normalization)
+                        })
+                    })
+                    .sum::<f64>();
+
+                (score > 0.0).then_some((score, &doc.id, &doc.document))
+            })
+            .collect::<Vec<_>>();
+
+        results.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        results.truncate(n);
+        results
+    }
+}
+
+impl<D: Serialize + Clone> InMemoryVectorStore<D> {
+    /// Create a [Bm25Index] of the documents of the store. The indexed text of each document
+    /// is the text of its embeddings.
+    pub fn keyword_index(&self) -> Bm25Index<D> {
+        Bm25Index::from_documents_with_ids(self.iter().map(|(id, (doc, embeddings))| {
+            let text = embeddings
+                .iter()
+                .map(|embedding| embedding.document.as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+            (id, text, doc.clone())
+        }))
+    }
+}
+
+impl<D: Serialize + Send + Sync> VectorStoreIndex for Bm25Index<D> {
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        telemetry::instrument_retrieval(telemetry::retrieval_span("bm25", query, n), async {
+            self.search(query, n)
+                .into_iter()
+                .map(|(score, id, doc)| {
+                    Ok((
+                        score,
+                        id.clone(),
+                        serde_json::from_value(serde_json::to_value(doc)?)?,
+                    ))
+                })
+                .collect()
+        })
+        .await
+    }
+
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        telemetry::instrument_retrieval(telemetry::retrieval_span("bm25", query, n), async {
+            Ok(self
+                .search(query, n)
+                .into_iter()
+                .map(|(score, id, _)| (score, id.clone()))
+                .collect())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{embeddings::Embedding, OneOrMany};
+
+    use super::*;
+
+    fn index() -> Bm25Index<String> {
+        Bm25Index::from_documents_with_ids([
+            (
+                "sol",
+                "SOL (mint So11111111111111111111111111111111111111112) rallied 12% today.",
+                "sol".to_string(),
+            ),
+            (
+                "bonk",
+                "BONK volume doubled while SOL was flat.",
+                "bonk".to_string(),
+            ),
+            (
+                "jup",
+                "JUP governance vote passed, JUP holders approved the proposal.",
+                "jup".to_string(),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("$SOL, BONK-usd!").collect::<Vec<_>>(),
+            vec!["sol", "bonk", "usd"]
+        );
+    }
+
+    #[test]
+    fn test_exact_token_match() {
+        let index = index();
+
+        let results = index.search("So11111111111111111111111111111111111111112", 10);
+        assert_eq!(
+            results
+                .iter()
+                .map(|(_, id, _)| id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["sol"]
+        );
+
+        let results = index.search("What happened to $SOL?", 10);
+        assert_eq!(
+            results
+                .iter()
+                .map(|(_, id, _)| id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["sol", "bonk"]
+        );
+
+        assert!(index.search("ethereum", 10).is_empty());
+    }
+
+    #[test]
+    fn test_term_frequency() {
+        let index = index();
+        let results = index.search("jup bonk", 10);
+        assert_eq!(results[0].1, "jup");
+    }
+
+    #[tokio::test]
+    async fn test_keyword_index_of_store() {
+        let store = InMemoryVectorStore::from_documents_with_ids([(
+            "doc1",
+            "WIF report".to_string(),
+            OneOrMany::one(Embedding {
+                document: "dogwifhat (WIF) listed on a new exchange".to_string(),
+                vec: vec![0.1, 0.2],
+            }),
+        )]);
+
+        let results = store
+            .keyword_index()
+            .top_n::<String>("wif", 1)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1, "doc1");
+        assert_eq!(results[0].2, "WIF report");
+    }
+}
@@ -0,0 +1,293 @@
+//! Backend-agnostic filter expressions over the fields of the documents of a vector store.
+//!
+//! A [Filter] is passed per query with [SearchOptions::filter](super::SearchOptions::filter).
+//! Vector stores translate it to their native form (e.g.: a MongoDB `$vectorSearch` pre-filter
+//! or a Cypher `WHERE` clause) when they can, and otherwise evaluate it as a predicate on the
+//! returned documents with [Filter::matches].
+//!
+//! Fields are referenced by their (dot separated) path in the serialized document, e.g.:
+//! `"metadata.chain"`.
+//!
+//! # Example
+//! ```rust
+//! use rig::vector_store::{filter::Filter, SearchOptions, VectorStoreIndex};
+//!
+//! // Only search the reports on Solana tokens published in 2024
+//! let filter = Filter::eq("chain", "solana")
+//!     .and(Filter::between("published_at", "2024-01-01", "2024-12-31"))
+//!     .and(!Filter::one_of("status", ["draft", "retracted"]));
+//!
+//! let results = index
+//!     .top_n_with_options::<TokenReport>("SOL outlook", 5, &SearchOptions::new().filter(filter))
+//!     .await?;
+//! ```
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Filter expression over the fields of a document.
+///
+/// Comparisons are only true between values of the same type: numbers are compared
+/// numerically, strings lexicographically. A comparison on a missing field is false, except
+/// for [Filter::Ne] (and negated expressions) which are true.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Filter {
+    /// `field == value`
+    Eq { field: String, value: Value },
+    /// `field != value`
+    Ne { field: String, value: Value },
+    /// `field > value`
+    Gt { field: String, value: Value },
+    /// `field >= value`
+    Gte { field: String, value: Value },
+    /// `field < value`
+    Lt { field: String, value: Value },
+    /// `field <= value`
+    Lte { field: String, value: Value },
+    /// `field` is equal to one of `values`
+    In { field: String, values: Vec<Value> },
+    /// All the filters match
+    And(Vec<Filter>),
+    /// At least one of the filters matches
+    Or(Vec<Filter>),
+    /// The filter does not match
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    pub fn eq(field: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::Eq {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn ne(field: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::Ne {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn gt(field: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::Gt {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn gte(field: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::Gte {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn lt(field: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::Lt {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn lte(field: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::Lte {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    /// `field` is equal to one of `values`
+    pub fn one_of(
+        field: impl Into<String>,
+        values: impl IntoIterator<Item = impl Into<Value>>,
+    ) -> Self {
+        Self::In {
+            field: field.into(),
+            values: values.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// `min <= field <= max`
+    pub fn between(field: impl Into<String>, min: impl Into<Value>, max: impl Into<Value>) -> Self {
+        let field = field.into();
+        Self::And(vec![Self::gte(field.clone(), min), Self::lte(field, max)])
+    }
+
+    /// Both `self` and `other` match
+    pub fn and(self, other: Filter) -> Self {
+        match (self, other) {
+            (Self::And(mut filters), Self::And(others)) => {
+                filters.extend(others);
+                Self::And(filters)
+            }
+            (Self::And(mut filters), other) => {
+                filters.push(other);
+                Self::And(filters)
+            }
+            (filter, other) => Self::And(vec![filter, other]),
+        }
+    }
+
+    /// Either `self` or `other` matches
+    pub fn or(self, other: Filter) -> Self {
+        match (self, other) {
+            (Self::Or(mut filters), Self::Or(others)) => {
+                filters.extend(others);
+                Self::Or(filters)
+            }
+            (Self::Or(mut filters), other) => {
+                filters.push(other);
+                Self::Or(filters)
+            }
+            (filter, other) => Self::Or(vec![filter, other]),
+        }
+    }
+
+    /// Evaluate the filter on a serialized `document`
+    pub fn matches(&self, document: &Value) -> bool {
+        match self {
+            Self::Eq { field, value } => {
+                lookup(document, field).is_some_and(|v| values_eq(v, value))
+            }
+            Self::Ne { field, value } => {
+                lookup(document, field).is_none_or(|v| !values_eq(v, value))
+            }
+            Self::Gt { field, value } => compare(document, field, value, Ordering::is_gt),
+            Self::Gte { field, value } => compare(document, field, value, Ordering::is_ge),
+            Self::Lt { field, value } => compare(document, field, value, Ordering::is_lt),
+            Self::Lte { field, value } => compare(document, field, value, Ordering::is_le),
+            Self::In { field, values } => lookup(document, field)
+                .is_some_and(|v| values.iter().any(|value| values_eq(v, value))),
+            Self::And(filters) => filters.iter().all(|filter| filter.matches(document)),
+            Self::Or(filters) => filters.iter().any(|filter| filter.matches(document)),
+            Self::Not(filter) => !filter.matches(document),
+        }
+    }
+}
+
+impl std::ops::Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Self::Output {
+        match self {
+            Self::Not(filter) => *filter,
+            filter => Self::Not(Box::new(filter)),
+        }
+    }
+}
+
+/// Get the value at the dot separated `path` of `document`
+fn lookup<'a>(document: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(document, |value, key| value.get(key))
+        .filter(|value| !value.is_null())
+}
+
+/// Equality of JSON values, with numbers compared numerically (so that `1` equals `1.0`)
+fn values_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        _ => a == b,
+    }
+}
+
+fn compare(document: &Value, field: &str, value: &Value, f: fn(Ordering) -> bool) -> bool {
+    let ordering = match (lookup(document, field), value) {
+        (Some(Value::Number(a)), Value::Number(b)) => a
+            .as_f64()
+            .zip(b.as_f64())
+            .and_then(|(a, b)| a.partial_cmp(&b)),
+        (Some(Value::String(a)), Value::String(b)) => Some(a.cmp(b)),
+        (Some(Value::Bool(a)), Value::Bool(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+
+    ordering.is_some_and(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn report() -> Value {
+        json!({
+            "symbol": "SOL",
+            "price": 142.5,
+            "published_at": "2024-06-01",
+            "metadata": { "chain": "solana", "verified": true },
+            "tags": null,
+        })
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let report = report();
+
+        assert!(Filter::eq("symbol", "SOL").matches(&report));
+        assert!(!Filter::eq("symbol", "sol").matches(&report));
+        assert!(Filter::eq("metadata.chain", "solana").matches(&report));
+        assert!(Filter::gt("price", 100).matches(&report));
+        assert!(Filter::eq("metadata.verified", true).matches(&report));
+        assert!(Filter::eq("price", 142.5).matches(&json!({ "price": 142.5 })));
+        assert!(Filter::eq("price", 142).matches(&json!({ "price": 142.0 })));
+        assert!(Filter::lte("price", 142.5).matches(&report));
+        assert!(!Filter::lt("price", 142.5).matches(&report));
+        assert!(Filter::between("published_at", "2024-01-01", "2024-12-31").matches(&report));
+        assert!(Filter::one_of("symbol", ["BTC", "SOL"]).matches(&report));
+
+        // Mixed types never compare
+        assert!(!Filter::gt("price", "100").matches(&report));
+        assert!(!Filter::eq("price", "142.5").matches(&report));
+    }
+
+    #[test]
+    fn test_missing_fields() {
+        let report = report();
+
+        assert!(!Filter::eq("volume", 1).matches(&report));
+        assert!(!Filter::gte("volume", 0).matches(&report));
+        assert!(!Filter::eq("tags", Value::Null).matches(&report));
+        assert!(Filter::ne("volume", 1).matches(&report));
+        assert!((!Filter::gte("metadata.volume", 0)).matches(&report));
+    }
+
+    #[test]
+    fn test_combinators() {
+        let report = report();
+
+        let filter = Filter::eq("symbol", "SOL")
+            .and(Filter::eq("metadata.verified", true))
+            .and(!Filter::one_of("metadata.chain", ["ethereum"]));
+        assert!(matches!(&filter, Filter::And(filters) if filters.len() == 3));
+        assert!(filter.matches(&report));
+
+        let filter = Filter::eq("symbol", "BTC").or(Filter::gt("price", 1000));
+        assert!(!filter.matches(&report));
+        assert!(filter
+            .clone()
+            .or(Filter::eq("symbol", "SOL"))
+            .matches(&report));
+        assert_eq!(!!filter.clone(), filter);
+    }
+
+    #[test]
+    fn test_serde() {
+        let filter = Filter::eq("symbol", "SOL").and(!Filter::gt("price", 100));
+        let json = serde_json::to_value(&filter).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "and": [
+                    { "eq": { "field": "symbol", "value": "SOL" } },
+                    { "not": { "gt": { "field": "price", "value": 100 } } },
+                ]
+            })
+        );
+        assert_eq!(serde_json::from_value::<Filter>(json).unwrap(), filter);
+    }
+}
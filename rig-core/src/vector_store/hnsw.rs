@@ -0,0 +1,572 @@
+//! Approximate nearest neighbor search for the [InMemoryVectorStore] using a
+//! [Hierarchical Navigable Small World](https://arxiv.org/abs/1603.09320) (HNSW) graph.
+//!
+//! By default, the in-memory store compares the query with every embedding of the store. With
+//! an HNSW index (see [InMemoryVectorStore::with_hnsw]), queries only visit a small part of the
+//! embeddings, at the cost of occasionally missing some of the nearest documents. The recall can
+//! be traded for speed with the [HnswConfig] parameters.
+//!
+//! # Example
+//! ```rust
+//! use rig::vector_store::{hnsw::HnswConfig, in_memory_store::InMemoryVectorStore};
+//!
+//! let store = InMemoryVectorStore::from_documents(embeddings)
+//!     .with_hnsw(HnswConfig::new().m(32).ef_search(100));
+//!
+//! // Documents added to the store are also added to the index
+//! let index = store.index(model);
+//! ```
+use std::{
+    borrow::Cow,
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+
+use super::in_memory_store::{stored_vector, InMemoryVectorStore};
+use crate::embeddings::distance::DistanceMetric;
+
+/// Default number of neighbors of each node
+pub const DEFAULT_M: usize = 16;
+/// Default size of the candidate list when inserting a node
+pub const DEFAULT_EF_CONSTRUCTION: usize = 200;
+/// Default size of the candidate list when searching
+pub const DEFAULT_EF_SEARCH: usize = 64;
+
+/// Parameters of an HNSW index
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HnswConfig {
+    /// Number of neighbors of each node (twice as many on the bottom layer). Higher values
+    /// improve the recall at the cost of memory and insertion time.
+    pub m: usize,
+    /// Size of the candidate list when inserting a node. Higher values build a better graph
+    /// at the cost of insertion time.
+    pub ef_construction: usize,
+    /// Size of the candidate list when searching (at least the number of requested results).
+    /// Higher values improve the recall at the cost of search time.
+    pub ef_search: usize,
+    /// Seed of the random generator drawing the layer of each node
+    pub seed: u64,
+}
+
+impl Default for HnswConfig {
+    fn default() -> Self {
+        Self {
+            m: DEFAULT_M,
+            ef_construction: DEFAULT_EF_CONSTRUCTION,
+            ef_search: DEFAULT_EF_SEARCH,
+            seed: 0x5EED,
+        }
+    }
+}
+
+impl HnswConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn m(mut self, m: usize) -> Self {
+        self.m = m.max(2);
+        self
+    }
+
+    pub fn ef_construction(mut self, ef_construction: usize) -> Self {
+        self.ef_construction = ef_construction.max(1);
+        self
+    }
+
+    pub fn ef_search(mut self, ef_search: usize) -> Self {
+        self.ef_search = ef_search.max(1);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// Vectors of the nodes of an [Hnsw] graph, given the id of their document and their position
+/// among the embeddings of the document. The graph reads the vectors from the store instead of
+/// keeping a copy of them.
+pub(crate) type Vectors<'a> = dyn Fn(&str, usize) -> Cow<'a, [f64]> + 'a;
+
+/// HNSW graph over the embeddings of a store. Each node is an embedding, identified by the id
+/// of its document and its position among the embeddings of the document.
+#[derive(Clone, Debug)]
+pub(crate) struct Hnsw {
+    config: HnswConfig,
+    /// Metric of the store, the graph connects the nodes closest according to it
+    metric: DistanceMetric,
+    keys: Vec<(String, usize)>,
+    /// Neighbors of each node on each of its layers
+    neighbors: Vec<Vec<Vec<usize>>>,
+    /// Removed nodes are kept in the graph to navigate it, but never returned. Their vectors
+    /// are no longer in the store, so the graph keeps them until it is rebuilt without the
+    /// removed nodes (see [Hnsw::remove]).
+    removed: HashMap<usize, Vec<f64>>,
+    nodes: HashMap<String, Vec<usize>>,
+    entry_point: Option<usize>,
+    max_layer: usize,
+    rng: u64,
+}
+
+impl Hnsw {
+    pub(crate) fn new(config: HnswConfig, metric: DistanceMetric) -> Self {
+        Self {
+            config,
+            metric,
+            keys: vec![],
+            neighbors: vec![],
+            removed: HashMap::new(),
+            nodes: HashMap::new(),
+            entry_point: None,
+            max_layer: 0,
+            rng: config.seed,
+        }
+    }
+
+    pub(crate) fn config(&self) -> &HnswConfig {
+        &self.config
+    }
+
+    /// Id of the document and position of the embedding of `node`
+    pub(crate) fn key(&self, node: usize) -> (&String, usize) {
+        let (id, position) = &self.keys[node];
+        (id, *position)
+    }
+
+    /// Insert the `count` embeddings of document `id`, whose vectors must already be in
+    /// `vectors`. The previous embeddings of the document must have been [removed](Hnsw::remove).
+    pub(crate) fn insert(&mut self, id: &str, count: usize, vectors: &Vectors<'_>) {
+        for position in 0..count {
+            let node = self.insert_node((id.to_string(), position), vectors);
+            self.nodes.entry(id.to_string()).or_default().push(node);
+        }
+    }
+
+    /// Remove the embeddings of document `id`, whose vectors must still be in `vectors`.
+    /// The graph is rebuilt once more than half of its nodes are removed.
+    pub(crate) fn remove(&mut self, id: &str, vectors: &Vectors<'_>) {
+        for node in self.nodes.remove(id).unwrap_or_default() {
+            let (id, position) = &self.keys[node];
+            self.removed
+                .insert(node, vectors(id, *position).into_owned());
+        }
+
+        if self.removed.len() * 2 > self.keys.len() {
+            self.rebuild(vectors);
+        }
+    }
+
+    /// Rebuild the graph with the nodes which were not removed
+    fn rebuild(&mut self, vectors: &Vectors<'_>) {
+        let mut hnsw = Hnsw::new(self.config, self.metric);
+        hnsw.rng = self.rng;
+        for (node, key) in std::mem::take(&mut self.keys).into_iter().enumerate() {
+            if !self.removed.contains_key(&node) {
+                let id = key.0.clone();
+                let node = hnsw.insert_node(key, vectors);
+                hnsw.nodes.entry(id).or_default().push(node);
+            }
+        }
+        *self = hnsw;
+    }
+
+    /// Get the `n` nodes closest to `query` among the nodes accepted by `accept`, as
+    /// `(score, node)` sorted by decreasing score (see [DistanceMetric::score_of]).
+    pub(crate) fn search(
+        &self,
+        query: &[f64],
+        n: usize,
+        vectors: &Vectors<'_>,
+        accept: &mut dyn FnMut(usize) -> bool,
+    ) -> Vec<(f64, usize)> {
+        let Some(mut entry_point) = self.entry_point else {
+            return vec![];
+        };
+
+        for layer in (1..=self.max_layer).rev() {
+            entry_point = self.closest(query, entry_point, layer, vectors);
+        }
+
+        let removed = &self.removed;
+        let mut accept = |node: usize| !removed.contains_key(&node) && accept(node);
+        self.search_layer(
+            query,
+            &[entry_point],
+            n.max(self.config.ef_search),
+            0,
+            vectors,
+            &mut accept,
+        )
+        .into_iter()
+        .take(n)
+        .map(|(distance, node)| (self.metric.score_of(self.value_of(distance)), node))
+        .collect()
+    }
+
+    /// Call `f` with the vector of `node`
+    fn with_vector<R>(&self, node: usize, vectors: &Vectors<'_>, f: impl FnOnce(&[f64]) -> R) -> R {
+        match self.removed.get(&node) {
+            Some(vector) => f(vector),
+            None => {
+                let (id, position) = &self.keys[node];
+                f(&vectors(id, *position))
+            }
+        }
+    }
+
+    /// Distance between `vector` and the vector of `node`: the value of the metric, negated
+    /// for similarities so that lower distances are always closer
+    fn distance(&self, vector: &[f64], node: usize, vectors: &Vectors<'_>) -> f64 {
+        self.with_vector(node, vectors, |other| {
+            self.value_of(self.metric.compute_slices(vector, other))
+        })
+    }
+
+    /// Convert a value of the metric to a distance and back
+    fn value_of(&self, distance: f64) -> f64 {
+        if self.metric.is_similarity() {
+            -distance
+        } else {
+            distance
+        }
+    }
+
+    fn insert_node(&mut self, key: (String, usize), vectors: &Vectors<'_>) -> usize {
+        let node = self.keys.len();
+        let layer = self.random_layer();
+        self.keys.push(key);
+        self.neighbors.push(vec![vec![]; layer + 1]);
+
+        let Some(mut entry_point) = self.entry_point else {
+            self.entry_point = Some(node);
+            self.max_layer = layer;
+            return node;
+        };
+
+        let vector = self.with_vector(node, vectors, <[f64]>::to_vec);
+        for current in (layer + 1..=self.max_layer).rev() {
+            entry_point = self.closest(&vector, entry_point, current, vectors);
+        }
+
+        let mut entry_points = vec![entry_point];
+        for current in (0..=layer.min(self.max_layer)).rev() {
+            let candidates = self.search_layer(
+                &vector,
+                &entry_points,
+                self.config.ef_construction,
+                current,
+                vectors,
+                &mut |_| true,
+            );
+
+            let neighbors = self.select_neighbors(&candidates, self.config.m, vectors);
+            for &neighbor in &neighbors {
+                self.connect(neighbor, node, current, vectors);
+            }
+            self.neighbors[node][current] = neighbors;
+
+            entry_points = candidates.into_iter().map(|(_, node)| node).collect();
+        }
+
+        if layer > self.max_layer {
+            self.max_layer = layer;
+            self.entry_point = Some(node);
+        }
+        node
+    }
+
+    /// Add `node` to the neighbors of `neighbor`, keeping its closest neighbors if it has too many
+    fn connect(&mut self, neighbor: usize, node: usize, layer: usize, vectors: &Vectors<'_>) {
+        let max_neighbors = if layer == 0 {
+            self.config.m * 2
+        } else {
+            self.config.m
+        };
+
+        self.neighbors[neighbor][layer].push(node);
+        if self.neighbors[neighbor][layer].len() > max_neighbors {
+            let mut candidates = self.with_vector(neighbor, vectors, |vector| {
+                self.neighbors[neighbor][layer]
+                    .iter()
+                    .map(|&other| (self.distance(vector, other, vectors), other))
+                    .collect::<Vec<_>>()
+            });
+            candidates.sort_by_key(|(distance, _)| OrderedFloat(*distance));
+            self.neighbors[neighbor][layer] =
+                self.select_neighbors(&candidates, max_neighbors, vectors);
+        }
+    }
+
+    /// Select up to `m` neighbors among `candidates` (sorted by increasing distance), preferring
+    /// candidates closer to the node than to the already selected neighbors so that the
+    /// neighbors point in diverse directions (heuristic of the HNSW paper).
+    fn select_neighbors(
+        &self,
+        candidates: &[(f64, usize)],
+        m: usize,
+        vectors: &Vectors<'_>,
+    ) -> Vec<usize> {
+        let mut selected: Vec<usize> = Vec::with_capacity(m);
+        let mut pruned = vec![];
+        for &(distance_to_node, candidate) in candidates {
+            if selected.len() >= m {
+                break;
+            }
+            let diverse = self.with_vector(candidate, vectors, |vector| {
+                selected
+                    .iter()
+                    .all(|&other| self.distance(vector, other, vectors) > distance_to_node)
+            });
+            if diverse {
+                selected.push(candidate);
+            } else {
+                pruned.push(candidate);
+            }
+        }
+
+        // Keep the closest pruned candidates if there are not enough diverse ones
+        let missing = m.saturating_sub(selected.len());
+        selected.extend(pruned.into_iter().take(missing));
+        selected
+    }
+
+    /// Greedily walk `layer` towards the node closest to `query`
+    fn closest(
+        &self,
+        query: &[f64],
+        entry_point: usize,
+        layer: usize,
+        vectors: &Vectors<'_>,
+    ) -> usize {
+        self.search_layer(query, &[entry_point], 1, layer, vectors, &mut |_| true)
+            .first()
+            .map_or(entry_point, |(_, node)| *node)
+    }
+
+    /// Get the `ef` accepted nodes of `layer` closest to `query`, as `(distance, node)` sorted by
+    /// increasing distance. Nodes not accepted are still visited to navigate the graph.
+    fn search_layer(
+        &self,
+        query: &[f64],
+        entry_points: &[usize],
+        ef: usize,
+        layer: usize,
+        vectors: &Vectors<'_>,
+        accept: &mut dyn FnMut(usize) -> bool,
+    ) -> Vec<(f64, usize)> {
+        // Searches only visit a small part of the graph
+        let mut visited = HashSet::new();
+        let mut candidates = BinaryHeap::new();
+        let mut results: BinaryHeap<(OrderedFloat<f64>, usize)> = BinaryHeap::new();
+
+        for &node in entry_points {
+            if visited.insert(node) {
+                let distance = OrderedFloat(self.distance(query, node, vectors));
+                candidates.push(Reverse((distance, node)));
+                if accept(node) {
+                    results.push((distance, node));
+                }
+            }
+        }
+        while results.len() > ef {
+            results.pop();
+        }
+
+        while let Some(Reverse((distance_to_candidate, candidate))) = candidates.pop() {
+            if results.len() >= ef
+                && results
+                    .peek()
+                    .is_some_and(|(furthest, _)| distance_to_candidate > *furthest)
+            {
+                break;
+            }
+
+            for &neighbor in &self.neighbors[candidate][layer] {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+
+                let distance = OrderedFloat(self.distance(query, neighbor, vectors));
+                if results.len() < ef
+                    || results
+                        .peek()
+                        .is_some_and(|(furthest, _)| distance < *furthest)
+                {
+                    candidates.push(Reverse((distance, neighbor)));
+                    if accept(neighbor) {
+                        results.push((distance, neighbor));
+                        if results.len() > ef {
+                            results.pop();
+                        }
+                    }
+                }
+            }
+        }
+
+        results
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(distance, node)| (distance.0, node))
+            .collect()
+    }
+
+    /// Draw the top layer of a new node, with an exponentially decreasing probability
+    fn random_layer(&mut self) -> usize {
+        // SplitMix64
+        self.rng = self.rng.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^= z >> 31;
+
+        // Uniform in (0, 1]
+        let uniform = ((z >> 11) + 1) as f64 / (1u64 << 53) as f64;
+        let level_multiplier = 1.0 / (self.config.m as f64).ln();
+        (-uniform.ln() * level_multiplier).floor() as usize
+    }
+}
+
+impl<D: serde::Serialize> InMemoryVectorStore<D> {
+    /// Index the embeddings of the store with an HNSW graph, used by the vector searches
+    /// instead of comparing the query with every embedding. Documents added to (or removed
+    /// from) the store are also added to (or removed from) the index.
+    ///
+    /// The graph is built with the metric of the store (see [InMemoryVectorStore::with_metric]),
+    /// and rebuilt if the metric changes.
+    pub fn with_hnsw(mut self, config: HnswConfig) -> Self {
+        let mut hnsw = Hnsw::new(config, self.metric());
+
+        // Insert the documents in a deterministic order so that the graph is reproducible
+        let mut documents = self.iter().collect::<Vec<_>>();
+        documents.sort_by_key(|(id, _)| *id);
+        // Quantized stores may not keep the vectors of their embeddings
+        let vectors = |id: &str, position: usize| {
+            stored_vector(&self.embeddings, self.quantized.as_ref(), id, position)
+        };
+        for (id, (_, embeddings)) in documents {
+            hnsw.insert(id, embeddings.len(), &vectors);
+        }
+
+        self.hnsw = Some(hnsw);
+        self
+    }
+
+    /// Parameters of the HNSW index of the store, if any
+    pub fn hnsw_config(&self) -> Option<&HnswConfig> {
+        self.hnsw.as_ref().map(Hnsw::config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random vectors
+    fn vectors(n: usize, dims: usize) -> Vec<Vec<f64>> {
+        let mut state = 42u64;
+        (0..n)
+            .map(|_| {
+                (0..dims)
+                    .map(|_| {
+                        state = state
+                            .wrapping_mul(6364136223846793005)
+                            .wrapping_add(1442695040888963407);
+                        (state >> 33) as f64 / (1u64 << 31) as f64 - 0.5
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn exact(vectors: &[Vec<f64>], query: &[f64], n: usize) -> Vec<usize> {
+        let mut nodes = (0..vectors.len()).collect::<Vec<_>>();
+        nodes.sort_by_key(|&node| {
+            OrderedFloat(-DistanceMetric::Cosine.compute_slices(query, &vectors[node]))
+        });
+        nodes.truncate(n);
+        nodes
+    }
+
+    /// Vectors of the documents `"0"`, `"1"`, ... with a single embedding each
+    fn lookup<'a>(data: &'a [Vec<f64>]) -> impl Fn(&str, usize) -> Cow<'a, [f64]> + 'a {
+        |id: &str, _| Cow::Borrowed(&data[id.parse::<usize>().unwrap()])
+    }
+
+    fn index(data: &[Vec<f64>], config: HnswConfig) -> Hnsw {
+        let mut hnsw = Hnsw::new(config, DistanceMetric::Cosine);
+        for i in 0..data.len() {
+            hnsw.insert(&i.to_string(), 1, &lookup(data));
+        }
+        hnsw
+    }
+
+    #[test]
+    fn test_recall() {
+        let data = vectors(2000, 16);
+        let hnsw = index(&data, HnswConfig::new());
+
+        let queries = vectors(2050, 16).split_off(2000);
+        let mut found = 0;
+        for query in &queries {
+            let expected = exact(&data, query, 10);
+            let results = hnsw.search(query, 10, &lookup(&data), &mut |_| true);
+            found += results
+                .iter()
+                .filter(|(_, node)| expected.contains(node))
+                .count();
+        }
+
+        let recall = found as f64 / (queries.len() * 10) as f64;
+        assert!(recall > 0.9, "recall: {recall}");
+    }
+
+    #[test]
+    fn test_remove_and_filter() {
+        let data = vectors(300, 8);
+        let mut hnsw = index(&data, HnswConfig::new().m(8));
+
+        let vectors = lookup(&data);
+        let query = data[7].clone();
+        let results = hnsw.search(&query, 1, &vectors, &mut |_| true);
+        assert_eq!(hnsw.key(results[0].1), (&"7".to_string(), 0));
+        assert!((results[0].0 - 1.0).abs() < 1e-9);
+
+        hnsw.remove("7", &vectors);
+        let results = hnsw.search(&query, 5, &vectors, &mut |_| true);
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(|(_, node)| hnsw.key(*node).0 != "7"));
+
+        // Only even documents
+        let results = hnsw.search(&query, 5, &vectors, &mut |node| node % 2 == 0);
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(|(_, node)| node % 2 == 0));
+    }
+
+    #[test]
+    fn test_rebuild_after_removals() {
+        let data = vectors(100, 8);
+        let vectors = lookup(&data);
+        let mut hnsw = index(&data, HnswConfig::new().m(8));
+
+        // Removed nodes are kept until more than half of the nodes are removed
+        for i in 0..50 {
+            hnsw.remove(&i.to_string(), &vectors);
+        }
+        assert_eq!(hnsw.removed.len(), 50);
+        assert_eq!(hnsw.keys.len(), 100);
+
+        hnsw.remove("50", &vectors);
+        assert!(hnsw.removed.is_empty());
+        assert_eq!(hnsw.keys.len(), 49);
+
+        let results = hnsw.search(&data[70], 1, &vectors, &mut |_| true);
+        assert_eq!(hnsw.key(results[0].1), (&"70".to_string(), 0));
+        let results = hnsw.search(&data[70], 60, &vectors, &mut |_| true);
+        assert_eq!(results.len(), 49);
+    }
+}
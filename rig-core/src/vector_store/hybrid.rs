@@ -0,0 +1,310 @@
+//! Hybrid search combining a keyword index with a vector index.
+//!
+//! A [HybridIndex] queries both indexes and fuses their rankings (see [Fusion]), so that
+//! documents matching the exact terms of the query (e.g.: a ticker or a contract address) are
+//! retrieved along with the documents that are semantically close to it.
+//!
+//! # Example
+//! ```rust
+//! use rig::vector_store::hybrid::{Fusion, HybridIndex};
+//!
+//! let keyword_index = vector_store.keyword_index();
+//! let vector_index = vector_store.index(embedding_model);
+//!
+//! let index = HybridIndex::new(keyword_index, vector_index).fusion(Fusion::rrf());
+//!
+//! let agent = openai.agent("gpt-4o")
+//!     .dynamic_context(4, index)
+//!     .build();
+//! ```
+use std::collections::HashMap;
+
+use futures::try_join;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{VectorStoreError, VectorStoreIndex};
+
+/// Default constant of reciprocal rank fusion
+pub const DEFAULT_RRF_K: f64 = 60.0;
+
+/// Method used to fuse the keyword and vector rankings of a [HybridIndex]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Fusion {
+    /// Reciprocal rank fusion: each document is scored `sum(1 / (k + rank))` over the rankings
+    /// it appears in. Only ranks matter, so the scores of both indexes need not be comparable.
+    ReciprocalRank { k: f64 },
+    /// Weighted sum of the scores of both indexes, after min-max normalization of each ranking
+    Weighted { keyword: f64, vector: f64 },
+}
+
+impl Default for Fusion {
+    fn default() -> Self {
+        Self::rrf()
+    }
+}
+
+impl Fusion {
+    /// Reciprocal rank fusion with the default constant ([DEFAULT_RRF_K])
+    pub fn rrf() -> Self {
+        Self::ReciprocalRank { k: DEFAULT_RRF_K }
+    }
+
+    /// Weighted sum of the normalized scores
+    pub fn weighted(keyword: f64, vector: f64) -> Self {
+        Self::Weighted { keyword, vector }
+    }
+
+    /// Fuse the `keyword` and `vector` rankings (lists of scores and ids), returning the fused
+    /// scores and ids sorted by decreasing score.
+    pub fn fuse(&self, keyword: &[(f64, String)], vector: &[(f64, String)]) -> Vec<(f64, String)> {
+        let (keyword_weight, vector_weight) = match self {
+            Self::ReciprocalRank { .. } => (1.0, 1.0),
+            Self::Weighted { keyword, vector } => (*keyword, *vector),
+        };
+
+        // Ids in order of first appearance, so that ties are broken deterministically
+        let mut ids = vec![];
+        let mut scores = HashMap::new();
+        for (ranking, weight) in [(keyword, keyword_weight), (vector, vector_weight)] {
+            for (score, id) in self.scores(ranking) {
+                if !scores.contains_key(id) {
+                    ids.push(id.clone());
+                }
+                *scores.entry(id.clone()).or_insert(0.0) += weight * score;
+            }
+        }
+
+        let mut fused = ids
+            .into_iter()
+            .map(|id| (scores[&id], id))
+            .collect::<Vec<_>>();
+        fused.sort_by(|a, b| b.0.total_cmp(&a.0));
+        fused
+    }
+
+    /// Score of each document of `ranking` according to the fusion method
+    fn scores<'a>(&self, ranking: &'a [(f64, String)]) -> Vec<(f64, &'a String)> {
+        let mut ranking = ranking.iter().collect::<Vec<_>>();
+        ranking.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        match self {
+            Self::ReciprocalRank { k } => ranking
+                .into_iter()
+                .enumerate()
+                .map(|(rank, (_, id))| (1.0 / (k + rank as f64 + 1.0), id))
+                .collect(),
+            Self::Weighted { .. } => {
+                let max = ranking.first().map(|(score, _)| *score).unwrap_or(0.0);
+                let min = ranking.last().map(|(score, _)| *score).unwrap_or(0.0);
+                ranking
+                    .into_iter()
+                    .map(|(score, id)| {
+                        let normalized = if max > min {
+                            (score - min) / (max - min)
+                        } else {
+                            1.0
+                        };
+                        (normalized, id)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Index combining a keyword index (e.g.: a [Bm25Index](super::bm25::Bm25Index)) with a vector
+/// index. Both indexes are queried concurrently and their rankings are fused according to the
+/// [Fusion] method (reciprocal rank fusion by default). The returned scores are the fused scores.
+///
+/// The keyword index can be any [VectorStoreIndex], e.g.: a full text search index of a database.
+pub struct HybridIndex<K: VectorStoreIndex, V: VectorStoreIndex> {
+    keyword: K,
+    vector: V,
+    fusion: Fusion,
+    candidates: Option<usize>,
+}
+
+impl<K: VectorStoreIndex, V: VectorStoreIndex> HybridIndex<K, V> {
+    pub fn new(keyword: K, vector: V) -> Self {
+        Self {
+            keyword,
+            vector,
+            fusion: Fusion::default(),
+            candidates: None,
+        }
+    }
+
+    /// Set the fusion method
+    pub fn fusion(mut self, fusion: Fusion) -> Self {
+        self.fusion = fusion;
+        self
+    }
+
+    /// Set the number of candidates fetched from each index (defaults to twice the number of
+    /// requested documents)
+    pub fn candidates(mut self, candidates: usize) -> Self {
+        self.candidates = Some(candidates);
+        self
+    }
+
+    fn num_candidates(&self, n: usize) -> usize {
+        self.candidates.unwrap_or(n * 2).max(n)
+    }
+}
+
+impl<K: VectorStoreIndex, V: VectorStoreIndex> VectorStoreIndex for HybridIndex<K, V> {
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        let candidates = self.num_candidates(n);
+        let (keyword, vector) = try_join!(
+            self.keyword.top_n::<Value>(query, candidates),
+            self.vector.top_n::<Value>(query, candidates),
+        )?;
+
+        let scores = |results: &[(f64, String, Value)]| {
+            results
+                .iter()
+                .map(|(score, id, _)| (*score, id.clone()))
+                .collect::<Vec<_>>()
+        };
+        let fused = self.fusion.fuse(&scores(&keyword), &scores(&vector));
+
+        // Prefer the documents returned by the vector index, which are usually more complete
+        let mut documents = keyword
+            .into_iter()
+            .chain(vector)
+            .map(|(_, id, doc)| (id, doc))
+            .collect::<HashMap<_, _>>();
+
+        fused
+            .into_iter()
+            .take(n)
+            .filter_map(|(score, id)| {
+                documents
+                    .remove(&id)
+                    .map(|doc| Ok((score, id, serde_json::from_value(doc)?)))
+            })
+            .collect()
+    }
+
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        let candidates = self.num_candidates(n);
+        let (keyword, vector) = try_join!(
+            self.keyword.top_n_ids(query, candidates),
+            self.vector.top_n_ids(query, candidates),
+        )?;
+
+        Ok(self
+            .fusion
+            .fuse(&keyword, &vector)
+            .into_iter()
+            .take(n)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranking(ids: &[(&str, f64)]) -> Vec<(f64, String)> {
+        ids.iter()
+            .map(|(id, score)| (*score, id.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        let keyword = ranking(&[("mint", 12.0), ("sol", 3.0)]);
+        let vector = ranking(&[("sol", 0.9), ("bonk", 0.8), ("mint", 0.7)]);
+
+        let fused = Fusion::rrf().fuse(&keyword, &vector);
+
+        assert_eq!(
+            fused.iter().map(|(_, id)| id.as_str()).collect::<Vec<_>>(),
+            vec!["sol", "mint", "bonk"]
+        );
+        assert_eq!(fused[2].0, 1.0 / 62.0);
+    }
+
+    #[test]
+    fn test_weighted_fusion() {
+        let keyword = ranking(&[("sol", 3.0), ("mint", 12.0)]);
+        let vector = ranking(&[("sol", 0.9), ("bonk", 0.8), ("mint", 0.7)]);
+
+        let fused = Fusion::weighted(0.3, 0.7).fuse(&keyword, &vector);
+
+        assert_eq!(
+            fused.iter().map(|(_, id)| id.as_str()).collect::<Vec<_>>(),
+            vec!["sol", "bonk", "mint"]
+        );
+        assert!((fused[0].0 - 0.7).abs() < 1e-9);
+        assert!((fused[2].0 - 0.3).abs() < 1e-9);
+    }
+
+    struct ListIndex(Vec<(f64, &'static str)>);
+
+    impl VectorStoreIndex for ListIndex {
+        async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+            &self,
+            _query: &str,
+            n: usize,
+        ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+            self.0
+                .iter()
+                .take(n)
+                .map(|(score, id)| {
+                    Ok((
+                        *score,
+                        id.to_string(),
+                        serde_json::from_value(serde_json::json!({ "id": id }))?,
+                    ))
+                })
+                .collect()
+        }
+
+        async fn top_n_ids(
+            &self,
+            _query: &str,
+            n: usize,
+        ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+            Ok(self
+                .0
+                .iter()
+                .take(n)
+                .map(|(score, id)| (*score, id.to_string()))
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_index() {
+        let index = HybridIndex::new(
+            ListIndex(vec![(9.0, "mint")]),
+            ListIndex(vec![(0.9, "sol"), (0.8, "mint"), (0.7, "bonk")]),
+        );
+
+        let results = index.top_n::<Value>("mint", 2).await.unwrap();
+        assert_eq!(
+            results
+                .iter()
+                .map(|(_, id, doc)| (id.as_str(), doc["id"].as_str().unwrap()))
+                .collect::<Vec<_>>(),
+            vec![("mint", "mint"), ("sol", "sol")]
+        );
+
+        let ids = index.top_n_ids("mint", 3).await.unwrap();
+        assert_eq!(
+            ids.iter().map(|(_, id)| id.as_str()).collect::<Vec<_>>(),
+            vec!["mint", "sol", "bonk"]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 //! In-memory implementation of a vector store.
 use std::{
+    borrow::Cow,
     cmp::Reverse,
     collections::{BinaryHeap, HashMap},
 };
@@ -7,10 +8,21 @@ use std::{
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 
-use super::{VectorStoreError, VectorStoreIndex};
+#[cfg(feature = "hnsw")]
+use super::hnsw::Hnsw;
+use super::{
+    check_query_dimensions,
+    filter::Filter,
+    quantization::{Quantized, QuantizedVector},
+    scoring::{EmbeddingMatch, ScoringStrategy},
+    SearchOptions, VectorSearchIndex, VectorStoreError, VectorStoreIndex, WritableVectorStore,
+};
 use crate::{
-    embeddings::{distance::VectorDistance, Embedding, EmbeddingModel},
-    OneOrMany,
+    embeddings::{
+        distance::{kernels, DistanceMetric},
+        Embedding, EmbeddingModel,
+    },
+    telemetry, OneOrMany,
 };
 
 /// [InMemoryVectorStore] is a simple in-memory vector store that stores embeddings
@@ -20,7 +32,20 @@ pub struct InMemoryVectorStore<D: Serialize> {
     /// The embeddings are stored in a HashMap.
     /// Hashmap key is the document id.
     /// Hashmap value is a tuple of the serializable document and its corresponding embeddings.
-    embeddings: HashMap<String, (D, OneOrMany<Embedding>)>,
+    pub(crate) embeddings: HashMap<String, (D, OneOrMany<Embedding>)>,
+    /// Metric used to rank the embeddings (see [InMemoryVectorStore::with_metric])
+    metric: DistanceMetric,
+    /// Whether the embeddings are normalized when inserted
+    /// (see [InMemoryVectorStore::with_normalized_embeddings])
+    normalized: bool,
+    /// Aggregation of the scores of the embeddings of a document
+    /// (see [InMemoryVectorStore::with_scoring])
+    pub(crate) scoring: ScoringStrategy,
+    /// Quantized embeddings (see [InMemoryVectorStore::with_quantization])
+    pub(crate) quantized: Option<Quantized>,
+    /// Approximate nearest neighbor index of the embeddings (see [InMemoryVectorStore::with_hnsw])
+    #[cfg(feature = "hnsw")]
+    pub(crate) hnsw: Option<Hnsw>,
 }
 
 impl<D: Serialize + Eq> InMemoryVectorStore<D> {
@@ -28,27 +53,18 @@ impl<D: Serialize + Eq> InMemoryVectorStore<D> {
     /// Ids are automatically generated have will have the form `"doc{n}"` where `n`
     /// is the index of the document.
     pub fn from_documents(documents: impl IntoIterator<Item = (D, OneOrMany<Embedding>)>) -> Self {
-        let mut store = HashMap::new();
-        documents
-            .into_iter()
-            .enumerate()
-            .for_each(|(i, (doc, embeddings))| {
-                store.insert(format!("doc{i}"), (doc, embeddings));
-            });
-
-        Self { embeddings: store }
+        let mut store = Self::empty();
+        store.add_documents(documents);
+        store
     }
 
     /// Create a new [InMemoryVectorStore] from documents and and their corresponding embeddings with ids.
     pub fn from_documents_with_ids(
         documents: impl IntoIterator<Item = (impl ToString, D, OneOrMany<Embedding>)>,
     ) -> Self {
-        let mut store = HashMap::new();
-        documents.into_iter().for_each(|(i, doc, embeddings)| {
-            store.insert(i.to_string(), (doc, embeddings));
-        });
-
-        Self { embeddings: store }
+        let mut store = Self::empty();
+        store.add_documents_with_ids(documents);
+        store
     }
 
     /// Create a new [InMemoryVectorStore] from documents and their corresponding embeddings.
@@ -57,40 +73,56 @@ impl<D: Serialize + Eq> InMemoryVectorStore<D> {
         documents: impl IntoIterator<Item = (D, OneOrMany<Embedding>)>,
         f: fn(&D) -> String,
     ) -> Self {
-        let mut store = HashMap::new();
-        documents.into_iter().for_each(|(doc, embeddings)| {
-            store.insert(f(&doc), (doc, embeddings));
-        });
-
-        Self { embeddings: store }
+        let mut store = Self::empty();
+        for (doc, embeddings) in documents {
+            store.insert(f(&doc), doc, embeddings);
+        }
+        store
     }
 
     /// Implement vector search on [InMemoryVectorStore].
     /// To be used by implementations of [VectorStoreIndex::top_n] and [VectorStoreIndex::top_n_ids] methods.
-    fn vector_search(&self, prompt_embedding: &Embedding, n: usize) -> EmbeddingRanking<D> {
+    /// Documents not matching the `filter` are skipped before ranking.
+    fn vector_search(
+        &self,
+        prompt_embedding: &Embedding,
+        n: usize,
+        filter: Option<&Filter>,
+    ) -> EmbeddingRanking<D> {
+        let scorer = Scorer::new(self, prompt_embedding);
+        let candidates = scorer.candidates(n);
+
+        #[cfg(feature = "hnsw")]
+        if let Some(hnsw) = &self.hnsw {
+            let docs = self.hnsw_search(hnsw, &scorer, candidates, filter);
+            return self.rescore(docs, &scorer, n);
+        }
+
         // Sort documents by best embedding distance
         let mut docs = BinaryHeap::new();
 
         for (id, (doc, embeddings)) in self.embeddings.iter() {
-            // Get the best context for the document given the prompt
-            if let Some((distance, embed_doc)) = embeddings
-                .iter()
-                .map(|embedding| {
-                    (
-                        OrderedFloat(embedding.cosine_similarity(prompt_embedding, false)),
-                        &embedding.document,
-                    )
+            if let Some(filter) = filter {
+                if !serde_json::to_value(doc).is_ok_and(|doc| filter.matches(&doc)) {
+                    continue;
+                }
+            }
+
+            // Score the document and get its best context given the prompt
+            if let Some((distance, embedding)) = self
+                .score_document(embeddings, |position, embedding| {
+                    scorer.similarity(id, position, embedding)
                 })
-                .max_by(|a, b| a.0.cmp(&b.0))
             {
-                docs.push(Reverse(RankingItem(distance, id, doc, embed_doc)));
+                docs.push(Reverse(RankingItem(distance, id, doc, embedding)));
             };
 
-            // If the heap size exceeds n, pop the least old element.
-            if docs.len() > n {
+            // If the heap size exceeds the number of candidates, pop the least old element.
+            if docs.len() > candidates {
                 docs.pop();
             }
         }
+        let docs = self.rescore(docs, &scorer, n);
 
         // Log selected tools with their distances
         tracing::info!(target: "rig",
@@ -104,6 +136,250 @@ impl<D: Serialize + Eq> InMemoryVectorStore<D> {
         docs
     }
 
+    /// Same as [Self::vector_search], using the HNSW index to find the approximate nearest
+    /// embeddings instead of comparing the query with every embedding.
+    #[cfg(feature = "hnsw")]
+    fn hnsw_search(
+        &self,
+        hnsw: &Hnsw,
+        scorer: &Scorer<'_>,
+        n: usize,
+        filter: Option<&Filter>,
+    ) -> EmbeddingRanking<'_, D> {
+        let mut matches = HashMap::new();
+        let mut accept = |node: usize| {
+            let Some(filter) = filter else {
+                return true;
+            };
+            let (id, _) = hnsw.key(node);
+            *matches.entry(id.clone()).or_insert_with(|| {
+                self.embeddings.get(id).is_some_and(|(doc, _)| {
+                    serde_json::to_value(doc).is_ok_and(|doc| filter.matches(&doc))
+                })
+            })
+        };
+
+        // Documents with several embeddings may be found several times, so search for more
+        // embeddings until n distinct documents are found
+        let mut k = n;
+        let mut docs = loop {
+            let nodes = hnsw.search(
+                &scorer.prompt.vec,
+                k,
+                &|id: &str, position| {
+                    stored_vector(&self.embeddings, self.quantized.as_ref(), id, position)
+                },
+                &mut accept,
+            );
+            let mut docs: HashMap<&String, RankingItem<'_, D>> = HashMap::new();
+            for &(_, node) in &nodes {
+                let (id, _) = hnsw.key(node);
+                let Some((id, (doc, embeddings))) = self.embeddings.get_key_value(id) else {
+                    continue;
+                };
+                if docs.contains_key(id) {
+                    continue;
+                }
+                // Documents are scored with all their embeddings
+                if let Some((score, embedding)) = self
+                    .score_document(embeddings, |position, embedding| {
+                        scorer.similarity(id, position, embedding)
+                    })
+                {
+                    docs.insert(id, RankingItem(score, id, doc, embedding));
+                }
+            }
+
+            if docs.len() >= n || nodes.len() < k {
+                break docs.into_values().map(Reverse).collect::<BinaryHeap<_>>();
+            }
+            k *= 2;
+        };
+
+        while docs.len() > n {
+            docs.pop();
+        }
+        docs
+    }
+
+    /// Rank the candidates of a search on quantized embeddings with their full precision
+    /// embeddings when rescoring is enabled, keeping the `n` best ones.
+    fn rescore<'a>(
+        &'a self,
+        mut docs: EmbeddingRanking<'a, D>,
+        scorer: &Scorer<'_>,
+        n: usize,
+    ) -> EmbeddingRanking<'a, D> {
+        if self
+            .quantized
+            .as_ref()
+            .is_some_and(|quantized| quantized.config().rescore.is_some())
+        {
+            docs = docs
+                .into_iter()
+                .filter_map(|Reverse(RankingItem(_, id, doc, _))| {
+                    let (_, embeddings) = self.embeddings.get(id)?;
+                    self.score_document(embeddings, |_, embedding| scorer.exact(embedding))
+                        .map(|(distance, embedding)| {
+                            Reverse(RankingItem(distance, id, doc, embedding))
+                        })
+                })
+                .collect();
+        }
+
+        while docs.len() > n {
+            docs.pop();
+        }
+        docs
+    }
+
+    /// Score of a document with the scoring strategy of the store, given the `score` of each of
+    /// its embeddings. Returns the score of the document and its best matching embedding.
+    fn score_document<'a>(
+        &self,
+        embeddings: &'a OneOrMany<Embedding>,
+        score: impl Fn(usize, &Embedding) -> f64,
+    ) -> Option<(OrderedFloat<f64>, &'a Embedding)> {
+        let (distance, position) = self.scoring.aggregate(
+            embeddings
+                .iter()
+                .enumerate()
+                .map(|(position, embedding)| score(position, embedding)),
+        )?;
+        Some((OrderedFloat(distance), embeddings.iter().nth(position)?))
+    }
+
+    /// Vector of an `embedding` of document `id`, approximated from its quantized embedding
+    /// if the store does not keep full precision embeddings.
+    pub(crate) fn vector_of<'a>(&'a self, id: &str, embedding: &'a Embedding) -> Cow<'a, [f64]> {
+        let quantized = self.quantized.as_ref().filter(|_| embedding.vec.is_empty());
+        quantized
+            .zip(self.embeddings.get(id))
+            .and_then(|(quantized, (_, embeddings))| {
+                let position = embeddings
+                    .iter()
+                    .position(|other| std::ptr::eq(other, embedding))?;
+                quantized.vector(id, position)
+            })
+            .map_or(Cow::Borrowed(embedding.vec.as_slice()), Cow::Owned)
+    }
+
+    /// Get the `n` documents closest to `prompt_embedding`, deserialized to `T`
+    fn top_n<T: for<'a> Deserialize<'a>>(
+        &self,
+        prompt_embedding: &Embedding,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        let docs = self.vector_search(prompt_embedding, n, None);
+
+        // Return n best
+        docs.into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(RankingItem(distance, id, doc, _))| {
+                Ok((
+                    distance.0,
+                    id.clone(),
+                    serde_json::from_str(
+                        &serde_json::to_string(doc).map_err(VectorStoreError::JsonError)?,
+                    )
+                    .map_err(VectorStoreError::JsonError)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, _>>()
+    }
+
+    /// Get the ids of the `n` documents closest to `prompt_embedding`
+    fn top_n_ids(&self, prompt_embedding: &Embedding, n: usize) -> Vec<(f64, String)> {
+        let docs = self.vector_search(prompt_embedding, n, None);
+
+        docs.into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(RankingItem(distance, id, _, _))| (distance.0, id.clone()))
+            .collect()
+    }
+
+    /// Get the `n` documents closest to `prompt_embedding`, deserialized to `T`, with their best
+    /// matching embedding
+    fn top_n_with_matches<T: for<'a> Deserialize<'a>>(
+        &self,
+        prompt_embedding: &Embedding,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T, EmbeddingMatch)>, VectorStoreError> {
+        let scorer = Scorer::new(self, prompt_embedding);
+        let docs = self.vector_search(prompt_embedding, n, None);
+
+        docs.into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(RankingItem(distance, id, doc, embedding))| {
+                let position = self
+                    .embeddings
+                    .get(id)
+                    .and_then(|(_, embeddings)| {
+                        embeddings
+                            .iter()
+                            .position(|other| std::ptr::eq(other, embedding))
+                    })
+                    .ok_or_else(|| VectorStoreError::MissingIdError(id.clone()))?;
+                let matched = EmbeddingMatch {
+                    position,
+                    text: embedding.document.clone(),
+                    score: scorer.result_score(id, position, embedding),
+                };
+
+                Ok((
+                    distance.0,
+                    id.clone(),
+                    serde_json::from_str(
+                        &serde_json::to_string(doc).map_err(VectorStoreError::JsonError)?,
+                    )
+                    .map_err(VectorStoreError::JsonError)?,
+                    matched,
+                ))
+            })
+            .collect::<Result<Vec<_>, _>>()
+    }
+
+    /// Implement vector search on [InMemoryVectorStore] applying the search `options`.
+    /// Documents not matching the filter are excluded before ranking, documents below the
+    /// minimum score are dropped from the ranking, and MMR selects among the best candidates
+    /// using their stored embeddings. Results are sorted by decreasing score, or in order of
+    /// selection when using MMR.
+    fn vector_search_with_options(
+        &self,
+        prompt_embedding: &Embedding,
+        n: usize,
+        options: &SearchOptions,
+    ) -> Vec<RankingItem<'_, D>> {
+        let candidates = self
+            .vector_search(
+                prompt_embedding,
+                options.candidates(n),
+                options.filter.as_ref(),
+            )
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(item)| item)
+            .filter(|RankingItem(distance, ..)| options.accepts(distance.0))
+            .collect::<Vec<_>>();
+
+        let Some(mmr) = &options.mmr else {
+            return candidates.into_iter().take(n).collect();
+        };
+
+        let vectors = candidates
+            .iter()
+            .map(|RankingItem(_, id, _, embedding)| self.vector_of(id, embedding))
+            .collect::<Vec<_>>();
+        let embeddings = vectors.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+        let selection = mmr.select(&prompt_embedding.vec, &embeddings, n);
+
+        let mut candidates = candidates.into_iter().map(Some).collect::<Vec<_>>();
+        selection
+            .into_iter()
+            .filter_map(|i| candidates[i].take())
+            .collect()
+    }
+
     /// Add documents and their corresponding embeddings to the store.
     /// Ids are automatically generated have will have the form `"doc{n}"` where `n`
     /// is the index of the document.
@@ -116,8 +392,7 @@ impl<D: Serialize + Eq> InMemoryVectorStore<D> {
             .into_iter()
             .enumerate()
             .for_each(|(index, (doc, embeddings))| {
-                self.embeddings
-                    .insert(format!("doc{}", index + current_index), (doc, embeddings));
+                self.insert(format!("doc{}", index + current_index), doc, embeddings);
             });
     }
 
@@ -127,7 +402,7 @@ impl<D: Serialize + Eq> InMemoryVectorStore<D> {
         documents: impl IntoIterator<Item = (impl ToString, D, OneOrMany<Embedding>)>,
     ) {
         documents.into_iter().for_each(|(id, doc, embeddings)| {
-            self.embeddings.insert(id.to_string(), (doc, embeddings));
+            self.insert(id.to_string(), doc, embeddings);
         });
     }
 
@@ -140,7 +415,7 @@ impl<D: Serialize + Eq> InMemoryVectorStore<D> {
     ) {
         for (doc, embeddings) in documents {
             let id = f(&doc);
-            self.embeddings.insert(id, (doc, embeddings));
+            self.insert(id, doc, embeddings);
         }
     }
 
@@ -157,9 +432,87 @@ impl<D: Serialize + Eq> InMemoryVectorStore<D> {
     }
 }
 
-/// RankingItem(distance, document_id, serializable document, embeddings document)
+/// Score of the embeddings of a store with the embedding of a query for the metric of the store,
+/// computed with the quantized embeddings of the store if any.
+struct Scorer<'a> {
+    prompt: Cow<'a, Embedding>,
+    metric: DistanceMetric,
+    normalized: bool,
+    quantized: Option<(&'a Quantized, QuantizedVector)>,
+}
+
+impl<'a> Scorer<'a> {
+    fn new<D: Serialize>(store: &'a InMemoryVectorStore<D>, prompt: &'a Embedding) -> Self {
+        let prompt = if store.normalized {
+            let mut prompt = prompt.clone();
+            normalize(&mut prompt.vec);
+            Cow::Owned(prompt)
+        } else {
+            Cow::Borrowed(prompt)
+        };
+        let quantized = store
+            .quantized
+            .as_ref()
+            .map(|quantized| (quantized, quantized.query(&prompt.vec)));
+
+        Self {
+            prompt,
+            metric: store.metric,
+            normalized: store.normalized,
+            quantized,
+        }
+    }
+
+    /// Number of candidates to search for `n` results, more than `n` when they are rescored
+    fn candidates(&self, n: usize) -> usize {
+        self.quantized
+            .as_ref()
+            .and_then(|(quantized, _)| quantized.config().rescore)
+            .map_or(n, |oversampling| n.saturating_mul(oversampling))
+    }
+
+    /// Score of the `embedding` at `position` of document `id`, higher is closer
+    fn similarity(&self, id: &str, position: usize, embedding: &Embedding) -> f64 {
+        match &self.quantized {
+            Some((quantized, query)) => quantized
+                .score(self.metric, query, &self.prompt.vec, id, position)
+                .unwrap_or(f64::NEG_INFINITY),
+            None => self.exact(embedding),
+        }
+    }
+
+    /// Score of a full precision `embedding`, higher is closer
+    fn exact(&self, embedding: &Embedding) -> f64 {
+        self.metric
+            .score(embedding, self.prompt.as_ref(), self.normalized)
+    }
+
+    /// Score of the `embedding` at `position` of document `id` in the results of a search,
+    /// computed with the full precision embedding when the results are rescored
+    fn result_score(&self, id: &str, position: usize, embedding: &Embedding) -> f64 {
+        let rescored = self
+            .quantized
+            .as_ref()
+            .is_some_and(|(quantized, _)| quantized.config().rescore.is_some());
+        if rescored {
+            self.exact(embedding)
+        } else {
+            self.similarity(id, position, embedding)
+        }
+    }
+}
+
+/// Scale `vector` to a unit norm
+fn normalize(vector: &mut [f64]) {
+    let norm = kernels::squared_norm(vector).sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+/// RankingItem(distance, document_id, serializable document, best matching embedding)
 #[derive(Eq, PartialEq)]
-struct RankingItem<'a, D: Serialize>(OrderedFloat<f64>, &'a String, &'a D, &'a String);
+struct RankingItem<'a, D: Serialize>(OrderedFloat<f64>, &'a String, &'a D, &'a Embedding);
 
 impl<D: Serialize + Eq> Ord for RankingItem<'_, D> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
@@ -173,9 +526,142 @@ impl<D: Serialize + Eq> PartialOrd for RankingItem<'_, D> {
     }
 }
 
+/// Vector of the embedding at `position` of document `id`, approximated from its quantized
+/// embedding if the store does not keep full precision embeddings (see
+/// [InMemoryVectorStore::vector_of])
+#[cfg(feature = "hnsw")]
+pub(crate) fn stored_vector<'a, D>(
+    embeddings: &'a HashMap<String, (D, OneOrMany<Embedding>)>,
+    quantized: Option<&Quantized>,
+    id: &str,
+    position: usize,
+) -> Cow<'a, [f64]> {
+    let Some(embedding) = embeddings
+        .get(id)
+        .and_then(|(_, embeddings)| embeddings.iter().nth(position))
+    else {
+        return Cow::Borrowed(&[]);
+    };
+    match quantized
+        .filter(|_| embedding.vec.is_empty())
+        .and_then(|quantized| quantized.vector(id, position))
+    {
+        Some(vector) => Cow::Owned(vector),
+        None => Cow::Borrowed(&embedding.vec),
+    }
+}
+
 type EmbeddingRanking<'a, D> = BinaryHeap<Reverse<RankingItem<'a, D>>>;
 
 impl<D: Serialize> InMemoryVectorStore<D> {
+    fn empty() -> Self {
+        Self {
+            embeddings: HashMap::new(),
+            metric: DistanceMetric::default(),
+            normalized: false,
+            scoring: ScoringStrategy::default(),
+            quantized: None,
+            #[cfg(feature = "hnsw")]
+            hnsw: None,
+        }
+    }
+
+    /// Insert a document, replacing the document with the same id
+    fn insert(&mut self, id: String, doc: D, mut embeddings: OneOrMany<Embedding>) {
+        if self.normalized {
+            for embedding in embeddings.iter_mut() {
+                normalize(&mut embedding.vec);
+            }
+        }
+        // The HNSW index reads the vectors of the embeddings from the store, so the previous
+        // embeddings are removed from the index before the document is replaced, and the new
+        // ones inserted after
+        #[cfg(feature = "hnsw")]
+        if let Some(hnsw) = &mut self.hnsw {
+            hnsw.remove(&id, &|id: &str, position| {
+                stored_vector(&self.embeddings, self.quantized.as_ref(), id, position)
+            });
+        }
+        #[cfg(feature = "hnsw")]
+        let count = embeddings.len();
+        let embeddings = match &mut self.quantized {
+            Some(quantized) => quantized.insert(&id, embeddings),
+            None => embeddings,
+        };
+        self.embeddings.insert(id.clone(), (doc, embeddings));
+        #[cfg(feature = "hnsw")]
+        if let Some(hnsw) = &mut self.hnsw {
+            hnsw.insert(&id, count, &|id: &str, position| {
+                stored_vector(&self.embeddings, self.quantized.as_ref(), id, position)
+            });
+        }
+    }
+
+    /// Remove a document, returning whether it was in the store
+    fn remove(&mut self, id: &str) -> bool {
+        #[cfg(feature = "hnsw")]
+        if let Some(hnsw) = &mut self.hnsw {
+            hnsw.remove(id, &|id: &str, position| {
+                stored_vector(&self.embeddings, self.quantized.as_ref(), id, position)
+            });
+        }
+        if let Some(quantized) = &mut self.quantized {
+            quantized.remove(id);
+        }
+        self.embeddings.remove(id).is_some()
+    }
+
+    /// Rank the documents with the given `metric` (cosine similarity by default). Scores of
+    /// distance metrics are converted so that higher scores are closer (see
+    /// [DistanceMetric::score_of]). The HNSW index of the store (if any) is rebuilt with the
+    /// new metric.
+    pub fn with_metric(mut self, metric: DistanceMetric) -> Self {
+        #[cfg(feature = "hnsw")]
+        if let Some(config) = self
+            .hnsw_config()
+            .copied()
+            .filter(|_| self.metric != metric)
+        {
+            self.metric = metric;
+            return self.with_hnsw(config);
+        }
+        self.metric = metric;
+        self
+    }
+
+    pub fn metric(&self) -> DistanceMetric {
+        self.metric
+    }
+
+    /// Whether the embeddings of the store are normalized (see
+    /// [InMemoryVectorStore::with_normalized_embeddings])
+    pub fn normalized_embeddings(&self) -> bool {
+        self.normalized
+    }
+
+    /// Normalize the embeddings of the store (and the ones added later) to a unit norm, as well
+    /// as the queries, so that the cosine similarity is computed as a dot product. The HNSW
+    /// index of the store (if any) is rebuilt with the normalized embeddings.
+    pub fn with_normalized_embeddings(mut self) -> Self {
+        let quantization = self.dequantize_embeddings();
+        for (_, embeddings) in self.embeddings.values_mut() {
+            for embedding in embeddings.iter_mut() {
+                normalize(&mut embedding.vec);
+            }
+        }
+        self.normalized = true;
+
+        let store = match quantization {
+            Some(config) => self.with_quantization(config),
+            None => self,
+        };
+        #[cfg(feature = "hnsw")]
+        if let Some(config) = store.hnsw_config().copied() {
+            return store.with_hnsw(config);
+        }
+        store
+    }
+
     pub fn index<M: EmbeddingModel>(self, model: M) -> InMemoryVectorIndex<M, D> {
         InMemoryVectorIndex::new(model, self)
     }
@@ -193,6 +679,47 @@ impl<D: Serialize> InMemoryVectorStore<D> {
     }
 }
 
+impl<D: Serialize + for<'a> Deserialize<'a> + Eq + Send + Sync> WritableVectorStore
+    for InMemoryVectorStore<D>
+{
+    async fn upsert_documents_with_ids<T: Serialize + Send>(
+        &mut self,
+        documents: Vec<(String, T, OneOrMany<Embedding>)>,
+    ) -> Result<(), VectorStoreError> {
+        let documents = documents
+            .into_iter()
+            .map(|(id, doc, embeddings)| {
+                Ok((
+                    id,
+                    serde_json::from_value(serde_json::to_value(doc)?)?,
+                    embeddings,
+                ))
+            })
+            .collect::<Result<Vec<_>, VectorStoreError>>()?;
+
+        self.add_documents_with_ids(documents);
+        Ok(())
+    }
+
+    async fn delete_documents(&mut self, ids: Vec<String>) -> Result<usize, VectorStoreError> {
+        Ok(ids.iter().filter(|id| self.remove(id)).count())
+    }
+
+    async fn delete_documents_by_filter(
+        &mut self,
+        filter: &Filter,
+    ) -> Result<usize, VectorStoreError> {
+        let mut ids = vec![];
+        for (id, (doc, _)) in self.embeddings.iter() {
+            if filter.matches(&serde_json::to_value(doc)?) {
+                ids.push(id.clone());
+            }
+        }
+
+        self.delete_documents(ids).await
+    }
+}
+
 pub struct InMemoryVectorIndex<M: EmbeddingModel, D: Serialize> {
     model: M,
     pub store: InMemoryVectorStore<D>,
@@ -203,6 +730,19 @@ impl<M: EmbeddingModel, D: Serialize> InMemoryVectorIndex<M, D> {
         Self { model, store }
     }
 
+    /// Rank the documents with the given `metric` (see [InMemoryVectorStore::with_metric])
+    pub fn with_metric(mut self, metric: DistanceMetric) -> Self {
+        self.store = self.store.with_metric(metric);
+        self
+    }
+
+    /// Score the documents with several embeddings with the given `strategy`
+    /// (see [InMemoryVectorStore::with_scoring])
+    pub fn with_scoring(mut self, strategy: ScoringStrategy) -> Self {
+        self.store.scoring = strategy;
+        self
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = (&String, &(D, OneOrMany<Embedding>))> {
         self.store.iter()
     }
@@ -216,6 +756,48 @@ impl<M: EmbeddingModel, D: Serialize> InMemoryVectorIndex<M, D> {
     }
 }
 
+impl<M: EmbeddingModel, D: Serialize + for<'a> Deserialize<'a> + Eq + Send + Sync>
+    WritableVectorStore for InMemoryVectorIndex<M, D>
+{
+    async fn upsert_documents_with_ids<T: Serialize + Send>(
+        &mut self,
+        documents: Vec<(String, T, OneOrMany<Embedding>)>,
+    ) -> Result<(), VectorStoreError> {
+        self.store.upsert_documents_with_ids(documents).await
+    }
+
+    async fn delete_documents(&mut self, ids: Vec<String>) -> Result<usize, VectorStoreError> {
+        self.store.delete_documents(ids).await
+    }
+
+    async fn delete_documents_by_filter(
+        &mut self,
+        filter: &Filter,
+    ) -> Result<usize, VectorStoreError> {
+        self.store.delete_documents_by_filter(filter).await
+    }
+}
+
+impl<M: EmbeddingModel + Sync, D: Serialize + Sync + Send + Eq> InMemoryVectorIndex<M, D> {
+    /// Same as [VectorStoreIndex::top_n], also returning the embedding of each document best
+    /// matching the query, e.g.: to know which embedded field of the documents matched.
+    pub async fn top_n_with_matches<T: for<'a> Deserialize<'a>>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T, EmbeddingMatch)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::retrieval_span("in_memory", query, n),
+            async move {
+                let prompt_embedding = &self.model.embed_text(query).await?;
+
+                self.store.top_n_with_matches(prompt_embedding, n)
+            },
+        )
+        .await
+    }
+}
+
 impl<M: EmbeddingModel + Sync, D: Serialize + Sync + Send + Eq> VectorStoreIndex
     for InMemoryVectorIndex<M, D>
 {
@@ -224,23 +806,15 @@ impl<M: EmbeddingModel + Sync, D: Serialize + Sync + Send + Eq> VectorStoreIndex
         query: &str,
         n: usize,
     ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
-        let prompt_embedding = &self.model.embed_text(query).await?;
-
-        let docs = self.store.vector_search(prompt_embedding, n);
+        telemetry::instrument_retrieval(
+            telemetry::retrieval_span("in_memory", query, n),
+            async move {
+                let prompt_embedding = &self.model.embed_text(query).await?;
 
-        // Return n best
-        docs.into_iter()
-            .map(|Reverse(RankingItem(distance, id, doc, _))| {
-                Ok((
-                    distance.0,
-                    id.clone(),
-                    serde_json::from_str(
-                        &serde_json::to_string(doc).map_err(VectorStoreError::JsonError)?,
-                    )
-                    .map_err(VectorStoreError::JsonError)?,
-                ))
-            })
-            .collect::<Result<Vec<_>, _>>()
+                self.store.top_n(prompt_embedding, n)
+            },
+        )
+        .await
     }
 
     async fn top_n_ids(
@@ -248,24 +822,112 @@ impl<M: EmbeddingModel + Sync, D: Serialize + Sync + Send + Eq> VectorStoreIndex
         query: &str,
         n: usize,
     ) -> Result<Vec<(f64, String)>, VectorStoreError> {
-        let prompt_embedding = &self.model.embed_text(query).await?;
+        telemetry::instrument_retrieval(
+            telemetry::retrieval_span("in_memory", query, n),
+            async move {
+                let prompt_embedding = &self.model.embed_text(query).await?;
 
-        let docs = self.store.vector_search(prompt_embedding, n);
+                Ok(self.store.top_n_ids(prompt_embedding, n))
+            },
+        )
+        .await
+    }
 
-        // Return n best
-        docs.into_iter()
-            .map(|Reverse(RankingItem(distance, id, _, _))| Ok((distance.0, id.clone())))
-            .collect::<Result<Vec<_>, _>>()
+    async fn top_n_with_options<T: for<'a> Deserialize<'a>>(
+        &self,
+        query: &str,
+        n: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::retrieval_span("in_memory", query, n),
+            async move {
+                let prompt_embedding = &self.model.embed_text(query).await?;
+
+                let docs = self
+                    .store
+                    .vector_search_with_options(prompt_embedding, n, options);
+
+                docs.into_iter()
+                    .map(|RankingItem(distance, id, doc, _)| {
+                        Ok((
+                            distance.0,
+                            id.clone(),
+                            serde_json::from_str(
+                                &serde_json::to_string(doc).map_err(VectorStoreError::JsonError)?,
+                            )
+                            .map_err(VectorStoreError::JsonError)?,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )
+        .await
     }
 }
 
+impl<M: EmbeddingModel + Sync, D: Serialize + Sync + Send + Eq> VectorSearchIndex
+    for InMemoryVectorIndex<M, D>
+{
+    type Model = M;
+
+    fn embedding_model(&self) -> &M {
+        &self.model
+    }
+
+    async fn top_n_by_vector<T: for<'a> Deserialize<'a>>(
+        &self,
+        vector: &[f64],
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::vector_retrieval_span("in_memory", vector.len(), n),
+            async move { self.store.top_n(&query_embedding(&self.model, vector)?, n) },
+        )
+        .await
+    }
+
+    async fn top_n_ids_by_vector(
+        &self,
+        vector: &[f64],
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::vector_retrieval_span("in_memory", vector.len(), n),
+            async move {
+                Ok(self
+                    .store
+                    .top_n_ids(&query_embedding(&self.model, vector)?, n))
+            },
+        )
+        .await
+    }
+}
+
+/// Embedding of a query given as a vector, which must have the dimensions of the embeddings
+/// of the `model`
+fn query_embedding(
+    model: &impl EmbeddingModel,
+    vector: &[f64],
+) -> Result<Embedding, VectorStoreError> {
+    check_query_dimensions(vector, model.ndims())?;
+    Ok(Embedding {
+        document: String::new(),
+        vec: vector.to_vec(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use std::cmp::Reverse;
 
     use crate::{embeddings::embedding::Embedding, OneOrMany};
 
-    use super::{InMemoryVectorStore, RankingItem};
+    use super::{DistanceMetric, InMemoryVectorStore, RankingItem};
+    use crate::vector_store::{
+        filter::Filter, scoring::ScoringStrategy, Mmr, SearchOptions, VectorSearchIndex,
+        VectorStoreError, WritableVectorStore,
+    };
 
     #[test]
     fn test_auto_ids() {
@@ -405,6 +1067,7 @@ mod tests {
                 vec: vec![0.0, 0.1, 0.6],
             },
             1,
+            None,
         );
 
         assert_eq!(
@@ -482,6 +1145,7 @@ mod tests {
                 vec: vec![0.0, 0.1, 0.6],
             },
             1,
+            None,
         );
 
         assert_eq!(
@@ -502,4 +1166,548 @@ mod tests {
             )]
         )
     }
+
+    #[test]
+    fn test_scoring_strategies() {
+        let embedding = |document: &str, vec: Vec<f64>| Embedding {
+            document: document.to_string(),
+            vec,
+        };
+        let store = InMemoryVectorStore::from_documents_with_ids(vec![
+            (
+                "a",
+                "a".to_string(),
+                OneOrMany::many(vec![
+                    embedding("a-description", vec![1.0, 0.0]),
+                    embedding("a-sentiment", vec![0.0, 1.0]),
+                ])
+                .unwrap(),
+            ),
+            (
+                "b",
+                "b".to_string(),
+                OneOrMany::many(vec![
+                    embedding("b-description", vec![0.8, 0.6]),
+                    embedding("b-sentiment", vec![0.8, 0.6]),
+                ])
+                .unwrap(),
+            ),
+        ]);
+        let query = embedding("query", vec![1.0, 0.0]);
+
+        let ranking = |store: &InMemoryVectorStore<String>| {
+            store
+                .top_n_ids(&query, 2)
+                .into_iter()
+                .map(|(_, id)| id)
+                .collect::<Vec<_>>()
+        };
+
+        // The best embedding of "a" matches exactly, but "b" matches with all its embeddings
+        assert_eq!(ranking(&store), vec!["a", "b"]);
+        for strategy in [ScoringStrategy::Mean, ScoringStrategy::TopK(2)] {
+            let store = store.clone().with_scoring(strategy);
+            assert_eq!(ranking(&store), vec!["b", "a"]);
+        }
+        let store = store.with_scoring(ScoringStrategy::WeightedByPosition(vec![0.0, 1.0]));
+        assert_eq!(ranking(&store), vec!["b", "a"]);
+
+        // Only the weighted embeddings can match
+        let results = store.top_n_with_matches::<String>(&query, 2).unwrap();
+        let (score, id, _, matched) = &results[1];
+        assert_eq!(id, "a");
+        assert_eq!(*score, 0.0);
+        assert_eq!(matched.position, 1);
+        assert_eq!(matched.text, "a-sentiment");
+
+        let store = store.with_scoring(ScoringStrategy::Max);
+        let results = store.top_n_with_matches::<String>(&query, 2).unwrap();
+        let (_, id, doc, matched) = &results[0];
+        assert_eq!((id.as_str(), doc.as_str()), ("a", "a"));
+        assert_eq!(matched.position, 0);
+        assert_eq!(matched.text, "a-description");
+        assert!((matched.score - 1.0).abs() < 1e-9);
+
+        #[cfg(feature = "hnsw")]
+        {
+            let store = store
+                .with_hnsw(crate::vector_store::hnsw::HnswConfig::new())
+                .with_scoring(ScoringStrategy::Mean);
+            assert_eq!(ranking(&store), vec!["b", "a"]);
+        }
+    }
+
+    #[test]
+    fn test_search_with_options() {
+        let embedding = |vec: Vec<f64>| {
+            OneOrMany::one(Embedding {
+                document: String::new(),
+                vec,
+            })
+        };
+        let vector_store = InMemoryVectorStore::from_documents_with_ids(vec![
+            ("day1", "summary 1", embedding(vec![1.0, 0.1, 0.0])),
+            ("day2", "summary 2", embedding(vec![1.0, 0.11, 0.0])),
+            ("day3", "summary 3", embedding(vec![1.0, 0.12, 0.0])),
+            ("news", "news", embedding(vec![0.6, 0.0, 0.8])),
+            ("noise", "noise", embedding(vec![0.0, 1.0, 0.0])),
+        ]);
+        let query = Embedding {
+            document: "query".to_string(),
+            vec: vec![1.0, 0.0, 0.0],
+        };
+        let ids = |options: SearchOptions, n: usize| {
+            vector_store
+                .vector_search_with_options(&query, n, &options)
+                .into_iter()
+                .map(|RankingItem(_, id, _, _)| id.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(SearchOptions::new(), 2), vec!["day1", "day2"]);
+        assert_eq!(
+            ids(SearchOptions::new().min_score(0.5), 10),
+            vec!["day1", "day2", "day3", "news"]
+        );
+        assert_eq!(
+            ids(SearchOptions::new().mmr(Mmr::new(0.5)), 2),
+            vec!["day1", "news"]
+        );
+    }
+
+    #[test]
+    fn test_search_with_filter() {
+        let document = |symbol: &str, price: f64, vec: Vec<f64>| {
+            (
+                symbol.to_lowercase(),
+                serde_json::json!({ "symbol": symbol, "price": price }),
+                OneOrMany::one(Embedding {
+                    document: symbol.to_string(),
+                    vec,
+                }),
+            )
+        };
+        let vector_store = InMemoryVectorStore::from_documents_with_ids(vec![
+            document("SOL", 142.5, vec![1.0, 0.0, 0.0]),
+            document("JUP", 0.9, vec![0.9, 0.1, 0.0]),
+            document("BONK", 0.00002, vec![0.8, 0.2, 0.0]),
+            document("WIF", 2.1, vec![0.0, 1.0, 0.0]),
+        ]);
+        let query = Embedding {
+            document: "query".to_string(),
+            vec: vec![1.0, 0.0, 0.0],
+        };
+        let ids = |options: SearchOptions, n: usize| {
+            vector_store
+                .vector_search_with_options(&query, n, &options)
+                .into_iter()
+                .map(|RankingItem(_, id, _, _)| id.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        // The filter is applied before ranking, so that n documents are still returned
+        assert_eq!(
+            ids(SearchOptions::new().filter(Filter::lt("price", 10)), 2),
+            vec!["jup", "bonk"]
+        );
+        assert_eq!(
+            ids(
+                SearchOptions::new().filter(!Filter::one_of("symbol", ["SOL", "BONK"])),
+                10
+            ),
+            vec!["jup", "wif"]
+        );
+        assert!(ids(SearchOptions::new().filter(Filter::eq("symbol", "BTC")), 2).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_upsert_and_delete() {
+        let document = |id: &str, chain: &str| {
+            (
+                id.to_string(),
+                serde_json::json!({ "symbol": id, "chain": chain }),
+                OneOrMany::one(Embedding {
+                    document: id.to_string(),
+                    vec: vec![0.1, 0.2],
+                }),
+            )
+        };
+        let mut vector_store = InMemoryVectorStore::<serde_json::Value>::default();
+
+        vector_store
+            .upsert_documents_with_ids(vec![
+                document("SOL", "solana"),
+                document("JUP", "solana"),
+                document("ETH", "ethereum"),
+            ])
+            .await
+            .unwrap();
+        vector_store
+            .upsert_documents_with_ids(vec![document("ETH", "mainnet")])
+            .await
+            .unwrap();
+        assert_eq!(vector_store.len(), 3);
+        assert_eq!(
+            vector_store
+                .get_document::<serde_json::Value>("ETH")
+                .unwrap()
+                .unwrap()["chain"],
+            "mainnet"
+        );
+
+        let deleted = vector_store
+            .delete_documents_by_filter(&Filter::eq("chain", "solana"))
+            .await
+            .unwrap();
+        assert_eq!(deleted, 2);
+
+        let deleted = vector_store
+            .delete_documents(vec!["ETH".to_string(), "BTC".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(deleted, 1);
+        assert!(vector_store.is_empty());
+    }
+
+    /// Embedding model mapping the queries "x" and "y" to axis vectors, counting its requests
+    #[derive(Clone, Default)]
+    struct AxisModel(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+    impl crate::embeddings::EmbeddingModel for AxisModel {
+        const MAX_DOCUMENTS: usize = 2;
+
+        fn ndims(&self) -> usize {
+            2
+        }
+
+        async fn embed_texts(
+            &self,
+            texts: impl IntoIterator<Item = String> + Send,
+        ) -> Result<Vec<Embedding>, crate::embeddings::EmbeddingError> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(texts
+                .into_iter()
+                .map(|text| Embedding {
+                    vec: if text == "x" {
+                        vec![1.0, 0.0]
+                    } else {
+                        vec![0.0, 1.0]
+                    },
+                    document: text,
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_by_vector_and_batch() {
+        let document = |id: &str, vec: Vec<f64>| {
+            (
+                id.to_string(),
+                id.to_string(),
+                OneOrMany::one(Embedding {
+                    document: id.to_string(),
+                    vec,
+                }),
+            )
+        };
+        let model = AxisModel::default();
+        let index = InMemoryVectorStore::from_documents_with_ids(vec![
+            document("east", vec![1.0, 0.1]),
+            document("north", vec![0.1, 1.0]),
+            document("north-east", vec![1.0, 1.0]),
+        ])
+        .index(model.clone());
+
+        let results = index
+            .top_n_by_vector::<String>(&[1.0, 0.9], 2)
+            .await
+            .unwrap();
+        let ids = results
+            .iter()
+            .map(|(_, id, _)| id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["north-east", "east"]);
+        assert_eq!(results[0].2, "north-east");
+
+        let ids = index.top_n_ids_by_vector(&[0.0, 1.0], 1).await.unwrap();
+        assert_eq!(ids[0].1, "north");
+
+        // Query vectors must have the dimensions of the embedding model
+        assert!(matches!(
+            index.top_n_ids_by_vector(&[0.0, 1.0, 0.0], 1).await,
+            Err(VectorStoreError::DimensionsMismatch {
+                expected: 2,
+                found: 3
+            })
+        ));
+
+        // Queries are embedded by chunks of MAX_DOCUMENTS, results are in the order of the queries
+        let results = index.top_n_ids_batch(&["y", "x", "y"], 1).await.unwrap();
+        assert_eq!(model.0.load(std::sync::atomic::Ordering::SeqCst), 2);
+        let ids = results
+            .iter()
+            .map(|results| results[0].1.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["north", "east", "north"]);
+
+        let results = index.top_n_batch::<String>(&["x"], 3).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].len(), 3);
+        assert_eq!(results[0][0].2, "east");
+    }
+
+    #[test]
+    fn test_distance_metrics() {
+        let document = |id: &str, vec: Vec<f64>| {
+            (
+                id.to_string(),
+                id.to_string(),
+                OneOrMany::one(Embedding {
+                    document: id.to_string(),
+                    vec,
+                }),
+            )
+        };
+        // "near" points in the same direction as the query but is far from it, "close" is
+        // close to the query but in a different direction
+        let vector_store = InMemoryVectorStore::from_documents_with_ids(vec![
+            document("near", vec![10.0, 10.0]),
+            document("close", vec![1.2, 0.6]),
+        ]);
+        let query = Embedding {
+            document: "query".to_string(),
+            vec: vec![1.0, 1.0],
+        };
+        let ranking = |vector_store: &InMemoryVectorStore<String>| {
+            vector_store
+                .vector_search(&query, 2, None)
+                .into_sorted_vec()
+                .into_iter()
+                .map(|Reverse(RankingItem(score, id, _, _))| (id.clone(), score.0))
+                .collect::<Vec<_>>()
+        };
+
+        let cosine = ranking(&vector_store);
+        assert_eq!(cosine[0].0, "near");
+        assert!((cosine[0].1 - 1.0).abs() < 1e-12);
+
+        // Lower distances rank first, with scores in (0, 1]
+        let vector_store = vector_store.with_metric(DistanceMetric::Euclidean);
+        let euclidean = ranking(&vector_store);
+        assert_eq!(euclidean[0].0, "close");
+        assert!((euclidean[0].1 - 1.0 / (1.0 + 0.2f64.sqrt())).abs() < 1e-12);
+        assert!(euclidean[1].1 < euclidean[0].1);
+
+        // Normalized embeddings and queries: "near" becomes the query
+        let vector_store = vector_store.with_normalized_embeddings();
+        let normalized = ranking(&vector_store);
+        assert_eq!(normalized[0], ("near".to_string(), 1.0));
+
+        let mut vector_store = vector_store.with_metric(DistanceMetric::DotProduct);
+        vector_store.add_documents_with_ids([document("far", vec![-3.0, -4.0])]);
+        let (_, (_, embeddings)) = vector_store.iter().find(|(id, _)| *id == "far").unwrap();
+        assert_eq!(embeddings.first().vec, vec![-0.6, -0.8]);
+        assert_eq!(vector_store.metric(), DistanceMetric::DotProduct);
+    }
+
+    #[test]
+    fn test_quantized_search() {
+        use crate::vector_store::quantization::{Quantization, QuantizationConfig};
+
+        let mut state = 3u64;
+        let mut vector = || {
+            (0..32)
+                .map(|_| {
+                    state = state
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    (state >> 33) as f64 / (1u64 << 31) as f64 - 0.5
+                })
+                .collect::<Vec<_>>()
+        };
+        let brute_force = InMemoryVectorStore::from_documents((0..100).map(|i| {
+            let embedding = Embedding {
+                document: i.to_string(),
+                vec: vector(),
+            };
+            (i, OneOrMany::one(embedding))
+        }));
+        let query = Embedding {
+            document: "query".to_string(),
+            vec: vector(),
+        };
+        let results = |vector_store: &InMemoryVectorStore<i32>| {
+            vector_store
+                .vector_search(&query, 5, None)
+                .into_sorted_vec()
+                .into_iter()
+                .map(|Reverse(RankingItem(score, id, _, _))| (id.clone(), score.0))
+                .collect::<Vec<_>>()
+        };
+        let expected = results(&brute_force);
+
+        // Without rescoring, full precision vectors are dropped and scores are approximate
+        let f32_store = brute_force
+            .clone()
+            .with_quantization(QuantizationConfig::new(Quantization::F32));
+        assert!(f32_store
+            .iter()
+            .all(|(_, (_, embeddings))| embeddings.first().vec.is_empty()));
+        for ((id, score), (expected_id, expected_score)) in
+            results(&f32_store).iter().zip(&expected)
+        {
+            assert_eq!(id, expected_id);
+            assert!((score - expected_score).abs() < 1e-6);
+        }
+
+        // Rescored candidates have exact scores
+        let int8_store = brute_force
+            .clone()
+            .with_quantization(QuantizationConfig::new(Quantization::Int8).rescore(4));
+        assert_eq!(results(&int8_store), expected);
+
+        let mut binary_store = brute_force
+            .with_quantization(QuantizationConfig::new(Quantization::Binary).rescore(10));
+        assert_eq!(results(&binary_store)[0], expected[0]);
+
+        // Documents added to the store are quantized
+        binary_store.add_documents_with_ids([("query", 100, OneOrMany::one(query.clone()))]);
+        assert_eq!(results(&binary_store)[0], ("query".to_string(), 1.0));
+    }
+
+    #[cfg(feature = "hnsw")]
+    #[tokio::test]
+    async fn test_hnsw_search() {
+        use crate::vector_store::hnsw::HnswConfig;
+
+        let mut state = 7u64;
+        let mut vector = || {
+            (0..4)
+                .map(|_| {
+                    state = state
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    (state >> 33) as f64 / (1u64 << 31) as f64 - 0.5
+                })
+                .collect::<Vec<_>>()
+        };
+        let documents = (0..300)
+            .map(|i| {
+                let embeddings: Vec<_> = (0..2)
+                    .map(|_| Embedding {
+                        document: i.to_string(),
+                        vec: vector(),
+                    })
+                    .collect();
+                (
+                    i.to_string(),
+                    serde_json::json!({ "rank": i }),
+                    OneOrMany::many(embeddings).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let brute_force = InMemoryVectorStore::from_documents_with_ids(documents);
+        let mut vector_store = brute_force.clone().with_hnsw(HnswConfig::new().m(8));
+        assert_eq!(vector_store.hnsw_config().unwrap().m, 8);
+
+        let query = Embedding {
+            document: "query".to_string(),
+            vec: vector(),
+        };
+        let ids = |vector_store: &InMemoryVectorStore<serde_json::Value>, filter| {
+            vector_store
+                .vector_search(&query, 10, filter)
+                .into_sorted_vec()
+                .into_iter()
+                .map(|Reverse(RankingItem(_, id, _, _))| id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // Documents are only returned once, with the score of their best embedding
+        let filter = Filter::lt("rank", 150);
+        assert_eq!(ids(&vector_store, None), ids(&brute_force, None));
+        assert_eq!(
+            ids(&vector_store, Some(&filter)),
+            ids(&brute_force, Some(&filter))
+        );
+
+        // The graph is rebuilt with the metric of the store
+        let euclidean = vector_store.clone().with_metric(DistanceMetric::Euclidean);
+        let brute_force_euclidean = brute_force.clone().with_metric(DistanceMetric::Euclidean);
+        assert_eq!(ids(&euclidean, None), ids(&brute_force_euclidean, None));
+        assert_ne!(ids(&euclidean, None), ids(&vector_store, None));
+
+        // Upserted documents are indexed, deleted documents are not returned anymore
+        vector_store
+            .upsert_documents_with_ids(vec![(
+                "query".to_string(),
+                serde_json::json!({ "rank": 1000 }),
+                OneOrMany::one(query.clone()),
+            )])
+            .await
+            .unwrap();
+        assert_eq!(ids(&vector_store, None)[0], "query");
+        vector_store
+            .delete_documents(vec!["query".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(ids(&vector_store, None), ids(&brute_force, None));
+    }
+
+    #[cfg(feature = "hnsw")]
+    #[test]
+    fn test_hnsw_normalized_embeddings() {
+        use crate::vector_store::hnsw::HnswConfig;
+
+        // Norms spread over several orders of magnitude, so that the neighbors of the
+        // embeddings before and after normalization differ
+        let mut state = 11u64;
+        let mut random = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as f64 / (1u64 << 31) as f64
+        };
+        let mut vector = || {
+            let scale = 10f64.powf(6.0 * random() - 3.0);
+            (0..4).map(|_| scale * (random() - 0.5)).collect::<Vec<_>>()
+        };
+        let documents = (0..300)
+            .map(|i| {
+                (
+                    i.to_string(),
+                    i,
+                    OneOrMany::one(Embedding {
+                        document: i.to_string(),
+                        vec: vector(),
+                    }),
+                )
+            })
+            .collect::<Vec<_>>();
+        let brute_force = InMemoryVectorStore::from_documents_with_ids(documents)
+            .with_metric(DistanceMetric::Euclidean);
+        let vector_store = brute_force
+            .clone()
+            .with_hnsw(HnswConfig::new().m(8).ef_search(10));
+
+        let query = Embedding {
+            document: "query".to_string(),
+            vec: vector(),
+        };
+        let ids = |vector_store: &InMemoryVectorStore<i32>| {
+            vector_store
+                .vector_search(&query, 10, None)
+                .into_sorted_vec()
+                .into_iter()
+                .map(|Reverse(RankingItem(_, id, _, _))| id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // The graph is rebuilt with the normalized embeddings
+        let vector_store = vector_store.with_normalized_embeddings();
+        let brute_force = brute_force.with_normalized_embeddings();
+        assert_eq!(vector_store.hnsw_config().unwrap().m, 8);
+        assert_eq!(ids(&vector_store), ids(&brute_force));
+    }
 }
@@ -1,10 +1,27 @@
-use futures::future::BoxFuture;
-use serde::Deserialize;
+use futures::future::{try_join_all, BoxFuture};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::embeddings::EmbeddingError;
+use crate::{
+    completion::CompletionError,
+    embeddings::{Embedding, EmbeddingError, EmbeddingModel},
+    OneOrMany,
+};
 
+pub mod bm25;
+pub mod filter;
+#[cfg(feature = "hnsw")]
+pub mod hnsw;
+pub mod hybrid;
 pub mod in_memory_store;
+pub mod persistence;
+pub mod quantization;
+pub mod query_transform;
+pub mod scoring;
+pub mod search;
+
+pub use filter::Filter;
+pub use search::{Mmr, SearchOptions};
 
 #[derive(Debug, thiserror::Error)]
 pub enum VectorStoreError {
@@ -20,6 +37,30 @@ pub enum VectorStoreError {
 
     #[error("Missing Id: {0}")]
     MissingIdError(String),
+
+    /// Error rewriting the query with a completion model (see [query_transform])
+    #[error("Query transformation error: {0}")]
+    QueryTransformError(#[from] CompletionError),
+
+    /// Error saving or loading a store (see [persistence])
+    #[error("Persistence error: {0}")]
+    PersistenceError(#[from] persistence::PersistenceError),
+
+    /// Query vector whose number of dimensions differs from the embeddings of the index
+    #[error("Query vector has {found} dimensions, expected {expected}")]
+    DimensionsMismatch { expected: usize, found: usize },
+}
+
+/// Check that a query `vector` has the `ndims` dimensions of the embeddings of an index, used
+/// by the implementations of [VectorSearchIndex::top_n_by_vector].
+pub fn check_query_dimensions(vector: &[f64], ndims: usize) -> Result<(), VectorStoreError> {
+    if vector.len() != ndims {
+        return Err(VectorStoreError::DimensionsMismatch {
+            expected: ndims,
+            found: vector.len(),
+        });
+    }
+    Ok(())
 }
 
 /// Trait for vector store indexes
@@ -38,10 +79,207 @@ pub trait VectorStoreIndex: Send + Sync {
         query: &str,
         n: usize,
     ) -> impl std::future::Future<Output = Result<Vec<(f64, String)>, VectorStoreError>> + Send;
+
+    /// Same as `top_n` but applies the given search `options` (e.g.: minimum score, MMR
+    /// diversification, filter), so that fewer than `n` documents may be returned.
+    ///
+    /// The default implementation only applies the minimum score cutoff and evaluates the
+    /// filter on the `n` best documents, since it does not have access to the embeddings of the
+    /// documents nor to a native filter. Vector stores supporting MMR or filtering override it.
+    fn top_n_with_options<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+        options: &SearchOptions,
+    ) -> impl std::future::Future<Output = Result<Vec<(f64, String, T)>, VectorStoreError>> + Send
+    {
+        async move {
+            if options.mmr.is_some() {
+                tracing::warn!(target: "rig", "MMR is not supported by this vector store, ignoring it");
+            }
+
+            let Some(filter) = &options.filter else {
+                return Ok(self
+                    .top_n::<T>(query, n)
+                    .await?
+                    .into_iter()
+                    .filter(|(score, _, _)| options.accepts(*score))
+                    .collect());
+            };
+
+            self.top_n::<Value>(query, n)
+                .await?
+                .into_iter()
+                .filter(|(score, _, doc)| options.accepts(*score) && filter.matches(doc))
+                .map(|(score, id, doc)| Ok((score, id, serde_json::from_value(doc)?)))
+                .collect()
+        }
+    }
+}
+
+/// Trait for vector store indexes that can be searched with an embedding vector instead of a text
+/// query, e.g.: a precomputed or averaged embedding, or a query embedding shared by several
+/// indexes. Also provides batched searches of several queries.
+///
+/// # Example
+/// ```rust
+/// use rig::vector_store::VectorSearchIndex;
+///
+/// // Embed the query once and search both indexes with it
+/// let query = model.embed_text("memecoins with growing liquidity").await?;
+/// let reports = reports_index.top_n_by_vector::<TokenReport>(&query.vec, 5).await?;
+/// let tweets = tweets_index.top_n_ids_by_vector(&query.vec, 10).await?;
+///
+/// // Search the closest tokens of a token, by its feature vector
+/// let similar = tokens_index.top_n_ids_by_vector(&token.features, 5).await?;
+///
+/// // Embed all the queries with a single request to the embedding model
+/// let results = reports_index
+///     .top_n_batch::<TokenReport>(&["SOL outlook", "JUP outlook"], 3)
+///     .await?;
+/// ```
+pub trait VectorSearchIndex: VectorStoreIndex {
+    type Model: EmbeddingModel;
+
+    /// Embedding model used to embed the text queries
+    fn embedding_model(&self) -> &Self::Model;
+
+    /// Get the top n documents based on the distance to the given embedding `vector`.
+    /// The result is a list of tuples of the form (score, id, document)
+    fn top_n_by_vector<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        vector: &[f64],
+        n: usize,
+    ) -> impl std::future::Future<Output = Result<Vec<(f64, String, T)>, VectorStoreError>> + Send;
+
+    /// Same as `top_n_by_vector` but returns the document ids only.
+    fn top_n_ids_by_vector(
+        &self,
+        vector: &[f64],
+        n: usize,
+    ) -> impl std::future::Future<Output = Result<Vec<(f64, String)>, VectorStoreError>> + Send;
+
+    /// Embed the `queries` in as few requests to the embedding model as possible (a single one
+    /// unless there are more than [EmbeddingModel::MAX_DOCUMENTS] queries).
+    fn embed_queries(
+        &self,
+        queries: &[&str],
+    ) -> impl std::future::Future<Output = Result<Vec<Vec<f64>>, VectorStoreError>> + Send {
+        async move {
+            let mut vectors = Vec::with_capacity(queries.len());
+            for chunk in queries.chunks(Self::Model::MAX_DOCUMENTS.max(1)) {
+                let embeddings = self
+                    .embedding_model()
+                    .embed_texts(chunk.iter().map(|query| query.to_string()))
+                    .await?;
+                vectors.extend(embeddings.into_iter().map(|embedding| embedding.vec));
+            }
+            Ok(vectors)
+        }
+    }
+
+    /// Get the top n documents of each of the `queries`, in the same order as the queries.
+    /// The queries are embedded together (see [VectorSearchIndex::embed_queries]) and searched
+    /// concurrently.
+    fn top_n_batch<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        queries: &[&str],
+        n: usize,
+    ) -> impl std::future::Future<Output = BatchResults<T>> + Send {
+        async move {
+            let vectors = self.embed_queries(queries).await?;
+            self.top_n_batch_by_vectors(&vectors, n).await
+        }
+    }
+
+    /// Same as `top_n_batch` but returns the document ids only.
+    fn top_n_ids_batch(
+        &self,
+        queries: &[&str],
+        n: usize,
+    ) -> impl std::future::Future<Output = Result<Vec<Vec<(f64, String)>>, VectorStoreError>> + Send
+    {
+        async move {
+            let vectors = self.embed_queries(queries).await?;
+            try_join_all(
+                vectors
+                    .iter()
+                    .map(|vector| self.top_n_ids_by_vector(vector, n)),
+            )
+            .await
+        }
+    }
+
+    /// Get the top n documents of each of the embedding `vectors`, searched concurrently.
+    fn top_n_batch_by_vectors<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        vectors: &[Vec<f64>],
+        n: usize,
+    ) -> impl std::future::Future<Output = BatchResults<T>> + Send {
+        try_join_all(vectors.iter().map(|vector| self.top_n_by_vector(vector, n)))
+    }
+}
+
+/// Trait for vector stores that documents and their embeddings can be written to, e.g.: the
+/// output of an [EmbeddingsBuilder](crate::embeddings::EmbeddingsBuilder).
+/// Documents are serialized to the representation of the vector store, in the same way
+/// [VectorStoreIndex] deserializes the documents it returns.
+///
+/// # Example
+/// ```rust
+/// use rig::{embeddings::EmbeddingsBuilder, vector_store::{Filter, WritableVectorStore}};
+///
+/// async fn ingest(
+///     store: &mut impl WritableVectorStore,
+///     model: impl EmbeddingModel,
+///     reports: Vec<TokenReport>,
+/// ) -> Result<(), anyhow::Error> {
+///     let embeddings = EmbeddingsBuilder::new(model).documents(reports)?.build().await?;
+///
+///     // Replace the previous reports on the same tokens, and remove the retracted ones
+///     store.upsert_documents(embeddings, |report| report.symbol.clone()).await?;
+///     store.delete_documents_by_filter(&Filter::eq("status", "retracted")).await?;
+///     Ok(())
+/// }
+/// ```
+pub trait WritableVectorStore: Send + Sync {
+    /// Insert documents and their embeddings, replacing the documents with the same id.
+    fn upsert_documents_with_ids<T: Serialize + Send>(
+        &mut self,
+        documents: Vec<(String, T, OneOrMany<Embedding>)>,
+    ) -> impl std::future::Future<Output = Result<(), VectorStoreError>> + Send;
+
+    /// Same as `upsert_documents_with_ids`, with the id of each document generated using `id`.
+    fn upsert_documents<T: Serialize + Send>(
+        &mut self,
+        documents: Vec<(T, OneOrMany<Embedding>)>,
+        id: impl Fn(&T) -> String + Send,
+    ) -> impl std::future::Future<Output = Result<(), VectorStoreError>> + Send {
+        let documents = documents
+            .into_iter()
+            .map(|(doc, embeddings)| (id(&doc), doc, embeddings))
+            .collect();
+        self.upsert_documents_with_ids(documents)
+    }
+
+    /// Delete the documents with the given ids, returning the number of deleted documents.
+    fn delete_documents(
+        &mut self,
+        ids: Vec<String>,
+    ) -> impl std::future::Future<Output = Result<usize, VectorStoreError>> + Send;
+
+    /// Delete the documents matching `filter`, returning the number of deleted documents.
+    fn delete_documents_by_filter(
+        &mut self,
+        filter: &Filter,
+    ) -> impl std::future::Future<Output = Result<usize, VectorStoreError>> + Send;
 }
 
 pub type TopNResults = Result<Vec<(f64, String, Value)>, VectorStoreError>;
 
+/// Results of a batched search, one list of results per query
+pub type BatchResults<T> = Result<Vec<Vec<(f64, String, T)>>, VectorStoreError>;
+
 pub trait VectorStoreIndexDyn: Send + Sync {
     fn top_n<'a>(&'a self, query: &'a str, n: usize) -> BoxFuture<'a, TopNResults>;
 
@@ -50,6 +288,13 @@ pub trait VectorStoreIndexDyn: Send + Sync {
         query: &'a str,
         n: usize,
     ) -> BoxFuture<'a, Result<Vec<(f64, String)>, VectorStoreError>>;
+
+    fn top_n_with_options<'a>(
+        &'a self,
+        query: &'a str,
+        n: usize,
+        options: &'a SearchOptions,
+    ) -> BoxFuture<'a, TopNResults>;
 }
 
 impl<I: VectorStoreIndex> VectorStoreIndexDyn for I {
@@ -75,6 +320,22 @@ impl<I: VectorStoreIndex> VectorStoreIndexDyn for I {
     ) -> BoxFuture<'a, Result<Vec<(f64, String)>, VectorStoreError>> {
         Box::pin(self.top_n_ids(query, n))
     }
+
+    fn top_n_with_options<'a>(
+        &'a self,
+        query: &'a str,
+        n: usize,
+        options: &'a SearchOptions,
+    ) -> BoxFuture<'a, TopNResults> {
+        Box::pin(async move {
+            Ok(self
+                .top_n_with_options::<serde_json::Value>(query, n, options)
+                .await?
+                .into_iter()
+                .map(|(score, id, doc)| (score, id, prune_document(doc).unwrap_or_default()))
+                .collect::<Vec<_>>())
+        })
+    }
 }
 
 fn prune_document(document: serde_json::Value) -> Option<serde_json::Value> {
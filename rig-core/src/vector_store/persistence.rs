@@ -0,0 +1,622 @@
+//! On-disk persistence of an [InMemoryVectorStore], so that documents don't have to be embedded
+//! again every time the process starts.
+//!
+//! Stores are saved in one of two formats (see [StoreFormat]): a compact binary format, or JSON.
+//! Both carry the version of the format and the [StoreMetadata] of the embedding model used to
+//! build the store, which are checked on load so that a store is not queried with embeddings
+//! of a different model. The configuration of the store (distance metric, normalization,
+//! scoring strategy, quantization and HNSW index) is saved as well and restored on load.
+//! Files are written atomically: the store is written to a temporary file which is then
+//! renamed, so that a crash never leaves a truncated store behind.
+//!
+//! # Example
+//! ```rust
+//! use rig::vector_store::{in_memory_store::InMemoryVectorStore, persistence::{StoreFormat, StoreMetadata}};
+//!
+//! let metadata = StoreMetadata::new(openai::TEXT_EMBEDDING_3_SMALL, model.ndims());
+//!
+//! let store = match InMemoryVectorStore::<TokenReport>::load("reports.rvs", &metadata) {
+//!     Ok(store) => store,
+//!     Err(_) => {
+//!         let embeddings = EmbeddingsBuilder::new(model.clone()).documents(reports)?.build().await?;
+//!         let store = InMemoryVectorStore::from_documents_with_id_f(embeddings, |report| report.symbol.clone());
+//!         store.save("reports.rvs", StoreFormat::Binary, &metadata)?;
+//!         store
+//!     }
+//! };
+//! ```
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "hnsw")]
+use super::hnsw::HnswConfig;
+use super::{
+    in_memory_store::InMemoryVectorStore, quantization::QuantizationConfig,
+    scoring::ScoringStrategy, VectorStoreError,
+};
+use crate::{
+    embeddings::{distance::DistanceMetric, Embedding},
+    OneOrMany,
+};
+
+/// Magic bytes starting the files of the binary format
+pub const MAGIC: &[u8; 8] = b"RIGVSTOR";
+
+/// Current version of the file formats
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PersistenceError {
+    /// Error reading or writing the store file
+    #[error("IoError: {0}")]
+    IoError(#[from] std::io::Error),
+
+    /// Malformed store file
+    #[error("Invalid store file: {0}")]
+    InvalidFormat(String),
+
+    /// Store file written by an unsupported version of the format
+    #[error("Unsupported store format version: {0} (expected {FORMAT_VERSION})")]
+    UnsupportedVersion(u32),
+
+    /// Store built with a different embedding model than the expected one
+    #[error("Store metadata mismatch: expected {expected:?}, found {found:?}")]
+    MetadataMismatch {
+        expected: StoreMetadata,
+        found: StoreMetadata,
+    },
+
+    /// Store keeping quantized embeddings only, which would be saved with lossy vectors
+    #[error("Quantized stores can only be saved if they keep full precision embeddings")]
+    QuantizedEmbeddings,
+
+    /// Embedding whose number of dimensions differs from the metadata of the store
+    #[error("Embedding of document {id} has {found} dimensions, expected {expected}")]
+    DimensionsMismatch {
+        id: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+/// Format of a store file
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StoreFormat {
+    /// Compact binary format: embeddings are stored as little endian floats and documents as JSON
+    Binary,
+    /// JSON format, larger but human readable
+    Json,
+}
+
+/// Embedding model used to build a store. Both the model name and the number of dimensions of
+/// its embeddings must match for a store to be loaded.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoreMetadata {
+    pub model: String,
+    pub ndims: usize,
+}
+
+impl StoreMetadata {
+    pub fn new(model: impl Into<String>, ndims: usize) -> Self {
+        Self {
+            model: model.into(),
+            ndims,
+        }
+    }
+}
+
+/// Configuration of a store, restored on load
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct StoreConfig {
+    metric: DistanceMetric,
+    normalized: bool,
+    scoring: ScoringStrategy,
+    quantization: Option<QuantizationConfig>,
+    #[cfg(feature = "hnsw")]
+    hnsw: Option<HnswConfig>,
+}
+
+impl StoreConfig {
+    fn of<D: Serialize + Eq>(store: &InMemoryVectorStore<D>) -> Self {
+        Self {
+            metric: store.metric(),
+            normalized: store.normalized_embeddings(),
+            scoring: store.scoring().clone(),
+            quantization: store.quantization_config().copied(),
+            #[cfg(feature = "hnsw")]
+            hnsw: store.hnsw_config().copied(),
+        }
+    }
+
+    /// Apply the configuration to a store loaded with the default configuration
+    fn apply<D: Serialize + Eq>(self, store: InMemoryVectorStore<D>) -> InMemoryVectorStore<D> {
+        let mut store = store.with_metric(self.metric).with_scoring(self.scoring);
+        if self.normalized {
+            store = store.with_normalized_embeddings();
+        }
+        if let Some(config) = self.quantization {
+            store = store.with_quantization(config);
+        }
+        #[cfg(feature = "hnsw")]
+        if let Some(config) = self.hnsw {
+            store = store.with_hnsw(config);
+        }
+        store
+    }
+}
+
+/// Document of the JSON format
+#[derive(Serialize, Deserialize)]
+struct JsonDocument<D> {
+    id: String,
+    document: D,
+    embeddings: Vec<Embedding>,
+}
+
+/// Layout of the JSON format
+#[derive(Serialize, Deserialize)]
+struct JsonStore<D> {
+    version: u32,
+    metadata: StoreMetadata,
+    config: StoreConfig,
+    documents: Vec<JsonDocument<D>>,
+}
+
+impl<D: Serialize + for<'a> Deserialize<'a> + Eq> InMemoryVectorStore<D> {
+    /// Save the store to `path` in the given `format`, along with the `metadata` of the
+    /// embedding model used to build it and the configuration of the store. The file is
+    /// replaced atomically if it exists.
+    pub fn save(
+        &self,
+        path: impl AsRef<Path>,
+        format: StoreFormat,
+        metadata: &StoreMetadata,
+    ) -> Result<(), VectorStoreError> {
+        if self
+            .quantization_config()
+            .is_some_and(|config| config.rescore.is_none())
+        {
+            return Err(PersistenceError::QuantizedEmbeddings.into());
+        }
+
+        // Sort the documents so that saving the same store always gives the same file
+        let mut documents = self.iter().collect::<Vec<_>>();
+        documents.sort_by_key(|(id, _)| *id);
+
+        for (id, (_, embeddings)) in &documents {
+            for embedding in embeddings.iter() {
+                if embedding.vec.len() != metadata.ndims {
+                    return Err(PersistenceError::DimensionsMismatch {
+                        id: id.to_string(),
+                        expected: metadata.ndims,
+                        found: embedding.vec.len(),
+                    }
+                    .into());
+                }
+            }
+        }
+
+        let config = StoreConfig::of(self);
+
+        write_atomic(path.as_ref(), |writer| match format {
+            StoreFormat::Json => {
+                let store = JsonStore {
+                    version: FORMAT_VERSION,
+                    metadata: metadata.clone(),
+                    config,
+                    documents: documents
+                        .iter()
+                        .map(|(id, (document, embeddings))| JsonDocument {
+                            id: id.to_string(),
+                            document,
+                            embeddings: embeddings.iter().cloned().collect(),
+                        })
+                        .collect(),
+                };
+                serde_json::to_writer(writer, &store)?;
+                Ok(())
+            }
+            StoreFormat::Binary => {
+                writer.write_all(MAGIC).map_err(PersistenceError::from)?;
+                write_u32(writer, FORMAT_VERSION)?;
+                write_bytes(writer, metadata.model.as_bytes())?;
+                write_u64(writer, metadata.ndims as u64)?;
+                write_bytes(writer, &serde_json::to_vec(&config)?)?;
+                write_u64(writer, documents.len() as u64)?;
+
+                for (id, (document, embeddings)) in &documents {
+                    write_bytes(writer, id.as_bytes())?;
+                    write_bytes(writer, &serde_json::to_vec(document)?)?;
+                    write_u32(writer, embeddings.len() as u32)?;
+                    for embedding in embeddings.iter() {
+                        write_bytes(writer, embedding.document.as_bytes())?;
+                        for value in &embedding.vec {
+                            writer
+                                .write_all(&value.to_le_bytes())
+                                .map_err(PersistenceError::from)?;
+                        }
+                    }
+                }
+                Ok(())
+            }
+        })
+    }
+
+    /// Load a store saved with [InMemoryVectorStore::save] (in either format), checking that
+    /// it was built with the embedding model described by `metadata`. The store is loaded with
+    /// the configuration it was saved with.
+    pub fn load(
+        path: impl AsRef<Path>,
+        metadata: &StoreMetadata,
+    ) -> Result<Self, VectorStoreError> {
+        let mut reader = BufReader::new(File::open(path).map_err(PersistenceError::from)?);
+
+        let mut magic = [0; MAGIC.len()];
+        let is_binary = match reader.read_exact(&mut magic) {
+            Ok(()) => &magic == MAGIC,
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => false,
+            Err(e) => return Err(PersistenceError::from(e).into()),
+        };
+
+        let (config, documents) = if is_binary {
+            read_binary(&mut reader, metadata)?
+        } else {
+            let store: JsonStore<D> = serde_json::from_reader(magic.as_slice().chain(reader))?;
+            check_header(store.version, &store.metadata, metadata)?;
+            let documents = store
+                .documents
+                .into_iter()
+                .map(
+                    |JsonDocument {
+                         id,
+                         document,
+                         embeddings,
+                     }| {
+                        Ok((
+                            id.clone(),
+                            document,
+                            embeddings_of(id, embeddings, metadata)?,
+                        ))
+                    },
+                )
+                .collect::<Result<Vec<_>, VectorStoreError>>()?;
+            (store.config, documents)
+        };
+
+        Ok(config.apply(Self::from_documents_with_ids(documents)))
+    }
+}
+
+type Documents<D> = Vec<(String, D, OneOrMany<Embedding>)>;
+
+fn read_binary<D: for<'a> Deserialize<'a>>(
+    reader: &mut impl Read,
+    metadata: &StoreMetadata,
+) -> Result<(StoreConfig, Documents<D>), VectorStoreError> {
+    let version = read_u32(reader)?;
+    let found = StoreMetadata {
+        model: read_string(reader)?,
+        ndims: read_u64(reader)? as usize,
+    };
+    check_header(version, &found, metadata)?;
+    let config = serde_json::from_slice(&read_bytes(reader)?)?;
+
+    let count = read_u64(reader)?;
+    let mut documents = Vec::new();
+    for _ in 0..count {
+        let id = read_string(reader)?;
+        let document = serde_json::from_slice(&read_bytes(reader)?)?;
+
+        let mut embeddings = Vec::new();
+        for _ in 0..read_u32(reader)? {
+            let text = read_string(reader)?;
+            let mut vec = Vec::with_capacity(metadata.ndims);
+            let mut value = [0; 8];
+            for _ in 0..metadata.ndims {
+                reader
+                    .read_exact(&mut value)
+                    .map_err(PersistenceError::from)?;
+                vec.push(f64::from_le_bytes(value));
+            }
+            embeddings.push(Embedding {
+                document: text,
+                vec,
+            });
+        }
+
+        let embeddings = embeddings_of(id.clone(), embeddings, metadata)?;
+        documents.push((id, document, embeddings));
+    }
+
+    Ok((config, documents))
+}
+
+fn check_header(
+    version: u32,
+    found: &StoreMetadata,
+    expected: &StoreMetadata,
+) -> Result<(), PersistenceError> {
+    if version != FORMAT_VERSION {
+        return Err(PersistenceError::UnsupportedVersion(version));
+    }
+    if found != expected {
+        return Err(PersistenceError::MetadataMismatch {
+            expected: expected.clone(),
+            found: found.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Check the embeddings of a loaded document
+fn embeddings_of(
+    id: String,
+    embeddings: Vec<Embedding>,
+    metadata: &StoreMetadata,
+) -> Result<OneOrMany<Embedding>, PersistenceError> {
+    if let Some(embedding) = embeddings
+        .iter()
+        .find(|embedding| embedding.vec.len() != metadata.ndims)
+    {
+        return Err(PersistenceError::DimensionsMismatch {
+            id,
+            expected: metadata.ndims,
+            found: embedding.vec.len(),
+        });
+    }
+
+    OneOrMany::many(embeddings)
+        .map_err(|_| PersistenceError::InvalidFormat(format!("Document {id} has no embeddings")))
+}
+
+/// Write a file by writing to a temporary file in the same directory and renaming it, so that
+/// the file is either fully written or left untouched.
+fn write_atomic(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<(), VectorStoreError>,
+) -> Result<(), VectorStoreError> {
+    let file_name = path.file_name().ok_or_else(|| {
+        PersistenceError::InvalidFormat(format!("Invalid store path: {}", path.display()))
+    })?;
+    // Temporary files are unique to each call, so that concurrent saves to the same path
+    // (from several processes or threads) don't write to the same temporary file
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut temp_name = file_name.to_os_string();
+    temp_name.push(format!(
+        ".tmp{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut writer = BufWriter::new(File::create(&temp_path).map_err(PersistenceError::from)?);
+        write(&mut writer)?;
+        let file = writer
+            .into_inner()
+            .map_err(|e| PersistenceError::from(e.into_error()))?;
+        file.sync_all().map_err(PersistenceError::from)?;
+        std::fs::rename(&temp_path, path).map_err(PersistenceError::from)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+fn write_u32(writer: &mut impl Write, value: u32) -> Result<(), PersistenceError> {
+    Ok(writer.write_all(&value.to_le_bytes())?)
+}
+
+fn write_u64(writer: &mut impl Write, value: u64) -> Result<(), PersistenceError> {
+    Ok(writer.write_all(&value.to_le_bytes())?)
+}
+
+/// Write length prefixed bytes
+fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> Result<(), PersistenceError> {
+    write_u32(writer, bytes.len() as u32)?;
+    Ok(writer.write_all(bytes)?)
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32, PersistenceError> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64, PersistenceError> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_bytes(reader: &mut impl Read) -> Result<Vec<u8>, PersistenceError> {
+    let len = read_u32(reader)? as usize;
+    let mut bytes = vec![];
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(PersistenceError::InvalidFormat(
+            "Unexpected end of file".to_string(),
+        ));
+    }
+    Ok(bytes)
+}
+
+fn read_string(reader: &mut impl Read) -> Result<String, PersistenceError> {
+    String::from_utf8(read_bytes(reader)?)
+        .map_err(|e| PersistenceError::InvalidFormat(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector_store::quantization::Quantization;
+
+    fn store() -> InMemoryVectorStore<serde_json::Value> {
+        let embedding = |document: &str, vec: Vec<f64>| Embedding {
+            document: document.to_string(),
+            vec,
+        };
+        InMemoryVectorStore::from_documents_with_ids([
+            (
+                "sol",
+                serde_json::json!({ "symbol": "SOL", "price": 142.5 }),
+                OneOrMany::one(embedding("SOL report", vec![0.1, -0.2, 0.3])),
+            ),
+            (
+                "jup",
+                serde_json::json!({ "symbol": "JUP", "price": 0.9 }),
+                OneOrMany::many([
+                    embedding("JUP report", vec![1.0, 0.0, 0.0]),
+                    embedding("JUP governance", vec![0.5, 0.5, f64::MIN_POSITIVE]),
+                ])
+                .unwrap(),
+            ),
+        ])
+    }
+
+    fn assert_same(
+        a: &InMemoryVectorStore<serde_json::Value>,
+        b: &InMemoryVectorStore<serde_json::Value>,
+    ) {
+        assert_eq!(a.len(), b.len());
+        for (id, (document, embeddings)) in a.iter() {
+            let (other_document, other_embeddings) =
+                b.iter().find(|(other, _)| *other == id).unwrap().1;
+            assert_eq!(document, other_document);
+            // Embeddings only compare their document
+            assert_eq!(embeddings, other_embeddings);
+            for (embedding, other) in embeddings.iter().zip(other_embeddings.iter()) {
+                assert_eq!(embedding.vec, other.vec);
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let metadata = StoreMetadata::new("text-embedding-3-small", 3);
+        let store = store();
+
+        for (format, file) in [
+            (StoreFormat::Binary, "store.rvs"),
+            (StoreFormat::Json, "store.json"),
+        ] {
+            let path = dir.path().join(file);
+            store.save(&path, format, &metadata).unwrap();
+            assert_same(
+                &store,
+                &InMemoryVectorStore::load(&path, &metadata).unwrap(),
+            );
+        }
+
+        // The binary format is smaller
+        let size = |file: &str| std::fs::metadata(dir.path().join(file)).unwrap().len();
+        assert!(size("store.rvs") < size("store.json"));
+
+        // No temporary file is left behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_config_round_trip() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let metadata = StoreMetadata::new("text-embedding-3-small", 3);
+        let store = store()
+            .with_metric(DistanceMetric::DotProduct)
+            .with_normalized_embeddings()
+            .with_scoring(ScoringStrategy::WeightedByPosition(vec![0.7, 0.3]))
+            .with_quantization(QuantizationConfig::new(Quantization::Int8).rescore(2));
+        #[cfg(feature = "hnsw")]
+        let store = store.with_hnsw(HnswConfig::new().m(8));
+
+        for format in [StoreFormat::Binary, StoreFormat::Json] {
+            let path = dir.path().join("store");
+            store.save(&path, format, &metadata).unwrap();
+            let loaded = InMemoryVectorStore::<serde_json::Value>::load(&path, &metadata).unwrap();
+            assert_eq!(StoreConfig::of(&loaded), StoreConfig::of(&store));
+            assert_eq!(loaded.len(), store.len());
+        }
+    }
+
+    #[test]
+    fn test_concurrent_saves() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.path().join("store.rvs");
+        let metadata = StoreMetadata::new("model", 3);
+        let store = store();
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| store.save(&path, StoreFormat::Binary, &metadata).unwrap());
+            }
+        });
+
+        assert_same(
+            &store,
+            &InMemoryVectorStore::load(&path, &metadata).unwrap(),
+        );
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_metadata_is_checked() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.path().join("store.rvs");
+        store()
+            .save(
+                &path,
+                StoreFormat::Binary,
+                &StoreMetadata::new("text-embedding-3-small", 3),
+            )
+            .unwrap();
+
+        let result = InMemoryVectorStore::<serde_json::Value>::load(
+            &path,
+            &StoreMetadata::new("text-embedding-ada-002", 3),
+        );
+        assert!(matches!(
+            result,
+            Err(VectorStoreError::PersistenceError(
+                PersistenceError::MetadataMismatch { .. }
+            ))
+        ));
+
+        // Embeddings must have the dimensions of the metadata
+        let result = store().save(&path, StoreFormat::Json, &StoreMetadata::new("model", 1536));
+        assert!(matches!(
+            result,
+            Err(VectorStoreError::PersistenceError(
+                PersistenceError::DimensionsMismatch { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.path().join("store.rvs");
+        let metadata = StoreMetadata::new("model", 3);
+        store().save(&path, StoreFormat::Binary, &metadata).unwrap();
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&2u32.to_le_bytes());
+        std::fs::write(&path, bytes).unwrap();
+
+        assert!(matches!(
+            InMemoryVectorStore::<serde_json::Value>::load(&path, &metadata),
+            Err(VectorStoreError::PersistenceError(
+                PersistenceError::UnsupportedVersion(2)
+            ))
+        ));
+    }
+}
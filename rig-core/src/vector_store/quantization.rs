@@ -0,0 +1,335 @@
+//! Compact storage of the embeddings of the [InMemoryVectorStore].
+//!
+//! Embeddings are vectors of `f64`, while embedding models return `f32` values (so half of the
+//! memory of the store is wasted). A quantized store keeps its embeddings as:
+//! - [Quantization::F32]: `f32` values, half the memory with virtually the same scores.
+//! - [Quantization::Int8]: `i8` values scaled per embedding (scalar quantization), an eighth of
+//!   the memory.
+//! - [Quantization::Binary]: the sign of each value as a bit, 1/64th of the memory. Only suited
+//!   to models with many dimensions.
+//!
+//! Scores of quantized embeddings are approximate. With rescoring (see
+//! [QuantizationConfig::rescore]), the full precision embeddings are also kept: the quantized
+//! embeddings are used to select candidates quickly, which are then ranked with their full
+//! precision embeddings.
+//!
+//! Without rescoring, the vectors of the [Embedding]s of the store (e.g.: returned by
+//! [InMemoryVectorStore::iter]) are empty.
+//!
+//! # Example
+//! ```rust
+//! use rig::vector_store::{
+//!     in_memory_store::InMemoryVectorStore,
+//!     quantization::{Quantization, QuantizationConfig},
+//! };
+//!
+//! // Keep int8 embeddings, rescoring the 4 * n best candidates of each query
+//! let store = InMemoryVectorStore::from_documents(embeddings)
+//!     .with_quantization(QuantizationConfig::new(Quantization::Int8).rescore(4));
+//! ```
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::in_memory_store::InMemoryVectorStore;
+use crate::{
+    embeddings::{
+        distance::{kernels, DistanceMetric},
+        Embedding,
+    },
+    OneOrMany,
+};
+
+/// Storage of the embeddings of a quantized store
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Quantization {
+    /// 32 bits floats
+    F32,
+    /// 8 bits integers, scaled by the largest absolute value of each embedding
+    Int8,
+    /// 1 bit per dimension, set for positive values
+    Binary,
+}
+
+/// Parameters of the quantization of a store
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuantizationConfig {
+    pub quantization: Quantization,
+    /// When set, full precision embeddings are kept and the `n * rescore` best candidates
+    /// according to the quantized embeddings are rescored with them.
+    pub rescore: Option<usize>,
+}
+
+impl QuantizationConfig {
+    pub fn new(quantization: Quantization) -> Self {
+        Self {
+            quantization,
+            rescore: None,
+        }
+    }
+
+    /// Keep the full precision embeddings to rescore the `n * oversampling` best candidates
+    /// of a query for `n` results.
+    pub fn rescore(mut self, oversampling: usize) -> Self {
+        self.rescore = Some(oversampling.max(1));
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Codes {
+    F32(Vec<f32>),
+    Int8 { codes: Vec<i8>, scale: f32 },
+    Binary(Vec<u64>),
+}
+
+/// Quantized embedding, with the norm of the original vector
+#[derive(Clone, Debug)]
+pub(crate) struct QuantizedVector {
+    codes: Codes,
+    norm: f64,
+    ndims: usize,
+}
+
+impl QuantizedVector {
+    pub(crate) fn new(quantization: Quantization, vector: &[f64]) -> Self {
+        let codes = match quantization {
+            Quantization::F32 => Codes::F32(vector.iter().map(|&x| x as f32).collect()),
+            Quantization::Int8 => {
+                let max = vector.iter().fold(0.0, |max: f64, x| max.max(x.abs()));
+                let scale = if max > 0.0 { max / 127.0 } else { 1.0 };
+                Codes::Int8 {
+                    codes: vector.iter().map(|x| (x / scale).round() as i8).collect(),
+                    scale: scale as f32,
+                }
+            }
+            Quantization::Binary => {
+                let mut bits = vec![0u64; vector.len().div_ceil(64)];
+                for (i, _) in vector.iter().enumerate().filter(|(_, &x)| x > 0.0) {
+                    bits[i / 64] |= 1 << (i % 64);
+                }
+                Codes::Binary(bits)
+            }
+        };
+
+        Self {
+            codes,
+            norm: kernels::squared_norm(vector).sqrt(),
+            ndims: vector.len(),
+        }
+    }
+
+    /// Approximate cosine similarity with a `query` quantized the same way
+    fn similarity(&self, query: &QuantizedVector) -> f64 {
+        match (&self.codes, &query.codes) {
+            (Codes::F32(a), Codes::F32(b)) => kernels::dot(a, b) / (self.norm * query.norm),
+            (
+                Codes::Int8 {
+                    codes: a,
+                    scale: a_scale,
+                },
+                Codes::Int8 {
+                    codes: b,
+                    scale: b_scale,
+                },
+            ) => {
+                let dot = kernels::dot_i8(a, b) as f64 * *a_scale as f64 * *b_scale as f64;
+                dot / (self.norm * query.norm)
+            }
+            // The fraction of different signs approximates the angle between the vectors
+            (Codes::Binary(a), Codes::Binary(b)) => {
+                1.0 - 2.0 * kernels::hamming(a, b) as f64 / self.ndims.max(1) as f64
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Approximate score with `metric` of the embedding with the query `vector`, quantized as
+    /// `query`
+    pub(crate) fn score(
+        &self,
+        metric: DistanceMetric,
+        query: &QuantizedVector,
+        vector: &[f64],
+    ) -> f64 {
+        match (metric, &self.codes, &query.codes) {
+            (_, Codes::F32(a), Codes::F32(b)) => metric.score_of(metric.compute_slices(a, b)),
+            (DistanceMetric::Cosine, ..) => self.similarity(query),
+            (DistanceMetric::DotProduct, ..) => self.similarity(query) * self.norm * query.norm,
+            (DistanceMetric::Angular, ..) => metric
+                .score_of(self.similarity(query).clamp(-1.0, 1.0).acos() / std::f64::consts::PI),
+            // Distances are computed on the approximation of the embedding
+            _ => metric.score_of(metric.compute_slices(&self.dequantize(), vector)),
+        }
+    }
+
+    /// Approximation of the original vector
+    pub(crate) fn dequantize(&self) -> Vec<f64> {
+        match &self.codes {
+            Codes::F32(values) => values.iter().map(|&x| x as f64).collect(),
+            Codes::Int8 { codes, scale } => codes
+                .iter()
+                .map(|&code| code as f64 * *scale as f64)
+                .collect(),
+            Codes::Binary(bits) => {
+                // Unit signs scaled to the norm of the original vector
+                let value = self.norm / (self.ndims.max(1) as f64).sqrt();
+                (0..self.ndims)
+                    .map(|i| {
+                        if bits[i / 64] & (1 << (i % 64)) != 0 {
+                            value
+                        } else {
+                            -value
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Quantized embeddings of the documents of a store
+#[derive(Clone, Debug)]
+pub(crate) struct Quantized {
+    config: QuantizationConfig,
+    vectors: HashMap<String, Vec<QuantizedVector>>,
+}
+
+impl Quantized {
+    pub(crate) fn new(config: QuantizationConfig) -> Self {
+        Self {
+            config,
+            vectors: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn config(&self) -> &QuantizationConfig {
+        &self.config
+    }
+
+    /// Quantize the embeddings of a document, replacing its previous embeddings. The vectors
+    /// of the returned embeddings are emptied unless they are kept for rescoring.
+    pub(crate) fn insert(
+        &mut self,
+        id: &str,
+        mut embeddings: OneOrMany<Embedding>,
+    ) -> OneOrMany<Embedding> {
+        let vectors = embeddings
+            .iter()
+            .map(|embedding| QuantizedVector::new(self.config.quantization, &embedding.vec))
+            .collect();
+        self.vectors.insert(id.to_string(), vectors);
+
+        if self.config.rescore.is_none() {
+            for embedding in embeddings.iter_mut() {
+                embedding.vec = Vec::new();
+            }
+        }
+        embeddings
+    }
+
+    pub(crate) fn remove(&mut self, id: &str) {
+        self.vectors.remove(id);
+    }
+
+    /// Quantize a query, to compare it with the embeddings
+    pub(crate) fn query(&self, vector: &[f64]) -> QuantizedVector {
+        QuantizedVector::new(self.config.quantization, vector)
+    }
+
+    /// Approximate score with `metric` of the embedding at `position` of document `id` with
+    /// the query `vector`, quantized as `query`
+    pub(crate) fn score(
+        &self,
+        metric: DistanceMetric,
+        query: &QuantizedVector,
+        vector: &[f64],
+        id: &str,
+        position: usize,
+    ) -> Option<f64> {
+        Some(
+            self.vectors
+                .get(id)?
+                .get(position)?
+                .score(metric, query, vector),
+        )
+    }
+
+    /// Approximation of the embedding at `position` of document `id`
+    pub(crate) fn vector(&self, id: &str, position: usize) -> Option<Vec<f64>> {
+        Some(self.vectors.get(id)?.get(position)?.dequantize())
+    }
+}
+
+impl<D: Serialize> InMemoryVectorStore<D> {
+    /// Store the embeddings of the store (and the documents added to it later) with the given
+    /// quantization. Unless rescoring is enabled, full precision embeddings are dropped.
+    pub fn with_quantization(mut self, config: QuantizationConfig) -> Self {
+        self.dequantize_embeddings();
+        let mut quantized = Quantized::new(config);
+        for (id, (_, embeddings)) in self.embeddings.iter_mut() {
+            *embeddings = quantized.insert(id, embeddings.clone());
+        }
+
+        self.quantized = Some(quantized);
+        self
+    }
+
+    /// Remove the quantization of the store, approximating the vectors it dropped. Returns the
+    /// parameters of the removed quantization.
+    pub(crate) fn dequantize_embeddings(&mut self) -> Option<QuantizationConfig> {
+        let quantized = self.quantized.take()?;
+        for (id, (_, embeddings)) in self.embeddings.iter_mut() {
+            for (position, embedding) in embeddings.iter_mut().enumerate() {
+                if embedding.vec.is_empty() {
+                    embedding.vec = quantized.vector(id, position).unwrap_or_default();
+                }
+            }
+        }
+        Some(*quantized.config())
+    }
+
+    /// Parameters of the quantization of the store, if any
+    pub fn quantization_config(&self) -> Option<&QuantizationConfig> {
+        self.quantized.as_ref().map(Quantized::config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantized_similarity() {
+        let a = [0.3, -0.5, 0.8, 0.1, -0.2, 0.05, 0.6, -0.7];
+        let b = [0.25, -0.4, 0.9, -0.1, -0.3, 0.1, 0.5, -0.6];
+        let exact = kernels::dot(&a, &b)
+            / (kernels::squared_norm(&a).sqrt() * kernels::squared_norm(&b).sqrt());
+
+        for (quantization, tolerance) in [
+            (Quantization::F32, 1e-6),
+            (Quantization::Int8, 1e-2),
+            (Quantization::Binary, 0.3),
+        ] {
+            let a = QuantizedVector::new(quantization, &a);
+            let b = QuantizedVector::new(quantization, &b);
+            let similarity = a.similarity(&b);
+            assert!(
+                (similarity - exact).abs() < tolerance,
+                "{quantization:?}: {similarity} != {exact}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_dequantize() {
+        let vector = [0.3, -0.5, 0.8, 0.0];
+
+        let int8 = QuantizedVector::new(Quantization::Int8, &vector).dequantize();
+        assert!(int8.iter().zip(&vector).all(|(x, y)| (x - y).abs() < 0.01));
+
+        let binary = QuantizedVector::new(Quantization::Binary, &vector).dequantize();
+        assert!(binary.iter().take(3).zip(&vector).all(|(x, y)| x * y > 0.0));
+        assert!((kernels::squared_norm(&binary) - kernels::squared_norm(&vector)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,360 @@
+//! Query transformation strategies for retrieval augmented generation.
+//!
+//! A user prompt is often a poor search query: it may be phrased differently from the documents
+//! answering it, or be too specific to match any of them. A [QueryTransformIndex] wraps a
+//! [VectorStoreIndex] and uses a [CompletionModel] to rewrite the prompt before searching the
+//! index (see [QueryTransform]). The results of all the rewritten queries are merged and
+//! deduplicated by id, keeping the best score of each document.
+//!
+//! Since [QueryTransformIndex] implements [VectorStoreIndex], it can be used as the dynamic
+//! context of an agent or in a pipeline `lookup` op.
+//!
+//! # Example
+//! ```rust
+//! use rig::{providers::openai, vector_store::query_transform::QueryTransformIndex};
+//!
+//! let openai = openai::Client::from_env();
+//! let model = openai.completion_model(openai::GPT_4O_MINI);
+//!
+//! // Search the index with the prompt and 3 paraphrases of it
+//! let index = QueryTransformIndex::multi_query(model, index, 3);
+//!
+//! let agent = openai.agent(openai::GPT_4O)
+//!     .dynamic_context(4, index)
+//!     .build();
+//! ```
+use std::collections::HashMap;
+
+use futures::future::try_join_all;
+use serde::{Deserialize, Serialize};
+
+use super::{VectorStoreError, VectorStoreIndex};
+use crate::completion::{citation::answer_text, CompletionModel};
+
+/// Instructions of the [QueryTransform::MultiQuery] strategy. `{n}` is replaced by the number
+/// of queries to generate.
+pub const MULTI_QUERY_PREAMBLE: &str = "\
+    You are helping to search a knowledge base. Rewrite the user's question into {n} different \
+    search queries covering different phrasings and aspects of the question. Write one query \
+    per line, without numbering or any other text.";
+
+/// Instructions of the [QueryTransform::Hyde] strategy
+pub const HYDE_PREAMBLE: &str = "\
+    Write a short passage (a few sentences) that answers the user's question, as it would \
+    appear in a reference document. Do not mention that the passage is hypothetical.";
+
+/// Instructions of the [QueryTransform::StepBack] strategy
+pub const STEP_BACK_PREAMBLE: &str = "\
+    Rewrite the user's question into a more generic step-back question about the underlying \
+    concepts or background needed to answer it. Respond only with the step-back question.";
+
+/// Strategy used by a [QueryTransformIndex] to rewrite the query
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueryTransform {
+    /// Paraphrase the query into `n` alternative queries
+    MultiQuery { n: usize },
+    /// Hypothetical document embeddings: search with a hypothetical answer to the query,
+    /// which is usually closer to the answering documents than the question itself
+    Hyde,
+    /// Abstract the query into a more generic step-back question
+    StepBack,
+}
+
+impl QueryTransform {
+    fn default_preamble(&self) -> String {
+        match self {
+            Self::MultiQuery { n } => MULTI_QUERY_PREAMBLE.replace("{n}", &n.to_string()),
+            Self::Hyde => HYDE_PREAMBLE.to_string(),
+            Self::StepBack => STEP_BACK_PREAMBLE.to_string(),
+        }
+    }
+}
+
+/// Index rewriting the query with a completion model before searching the wrapped index.
+pub struct QueryTransformIndex<M: CompletionModel, I: VectorStoreIndex> {
+    model: M,
+    index: I,
+    transform: QueryTransform,
+    preamble: String,
+    include_original: bool,
+}
+
+impl<M: CompletionModel, I: VectorStoreIndex> QueryTransformIndex<M, I> {
+    /// Create a new [QueryTransformIndex]. The original query is also searched, except for
+    /// the [QueryTransform::Hyde] strategy.
+    pub fn new(model: M, index: I, transform: QueryTransform) -> Self {
+        Self {
+            model,
+            index,
+            preamble: transform.default_preamble(),
+            include_original: transform != QueryTransform::Hyde,
+            transform,
+        }
+    }
+
+    /// Search the index with the query and `n` paraphrases of it
+    pub fn multi_query(model: M, index: I, n: usize) -> Self {
+        Self::new(model, index, QueryTransform::MultiQuery { n })
+    }
+
+    /// Search the index with a hypothetical answer to the query
+    pub fn hyde(model: M, index: I) -> Self {
+        Self::new(model, index, QueryTransform::Hyde)
+    }
+
+    /// Search the index with the query and a step-back question abstracting it
+    pub fn step_back(model: M, index: I) -> Self {
+        Self::new(model, index, QueryTransform::StepBack)
+    }
+
+    /// Override the instructions given to the model to rewrite the query
+    pub fn preamble(mut self, preamble: &str) -> Self {
+        self.preamble = preamble.to_string();
+        self
+    }
+
+    /// Set whether the original query is searched along with the rewritten ones
+    pub fn include_original(mut self, include_original: bool) -> Self {
+        self.include_original = include_original;
+        self
+    }
+
+    /// Rewrite `query` according to the strategy of the index
+    pub async fn queries(&self, query: &str) -> Result<Vec<String>, VectorStoreError> {
+        let response = self
+            .model
+            .completion_request(query)
+            .preamble(self.preamble.clone())
+            .send()
+            .await?;
+        let text = answer_text(&response.choice);
+
+        let rewritten = match self.transform {
+            QueryTransform::MultiQuery { n } => text
+                .lines()
+                .map(|line| strip_list_marker(line).trim().to_string())
+                .filter(|line| !line.is_empty())
+                .take(n)
+                .collect::<Vec<_>>(),
+            QueryTransform::Hyde | QueryTransform::StepBack => {
+                vec![text.trim().to_string()]
+            }
+        };
+
+        let mut queries = vec![];
+        if self.include_original {
+            queries.push(query.to_string());
+        }
+        for rewritten in rewritten {
+            if !rewritten.is_empty() && !queries.contains(&rewritten) {
+                queries.push(rewritten);
+            }
+        }
+        tracing::debug!(target: "rig", "Rewritten queries: {:?}", queries);
+
+        Ok(queries)
+    }
+}
+
+/// Strip the list marker (e.g.: `1.`, `2)`, `-` or `*`, followed by whitespace) at the start of
+/// `line`, if any.
+fn strip_list_marker(line: &str) -> &str {
+    let trimmed = line.trim_start();
+    let digits = trimmed.len()
+        - trimmed
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    let rest = match trimmed[digits..].chars().next() {
+        Some('.' | ')') if digits > 0 => &trimmed[digits + 1..],
+        Some('-' | '*') if digits == 0 => &trimmed[1..],
+        _ => return line,
+    };
+
+    match rest.strip_prefix(char::is_whitespace) {
+        Some(rest) => rest,
+        None => line,
+    }
+}
+
+/// Merge the results of several queries, keeping the best score of each id, sorted by
+/// decreasing score.
+fn merge<T>(results: Vec<Vec<(f64, String, T)>>, n: usize) -> Vec<(f64, String, T)> {
+    let mut merged: HashMap<String, (f64, T)> = HashMap::new();
+    for (score, id, doc) in results.into_iter().flatten() {
+        match merged.get(&id) {
+            Some((best, _)) if *best >= score => {}
+            _ => {
+                merged.insert(id, (score, doc));
+            }
+        }
+    }
+
+    let mut merged = merged
+        .into_iter()
+        .map(|(id, (score, doc))| (score, id, doc))
+        .collect::<Vec<_>>();
+    merged.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    merged.truncate(n);
+    merged
+}
+
+impl<M: CompletionModel, I: VectorStoreIndex> VectorStoreIndex for QueryTransformIndex<M, I> {
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        let queries = self.queries(query).await?;
+        let results =
+            try_join_all(queries.iter().map(|query| self.index.top_n::<T>(query, n))).await?;
+
+        Ok(merge(results, n))
+    }
+
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        let queries = self.queries(query).await?;
+        let results = try_join_all(queries.iter().map(|query| self.index.top_n_ids(query, n)))
+            .await?
+            .into_iter()
+            .map(|results| {
+                results
+                    .into_iter()
+                    .map(|(score, id)| (score, id, ()))
+                    .collect()
+            })
+            .collect();
+
+        Ok(merge(results, n)
+            .into_iter()
+            .map(|(score, id, _)| (score, id))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        completion::{CompletionError, CompletionRequest, CompletionResponse},
+        message::AssistantContent,
+        OneOrMany,
+    };
+
+    use super::*;
+
+    /// Completion model answering with a fixed text
+    #[derive(Clone)]
+    struct FixedModel(&'static str);
+
+    impl CompletionModel for FixedModel {
+        type Response = ();
+
+        async fn completion(
+            &self,
+            _request: CompletionRequest,
+        ) -> Result<CompletionResponse<()>, CompletionError> {
+            Ok(CompletionResponse {
+                choice: OneOrMany::one(AssistantContent::text(self.0)),
+                raw_response: (),
+            })
+        }
+    }
+
+    /// Index returning one document per word of the query, scored by the word length
+    struct WordIndex;
+
+    impl VectorStoreIndex for WordIndex {
+        async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+            &self,
+            query: &str,
+            n: usize,
+        ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+            self.top_n_ids(query, n)
+                .await?
+                .into_iter()
+                .map(|(score, id)| {
+                    Ok((
+                        score,
+                        id.clone(),
+                        serde_json::from_value(serde_json::json!(id))?,
+                    ))
+                })
+                .collect()
+        }
+
+        async fn top_n_ids(
+            &self,
+            query: &str,
+            n: usize,
+        ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+            Ok(query
+                .split_whitespace()
+                .take(n)
+                .map(|word| (word.len() as f64, word.to_lowercase()))
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multi_query() {
+        let index = QueryTransformIndex::multi_query(
+            FixedModel("1. sol price\n2. solana token value\n\n3. ignored"),
+            WordIndex,
+            2,
+        );
+
+        assert_eq!(
+            index.queries("SOL price?").await.unwrap(),
+            vec!["SOL price?", "sol price", "solana token value"]
+        );
+
+        let results = index.top_n::<String>("SOL price?", 3).await.unwrap();
+        assert_eq!(
+            results
+                .iter()
+                .map(|(score, id, _)| (*score, id.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(6.0, "price?"), (6.0, "solana"), (5.0, "price")]
+        );
+    }
+
+    #[test]
+    fn test_strip_list_marker() {
+        assert_eq!(strip_list_marker("1. sol price"), "sol price");
+        assert_eq!(strip_list_marker("  12) sol price"), "sol price");
+        assert_eq!(strip_list_marker("- sol price"), "sol price");
+        assert_eq!(strip_list_marker("* sol price"), "sol price");
+
+        // Content starting with digits or dashes is kept
+        assert_eq!(strip_list_marker("2024 SOL outlook"), "2024 SOL outlook");
+        assert_eq!(strip_list_marker("3.5% APY staking"), "3.5% APY staking");
+        assert_eq!(strip_list_marker("-5% SOL drop"), "-5% SOL drop");
+        assert_eq!(strip_list_marker("1. 2024 SOL outlook"), "2024 SOL outlook");
+    }
+
+    #[tokio::test]
+    async fn test_hyde_replaces_query() {
+        let index =
+            QueryTransformIndex::hyde(FixedModel(" SOL rallied 12% this week. "), WordIndex);
+
+        assert_eq!(
+            index.queries("How did SOL do?").await.unwrap(),
+            vec!["SOL rallied 12% this week."]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_step_back() {
+        let index = QueryTransformIndex::step_back(
+            FixedModel("What drives Solana token prices?"),
+            WordIndex,
+        );
+
+        let ids = index.top_n_ids("Why is SOL up", 10).await.unwrap();
+        assert_eq!(ids.len(), 9);
+        assert_eq!(ids[0], (7.0, "prices?".to_string()));
+    }
+}
@@ -0,0 +1,162 @@
+//! Scoring of documents with several embeddings.
+//!
+//! Documents with several fields tagged with `#[embed]` (or fields embedding several texts) are
+//! stored with several embeddings by the [EmbeddingsBuilder](crate::embeddings::EmbeddingsBuilder).
+//! The [ScoringStrategy] of an [InMemoryVectorStore] aggregates the scores of the embeddings of a
+//! document with a query into the score of the document.
+//!
+//! The embeddings of a document are in the order the [Embed](crate::Embed) implementation of the
+//! document embeds its texts: when deriving [Embed](crate::Embed), fields tagged with `#[embed]`
+//! in the order of declaration, followed by the fields tagged with `#[embed(embed_with = "...")]`.
+//!
+//! Embeddings do not record the field they were generated from, so strategies and matches
+//! refer to embeddings by their position among the embeddings of the document
+//! ([ScoringStrategy::WeightedByPosition], [EmbeddingMatch::position]). Positions only
+//! correspond to fields when each embedded field embeds exactly one text: a field embedding
+//! several texts (e.g.: a `Vec<String>`) or none (e.g.: an empty `Vec`) shifts the positions of
+//! the embeddings of the following fields.
+//!
+//! # Example
+//! ```rust
+//! use rig::vector_store::{in_memory_store::InMemoryVectorStore, scoring::ScoringStrategy};
+//!
+//! #[derive(Embed, Serialize, Clone, Eq, PartialEq)]
+//! struct TokenAnalysis {
+//!     id: String,
+//!     #[embed]
+//!     description: String,
+//!     #[embed]
+//!     sentiment: String,
+//! }
+//!
+//! // Rank the analyses by their description first
+//! let index = InMemoryVectorStore::from_documents(embeddings)
+//!     .with_scoring(ScoringStrategy::WeightedByPosition(vec![0.7, 0.3]))
+//!     .index(model);
+//!
+//! for (score, id, analysis, matched) in index
+//!     .top_n_with_matches::<TokenAnalysis>("bullish on liquidity growth", 5)
+//!     .await?
+//! {
+//!     // `matched.position` is 0 when the description matched best, 1 for the sentiment
+//!     println!("{id} ({score}): matched \"{}\"", matched.text);
+//! }
+//! ```
+use serde::{Deserialize, Serialize};
+
+use super::in_memory_store::InMemoryVectorStore;
+
+/// Aggregation of the scores of the embeddings of a document with a query
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ScoringStrategy {
+    /// Score of the best matching embedding
+    #[default]
+    Max,
+    /// Mean of the scores of the embeddings
+    Mean,
+    /// Sum of the scores of the `k` best matching embeddings, favoring documents matching the
+    /// query with several embeddings
+    TopK(usize),
+    /// Sum of the scores of the embeddings weighted by their position among the embeddings of
+    /// the document (see the [module documentation](self) for the order of the embeddings).
+    /// Embeddings without a weight are ignored.
+    WeightedByPosition(Vec<f64>),
+}
+
+impl ScoringStrategy {
+    /// Weight of the embedding at `position`, if the embedding is scored
+    fn weight(&self, position: usize) -> Option<f64> {
+        match self {
+            ScoringStrategy::WeightedByPosition(weights) => weights
+                .get(position)
+                .copied()
+                .filter(|&weight| weight != 0.0),
+            _ => Some(1.0),
+        }
+    }
+
+    /// Aggregate the `scores` of the embeddings of a document, in the order of the embeddings.
+    /// Returns the score of the document and the position of its best matching embedding, if
+    /// any embedding is scored.
+    pub(crate) fn aggregate(&self, scores: impl IntoIterator<Item = f64>) -> Option<(f64, usize)> {
+        let mut scores = scores
+            .into_iter()
+            .enumerate()
+            .filter_map(|(position, score)| Some((score, self.weight(position)?, position)))
+            .collect::<Vec<_>>();
+        let (best, _, position) = scores.iter().copied().max_by(|a, b| a.0.total_cmp(&b.0))?;
+
+        let score = match self {
+            ScoringStrategy::Max => best,
+            ScoringStrategy::Mean => {
+                scores.iter().map(|(score, ..)| score).sum::<f64>() / scores.len() as f64
+            }
+            ScoringStrategy::TopK(k) => {
+                scores.sort_by(|a, b| b.0.total_cmp(&a.0));
+                scores.iter().take(*k).map(|(score, ..)| score).sum()
+            }
+            ScoringStrategy::WeightedByPosition(_) => {
+                scores.iter().map(|(score, weight, _)| score * weight).sum()
+            }
+        };
+        Some((score, position))
+    }
+}
+
+/// Embedding of a document best matching a query
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmbeddingMatch {
+    /// Position of the embedding in the embeddings of the document
+    pub position: usize,
+    /// Text the embedding was generated from
+    pub text: String,
+    /// Score of the embedding with the query
+    pub score: f64,
+}
+
+impl<D: Serialize> InMemoryVectorStore<D> {
+    /// Score the documents with several embeddings with the given `strategy`
+    /// ([ScoringStrategy::Max] by default).
+    pub fn with_scoring(mut self, strategy: ScoringStrategy) -> Self {
+        self.scoring = strategy;
+        self
+    }
+
+    pub fn scoring(&self) -> &ScoringStrategy {
+        &self.scoring
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate() {
+        let scores = [0.2, 0.8, 0.5];
+
+        assert_eq!(ScoringStrategy::Max.aggregate(scores), Some((0.8, 1)));
+
+        let (sum, position) = ScoringStrategy::TopK(2).aggregate(scores).unwrap();
+        assert!((sum - 1.3).abs() < 1e-9);
+        assert_eq!(position, 1);
+        let (sum, _) = ScoringStrategy::TopK(5).aggregate(scores).unwrap();
+        assert!((sum - 1.5).abs() < 1e-9);
+
+        let (mean, position) = ScoringStrategy::Mean.aggregate(scores).unwrap();
+        assert!((mean - 0.5).abs() < 1e-9);
+        assert_eq!(position, 1);
+
+        // The best match is among the weighted embeddings only
+        let weighted = ScoringStrategy::WeightedByPosition(vec![0.5, 0.0, 0.5]);
+        let (score, position) = weighted.aggregate(scores).unwrap();
+        assert!((score - 0.35).abs() < 1e-9);
+        assert_eq!(position, 2);
+
+        assert_eq!(
+            ScoringStrategy::WeightedByPosition(vec![]).aggregate(scores),
+            None
+        );
+        assert_eq!(ScoringStrategy::Max.aggregate([]), None);
+    }
+}
@@ -0,0 +1,256 @@
+//! Options refining the results of a vector search, see [SearchOptions].
+use serde::{Deserialize, Serialize};
+
+use super::filter::Filter;
+
+/// Options applied to the results of [VectorStoreIndex::top_n_with_options](super::VectorStoreIndex::top_n_with_options).
+///
+/// # Example
+/// ```rust
+/// use rig::vector_store::{filter::Filter, Mmr, SearchOptions, VectorStoreIndex};
+///
+/// // Keep the 5 most relevant and diverse Solana documents with a score of at least 0.75
+/// let options = SearchOptions::new()
+///     .min_score(0.75)
+///     .mmr(Mmr::new(0.5).fetch_k(20))
+///     .filter(Filter::eq("chain", "solana"));
+///
+/// let results = index
+///     .top_n_with_options::<Document>("SOL token analysis", 5, &options)
+///     .await?;
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchOptions {
+    /// Minimum score of the returned documents. The scale of the score depends on the
+    /// vector store (e.g.: the score of its [DistanceMetric](crate::embeddings::distance::DistanceMetric)
+    /// for the in-memory store).
+    pub min_score: Option<f64>,
+    /// Diversify the returned documents using maximal marginal relevance
+    pub mmr: Option<Mmr>,
+    /// Only return documents matching the filter
+    pub filter: Option<Filter>,
+}
+
+impl SearchOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only return documents with a score greater than or equal to `min_score`
+    pub fn min_score(mut self, min_score: f64) -> Self {
+        self.min_score = Some(min_score);
+        self
+    }
+
+    /// Diversify the returned documents using maximal marginal relevance
+    pub fn mmr(mut self, mmr: Mmr) -> Self {
+        self.mmr = Some(mmr);
+        self
+    }
+
+    /// Only return documents matching `filter`
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Whether a result with score `score` passes the minimum score cutoff
+    pub fn accepts(&self, score: f64) -> bool {
+        self.min_score.is_none_or(|min_score| score >= min_score)
+    }
+
+    /// Number of candidates to fetch from the vector store to return `n` results
+    pub fn candidates(&self, n: usize) -> usize {
+        match &self.mmr {
+            Some(mmr) => mmr.candidates(n),
+            None => n,
+        }
+    }
+}
+
+/// Maximal marginal relevance (MMR) parameters.
+///
+/// MMR fetches more candidates than needed and iteratively selects the candidate maximizing
+/// `lambda * similarity(query, candidate) - (1 - lambda) * max(similarity(candidate, selected))`,
+/// so that near-duplicates of already selected documents are skipped.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Mmr {
+    /// Trade-off between relevance (1.0) and diversity (0.0)
+    pub lambda: f64,
+    /// Number of candidates to select from (defaults to 4 times the number of results)
+    pub fetch_k: Option<usize>,
+}
+
+impl Mmr {
+    pub fn new(lambda: f64) -> Self {
+        Self {
+            lambda,
+            fetch_k: None,
+        }
+    }
+
+    /// Set the number of candidates to select from
+    pub fn fetch_k(mut self, fetch_k: usize) -> Self {
+        self.fetch_k = Some(fetch_k);
+        self
+    }
+
+    /// Number of candidates to fetch to return `n` results
+    pub fn candidates(&self, n: usize) -> usize {
+        self.fetch_k.unwrap_or(n * 4).max(n)
+    }
+
+    /// Select `n` of the `candidates` embeddings for the `query` embedding, returning their
+    /// indices in order of selection.
+    pub fn select(&self, query: &[f64], candidates: &[&[f64]], n: usize) -> Vec<usize> {
+        let relevance = candidates
+            .iter()
+            .map(|candidate| cosine_similarity(query, candidate))
+            .collect::<Vec<_>>();
+
+        let mut selected: Vec<usize> = Vec::with_capacity(n.min(candidates.len()));
+        // Highest similarity of each candidate to the selected candidates
+        let mut redundancy = vec![f64::NEG_INFINITY; candidates.len()];
+
+        while selected.len() < n.min(candidates.len()) {
+            let Some(best) = (0..candidates.len())
+                .filter(|i| !selected.contains(i))
+                .max_by(|&a, &b| {
+                    self.score(relevance[a], redundancy[a])
+                        .total_cmp(&self.score(relevance[b], redundancy[b]))
+                        // Prefer the first (i.e.: best ranked) candidate on ties
+                        .then(b.cmp(&a))
+                })
+            else {
+                break;
+            };
+
+            selected.push(best);
+            for (i, candidate) in candidates.iter().enumerate() {
+                redundancy[i] = redundancy[i].max(cosine_similarity(candidates[best], candidate));
+            }
+        }
+
+        selected
+    }
+
+    fn score(&self, relevance: f64, redundancy: f64) -> f64 {
+        if redundancy == f64::NEG_INFINITY {
+            relevance
+        } else {
+            self.lambda * relevance - (1.0 - self.lambda) * redundancy
+        }
+    }
+}
+
+fn cosine_similarity(a: &[f64], b: &[f64]) -> f64 {
+    let dot = a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>();
+    let norm = |v: &[f64]| v.iter().map(|x| x * x).sum::<f64>().sqrt();
+
+    let magnitude = norm(a) * norm(b);
+    if magnitude == 0.0 {
+        0.0
+    } else {
+        dot / magnitude
+    }
+}
+
+/// Post-process the `candidates` of a vector search (sorted by decreasing score, along with
+/// their embeddings) by applying `options`, returning at most `n` results. Used by vector stores
+/// that cannot apply the options natively.
+pub fn apply_options<T>(
+    query: &[f64],
+    candidates: Vec<(f64, String, T, Vec<f64>)>,
+    n: usize,
+    options: &SearchOptions,
+) -> Vec<(f64, String, T)> {
+    let candidates = candidates
+        .into_iter()
+        .filter(|(score, ..)| options.accepts(*score))
+        .collect::<Vec<_>>();
+
+    let Some(mmr) = &options.mmr else {
+        return candidates
+            .into_iter()
+            .take(n)
+            .map(|(score, id, doc, _)| (score, id, doc))
+            .collect();
+    };
+
+    let embeddings = candidates
+        .iter()
+        .map(|(.., embedding)| embedding.as_slice())
+        .collect::<Vec<_>>();
+    let selection = mmr.select(query, &embeddings, n);
+
+    let mut candidates = candidates.into_iter().map(Some).collect::<Vec<_>>();
+    selection
+        .into_iter()
+        .filter_map(|i| candidates[i].take())
+        .map(|(score, id, doc, _)| (score, id, doc))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates() -> Vec<(f64, String, &'static str, Vec<f64>)> {
+        vec![
+            (0.99, "day1".to_string(), "summary 1", vec![1.0, 0.1, 0.0]),
+            (0.98, "day2".to_string(), "summary 2", vec![1.0, 0.11, 0.0]),
+            (0.97, "day3".to_string(), "summary 3", vec![1.0, 0.12, 0.0]),
+            (0.80, "news".to_string(), "news", vec![0.6, 0.0, 0.8]),
+        ]
+    }
+
+    #[test]
+    fn test_min_score() {
+        let results = apply_options(
+            &[1.0, 0.0, 0.0],
+            candidates(),
+            5,
+            &SearchOptions::new().min_score(0.9),
+        );
+
+        assert_eq!(
+            results
+                .iter()
+                .map(|(_, id, _)| id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["day1", "day2", "day3"]
+        );
+    }
+
+    #[test]
+    fn test_mmr_skips_near_duplicates() {
+        let results = apply_options(
+            &[1.0, 0.0, 0.0],
+            candidates(),
+            2,
+            &SearchOptions::new().mmr(Mmr::new(0.5)),
+        );
+
+        assert_eq!(
+            results
+                .iter()
+                .map(|(_, id, _)| id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["day1", "news"]
+        );
+    }
+
+    #[test]
+    fn test_mmr_with_lambda_one_is_relevance() {
+        let embeddings = candidates()
+            .into_iter()
+            .map(|(.., embedding)| embedding)
+            .collect::<Vec<_>>();
+        let embeddings = embeddings.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+        assert_eq!(
+            Mmr::new(1.0).select(&[1.0, 0.0, 0.0], &embeddings, 3),
+            vec![0, 1, 2]
+        );
+    }
+}
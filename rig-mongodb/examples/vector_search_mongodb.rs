@@ -1,20 +1,19 @@
-use mongodb::{
-    bson::{self, doc},
-    options::ClientOptions,
-    Client as MongoClient, Collection,
-};
+use mongodb::{bson, options::ClientOptions, Client as MongoClient, Collection};
 use rig::providers::openai::TEXT_EMBEDDING_ADA_002;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::env;
 
 use rig::{
-    embeddings::EmbeddingsBuilder, providers::openai::Client, vector_store::VectorStoreIndex, Embed,
+    embeddings::EmbeddingsBuilder,
+    providers::openai::Client,
+    vector_store::{VectorStoreIndex, WritableVectorStore},
+    Embed,
 };
 use rig_mongodb::{MongoDbVectorIndex, SearchParams};
 
 // Shape of data that needs to be RAG'ed.
 // The definition field will be used to generate embeddings.
-#[derive(Embed, Clone, Deserialize, Debug)]
+#[derive(Embed, Clone, Serialize, Deserialize, Debug)]
 struct Word {
     #[serde(rename = "_id")]
     id: String,
@@ -66,28 +65,18 @@ async fn main() -> Result<(), anyhow::Error> {
         .build()
         .await?;
 
-    let mongo_documents = embeddings
-        .iter()
-        .map(|(Word { id, definition, .. }, embedding)| {
-            doc! {
-                "id": id.clone(),
-                "definition": definition.clone(),
-                "embedding": embedding.first().vec.clone(),
-            }
-        })
-        .collect::<Vec<_>>();
-
-    match collection.insert_many(mongo_documents).await {
-        Ok(_) => println!("Documents added successfully"),
-        Err(e) => println!("Error adding documents: {:?}", e),
-    };
-
     // Create a vector index on our vector store.
     // Note: a vector index called "vector_index" must exist on the MongoDB collection you are querying.
     // IMPORTANT: Reuse the same model that was used to generate the embeddings
-    let index =
+    let mut index =
         MongoDbVectorIndex::new(collection, model, "vector_index", SearchParams::new()).await?;
 
+    // Add the documents and their embeddings to the collection (replacing the documents with the same id)
+    index
+        .upsert_documents(embeddings, |word| word.id.clone())
+        .await?;
+    println!("Documents added successfully");
+
     // Query the index
     let results = index.top_n::<Word>("What is a linglingdong?", 1).await?;
 
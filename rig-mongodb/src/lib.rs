@@ -3,7 +3,12 @@ use mongodb::bson::{self, doc};
 
 use rig::{
     embeddings::embedding::{Embedding, EmbeddingModel},
-    vector_store::{VectorStoreError, VectorStoreIndex},
+    telemetry,
+    vector_store::{
+        check_query_dimensions, filter::Filter, search, SearchOptions, VectorSearchIndex,
+        VectorStoreError, VectorStoreIndex, WritableVectorStore,
+    },
+    OneOrMany,
 };
 use serde::{Deserialize, Serialize};
 
@@ -57,6 +62,224 @@ fn mongodb_to_rig_error(e: mongodb::error::Error) -> VectorStoreError {
     VectorStoreError::DatastoreError(Box::new(e))
 }
 
+/// Get the `score` and `_id` fields of a document returned by a search pipeline.
+fn score_and_id(doc: &serde_json::Value) -> Result<(f64, String), VectorStoreError> {
+    let score = doc
+        .get("score")
+        .and_then(serde_json::Value::as_f64)
+        .ok_or_else(|| {
+            VectorStoreError::DatastoreError("Missing or invalid `score` field".into())
+        })?;
+    let id = doc
+        .get("_id")
+        .ok_or_else(|| VectorStoreError::DatastoreError("Missing `_id` field".into()))?
+        .to_string();
+    Ok((score, id))
+}
+
+/// Remove the embedding at `path` (e.g.: `"embedding"` or `"chunk.embedding"`) from `doc`.
+fn take_embedding(doc: &mut serde_json::Value, path: &str) -> Result<Vec<f64>, VectorStoreError> {
+    let (parents, field) = match path.rsplit_once('.') {
+        Some((parents, field)) => (Some(parents), field),
+        None => (None, path),
+    };
+
+    let parent = parents
+        .into_iter()
+        .flat_map(|parents| parents.split('.'))
+        .try_fold(&mut *doc, |doc, key| doc.get_mut(key));
+
+    let embedding = parent
+        .and_then(|parent| parent.as_object_mut())
+        .and_then(|parent| parent.remove(field))
+        .ok_or_else(|| {
+            VectorStoreError::DatastoreError(format!("Missing embedding field: {path}").into())
+        })?;
+
+    Ok(serde_json::from_value(embedding)?)
+}
+
+/// Set the embedding at `path` (e.g.: `"embedding"` or `"chunk.embedding"`) of `doc`, creating
+/// the parent objects if needed.
+fn put_embedding(
+    doc: &mut serde_json::Value,
+    path: &str,
+    embedding: Vec<f64>,
+) -> Result<(), VectorStoreError> {
+    let mut parent = doc;
+    let mut keys = path.split('.').peekable();
+    while let Some(key) = keys.next() {
+        let object = parent.as_object_mut().ok_or_else(|| {
+            VectorStoreError::DatastoreError(
+                format!("Cannot set embedding field {path}: not an object").into(),
+            )
+        })?;
+        if keys.peek().is_none() {
+            object.insert(key.to_string(), embedding.into());
+            return Ok(());
+        }
+        parent = object
+            .entry(key)
+            .or_insert_with(|| serde_json::Value::Object(Default::default()));
+    }
+
+    Ok(())
+}
+
+/// Translate a [Filter] to a MongoDB query document, used as the pre-filter of the
+/// `$vectorSearch` stage. The filtered fields must be indexed with the `filter` type in the
+/// vector search index.
+///
+/// Negations are pushed down to the comparisons (e.g.: `$in` becomes `$nin`), so that the
+/// resulting document only uses operators supported by the `$vectorSearch` pre-filter.
+///
+/// Filters always matching (e.g.: an empty [Filter::And]) are translated to the empty document
+/// `{}`, and filters never matching (e.g.: an empty [Filter::Or]) to `{ "$nor": [{}] }`.
+pub fn filter_to_bson(filter: &Filter) -> Result<bson::Document, VectorStoreError> {
+    Ok(match filter_to_query(filter)? {
+        Clause::Always(true) => doc! {},
+        Clause::Always(false) => doc! { "$nor": [{}] },
+        Clause::Operator(query) => query,
+    })
+}
+
+/// Translate a [Filter] to a MongoDB query document (see [filter_to_bson]), or whether the
+/// filter always matches or never does.
+fn filter_to_query(filter: &Filter) -> Result<Clause, VectorStoreError> {
+    fn value(value: &serde_json::Value) -> Result<bson::Bson, VectorStoreError> {
+        bson::to_bson(value).map_err(|e| VectorStoreError::DatastoreError(Box::new(e)))
+    }
+
+    fn comparison(field: &str, operator: &str, value: bson::Bson, negate: bool) -> bson::Document {
+        if negate {
+            doc! { field: { "$not": { operator: value } } }
+        } else {
+            doc! { field: { operator: value } }
+        }
+    }
+
+    fn translate(filter: &Filter, negate: bool) -> Result<Clause, VectorStoreError> {
+        Ok(Clause::Operator(match filter {
+            Filter::Eq { field, value: v } => {
+                let operator = if negate { "$ne" } else { "$eq" };
+                doc! { field: { operator: value(v)? } }
+            }
+            Filter::Ne { field, value: v } => {
+                let operator = if negate { "$eq" } else { "$ne" };
+                doc! { field: { operator: value(v)? } }
+            }
+            Filter::Gt { field, value: v } => comparison(field, "$gt", value(v)?, negate),
+            Filter::Gte { field, value: v } => comparison(field, "$gte", value(v)?, negate),
+            Filter::Lt { field, value: v } => comparison(field, "$lt", value(v)?, negate),
+            Filter::Lte { field, value: v } => comparison(field, "$lte", value(v)?, negate),
+            Filter::In { field, values } => {
+                let operator = if negate { "$nin" } else { "$in" };
+                let values = values.iter().map(value).collect::<Result<Vec<_>, _>>()?;
+                doc! { field: { operator: values } }
+            }
+            Filter::And(filters) | Filter::Or(filters) => {
+                // De Morgan's laws: negating a conjunction gives a disjunction of the negations
+                let conjunction = matches!(
+                    (filter, negate),
+                    (Filter::And(_), false) | (Filter::Or(_), true)
+                );
+                // Clauses always matching (resp. never matching) are dropped from conjunctions
+                // (resp. disjunctions), and decide the result of disjunctions (resp. conjunctions)
+                let mut operators = Vec::new();
+                for filter in filters {
+                    match translate(filter, negate)? {
+                        Clause::Always(matches) if matches == conjunction => (),
+                        Clause::Always(matches) => return Ok(Clause::Always(matches)),
+                        Clause::Operator(operator) => operators.push(operator),
+                    }
+                }
+                match operators.len() {
+                    0 => return Ok(Clause::Always(conjunction)),
+                    1 => operators.remove(0),
+                    _ if conjunction => doc! { "$and": operators },
+                    _ => doc! { "$or": operators },
+                }
+            }
+            Filter::Not(filter) => return translate(filter, !negate),
+        }))
+    }
+
+    translate(filter, false)
+}
+
+/// A MongoDB query document or an [Atlas Search operator](https://www.mongodb.com/docs/atlas/atlas-search/operators-and-collectors/)
+/// translated from a [Filter], or whether the filter always matches (e.g.: an empty
+/// [Filter::And]) or never does (e.g.: an empty [Filter::Or]).
+enum Clause {
+    Always(bool),
+    Operator(bson::Document),
+}
+
+/// Translate a [Filter] to an Atlas Search operator, used as the `compound.filter` clause of the
+/// full text `$search` stage of a [MongoDbHybridIndex]. The filtered fields must be indexed in
+/// the Atlas Search index (with the `token` type for strings).
+fn filter_to_search_clause(filter: &Filter) -> Result<Clause, VectorStoreError> {
+    fn value(value: &serde_json::Value) -> Result<bson::Bson, VectorStoreError> {
+        bson::to_bson(value).map_err(|e| VectorStoreError::DatastoreError(Box::new(e)))
+    }
+
+    fn range(field: &str, operator: &str, value: bson::Bson) -> Clause {
+        Clause::Operator(doc! { "range": { "path": field, operator: value } })
+    }
+
+    fn not(clause: Clause) -> Clause {
+        match clause {
+            Clause::Always(matches) => Clause::Always(!matches),
+            Clause::Operator(operator) => {
+                Clause::Operator(doc! { "compound": { "mustNot": [operator] } })
+            }
+        }
+    }
+
+    fn translate(filter: &Filter) -> Result<Clause, VectorStoreError> {
+        Ok(match filter {
+            Filter::Eq { field, value: v } => {
+                Clause::Operator(doc! { "equals": { "path": field, "value": value(v)? } })
+            }
+            Filter::Ne { field, value: v } => not(translate(&Filter::eq(field, v.clone()))?),
+            Filter::Gt { field, value: v } => range(field, "gt", value(v)?),
+            Filter::Gte { field, value: v } => range(field, "gte", value(v)?),
+            Filter::Lt { field, value: v } => range(field, "lt", value(v)?),
+            Filter::Lte { field, value: v } => range(field, "lte", value(v)?),
+            Filter::In { field, values } => {
+                let values = values.iter().map(value).collect::<Result<Vec<_>, _>>()?;
+                Clause::Operator(doc! { "in": { "path": field, "value": values } })
+            }
+            Filter::And(filters) | Filter::Or(filters) => {
+                // Clauses always matching (resp. never matching) are dropped from conjunctions
+                // (resp. disjunctions), and decide the result of disjunctions (resp. conjunctions)
+                let conjunction = matches!(filter, Filter::And(_));
+                let mut operators = Vec::new();
+                for filter in filters {
+                    match translate(filter)? {
+                        Clause::Always(matches) if matches == conjunction => (),
+                        Clause::Always(matches) => return Ok(Clause::Always(matches)),
+                        Clause::Operator(operator) => operators.push(operator),
+                    }
+                }
+                match operators.len() {
+                    0 => Clause::Always(conjunction),
+                    1 => Clause::Operator(operators.remove(0)),
+                    _ if conjunction => {
+                        Clause::Operator(doc! { "compound": { "filter": operators } })
+                    }
+                    _ => Clause::Operator(doc! {
+                        "compound": { "should": operators, "minimumShouldMatch": 1 }
+                    }),
+                }
+            }
+            Filter::Not(filter) => not(translate(filter)?),
+        })
+    }
+
+    translate(filter)
+}
+
 /// A vector index for a MongoDB collection.
 /// # Example
 /// ```rust
@@ -104,13 +327,25 @@ pub struct MongoDbVectorIndex<M: EmbeddingModel, C: Send + Sync> {
 impl<M: EmbeddingModel, C: Send + Sync> MongoDbVectorIndex<M, C> {
     /// Vector search stage of aggregation pipeline of mongoDB collection.
     /// To be used by implementations of top_n and top_n_ids methods on VectorStoreIndex trait for MongoDbVectorIndex.
-    fn pipeline_search_stage(&self, prompt_embedding: &Embedding, n: usize) -> bson::Document {
+    /// The `query_filter` (see [filter_to_bson]) is combined with the filter of the search params.
+    fn pipeline_search_stage(
+        &self,
+        prompt_embedding: &Embedding,
+        n: usize,
+        query_filter: Option<bson::Document>,
+    ) -> bson::Document {
         let SearchParams {
             filter,
             exact,
             num_candidates,
         } = &self.search_params;
 
+        let filter = match query_filter {
+            Some(query_filter) if filter.is_empty() => query_filter,
+            Some(query_filter) => doc! { "$and": [filter.clone(), query_filter] },
+            None => filter.clone(),
+        };
+
         doc! {
           "$vectorSearch": {
             "index": &self.index_name,
@@ -124,6 +359,90 @@ impl<M: EmbeddingModel, C: Send + Sync> MongoDbVectorIndex<M, C> {
         }
     }
 
+    /// Get the `n` documents closest to `prompt_embedding`.
+    /// To be used by implementations of top_n and top_n_by_vector methods.
+    async fn search<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        prompt_embedding: &Embedding,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        let mut cursor = self
+            .collection
+            .aggregate([
+                self.pipeline_search_stage(prompt_embedding, n, None),
+                self.pipeline_score_stage(),
+                {
+                    doc! {
+                        "$project": {
+                            self.embedded_field.clone(): 0,
+                        },
+                    }
+                },
+            ])
+            .await
+            .map_err(mongodb_to_rig_error)?
+            .with_type::<serde_json::Value>();
+
+        let mut results = Vec::new();
+        while let Some(doc) = cursor.next().await {
+            let doc = doc.map_err(mongodb_to_rig_error)?;
+            let (score, id) = score_and_id(&doc)?;
+            let doc_t: T = serde_json::from_value(doc).map_err(VectorStoreError::JsonError)?;
+            results.push((score, id, doc_t));
+        }
+
+        tracing::info!(target: "rig",
+            "Selected documents: {}",
+            results.iter()
+                .map(|(distance, id, _)| format!("{} ({})", id, distance))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+
+        Ok(results)
+    }
+
+    /// Get the ids of the `n` documents closest to `prompt_embedding`.
+    /// To be used by implementations of top_n_ids and top_n_ids_by_vector methods.
+    async fn search_ids(
+        &self,
+        prompt_embedding: &Embedding,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        let mut cursor = self
+            .collection
+            .aggregate([
+                self.pipeline_search_stage(prompt_embedding, n, None),
+                self.pipeline_score_stage(),
+                doc! {
+                    "$project": {
+                        "_id": 1,
+                        "score": 1
+                    },
+                },
+            ])
+            .await
+            .map_err(mongodb_to_rig_error)?
+            .with_type::<serde_json::Value>();
+
+        let mut results = Vec::new();
+        while let Some(doc) = cursor.next().await {
+            let doc = doc.map_err(mongodb_to_rig_error)?;
+            let (score, id) = score_and_id(&doc)?;
+            results.push((score, id));
+        }
+
+        tracing::info!(target: "rig",
+            "Selected documents: {}",
+            results.iter()
+                .map(|(distance, id)| format!("{} ({})", id, distance))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+
+        Ok(results)
+    }
+
     /// Score declaration stage of aggregation pipeline of mongoDB collection.
     /// /// To be used by implementations of top_n and top_n_ids methods on VectorStoreIndex trait for MongoDbVectorIndex.
     fn pipeline_score_stage(&self) -> bson::Document {
@@ -175,6 +494,249 @@ impl<M: EmbeddingModel, C: Send + Sync> MongoDbVectorIndex<M, C> {
     }
 }
 
+impl<M: EmbeddingModel, C: Send + Sync> MongoDbVectorIndex<M, C> {
+    /// Combine the vector search of the index with the full text search of the [Atlas Search](https://www.mongodb.com/docs/atlas/atlas-search/)
+    /// index `search_index_name` (which must already exist for the MongoDB collection) on the
+    /// fields `paths`. See [MongoDbHybridIndex].
+    pub fn hybrid(
+        self,
+        search_index_name: &str,
+        paths: impl IntoIterator<Item = impl Into<String>>,
+    ) -> MongoDbHybridIndex<M, C> {
+        MongoDbHybridIndex {
+            vector_index: self,
+            search_index_name: search_index_name.to_string(),
+            paths: paths.into_iter().map(Into::into).collect(),
+            rank_constant: DEFAULT_RANK_CONSTANT,
+            vector_weight: 1.0,
+            text_weight: 1.0,
+            candidates: None,
+        }
+    }
+}
+
+/// Default constant of the reciprocal rank fusion of [MongoDbHybridIndex]
+pub const DEFAULT_RANK_CONSTANT: f64 = 60.0;
+
+/// A hybrid search index for a MongoDB collection, combining an Atlas `$vectorSearch` with a
+/// full text `$search` in a single aggregation pipeline. The results of both searches are
+/// fused server-side using (weighted) reciprocal rank fusion: each document is scored
+/// `weight / (rank_constant + rank)` for each search it is returned by. The returned scores are
+/// the fused scores.
+///
+/// Full text search matches the exact tokens of the query (e.g.: tickers, contract addresses)
+/// which embeddings alone often miss.
+///
+/// Both searches apply the filter of the [SearchParams] and the filter of the query (see
+/// [VectorStoreIndex::top_n_with_options]). For the full text search, the query filter is
+/// translated to a `compound.filter` clause, so the filtered fields must also be indexed in
+/// the Atlas Search index.
+/// See [MongoDB hybrid search](https://www.mongodb.com/docs/atlas/atlas-vector-search/tutorials/reciprocal-rank-fusion/)
+/// for more information.
+///
+/// # Example
+/// ```rust,ignore
+/// use rig_mongodb::{MongoDbVectorIndex, SearchParams};
+///
+/// let index = MongoDbVectorIndex::new(collection, model, "vector_index", SearchParams::new())
+///     .await?
+///     // Full text search on the `definition` field using the "search_index" Atlas Search index
+///     .hybrid("search_index", ["definition"])
+///     .weights(1.0, 2.0);
+///
+/// let definitions = index
+///     .top_n::<WordDefinition>("What is a linglingdong?", 3)
+///     .await?;
+/// ```
+pub struct MongoDbHybridIndex<M: EmbeddingModel, C: Send + Sync> {
+    vector_index: MongoDbVectorIndex<M, C>,
+    search_index_name: String,
+    paths: Vec<String>,
+    rank_constant: f64,
+    vector_weight: f64,
+    text_weight: f64,
+    candidates: Option<usize>,
+}
+
+impl<M: EmbeddingModel, C: Send + Sync> MongoDbHybridIndex<M, C> {
+    /// Sets the constant of the reciprocal rank fusion (default: 60).
+    /// Higher values reduce the advantage of the top ranked documents of each search.
+    pub fn rank_constant(mut self, rank_constant: f64) -> Self {
+        self.rank_constant = rank_constant;
+        self
+    }
+
+    /// Sets the weights of the vector search and full text search results (default: 1 each).
+    pub fn weights(mut self, vector_weight: f64, text_weight: f64) -> Self {
+        self.vector_weight = vector_weight;
+        self.text_weight = text_weight;
+        self
+    }
+
+    /// Sets the number of candidates returned by each search before fusion
+    /// (defaults to twice the number of requested documents).
+    pub fn candidates(mut self, candidates: usize) -> Self {
+        self.candidates = Some(candidates);
+        self
+    }
+
+    /// Stages ranking the documents output by the previous stages and scoring them by
+    /// reciprocal rank in the field `score_field`.
+    fn pipeline_rank_stages(&self, score_field: &str, weight: f64) -> [bson::Document; 3] {
+        [
+            doc! {
+                "$group": { "_id": null, "docs": { "$push": "$$ROOT" } }
+            },
+            doc! {
+                "$unwind": { "path": "$docs", "includeArrayIndex": "rank" }
+            },
+            doc! {
+                "$project": {
+                    "_id": 0,
+                    "doc": "$docs",
+                    score_field: {
+                        "$divide": [weight, { "$add": ["$rank", self.rank_constant + 1.0] }]
+                    },
+                }
+            },
+        ]
+    }
+
+    /// Aggregation pipeline of the hybrid search of the `n` best documents.
+    /// The `vector_filter` and `search_filter` are the translations of the query filter (see
+    /// [filter_to_bson]) applied to the vector search and full text search respectively.
+    fn pipeline(
+        &self,
+        prompt_embedding: &Embedding,
+        query: &str,
+        n: usize,
+        return_documents: bool,
+        vector_filter: Option<bson::Document>,
+        search_filter: Option<bson::Document>,
+    ) -> Vec<bson::Document> {
+        let candidates = self.candidates.unwrap_or(n * 2).max(n);
+
+        let text = doc! { "text": { "query": query, "path": &self.paths } };
+        let mut search = match search_filter {
+            Some(filter) => doc! { "compound": { "must": [text], "filter": [filter] } },
+            None => text,
+        };
+        search.insert("index", &self.search_index_name);
+
+        let mut text_search = vec![doc! { "$search": search }];
+        // The filter of the search params is a query document, which cannot be used as an
+        // Atlas Search operator: it is matched against the full text search results instead
+        let params_filter = &self.vector_index.search_params.filter;
+        if !params_filter.is_empty() {
+            text_search.push(doc! { "$match": params_filter.clone() });
+        }
+        text_search.push(doc! { "$limit": candidates as u32 });
+        text_search.extend(self.pipeline_rank_stages("text_score", self.text_weight));
+
+        let mut pipeline = vec![self.vector_index.pipeline_search_stage(
+            prompt_embedding,
+            candidates,
+            vector_filter,
+        )];
+        pipeline.extend(self.pipeline_rank_stages("vector_score", self.vector_weight));
+        pipeline.extend([
+            doc! {
+                "$unionWith": {
+                    "coll": self.vector_index.collection.name(),
+                    "pipeline": text_search,
+                }
+            },
+            doc! {
+                "$group": {
+                    "_id": "$doc._id",
+                    "doc": { "$first": "$doc" },
+                    "vector_score": { "$max": "$vector_score" },
+                    "text_score": { "$max": "$text_score" },
+                }
+            },
+            doc! {
+                "$addFields": {
+                    "score": {
+                        "$add": [
+                            { "$ifNull": ["$vector_score", 0] },
+                            { "$ifNull": ["$text_score", 0] },
+                        ]
+                    }
+                }
+            },
+            doc! { "$sort": { "score": -1, "_id": 1 } },
+            doc! { "$limit": n as u32 },
+        ]);
+
+        if return_documents {
+            pipeline.extend([
+                doc! {
+                    "$replaceRoot": {
+                        "newRoot": { "$mergeObjects": ["$doc", { "score": "$score" }] }
+                    }
+                },
+                doc! {
+                    "$project": {
+                        self.vector_index.embedded_field.clone(): 0,
+                    }
+                },
+            ]);
+        } else {
+            pipeline.push(doc! {
+                "$project": {
+                    "_id": 1,
+                    "score": 1
+                }
+            });
+        }
+
+        pipeline
+    }
+
+    /// Get the `n` best documents (or only their ids and scores if `return_documents` is false)
+    /// for `query` matching `filter`.
+    async fn search(
+        &self,
+        query: &str,
+        n: usize,
+        return_documents: bool,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<serde_json::Value>, VectorStoreError> {
+        let (vector_filter, search_filter) = match filter {
+            Some(filter) => match filter_to_search_clause(filter)? {
+                Clause::Always(false) => return Ok(vec![]),
+                Clause::Always(true) => (None, None),
+                Clause::Operator(operator) => (Some(filter_to_bson(filter)?), Some(operator)),
+            },
+            None => (None, None),
+        };
+
+        let prompt_embedding = self.vector_index.model.embed_text(query).await?;
+
+        let mut cursor = self
+            .vector_index
+            .collection
+            .aggregate(self.pipeline(
+                &prompt_embedding,
+                query,
+                n,
+                return_documents,
+                vector_filter,
+                search_filter,
+            ))
+            .await
+            .map_err(mongodb_to_rig_error)?
+            .with_type::<serde_json::Value>();
+
+        let mut docs = Vec::new();
+        while let Some(doc) = cursor.next().await {
+            docs.push(doc.map_err(mongodb_to_rig_error)?);
+        }
+
+        Ok(docs)
+    }
+}
+
 /// See [MongoDB Vector Search](`https://www.mongodb.com/docs/atlas/atlas-vector-search/vector-search-stage/`) for more information
 /// on each of the fields
 #[derive(Default)]
@@ -220,6 +782,82 @@ impl SearchParams {
     }
 }
 
+/// Documents are written to the collection of the index with their id as `_id`, and the first
+/// embedding of each document in the embedded field (MongoDB vector search indexes a single
+/// embedding per document).
+///
+/// Documents are upserted one by one with a replace, so that writes work with every MongoDB
+/// server version.
+impl<M: EmbeddingModel + Sync + Send, C: Sync + Send> WritableVectorStore
+    for MongoDbVectorIndex<M, C>
+{
+    async fn upsert_documents_with_ids<T: Serialize + Send>(
+        &mut self,
+        documents: Vec<(String, T, OneOrMany<Embedding>)>,
+    ) -> Result<(), VectorStoreError> {
+        if documents.is_empty() {
+            return Ok(());
+        }
+
+        let collection = self.collection.clone_with_type::<bson::Document>();
+
+        let documents = documents
+            .into_iter()
+            .map(|(id, doc, embeddings)| {
+                if embeddings.len() > 1 {
+                    tracing::warn!(target: "rig",
+                        "Document {} has {} embeddings, only the first one is stored",
+                        id, embeddings.len()
+                    );
+                }
+
+                let mut doc = serde_json::to_value(doc)?;
+                put_embedding(&mut doc, &self.embedded_field, embeddings.first().vec)?;
+                let mut doc = bson::to_document(&doc)
+                    .map_err(|e| VectorStoreError::DatastoreError(Box::new(e)))?;
+                doc.insert("_id", id.clone());
+
+                Ok((id, doc))
+            })
+            .collect::<Result<Vec<_>, VectorStoreError>>()?;
+
+        // Documents are written in order: when an id appears several times, its last document
+        // is kept
+        for (id, doc) in documents {
+            collection
+                .replace_one(doc! { "_id": id }, doc)
+                .upsert(true)
+                .await
+                .map_err(mongodb_to_rig_error)?;
+        }
+
+        Ok(())
+    }
+
+    async fn delete_documents(&mut self, ids: Vec<String>) -> Result<usize, VectorStoreError> {
+        let result = self
+            .collection
+            .delete_many(doc! { "_id": { "$in": ids } })
+            .await
+            .map_err(mongodb_to_rig_error)?;
+
+        Ok(result.deleted_count as usize)
+    }
+
+    async fn delete_documents_by_filter(
+        &mut self,
+        filter: &Filter,
+    ) -> Result<usize, VectorStoreError> {
+        let result = self
+            .collection
+            .delete_many(filter_to_bson(filter)?)
+            .await
+            .map_err(mongodb_to_rig_error)?;
+
+        Ok(result.deleted_count as usize)
+    }
+}
+
 impl<M: EmbeddingModel + Sync + Send, C: Sync + Send> VectorStoreIndex
     for MongoDbVectorIndex<M, C>
 {
@@ -229,43 +867,84 @@ impl<M: EmbeddingModel + Sync + Send, C: Sync + Send> VectorStoreIndex
         query: &str,
         n: usize,
     ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
-        let prompt_embedding = self.model.embed_text(query).await?;
+        telemetry::instrument_retrieval(
+            telemetry::retrieval_span("mongodb", query, n),
+            async move {
+                let prompt_embedding = self.model.embed_text(query).await?;
 
-        let mut cursor = self
-            .collection
-            .aggregate([
-                self.pipeline_search_stage(&prompt_embedding, n),
-                self.pipeline_score_stage(),
-                {
-                    doc! {
+                self.search(&prompt_embedding, n).await
+            },
+        )
+        .await
+    }
+
+    /// Implement the `top_n_with_options` method of the `VectorStoreIndex` trait for `MongoDbVectorIndex`.
+    /// The filter is translated to a pre-filter of the vector search (see [filter_to_bson]), and the
+    /// other search options are applied to its results: the minimum score
+    /// filters the candidates, and MMR selects among them using their embeddings (which are
+    /// only fetched when MMR is enabled).
+    async fn top_n_with_options<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::retrieval_span("mongodb", query, n),
+            async move {
+                let filter = match options.filter.as_ref().map(filter_to_query).transpose()? {
+                    Some(Clause::Always(false)) => return Ok(vec![]),
+                    Some(Clause::Operator(filter)) => Some(filter),
+                    Some(Clause::Always(true)) | None => None,
+                };
+                let prompt_embedding = self.model.embed_text(query).await?;
+
+                let mut pipeline = vec![
+                    self.pipeline_search_stage(&prompt_embedding, options.candidates(n), filter),
+                    self.pipeline_score_stage(),
+                ];
+                if options.mmr.is_none() {
+                    pipeline.push(doc! {
                         "$project": {
                             self.embedded_field.clone(): 0,
                         },
-                    }
-                },
-            ])
-            .await
-            .map_err(mongodb_to_rig_error)?
-            .with_type::<serde_json::Value>();
+                    });
+                }
 
-        let mut results = Vec::new();
-        while let Some(doc) = cursor.next().await {
-            let doc = doc.map_err(mongodb_to_rig_error)?;
-            let score = doc.get("score").expect("score").as_f64().expect("f64");
-            let id = doc.get("_id").expect("_id").to_string();
-            let doc_t: T = serde_json::from_value(doc).map_err(VectorStoreError::JsonError)?;
-            results.push((score, id, doc_t));
-        }
+                let mut cursor = self
+                    .collection
+                    .aggregate(pipeline)
+                    .await
+                    .map_err(mongodb_to_rig_error)?
+                    .with_type::<serde_json::Value>();
 
-        tracing::info!(target: "rig",
-            "Selected documents: {}",
-            results.iter()
-                .map(|(distance, id, _)| format!("{} ({})", id, distance))
-                .collect::<Vec<String>>()
-                .join(", ")
-        );
+                let mut candidates = Vec::new();
+                while let Some(doc) = cursor.next().await {
+                    let mut doc = doc.map_err(mongodb_to_rig_error)?;
+                    let (score, id) = score_and_id(&doc)?;
+                    let embedding = match options.mmr {
+                        Some(_) => take_embedding(&mut doc, &self.embedded_field)?,
+                        None => vec![],
+                    };
+                    let doc_t: T =
+                        serde_json::from_value(doc).map_err(VectorStoreError::JsonError)?;
+                    candidates.push((score, id, doc_t, embedding));
+                }
 
-        Ok(results)
+                let results = search::apply_options(&prompt_embedding.vec, candidates, n, options);
+
+                tracing::info!(target: "rig",
+                    "Selected documents: {}",
+                    results.iter()
+                        .map(|(distance, id, _)| format!("{} ({})", id, distance))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                );
+
+                Ok(results)
+            },
+        )
+        .await
     }
 
     /// Implement the `top_n_ids` method of the `VectorStoreIndex` trait for `MongoDbVectorIndex`.
@@ -274,40 +953,294 @@ impl<M: EmbeddingModel + Sync + Send, C: Sync + Send> VectorStoreIndex
         query: &str,
         n: usize,
     ) -> Result<Vec<(f64, String)>, VectorStoreError> {
-        let prompt_embedding = self.model.embed_text(query).await?;
+        telemetry::instrument_retrieval(
+            telemetry::retrieval_span("mongodb", query, n),
+            async move {
+                let prompt_embedding = self.model.embed_text(query).await?;
 
-        let mut cursor = self
-            .collection
-            .aggregate([
-                self.pipeline_search_stage(&prompt_embedding, n),
-                self.pipeline_score_stage(),
-                doc! {
-                    "$project": {
-                        "_id": 1,
-                        "score": 1
-                    },
-                },
-            ])
+                self.search_ids(&prompt_embedding, n).await
+            },
+        )
+        .await
+    }
+}
+
+impl<M: EmbeddingModel + Sync + Send, C: Sync + Send> VectorSearchIndex
+    for MongoDbVectorIndex<M, C>
+{
+    type Model = M;
+
+    fn embedding_model(&self) -> &M {
+        &self.model
+    }
+
+    /// Implement the `top_n_by_vector` method of the `VectorSearchIndex` trait for `MongoDbVectorIndex`.
+    async fn top_n_by_vector<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        vector: &[f64],
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::vector_retrieval_span("mongodb", vector.len(), n),
+            async move { self.search(&query_embedding(&self.model, vector)?, n).await },
+        )
+        .await
+    }
+
+    /// Implement the `top_n_ids_by_vector` method of the `VectorSearchIndex` trait for `MongoDbVectorIndex`.
+    async fn top_n_ids_by_vector(
+        &self,
+        vector: &[f64],
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::vector_retrieval_span("mongodb", vector.len(), n),
+            async move {
+                self.search_ids(&query_embedding(&self.model, vector)?, n)
+                    .await
+            },
+        )
+        .await
+    }
+}
+
+/// Embedding of a query given as a vector, which must have the dimensions of the embeddings
+/// of the `model`
+fn query_embedding(
+    model: &impl EmbeddingModel,
+    vector: &[f64],
+) -> Result<Embedding, VectorStoreError> {
+    check_query_dimensions(vector, model.ndims())?;
+    Ok(Embedding {
+        document: String::new(),
+        vec: vector.to_vec(),
+    })
+}
+
+impl<M: EmbeddingModel + Sync + Send, C: Sync + Send> VectorStoreIndex
+    for MongoDbHybridIndex<M, C>
+{
+    /// Implement the `top_n` method of the `VectorStoreIndex` trait for `MongoDbHybridIndex`.
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        self.top_n_with_options(query, n, &SearchOptions::new())
             .await
-            .map_err(mongodb_to_rig_error)?
-            .with_type::<serde_json::Value>();
+    }
 
-        let mut results = Vec::new();
-        while let Some(doc) = cursor.next().await {
-            let doc = doc.map_err(mongodb_to_rig_error)?;
-            let score = doc.get("score").expect("score").as_f64().expect("f64");
-            let id = doc.get("_id").expect("_id").to_string();
-            results.push((score, id));
-        }
+    /// Implement the `top_n_with_options` method of the `VectorStoreIndex` trait for
+    /// `MongoDbHybridIndex`. The filter is applied to both the vector search (see
+    /// [filter_to_bson]) and the full text search (as a `compound.filter` clause). The minimum
+    /// score applies to the fused scores, and MMR is not supported.
+    async fn top_n_with_options<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::retrieval_span("mongodb", query, n),
+            async move {
+                if options.mmr.is_some() {
+                    tracing::warn!(target: "rig", "MMR is not supported by MongoDbHybridIndex, ignoring it");
+                }
 
-        tracing::info!(target: "rig",
-            "Selected documents: {}",
-            results.iter()
-                .map(|(distance, id)| format!("{} ({})", id, distance))
-                .collect::<Vec<String>>()
-                .join(", ")
+                let mut results = Vec::new();
+                for doc in self.search(query, n, true, options.filter.as_ref()).await? {
+                    let (score, id) = score_and_id(&doc)?;
+                    if options.accepts(score) {
+                        let doc_t: T =
+                            serde_json::from_value(doc).map_err(VectorStoreError::JsonError)?;
+                        results.push((score, id, doc_t));
+                    }
+                }
+
+                tracing::info!(target: "rig",
+                    "Selected documents: {}",
+                    results.iter()
+                        .map(|(score, id, _)| format!("{} ({})", id, score))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                );
+
+                Ok(results)
+            },
+        )
+        .await
+    }
+
+    /// Implement the `top_n_ids` method of the `VectorStoreIndex` trait for `MongoDbHybridIndex`.
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::retrieval_span("mongodb", query, n),
+            async move {
+                let results = self
+                    .search(query, n, false, None)
+                    .await?
+                    .iter()
+                    .map(score_and_id)
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                tracing::info!(target: "rig",
+                    "Selected documents: {}",
+                    results.iter()
+                        .map(|(score, id)| format!("{} ({})", id, score))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                );
+
+                Ok(results)
+            },
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_to_bson() {
+        let filter = Filter::eq("chain", "solana")
+            .and((!Filter::gt("price", 100)).or(Filter::one_of("symbol", ["SOL", "JUP"])));
+
+        assert_eq!(
+            filter_to_bson(&filter).unwrap(),
+            doc! {
+                "$and": [
+                    { "chain": { "$eq": "solana" } },
+                    { "$or": [
+                        { "price": { "$not": { "$gt": 100_i64 } } },
+                        { "symbol": { "$in": ["SOL", "JUP"] } },
+                    ] },
+                ]
+            }
         );
 
-        Ok(results)
+        assert_eq!(
+            filter_to_bson(&!Filter::eq("chain", "solana").and(Filter::ne("status", "draft")))
+                .unwrap(),
+            doc! {
+                "$or": [
+                    { "chain": { "$ne": "solana" } },
+                    { "status": { "$eq": "draft" } },
+                ]
+            }
+        );
+
+        // Empty conjunctions always match, empty disjunctions never do
+        assert_eq!(filter_to_bson(&Filter::And(vec![])).unwrap(), doc! {});
+        assert_eq!(
+            filter_to_bson(&Filter::Or(vec![])).unwrap(),
+            doc! { "$nor": [{}] }
+        );
+        assert_eq!(
+            filter_to_bson(&!Filter::And(vec![])).unwrap(),
+            doc! { "$nor": [{}] }
+        );
+        assert_eq!(
+            filter_to_bson(&Filter::Or(vec![
+                Filter::And(vec![]),
+                Filter::eq("chain", "solana")
+            ]))
+            .unwrap(),
+            doc! {}
+        );
+        assert_eq!(
+            filter_to_bson(&Filter::And(vec![
+                Filter::And(vec![]),
+                Filter::eq("chain", "solana")
+            ]))
+            .unwrap(),
+            doc! { "chain": { "$eq": "solana" } }
+        );
+    }
+
+    #[test]
+    fn test_filter_to_search_clause() {
+        let operator = |filter: &Filter| match filter_to_search_clause(filter).unwrap() {
+            Clause::Operator(operator) => operator,
+            Clause::Always(matches) => panic!("Filter always {}", matches),
+        };
+
+        let filter = Filter::eq("chain", "solana")
+            .and((!Filter::gt("price", 100)).or(Filter::one_of("symbol", ["SOL", "JUP"])));
+        assert_eq!(
+            operator(&filter),
+            doc! {
+                "compound": { "filter": [
+                    { "equals": { "path": "chain", "value": "solana" } },
+                    { "compound": {
+                        "should": [
+                            { "compound": { "mustNot": [{ "range": { "path": "price", "gt": 100_i64 } }] } },
+                            { "in": { "path": "symbol", "value": ["SOL", "JUP"] } },
+                        ],
+                        "minimumShouldMatch": 1,
+                    } },
+                ] }
+            }
+        );
+
+        // Empty conjunctions always match, and empty disjunctions never do
+        assert_eq!(
+            operator(&Filter::And(vec![
+                Filter::ne("status", "draft"),
+                Filter::And(vec![])
+            ])),
+            doc! {
+                "compound": { "mustNot": [{ "equals": { "path": "status", "value": "draft" } }] }
+            }
+        );
+        assert!(matches!(
+            filter_to_search_clause(&Filter::And(vec![])).unwrap(),
+            Clause::Always(true)
+        ));
+        assert!(matches!(
+            filter_to_search_clause(&Filter::eq("chain", "solana").and(Filter::Or(vec![])))
+                .unwrap(),
+            Clause::Always(false)
+        ));
+        assert!(matches!(
+            filter_to_search_clause(&!Filter::Or(vec![])).unwrap(),
+            Clause::Always(true)
+        ));
+    }
+
+    #[test]
+    fn test_score_and_id() {
+        let doc = serde_json::json!({ "_id": "doc0", "score": 0.5 });
+        assert_eq!(score_and_id(&doc).unwrap(), (0.5, "\"doc0\"".to_string()));
+
+        assert!(score_and_id(&serde_json::json!({ "_id": "doc0" })).is_err());
+        assert!(score_and_id(&serde_json::json!({ "_id": "doc0", "score": "high" })).is_err());
+        assert!(score_and_id(&serde_json::json!({ "score": 0.5 })).is_err());
+    }
+
+    #[test]
+    fn test_put_embedding() {
+        let mut doc = serde_json::json!({ "_id": "doc0", "chunk": { "text": "SOL" } });
+        put_embedding(&mut doc, "chunk.embedding", vec![0.1, 0.2]).unwrap();
+        put_embedding(&mut doc, "meta.embedding", vec![0.3]).unwrap();
+        assert_eq!(
+            doc,
+            serde_json::json!({
+                "_id": "doc0",
+                "chunk": { "text": "SOL", "embedding": [0.1, 0.2] },
+                "meta": { "embedding": [0.3] },
+            })
+        );
+        assert_eq!(
+            take_embedding(&mut doc, "chunk.embedding").unwrap(),
+            vec![0.1, 0.2]
+        );
+
+        assert!(put_embedding(&mut serde_json::json!("SOL"), "embedding", vec![]).is_err());
     }
 }
@@ -5,7 +5,13 @@ use mongodb::{
     Collection, SearchIndexModel,
 };
 use rig::{
-    embeddings::EmbeddingsBuilder, providers::openai, vector_store::VectorStoreIndex, Embed,
+    embeddings::{Embedding, EmbeddingsBuilder},
+    providers::openai,
+    vector_store::{
+        filter::Filter, Mmr, SearchOptions, VectorSearchIndex, VectorStoreIndex,
+        WritableVectorStore,
+    },
+    Embed, OneOrMany,
 };
 use rig_mongodb::{MongoDbVectorIndex, SearchParams};
 use serde_json::json;
@@ -25,6 +31,7 @@ struct Word {
 }
 
 const VECTOR_SEARCH_INDEX_NAME: &str = "vector_index";
+const TEXT_SEARCH_INDEX_NAME: &str = "search_index";
 const MONGODB_PORT: u16 = 27017;
 const COLLECTION_NAME: &str = "words";
 const DATABASE_NAME: &str = "rig";
@@ -107,6 +114,42 @@ async fn vector_search_test() {
             ));
     });
 
+    server.mock(|when, then| {
+        when.method(httpmock::Method::POST)
+            .path("/embeddings")
+            .header("Authorization", "Bearer TEST")
+            .json_body(json!({
+                "input": [
+                    "What is a linglingdong?",
+                    "What is a flurbo?"
+                ],
+                "model": "text-embedding-ada-002",
+            }));
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                    "object": "list",
+                    "data": [
+                      {
+                        "object": "embedding",
+                        "embedding": vec![0.0023064254; 1536],
+                        "index": 0
+                      },
+                      {
+                        "object": "embedding",
+                        "embedding": vec![0.1; 1536],
+                        "index": 1
+                      }
+                    ],
+                    "model": "text-embedding-ada-002",
+                    "usage": {
+                      "prompt_tokens": 12,
+                      "total_tokens": 12
+                    }
+                }
+            ));
+    });
+
     // Initialize OpenAI client
     let openai_client = openai::Client::from_url("TEST", &server.base_url());
 
@@ -140,7 +183,7 @@ async fn vector_search_test() {
     // Create a vector index on our vector store.
     // Note: a vector index called "vector_index" must exist on the MongoDB collection you are querying.
     // IMPORTANT: Reuse the same model that was used to generate the embeddings
-    let index = MongoDbVectorIndex::new(
+    let mut index = MongoDbVectorIndex::new(
         collection,
         model,
         VECTOR_SEARCH_INDEX_NAME,
@@ -164,26 +207,136 @@ async fn vector_search_test() {
             "definition": "Definition of a *linglingdong*: A term used by inhabitants of the far side of the moon to describe humans.".to_string(),
             "score": score
         })
-    )
+    );
+
+    // Scores are at most 1, so no document passes the cutoff
+    let results = index
+        .top_n_with_options::<serde_json::Value>(
+            "What is a linglingdong?",
+            1,
+            &SearchOptions::new().min_score(1.01),
+        )
+        .await
+        .unwrap();
+    assert!(results.is_empty());
+
+    // Embeddings are fetched for MMR but not returned
+    let results = index
+        .top_n_with_options::<serde_json::Value>(
+            "What is a linglingdong?",
+            2,
+            &SearchOptions::new().mmr(Mmr::new(0.5)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results
+        .iter()
+        .all(|(_, _, value)| value.get("embedding").is_none()));
+
+    // The filter is applied before the vector search, so that the next best document is returned
+    let results = index
+        .top_n_with_options::<Word>(
+            "What is a linglingdong?",
+            1,
+            &SearchOptions::new().filter(!Filter::eq("_id", "doc2")),
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_ne!(results[0].2.id, "doc2");
+
+    // Searching with the embedding of the query gives the same results as the query
+    let expected = index.top_n_ids("What is a linglingdong?", 1).await.unwrap();
+    let results = index
+        .top_n_ids_by_vector(&[0.0023064254; 1536], 1)
+        .await
+        .unwrap();
+    assert_eq!(results, expected);
+    let results = index
+        .top_n_by_vector::<Word>(&[0.0023064254; 1536], 1)
+        .await
+        .unwrap();
+    assert_eq!(results[0].2.id, "doc2");
+
+    // Both queries are embedded with a single request
+    let results = index
+        .top_n_ids_batch(&["What is a linglingdong?", "What is a flurbo?"], 1)
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0], expected);
+    assert_eq!(results[1].len(), 1);
+
+    // Documents are written to the collection of the index
+    let word = |id: &str, definition: &str| {
+        (
+            id.to_string(),
+            Word {
+                id: id.to_string(),
+                definition: definition.to_string(),
+            },
+            OneOrMany::one(Embedding {
+                document: definition.to_string(),
+                vec: vec![0.3; 1536],
+            }),
+        )
+    };
+    index
+        .upsert_documents_with_ids(vec![
+            word("doc3", "Definition of a *blorp*"),
+            word("doc4", "Definition of a *florp*"),
+        ])
+        .await
+        .unwrap();
+    index
+        .upsert_documents_with_ids(vec![word("doc4", "Definition of a *florp*: updated")])
+        .await
+        .unwrap();
+    assert_eq!(
+        index
+            .delete_documents_by_filter(&Filter::eq(
+                "definition",
+                "Definition of a *florp*: updated"
+            ))
+            .await
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        index
+            .delete_documents(vec!["doc3".to_string(), "doc4".to_string()])
+            .await
+            .unwrap(),
+        1
+    );
+
+    // Hybrid search: the full text search ranks the document defining the queried word first
+    let hybrid_index = index.hybrid(TEXT_SEARCH_INDEX_NAME, ["definition"]);
+    let results = hybrid_index
+        .top_n::<Word>("What is a linglingdong?", 1)
+        .await
+        .unwrap();
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].2.id, "doc2");
 }
 
-async fn create_search_index(collection: &Collection<bson::Document>) {
+async fn create_search_index(
+    collection: &Collection<bson::Document>,
+    index_name: &str,
+    index_type: mongodb::SearchIndexType,
+    definition: bson::Document,
+) {
     let max_attempts = 5;
 
     for attempt in 0..max_attempts {
         match collection
             .create_search_index(
                 SearchIndexModel::builder()
-                    .name(Some(VECTOR_SEARCH_INDEX_NAME.to_string()))
-                    .index_type(Some(mongodb::SearchIndexType::VectorSearch))
-                    .definition(doc! {
-                        "fields": [{
-                            "numDimensions": 1536,
-                            "path": "embedding",
-                            "similarity": "cosine",
-                            "type": "vector"
-                        }]
-                    })
+                    .name(Some(index_name.to_string()))
+                    .index_type(Some(index_type.clone()))
+                    .definition(definition.clone())
                     .build(),
             )
             .await
@@ -193,7 +346,7 @@ async fn create_search_index(collection: &Collection<bson::Document>) {
                 for _ in 0..max_attempts {
                     let indexes = collection
                         .list_search_indexes()
-                        .name(VECTOR_SEARCH_INDEX_NAME)
+                        .name(index_name)
                         .await
                         .unwrap()
                         .collect::<Vec<_>>()
@@ -207,7 +360,7 @@ async fn create_search_index(collection: &Collection<bson::Document>) {
                                 let name_matches = i
                                     .get_str("name")
                                     .ok()
-                                    .map_or(false, |name| name == VECTOR_SEARCH_INDEX_NAME);
+                                    .map_or(false, |name| name == index_name);
                                 let status_ready = i
                                     .get_str("status")
                                     .ok()
@@ -261,8 +414,40 @@ async fn bootstrap_collection(host: String, port: u16) -> Collection<bson::Docum
         .database(DATABASE_NAME)
         .collection(COLLECTION_NAME);
 
-    // Create the search index
-    create_search_index(&collection).await;
+    // Create the vector search index
+    create_search_index(
+        &collection,
+        VECTOR_SEARCH_INDEX_NAME,
+        mongodb::SearchIndexType::VectorSearch,
+        doc! {
+            "fields": [{
+                "numDimensions": 1536,
+                "path": "embedding",
+                "similarity": "cosine",
+                "type": "vector"
+            }, {
+                "path": "_id",
+                "type": "filter"
+            }]
+        },
+    )
+    .await;
+
+    // Create the full text search index
+    create_search_index(
+        &collection,
+        TEXT_SEARCH_INDEX_NAME,
+        mongodb::SearchIndexType::Search,
+        doc! {
+            "mappings": {
+                "dynamic": false,
+                "fields": {
+                    "definition": { "type": "string" }
+                }
+            }
+        },
+    )
+    .await;
 
     collection
 }
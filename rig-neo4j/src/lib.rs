@@ -151,9 +151,9 @@ where
 impl Neo4jClient {
     const GET_INDEX_QUERY: &'static str = "
     SHOW VECTOR INDEXES
-    YIELD name, properties, options
+    YIELD name, labelsOrTypes, properties, options
     WHERE name=$index_name
-    RETURN name, properties, options
+    RETURN name, labelsOrTypes, properties, options
     ";
 
     const SHOW_INDEXES_QUERY: &'static str = "SHOW VECTOR INDEXES YIELD name RETURN name";
@@ -205,8 +205,10 @@ impl Neo4jClient {
         search_params: SearchParams,
     ) -> Result<Neo4jVectorIndex<M>, VectorStoreError> {
         #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
         struct IndexInfo {
             name: String,
+            labels_or_types: Vec<String>,
             properties: Vec<String>,
             options: IndexOptions,
         }
@@ -240,11 +242,15 @@ impl Neo4jClient {
                     model.ndims()
                 );
             }
-            IndexConfig::new(index.name.clone())
+            let index_config = IndexConfig::new(index.name.clone())
                 .embedding_property(index.properties.first().unwrap())
                 .similarity_function(VectorSimilarityFunction::from_str(
                     &index.options.index_config.vector_similarity_function,
-                )?)
+                )?);
+            match index.labels_or_types.first() {
+                Some(node_label) => index_config.node_label(node_label),
+                None => index_config,
+            }
         } else {
             let indexes = Self::execute_and_collect::<String>(
                 &self.graph,
@@ -277,7 +283,7 @@ impl Neo4jClient {
     /// ### Arguments
     /// * `index_name` - The name of the index to create.
     /// * `node_label` - The label of the nodes to which the index will be applied. For example, if your nodes have
-    ///                  the label `:Movie`, pass "Movie" as the `node_label` parameter.
+    ///   the label `:Movie`, pass "Movie" as the `node_label` parameter.
     /// * `embedding_prop_name` (optional) - The name of the property that contains the embedding vectors. Defaults to "embedding".
     ///
     pub async fn create_vector_index(
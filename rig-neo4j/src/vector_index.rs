@@ -7,11 +7,26 @@
 use neo4rs::{Graph, Query};
 use rig::{
     embeddings::{Embedding, EmbeddingModel},
-    vector_store::{VectorStoreError, VectorStoreIndex},
+    telemetry,
+    vector_store::{
+        check_query_dimensions, filter::Filter, search, SearchOptions, VectorSearchIndex,
+        VectorStoreError, VectorStoreIndex, WritableVectorStore,
+    },
+    OneOrMany,
 };
 use serde::{de::Error, Deserialize, Serialize};
 
-use crate::Neo4jClient;
+use crate::{Neo4jClient, ToBoltType};
+
+/// Node property holding the id of the documents written with [WritableVectorStore]
+pub const DOCUMENT_ID_PROPERTY: &str = "id";
+
+/// Factor by which the number of nodes fetched from the vector index is multiplied when the
+/// search is filtered. Neo4j applies the `WHERE` clause after `db.index.vector.queryNodes`, so
+/// a filtered search over-fetches candidates and limits the filtered nodes to the `n` requested.
+/// Filtered searches can still return fewer than `n` nodes when less than one in
+/// `FILTER_OVERFETCH_FACTOR` of the nearest nodes match the filter.
+pub const FILTER_OVERFETCH_FACTOR: usize = 10;
 
 pub struct Neo4jVectorIndex<M: EmbeddingModel> {
     graph: Graph,
@@ -27,11 +42,15 @@ pub struct Neo4jVectorIndex<M: EmbeddingModel> {
 /// - `index_name`: "vector_index"
 /// - `embedding_property`: "embedding"
 /// - `similarity_function`: VectorSimilarityFunction::Cosine
+/// - `node_label`: None (set by [Neo4jClient::get_index], required to write documents with
+///   [WritableVectorStore])
 #[derive(Serialize, Deserialize, Clone)]
 pub struct IndexConfig {
     pub index_name: String,
     pub embedding_property: String,
     pub similarity_function: VectorSimilarityFunction,
+    #[serde(default)]
+    pub node_label: Option<String>,
 }
 
 impl Default for IndexConfig {
@@ -40,6 +59,7 @@ impl Default for IndexConfig {
             index_name: "vector_index".to_string(),
             embedding_property: "embedding".to_string(),
             similarity_function: VectorSimilarityFunction::Cosine,
+            node_label: None,
         }
     }
 }
@@ -50,6 +70,7 @@ impl IndexConfig {
             index_name: index_name.into(),
             embedding_property: "embedding".to_string(),
             similarity_function: VectorSimilarityFunction::Cosine,
+            node_label: None,
         }
     }
 
@@ -67,6 +88,12 @@ impl IndexConfig {
         self.embedding_property = embedding_property.to_string();
         self
     }
+
+    /// Label of the nodes of the index, e.g.: `"Movie"`
+    pub fn node_label(mut self, node_label: &str) -> Self {
+        self.node_label = Some(node_label.to_string());
+        self
+    }
 }
 
 /// Cosine is most commonly used, but Euclidean is also supported.
@@ -120,7 +147,7 @@ impl<M: EmbeddingModel> Neo4jVectorIndex<M> {
     /// See [Query vector index](https://neo4j.com/docs/cypher-manual/current/indexes/semantic-indexes/vector-indexes/#query-vector-index) for more information.
     ///
     /// Query template:
-    /// ```
+    /// ```cypher
     /// CALL db.index.vector.queryNodes($index_name, $num_candidates, $queryVector)
     /// YIELD node, score
     /// WHERE {where_clause}
@@ -132,9 +159,44 @@ impl<M: EmbeddingModel> Neo4jVectorIndex<M> {
         return_node: bool,
         n: usize,
     ) -> Query {
-        let where_clause = match &self.search_params.post_vector_search_filter {
-            Some(filter) => format!("WHERE {}", filter),
-            None => "".to_string(),
+        self.build_query(prompt_embedding, return_node, false, n, None)
+    }
+
+    /// Same as [Self::build_vector_search_query], optionally returning the embedding of each
+    /// node as `embedding` (used to apply MMR on the results) and filtering the nodes with
+    /// `filter` in addition to the filter of the search params.
+    ///
+    /// Filtered queries fetch `n * FILTER_OVERFETCH_FACTOR` nodes from the vector index and
+    /// return the first `n` matching the filter (see [FILTER_OVERFETCH_FACTOR]).
+    fn build_query(
+        &self,
+        prompt_embedding: Embedding,
+        return_node: bool,
+        return_embedding: bool,
+        n: usize,
+        filter: Option<&Filter>,
+    ) -> Query {
+        let (filter, filter_params) = match filter {
+            Some(filter) => {
+                let (predicate, params) = filter_to_cypher(filter, "node");
+                (Some(predicate), params)
+            }
+            None => (None, Vec::new()),
+        };
+        let conditions = self
+            .search_params
+            .post_vector_search_filter
+            .iter()
+            .map(|filter| format!("({})", filter))
+            .chain(filter)
+            .collect::<Vec<_>>();
+        let (where_clause, limit_clause, num_candidates) = match conditions.is_empty() {
+            false => (
+                format!("WHERE {}", conditions.join(" AND ")),
+                format!("\n\tLIMIT {}", n),
+                n.saturating_mul(FILTER_OVERFETCH_FACTOR),
+            ),
+            true => ("".to_string(), "".to_string(), n),
         };
 
         // Propertiy containing the embedding vectors are excluded from the returned node
@@ -142,7 +204,7 @@ impl<M: EmbeddingModel> Neo4jVectorIndex<M> {
             "\
             {}\
             \t{}\n\
-            \tRETURN score, ID(node) as element_id {}
+            \tRETURN score, ID(node) as element_id {}{}{}
             ",
             BASE_VECTOR_SEARCH_QUERY,
             where_clause,
@@ -153,18 +215,133 @@ impl<M: EmbeddingModel> Neo4jVectorIndex<M> {
                 )
             } else {
                 "".to_string()
-            }
+            },
+            if return_embedding {
+                format!(
+                    ", node.{} as embedding",
+                    self.index_config.embedding_property
+                )
+            } else {
+                "".to_string()
+            },
+            limit_clause
         );
 
         tracing::debug!("Query before params: {}", query);
 
-        Query::new(query)
+        let query = Query::new(query)
             .param("queryVector", prompt_embedding.vec)
-            .param("num_candidates", n as i64)
-            .param("index_name", self.index_config.index_name.clone())
+            .param("num_candidates", num_candidates as i64)
+            .param("index_name", self.index_config.index_name.clone());
+        with_filter_params(query, filter_params)
+    }
+
+    /// Label of the nodes of the index, required to write documents
+    fn node_label(&self) -> Result<String, VectorStoreError> {
+        self.index_config
+            .node_label
+            .as_ref()
+            .map(|label| format!("`{}`", label.replace('`', "``")))
+            .ok_or_else(|| {
+                VectorStoreError::DatastoreError(
+                    format!(
+                        "Unknown node label of index `{}`, see `IndexConfig::node_label`",
+                        self.index_config.index_name
+                    )
+                    .into(),
+                )
+            })
+    }
+
+    /// Run a query deleting nodes and returning their number as `deleted`
+    async fn execute_delete(&self, query: Query) -> Result<usize, VectorStoreError> {
+        #[derive(Deserialize)]
+        struct Deleted {
+            deleted: i64,
+        }
+
+        let rows = Neo4jClient::execute_and_collect::<Deleted>(&self.graph, query).await?;
+        Ok(rows.first().map_or(0, |row| row.deleted as usize))
     }
 }
 
+/// Translate a [Filter] to a Cypher predicate on the properties of the node bound to `variable`
+/// (e.g.: `node.chain = $filter_0`), along with the parameters the predicate refers to. The
+/// values of the filter are never written in the predicate: each one is referred to by a
+/// `$filter_N` placeholder and must be attached to the query with [Query::param].
+/// Comparisons on missing properties evaluate to false rather than null, so that negations
+/// match the nodes missing the property.
+pub fn filter_to_cypher(
+    filter: &Filter,
+    variable: &str,
+) -> (String, Vec<(String, serde_json::Value)>) {
+    let mut params = Vec::new();
+    let predicate = write_cypher_predicate(filter, variable, &mut params);
+    (predicate, params)
+}
+
+/// Attach the parameters returned by [filter_to_cypher] to `query`
+fn with_filter_params(query: Query, params: Vec<(String, serde_json::Value)>) -> Query {
+    params.into_iter().fold(query, |query, (key, value)| {
+        query.param(&key, value.to_bolt_type())
+    })
+}
+
+fn write_cypher_predicate(
+    filter: &Filter,
+    variable: &str,
+    params: &mut Vec<(String, serde_json::Value)>,
+) -> String {
+    let property = |field: &str| {
+        std::iter::once(variable.to_string())
+            .chain(
+                field
+                    .split('.')
+                    .map(|key| format!("`{}`", key.replace('`', "``"))),
+            )
+            .collect::<Vec<_>>()
+            .join(".")
+    };
+    let mut comparison = |field: &str, operator: &str, value: serde_json::Value| {
+        let key = format!("filter_{}", params.len());
+        let predicate = format!("coalesce({} {} ${}, false)", property(field), operator, key);
+        params.push((key, value));
+        predicate
+    };
+
+    match filter {
+        Filter::Eq { field, value } => comparison(field, "=", value.clone()),
+        Filter::Ne { field, value } => format!("NOT {}", comparison(field, "=", value.clone())),
+        Filter::Gt { field, value } => comparison(field, ">", value.clone()),
+        Filter::Gte { field, value } => comparison(field, ">=", value.clone()),
+        Filter::Lt { field, value } => comparison(field, "<", value.clone()),
+        Filter::Lte { field, value } => comparison(field, "<=", value.clone()),
+        Filter::In { field, values } => {
+            comparison(field, "IN", serde_json::Value::Array(values.clone()))
+        }
+        Filter::And(filters) => join_cypher_predicates(filters, variable, params, " AND ", "true"),
+        Filter::Or(filters) => join_cypher_predicates(filters, variable, params, " OR ", "false"),
+        Filter::Not(filter) => format!("NOT {}", write_cypher_predicate(filter, variable, params)),
+    }
+}
+
+fn join_cypher_predicates(
+    filters: &[Filter],
+    variable: &str,
+    params: &mut Vec<(String, serde_json::Value)>,
+    operator: &str,
+    empty: &str,
+) -> String {
+    if filters.is_empty() {
+        return empty.to_string();
+    }
+    let filters = filters
+        .iter()
+        .map(|filter| write_cypher_predicate(filter, variable, params))
+        .collect::<Vec<_>>();
+    format!("({})", filters.join(operator))
+}
+
 /// Search parameters for a vector search. Neo4j currently only supports post-vector-search filtering.
 pub struct SearchParams {
     /// Sets the **post-filter** field of the search params. Uses a WHERE clause.
@@ -199,19 +376,146 @@ pub struct RowResultNode<T> {
     node: T,
 }
 
+#[derive(Debug, Deserialize)]
+struct RowResultNodeEmbedding<T> {
+    score: f64,
+    element_id: i64,
+    node: T,
+    embedding: Vec<f64>,
+}
+
 #[derive(Debug, Deserialize)]
 struct RowResult {
     score: f64,
     element_id: i64,
 }
 
+/// Documents are written as nodes with the label of the index, their properties, the id of the
+/// document as [DOCUMENT_ID_PROPERTY] and the first embedding of the document as the embedding
+/// property (Neo4j vector indexes hold a single embedding per node). Neo4j only supports
+/// primitive types and arrays as property values, so documents should not contain nested objects.
+impl<M: EmbeddingModel + Sync + Send> WritableVectorStore for Neo4jVectorIndex<M> {
+    async fn upsert_documents_with_ids<T: Serialize + Send>(
+        &mut self,
+        documents: Vec<(String, T, OneOrMany<Embedding>)>,
+    ) -> Result<(), VectorStoreError> {
+        let rows = documents
+            .into_iter()
+            .map(|(id, doc, embeddings)| {
+                if embeddings.len() > 1 {
+                    tracing::warn!(target: "rig",
+                        "Document {} has {} embeddings, only the first one is stored",
+                        id, embeddings.len()
+                    );
+                }
+                Ok(serde_json::json!({
+                    "id": id,
+                    "properties": serde_json::to_value(doc)?,
+                    "embedding": embeddings.first().vec,
+                }))
+            })
+            .collect::<Result<Vec<_>, VectorStoreError>>()?;
+
+        let query = format!(
+            "
+            UNWIND $rows AS row
+            MERGE (node:{label} {{{id}: row.id}})
+            SET node = row.properties, node.{id} = row.id, node.`{embedding}` = row.embedding
+            ",
+            label = self.node_label()?,
+            id = DOCUMENT_ID_PROPERTY,
+            embedding = self.index_config.embedding_property.replace('`', "``"),
+        );
+
+        self.graph
+            .run(Query::new(query).param("rows", rows.to_bolt_type()))
+            .await
+            .map_err(|e| VectorStoreError::DatastoreError(Box::new(e)))
+    }
+
+    async fn delete_documents(&mut self, ids: Vec<String>) -> Result<usize, VectorStoreError> {
+        let query = format!(
+            "
+            MATCH (node:{label})
+            WHERE node.{id} IN $ids
+            DETACH DELETE node
+            RETURN count(node) AS deleted
+            ",
+            label = self.node_label()?,
+            id = DOCUMENT_ID_PROPERTY,
+        );
+
+        self.execute_delete(Query::new(query).param("ids", ids))
+            .await
+    }
+
+    async fn delete_documents_by_filter(
+        &mut self,
+        filter: &Filter,
+    ) -> Result<usize, VectorStoreError> {
+        let (filter, params) = filter_to_cypher(filter, "node");
+        let query = format!(
+            "
+            MATCH (node:{label})
+            WHERE {filter}
+            DETACH DELETE node
+            RETURN count(node) AS deleted
+            ",
+            label = self.node_label()?,
+        );
+
+        self.execute_delete(with_filter_params(Query::new(query), params))
+            .await
+    }
+}
+
+impl<M: EmbeddingModel + std::marker::Sync + Send> Neo4jVectorIndex<M> {
+    /// Get the top n nodes and scores closest to `prompt_embedding`.
+    /// To be used by implementations of top_n and top_n_by_vector methods.
+    async fn search<T: for<'a> Deserialize<'a> + std::marker::Send>(
+        &self,
+        prompt_embedding: Embedding,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        let query = self.build_vector_search_query(prompt_embedding, true, n);
+
+        let rows = Neo4jClient::execute_and_collect::<RowResultNode<T>>(&self.graph, query).await?;
+
+        let results = rows
+            .into_iter()
+            .map(|row| (row.score, row.element_id.to_string(), row.node))
+            .collect::<Vec<_>>();
+
+        Ok(results)
+    }
+
+    /// Get the top n ids and scores closest to `prompt_embedding`.
+    /// To be used by implementations of top_n_ids and top_n_ids_by_vector methods.
+    async fn search_ids(
+        &self,
+        prompt_embedding: Embedding,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        let query = self.build_vector_search_query(prompt_embedding, false, n);
+
+        let rows = Neo4jClient::execute_and_collect::<RowResult>(&self.graph, query).await?;
+
+        let results = rows
+            .into_iter()
+            .map(|row| (row.score, row.element_id.to_string()))
+            .collect::<Vec<_>>();
+
+        Ok(results)
+    }
+}
+
 impl<M: EmbeddingModel + std::marker::Sync + Send> VectorStoreIndex for Neo4jVectorIndex<M> {
     /// Get the top n nodes and scores matching the query.
     ///
     /// #### Generic Type Parameters
     ///
     /// - `T`: The type used to deserialize the result from the Neo4j query.
-    ///        It must implement the `serde::Deserialize` trait.
+    ///   It must implement the `serde::Deserialize` trait.
     ///
     /// #### Returns
     ///
@@ -225,17 +529,62 @@ impl<M: EmbeddingModel + std::marker::Sync + Send> VectorStoreIndex for Neo4jVec
         query: &str,
         n: usize,
     ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
-        let prompt_embedding = self.embedding_model.embed_text(query).await?;
-        let query = self.build_vector_search_query(prompt_embedding, true, n);
+        telemetry::instrument_retrieval(telemetry::retrieval_span("neo4j", query, n), async move {
+            let prompt_embedding = self.embedding_model.embed_text(query).await?;
 
-        let rows = Neo4jClient::execute_and_collect::<RowResultNode<T>>(&self.graph, query).await?;
-
-        let results = rows
-            .into_iter()
-            .map(|row| (row.score, row.element_id.to_string(), row.node))
-            .collect::<Vec<_>>();
+            self.search(prompt_embedding, n).await
+        })
+        .await
+    }
 
-        Ok(results)
+    /// Get the top n nodes and scores matching the query, applying the search `options` to the
+    /// results of the vector search: the filter is added to the `WHERE` clause of the query (see
+    /// [filter_to_cypher]), the minimum score filters the candidates, and MMR selects
+    /// among them using their embeddings (which are only fetched when MMR is enabled).
+    ///
+    /// Since Neo4j filters the nodes after the vector search, filtered searches over-fetch
+    /// candidates and can return fewer than `n` results (see [FILTER_OVERFETCH_FACTOR]).
+    async fn top_n_with_options<T: for<'a> Deserialize<'a> + std::marker::Send>(
+        &self,
+        query: &str,
+        n: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        telemetry::instrument_retrieval(telemetry::retrieval_span("neo4j", query, n), async move {
+            let prompt_embedding = self.embedding_model.embed_text(query).await?;
+            let query_vector = prompt_embedding.vec.clone();
+            let query = self.build_query(
+                prompt_embedding,
+                true,
+                options.mmr.is_some(),
+                options.candidates(n),
+                options.filter.as_ref(),
+            );
+
+            let candidates = if options.mmr.is_some() {
+                Neo4jClient::execute_and_collect::<RowResultNodeEmbedding<T>>(&self.graph, query)
+                    .await?
+                    .into_iter()
+                    .map(|row| {
+                        (
+                            row.score,
+                            row.element_id.to_string(),
+                            row.node,
+                            row.embedding,
+                        )
+                    })
+                    .collect()
+            } else {
+                Neo4jClient::execute_and_collect::<RowResultNode<T>>(&self.graph, query)
+                    .await?
+                    .into_iter()
+                    .map(|row| (row.score, row.element_id.to_string(), row.node, vec![]))
+                    .collect()
+            };
+
+            Ok(search::apply_options(&query_vector, candidates, n, options))
+        })
+        .await
     }
 
     /// Get the top n ids and scores matching the query. Runs faster than top_n since it doesn't need to transfer and parse
@@ -245,17 +594,96 @@ impl<M: EmbeddingModel + std::marker::Sync + Send> VectorStoreIndex for Neo4jVec
         query: &str,
         n: usize,
     ) -> Result<Vec<(f64, String)>, VectorStoreError> {
-        let prompt_embedding = self.embedding_model.embed_text(query).await?;
+        telemetry::instrument_retrieval(telemetry::retrieval_span("neo4j", query, n), async move {
+            let prompt_embedding = self.embedding_model.embed_text(query).await?;
 
-        let query = self.build_vector_search_query(prompt_embedding, false, n);
+            self.search_ids(prompt_embedding, n).await
+        })
+        .await
+    }
+}
 
-        let rows = Neo4jClient::execute_and_collect::<RowResult>(&self.graph, query).await?;
+impl<M: EmbeddingModel + std::marker::Sync + Send> VectorSearchIndex for Neo4jVectorIndex<M> {
+    type Model = M;
 
-        let results = rows
-            .into_iter()
-            .map(|row| (row.score, row.element_id.to_string()))
-            .collect::<Vec<_>>();
+    fn embedding_model(&self) -> &M {
+        &self.embedding_model
+    }
 
-        Ok(results)
+    /// Get the top n nodes and scores closest to the embedding `vector`.
+    async fn top_n_by_vector<T: for<'a> Deserialize<'a> + std::marker::Send>(
+        &self,
+        vector: &[f64],
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::vector_retrieval_span("neo4j", vector.len(), n),
+            async move {
+                self.search(query_embedding(&self.embedding_model, vector)?, n)
+                    .await
+            },
+        )
+        .await
+    }
+
+    /// Get the top n ids and scores closest to the embedding `vector`.
+    async fn top_n_ids_by_vector(
+        &self,
+        vector: &[f64],
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::vector_retrieval_span("neo4j", vector.len(), n),
+            async move {
+                self.search_ids(query_embedding(&self.embedding_model, vector)?, n)
+                    .await
+            },
+        )
+        .await
+    }
+}
+
+/// Embedding of a query given as a vector, which must have the dimensions of the embeddings
+/// of the `model`
+fn query_embedding(
+    model: &impl EmbeddingModel,
+    vector: &[f64],
+) -> Result<Embedding, VectorStoreError> {
+    check_query_dimensions(vector, model.ndims())?;
+    Ok(Embedding {
+        document: String::new(),
+        vec: vector.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_to_cypher() {
+        let filter = Filter::eq("chain", "solana")
+            .and(Filter::gte("metadata.price", 1.5))
+            .and(!Filter::one_of("symbol", ["BONK", "it's"]))
+            .and(Filter::ne("status", "draft").or(Filter::Or(vec![])));
+
+        let (predicate, params) = filter_to_cypher(&filter, "node");
+
+        assert_eq!(
+            predicate,
+            "(coalesce(node.`chain` = $filter_0, false) \
+            AND coalesce(node.`metadata`.`price` >= $filter_1, false) \
+            AND NOT coalesce(node.`symbol` IN $filter_2, false) \
+            AND (NOT coalesce(node.`status` = $filter_3, false) OR false))"
+        );
+        assert_eq!(
+            params,
+            vec![
+                ("filter_0".to_string(), serde_json::json!("solana")),
+                ("filter_1".to_string(), serde_json::json!(1.5)),
+                ("filter_2".to_string(), serde_json::json!(["BONK", "it's"])),
+                ("filter_3".to_string(), serde_json::json!("draft")),
+            ]
+        );
     }
 }
@@ -6,7 +6,9 @@ use testcontainers::{
 };
 
 use futures::{StreamExt, TryStreamExt};
-use rig::vector_store::VectorStoreIndex;
+use rig::vector_store::{
+    filter::Filter, SearchOptions, VectorSearchIndex, VectorStoreIndex, WritableVectorStore,
+};
 use rig::{
     embeddings::{Embedding, EmbeddingsBuilder},
     providers::openai,
@@ -119,6 +121,42 @@ async fn vector_search_test() {
             ));
     });
 
+    server.mock(|when, then| {
+        when.method(httpmock::Method::POST)
+            .path("/embeddings")
+            .header("Authorization", "Bearer TEST")
+            .json_body(json!({
+                "input": [
+                    "What is a glarb?",
+                    "What is a flurbo?",
+                ],
+                "model": "text-embedding-ada-002",
+            }));
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                    "object": "list",
+                    "data": [
+                      {
+                        "object": "embedding",
+                        "embedding": vec![0.0024064254; 1536],
+                        "index": 0
+                      },
+                      {
+                        "object": "embedding",
+                        "embedding": vec![-0.001; 1536],
+                        "index": 1
+                      }
+                    ],
+                    "model": "text-embedding-ada-002",
+                    "usage": {
+                      "prompt_tokens": 12,
+                      "total_tokens": 12
+                    }
+                }
+            ));
+    });
+
     // Initialize OpenAI client
     let openai_client = openai::Client::from_url("TEST", &server.base_url());
 
@@ -184,7 +222,7 @@ async fn vector_search_test() {
 
     // Create a vector index on our vector store
     // IMPORTANT: Reuse the same model that was used to generate the embeddings
-    let index = neo4j_client
+    let mut index = neo4j_client
         .get_index(model, "vector_index", SearchParams::default())
         .await
         .expect("");
@@ -204,7 +242,72 @@ async fn vector_search_test() {
             "document": "Definition of a *glarb-glarb*: A glarb-glarb is a ancient tool used by the ancestors of the inhabitants of planet Jiro to farm the land.",
             "embedding": serde_json::Value::Null
         })
-    )
+    );
+
+    // Filtered nodes are excluded from the results
+    let results = index
+        .top_n_with_options::<serde_json::Value>(
+            "What is a glarb?",
+            3,
+            &SearchOptions::new().filter(Filter::ne("id", "doc1")),
+        )
+        .await
+        .expect("");
+
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|(_, _, value)| value["id"] != "doc1"));
+
+    // Searching with the embedding of the query gives the same results as the query
+    let expected = index.top_n_ids("What is a glarb?", 1).await.expect("");
+    let results = index
+        .top_n_ids_by_vector(&[0.0024064254; 1536], 1)
+        .await
+        .expect("");
+    assert_eq!(results, expected);
+
+    // Both queries are embedded with a single request
+    let results = index
+        .top_n_batch::<serde_json::Value>(&["What is a glarb?", "What is a flurbo?"], 1)
+        .await
+        .expect("");
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0][0].2["id"], "doc1");
+    assert_ne!(results[1][0].2["id"], "doc1");
+
+    // Documents are written as nodes with the label of the index
+    let word = |id: &str, document: &str| {
+        (
+            id.to_string(),
+            json!({ "document": document }),
+            OneOrMany::one(Embedding {
+                document: document.to_string(),
+                vec: vec![0.3; 1536],
+            }),
+        )
+    };
+    index
+        .upsert_documents_with_ids(vec![
+            word("doc3", "Definition of a *blorp*"),
+            word("doc4", "Definition of a *florp*"),
+        ])
+        .await
+        .expect("");
+    index
+        .upsert_documents_with_ids(vec![word("doc4", "Definition of a *florp*: updated")])
+        .await
+        .expect("");
+
+    let deleted = index
+        .delete_documents_by_filter(&Filter::eq("document", "Definition of a *florp*: updated"))
+        .await
+        .expect("");
+    assert_eq!(deleted, 1);
+
+    let deleted = index
+        .delete_documents(vec!["doc3".to_string(), "doc4".to_string()])
+        .await
+        .expect("");
+    assert_eq!(deleted, 1);
 }
 
 async fn create_embeddings(model: openai::EmbeddingModel) -> Vec<(Word, OneOrMany<Embedding>)> {
@@ -0,0 +1,787 @@
+//! PostgreSQL implementation of a Rig vector store, using the
+//! [pgvector](https://github.com/pgvector/pgvector) extension.
+//!
+//! The documents of an index are stored in a table with one row per embedding:
+//! ```sql
+//! CREATE TABLE {table} (
+//!     id TEXT NOT NULL,
+//!     position INTEGER NOT NULL,   -- position of the embedding in the embeddings of the document
+//!     document JSONB NOT NULL,     -- the serialized document, filtered on with `filter_to_sql`
+//!     embedded_text TEXT NOT NULL, -- text the embedding was generated from
+//!     embedding vector({ndims}) NOT NULL,
+//!     PRIMARY KEY (id, position)
+//! );
+//! ```
+//! The table can be created with [PostgresVectorIndex::create_table], and its embeddings indexed
+//! for approximate nearest neighbor search with [PostgresVectorIndex::create_hnsw_index] or
+//! [PostgresVectorIndex::create_ivfflat_index].
+//!
+//! # Filtered searches
+//! Filters are applied to the rows returned by the approximate index scans, which return a
+//! limited number of rows (e.g.: `hnsw.ef_search`, 40 by default). Filtered searches using an
+//! HNSW or IVFFlat index can therefore return fewer than `n` documents, unless the
+//! [iterative index scans](https://github.com/pgvector/pgvector#iterative-index-scans) of
+//! pgvector 0.8+ are enabled on the connection:
+//! ```sql
+//! SET hnsw.iterative_scan = relaxed_order;   -- or strict_order
+//! SET ivfflat.iterative_scan = relaxed_order;
+//! ```
+//!
+//! # Example
+//! ```rust,ignore
+//! use rig::{embeddings::EmbeddingsBuilder, vector_store::{VectorStoreIndex, WritableVectorStore}};
+//! use rig_postgres::{DistanceOperator, HnswParams, PostgresVectorIndex};
+//!
+//! let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls).await?;
+//! tokio::spawn(connection);
+//!
+//! let mut index = PostgresVectorIndex::new(client, model.clone(), "words")?
+//!     .with_distance(DistanceOperator::Cosine);
+//! index.create_table().await?;
+//! index.create_hnsw_index(HnswParams::new()).await?;
+//!
+//! // Documents are written with a bulk `COPY`
+//! let embeddings = EmbeddingsBuilder::new(model).documents(words)?.build().await?;
+//! index.upsert_documents(embeddings, |word| word.id.clone()).await?;
+//!
+//! let results = index.top_n::<Word>("What is a linglingdong?", 1).await?;
+//! ```
+use std::collections::HashSet;
+
+use pgvector::Vector;
+use rig::{
+    embeddings::{distance::DistanceMetric, Embedding, EmbeddingModel},
+    telemetry,
+    vector_store::{
+        check_query_dimensions, filter::Filter, search, SearchOptions, VectorSearchIndex,
+        VectorStoreError, VectorStoreIndex, WritableVectorStore,
+    },
+    OneOrMany,
+};
+use serde::{Deserialize, Serialize};
+use tokio_postgres::{binary_copy::BinaryCopyInWriter, types::ToSql, Client};
+
+/// Parameter of a query
+pub type SqlParam = Box<dyn ToSql + Sync + Send>;
+
+fn postgres_to_rig_error(e: tokio_postgres::Error) -> VectorStoreError {
+    VectorStoreError::DatastoreError(Box::new(e))
+}
+
+/// Distance operators of pgvector.
+/// See [pgvector querying](https://github.com/pgvector/pgvector#querying) for more information.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DistanceOperator {
+    /// Cosine distance (`<=>`)
+    #[default]
+    Cosine,
+    /// Euclidean distance (`<->`)
+    L2,
+    /// Negative inner product (`<#>`)
+    InnerProduct,
+}
+
+impl DistanceOperator {
+    fn operator(&self) -> &'static str {
+        match self {
+            Self::Cosine => "<=>",
+            Self::L2 => "<->",
+            Self::InnerProduct => "<#>",
+        }
+    }
+
+    /// Operator class of the indexes of the embeddings for the operator
+    fn operator_class(&self) -> &'static str {
+        match self {
+            Self::Cosine => "vector_cosine_ops",
+            Self::L2 => "vector_l2_ops",
+            Self::InnerProduct => "vector_ip_ops",
+        }
+    }
+
+    /// Convert a distance to a score where higher is closer: the cosine similarity, the inner
+    /// product, or `1 / (1 + d)` for the euclidean distance (see [DistanceMetric::score_of]).
+    fn score(&self, distance: f64) -> f64 {
+        match self {
+            Self::Cosine => 1.0 - distance,
+            Self::L2 => DistanceMetric::Euclidean.score_of(distance),
+            Self::InnerProduct => -distance,
+        }
+    }
+}
+
+/// Parameters of an HNSW index (see [PostgresVectorIndex::create_hnsw_index]).
+/// See [pgvector HNSW](https://github.com/pgvector/pgvector#hnsw) for more information.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HnswParams {
+    m: Option<u32>,
+    ef_construction: Option<u32>,
+}
+
+impl HnswParams {
+    /// Initializes a new `HnswParams` with the defaults of pgvector.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of connections per layer (16 by default).
+    pub fn m(mut self, m: u32) -> Self {
+        self.m = Some(m);
+        self
+    }
+
+    /// Sets the size of the dynamic candidate list for constructing the graph (64 by default).
+    pub fn ef_construction(mut self, ef_construction: u32) -> Self {
+        self.ef_construction = Some(ef_construction);
+        self
+    }
+}
+
+/// Translate a [Filter] to a SQL predicate on the JSONB documents of the `column` (e.g.:
+/// `"document"`), returning the predicate and its parameters, numbered from `$first_param`.
+///
+/// Comparisons are only true between values of the same JSON type, and comparisons on missing
+/// fields evaluate to false rather than null, so that negations match the documents missing the
+/// field.
+pub fn filter_to_sql(filter: &Filter, column: &str, first_param: usize) -> (String, Vec<SqlParam>) {
+    fn comparison(
+        column: &str,
+        field: &str,
+        operator: &str,
+        value: &serde_json::Value,
+        params: &mut Vec<SqlParam>,
+        first_param: usize,
+    ) -> String {
+        // Null fields are considered missing
+        if value.is_null() {
+            return "false".to_string();
+        }
+
+        let path = field.split('.').map(str::to_string).collect::<Vec<_>>();
+        params.push(Box::new(path));
+        params.push(Box::new(value.clone()));
+        let (path, value) = (
+            first_param + params.len() - 2,
+            first_param + params.len() - 1,
+        );
+        format!(
+            "COALESCE(jsonb_typeof({column} #> ${path}) = jsonb_typeof(${value}::jsonb) \
+            AND {column} #> ${path} {operator} ${value}::jsonb, false)"
+        )
+    }
+
+    fn translate(
+        filter: &Filter,
+        column: &str,
+        params: &mut Vec<SqlParam>,
+        first_param: usize,
+    ) -> String {
+        let mut join = |filters: &[Filter], operator: &str, empty: &str| {
+            if filters.is_empty() {
+                return empty.to_string();
+            }
+            let filters = filters
+                .iter()
+                .map(|filter| translate(filter, column, params, first_param))
+                .collect::<Vec<_>>();
+            format!("({})", filters.join(operator))
+        };
+
+        match filter {
+            Filter::Eq { field, value } => {
+                comparison(column, field, "=", value, params, first_param)
+            }
+            Filter::Ne { field, value } => format!(
+                "NOT {}",
+                comparison(column, field, "=", value, params, first_param)
+            ),
+            Filter::Gt { field, value } => {
+                comparison(column, field, ">", value, params, first_param)
+            }
+            Filter::Gte { field, value } => {
+                comparison(column, field, ">=", value, params, first_param)
+            }
+            Filter::Lt { field, value } => {
+                comparison(column, field, "<", value, params, first_param)
+            }
+            Filter::Lte { field, value } => {
+                comparison(column, field, "<=", value, params, first_param)
+            }
+            Filter::In { field, values } => join(
+                &values
+                    .iter()
+                    .map(|value| Filter::eq(field.clone(), value.clone()))
+                    .collect::<Vec<_>>(),
+                " OR ",
+                "false",
+            ),
+            Filter::And(filters) => join(filters, " AND ", "true"),
+            Filter::Or(filters) => join(filters, " OR ", "false"),
+            Filter::Not(filter) => {
+                format!("NOT {}", translate(filter, column, params, first_param))
+            }
+        }
+    }
+
+    let mut params = Vec::new();
+    let predicate = translate(filter, column, &mut params, first_param);
+    (predicate, params)
+}
+
+/// Document returned by a search
+struct SearchResult {
+    score: f64,
+    id: String,
+    document: Option<serde_json::Value>,
+    embedding: Option<Vector>,
+}
+
+/// A vector index for a PostgreSQL table with a pgvector column.
+/// # Example
+/// ```rust,ignore
+/// use rig::{providers::openai, vector_store::VectorStoreIndex};
+/// use rig_postgres::PostgresVectorIndex;
+///
+/// #[derive(serde::Deserialize, serde::Serialize, Debug)]
+/// struct WordDefinition {
+///     id: String,
+///     definition: String,
+/// }
+///
+/// let (client, connection) =
+///     tokio_postgres::connect("postgres://localhost/rig", tokio_postgres::NoTls).await?; // <-- replace with your database url.
+/// tokio::spawn(connection);
+///
+/// let openai_client = openai::Client::from_env();
+/// let model = openai_client.embedding_model(openai::TEXT_EMBEDDING_ADA_002); // <-- replace with your embedding model.
+///
+/// let index = PostgresVectorIndex::new(client, model, "definitions")?; // <-- replace with the name of your table.
+///
+/// // Query the index
+/// let definitions = index
+///     .top_n::<WordDefinition>("My boss says I zindle too much, what does that mean?", 1)
+///     .await?;
+/// ```
+pub struct PostgresVectorIndex<M: EmbeddingModel> {
+    client: Client,
+    model: M,
+    table: String,
+    distance: DistanceOperator,
+    oversampling: usize,
+}
+
+impl<M: EmbeddingModel> PostgresVectorIndex<M> {
+    /// Create a new `PostgresVectorIndex` on the table `table` (see [PostgresVectorIndex::create_table]).
+    pub fn new(client: Client, model: M, table: &str) -> Result<Self, VectorStoreError> {
+        let valid = table
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(VectorStoreError::DatastoreError(
+                format!("Invalid table name: {table}").into(),
+            ));
+        }
+
+        Ok(Self {
+            client,
+            model,
+            table: table.to_string(),
+            distance: DistanceOperator::default(),
+            oversampling: 4,
+        })
+    }
+
+    /// Sets the distance operator used to rank the documents (cosine distance by default).
+    /// Indexes of the embeddings must be created for the same operator to be used.
+    pub fn with_distance(mut self, distance: DistanceOperator) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    /// Sets the number of embeddings ranked for each requested document (4 by default), as
+    /// documents with several embeddings may be matched by several of them.
+    pub fn with_oversampling(mut self, oversampling: usize) -> Self {
+        self.oversampling = oversampling.max(1);
+        self
+    }
+
+    /// Create the pgvector extension and the table of the index, if they do not exist, with
+    /// embeddings of the dimensions of the model of the index.
+    pub async fn create_table(&self) -> Result<(), VectorStoreError> {
+        self.client
+            .batch_execute(&format!(
+                "CREATE EXTENSION IF NOT EXISTS vector;
+                CREATE TABLE IF NOT EXISTS {table} (
+                    id TEXT NOT NULL,
+                    position INTEGER NOT NULL,
+                    document JSONB NOT NULL,
+                    embedded_text TEXT NOT NULL,
+                    embedding vector({ndims}) NOT NULL,
+                    PRIMARY KEY (id, position)
+                );",
+                table = self.table,
+                ndims = self.model.ndims(),
+            ))
+            .await
+            .map_err(postgres_to_rig_error)
+    }
+
+    /// Create an HNSW index of the embeddings for the distance operator of the index, if it
+    /// does not exist. HNSW indexes have better query performance than IVFFlat indexes, but
+    /// are slower to build. See the [crate documentation](crate#filtered-searches) for
+    /// filtered searches using the index.
+    pub async fn create_hnsw_index(&self, params: HnswParams) -> Result<(), VectorStoreError> {
+        let options = [("m", params.m), ("ef_construction", params.ef_construction)]
+            .into_iter()
+            .filter_map(|(name, value)| Some(format!("{name} = {}", value?)))
+            .collect::<Vec<_>>();
+        let options = if options.is_empty() {
+            String::new()
+        } else {
+            format!(" WITH ({})", options.join(", "))
+        };
+
+        self.create_index("hnsw", &options).await
+    }
+
+    /// Create an IVFFlat index of the embeddings with `lists` inverted lists for the distance
+    /// operator of the index, if it does not exist. The index should be created once the table
+    /// contains data, with `lists` around `rows / 1000` (up to 1M rows). See the
+    /// [crate documentation](crate#filtered-searches) for filtered searches using the index.
+    /// See [pgvector IVFFlat](https://github.com/pgvector/pgvector#ivfflat) for more information.
+    pub async fn create_ivfflat_index(&self, lists: u32) -> Result<(), VectorStoreError> {
+        self.create_index("ivfflat", &format!(" WITH (lists = {lists})"))
+            .await
+    }
+
+    async fn create_index(&self, method: &str, options: &str) -> Result<(), VectorStoreError> {
+        self.client
+            .batch_execute(&format!(
+                "CREATE INDEX IF NOT EXISTS {table}_embedding_{method}_{class}_idx
+                ON {table} USING {method} (embedding {class}){options}",
+                table = self.table,
+                class = self.distance.operator_class(),
+            ))
+            .await
+            .map_err(postgres_to_rig_error)
+    }
+
+    /// Get the `n` documents closest to `prompt_embedding` among the documents matching
+    /// `filter`. The documents and their best matching embedding are only fetched when
+    /// requested.
+    /// To be used by implementations of the methods of VectorStoreIndex and VectorSearchIndex.
+    async fn search(
+        &self,
+        prompt_embedding: &Embedding,
+        n: usize,
+        filter: Option<&Filter>,
+        fetch_documents: bool,
+        fetch_embeddings: bool,
+    ) -> Result<Vec<SearchResult>, VectorStoreError> {
+        let query = Vector::from(
+            prompt_embedding
+                .vec
+                .iter()
+                .map(|&x| x as f32)
+                .collect::<Vec<_>>(),
+        );
+        let limit = n.saturating_mul(self.oversampling) as i64;
+        let (predicate, filter_params) = match filter {
+            Some(filter) => filter_to_sql(filter, "document", 3),
+            None => ("true".to_string(), vec![]),
+        };
+
+        let statement = format!(
+            "SELECT id, embedding {operator} $1 AS distance, {document}, {embedding}
+            FROM {table}
+            WHERE {predicate}
+            ORDER BY embedding {operator} $1
+            LIMIT $2",
+            operator = self.distance.operator(),
+            document = if fetch_documents {
+                "document"
+            } else {
+                "NULL::jsonb"
+            },
+            embedding = if fetch_embeddings {
+                "embedding"
+            } else {
+                "NULL::vector"
+            },
+            table = self.table,
+        );
+        let params = [&query as &(dyn ToSql + Sync), &limit]
+            .into_iter()
+            .chain(
+                filter_params
+                    .iter()
+                    .map(|param| param.as_ref() as &(dyn ToSql + Sync)),
+            )
+            .collect::<Vec<_>>();
+
+        let rows = self
+            .client
+            .query(&statement, &params)
+            .await
+            .map_err(postgres_to_rig_error)?;
+
+        // Keep the best matching embedding of each document
+        let mut ids = HashSet::new();
+        let mut results = Vec::new();
+        for row in rows {
+            let id: String = row.get(0);
+            if results.len() == n || !ids.insert(id.clone()) {
+                continue;
+            }
+            results.push(SearchResult {
+                score: self.distance.score(row.get(1)),
+                id,
+                document: row.get(2),
+                embedding: row.get(3),
+            });
+        }
+
+        tracing::info!(target: "rig",
+            "Selected documents: {}",
+            results.iter()
+                .map(|result| format!("{} ({})", result.id, result.score))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+
+        Ok(results)
+    }
+}
+
+/// Documents are written with a bulk `COPY` to a temporary table, from which they replace the
+/// rows of the documents with the same ids, in a single transaction.
+impl<M: EmbeddingModel + Sync + Send> WritableVectorStore for PostgresVectorIndex<M> {
+    async fn upsert_documents_with_ids<T: Serialize + Send>(
+        &mut self,
+        documents: Vec<(String, T, OneOrMany<Embedding>)>,
+    ) -> Result<(), VectorStoreError> {
+        // Keep the last copy of the documents with the same id, as if they were upserted one
+        // after the other (copies would violate the primary key of the table)
+        let mut ids = HashSet::new();
+        let mut documents = documents
+            .into_iter()
+            .rev()
+            .filter(|(id, ..)| ids.insert(id.clone()))
+            .map(|(id, doc, embeddings)| Ok((id, serde_json::to_value(doc)?, embeddings)))
+            .collect::<Result<Vec<_>, VectorStoreError>>()?;
+        documents.reverse();
+        let table = &self.table;
+
+        let tx = self
+            .client
+            .transaction()
+            .await
+            .map_err(postgres_to_rig_error)?;
+        tx.batch_execute(&format!(
+            "CREATE TEMP TABLE {table}_staging (LIKE {table}) ON COMMIT DROP"
+        ))
+        .await
+        .map_err(postgres_to_rig_error)?;
+
+        // The type of the vectors is only known once the extension is installed
+        let types = tx
+            .prepare(&format!(
+                "SELECT id, position, document, embedded_text, embedding FROM {table}_staging"
+            ))
+            .await
+            .map_err(postgres_to_rig_error)?
+            .columns()
+            .iter()
+            .map(|column| column.type_().clone())
+            .collect::<Vec<_>>();
+
+        let sink = tx
+            .copy_in(&format!(
+                "COPY {table}_staging (id, position, document, embedded_text, embedding)
+                FROM STDIN BINARY"
+            ))
+            .await
+            .map_err(postgres_to_rig_error)?;
+        let mut writer = std::pin::pin!(BinaryCopyInWriter::new(sink, &types));
+        for (id, document, embeddings) in &documents {
+            for (position, embedding) in embeddings.iter().enumerate() {
+                let vector =
+                    Vector::from(embedding.vec.iter().map(|&x| x as f32).collect::<Vec<_>>());
+                writer
+                    .as_mut()
+                    .write(&[
+                        id,
+                        &(position as i32),
+                        document,
+                        &embedding.document,
+                        &vector,
+                    ])
+                    .await
+                    .map_err(postgres_to_rig_error)?;
+            }
+        }
+        writer.finish().await.map_err(postgres_to_rig_error)?;
+
+        tx.batch_execute(&format!(
+            "DELETE FROM {table} WHERE id IN (SELECT id FROM {table}_staging);
+            INSERT INTO {table} SELECT * FROM {table}_staging;"
+        ))
+        .await
+        .map_err(postgres_to_rig_error)?;
+
+        tx.commit().await.map_err(postgres_to_rig_error)
+    }
+
+    async fn delete_documents(&mut self, ids: Vec<String>) -> Result<usize, VectorStoreError> {
+        let row = self
+            .client
+            .query_one(
+                &format!(
+                    "WITH deleted AS (DELETE FROM {} WHERE id = ANY($1) RETURNING id)
+                    SELECT COUNT(DISTINCT id) FROM deleted",
+                    self.table
+                ),
+                &[&ids],
+            )
+            .await
+            .map_err(postgres_to_rig_error)?;
+
+        Ok(row.get::<_, i64>(0) as usize)
+    }
+
+    async fn delete_documents_by_filter(
+        &mut self,
+        filter: &Filter,
+    ) -> Result<usize, VectorStoreError> {
+        let (predicate, params) = filter_to_sql(filter, "document", 1);
+        let params = params
+            .iter()
+            .map(|param| param.as_ref() as &(dyn ToSql + Sync))
+            .collect::<Vec<_>>();
+
+        let row = self
+            .client
+            .query_one(
+                &format!(
+                    "WITH deleted AS (DELETE FROM {} WHERE {predicate} RETURNING id)
+                    SELECT COUNT(DISTINCT id) FROM deleted",
+                    self.table
+                ),
+                &params,
+            )
+            .await
+            .map_err(postgres_to_rig_error)?;
+
+        Ok(row.get::<_, i64>(0) as usize)
+    }
+}
+
+impl<M: EmbeddingModel + Sync + Send> VectorStoreIndex for PostgresVectorIndex<M> {
+    /// Implement the `top_n` method of the `VectorStoreIndex` trait for `PostgresVectorIndex`.
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::retrieval_span("postgres", query, n),
+            async move {
+                let prompt_embedding = self.model.embed_text(query).await?;
+
+                documents(self.search(&prompt_embedding, n, None, true, false).await?)
+            },
+        )
+        .await
+    }
+
+    /// Implement the `top_n_with_options` method of the `VectorStoreIndex` trait for `PostgresVectorIndex`.
+    /// The filter is translated to SQL (see [filter_to_sql]) and applied by the vector search,
+    /// and the other search options are applied to its results: the minimum score filters the
+    /// candidates, and MMR selects among them using their embeddings (which are only fetched
+    /// when MMR is enabled).
+    ///
+    /// Filtered searches using an HNSW or IVFFlat index can return fewer than `n` results unless
+    /// iterative index scans are enabled (see the [crate documentation](crate#filtered-searches)).
+    async fn top_n_with_options<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::retrieval_span("postgres", query, n),
+            async move {
+                let prompt_embedding = self.model.embed_text(query).await?;
+
+                let candidates = self
+                    .search(
+                        &prompt_embedding,
+                        options.candidates(n),
+                        options.filter.as_ref(),
+                        true,
+                        options.mmr.is_some(),
+                    )
+                    .await?
+                    .into_iter()
+                    .map(|result| {
+                        Ok((
+                            result.score,
+                            result.id,
+                            serde_json::from_value(result.document.unwrap_or_default())?,
+                            result.embedding.map_or_else(Vec::new, |embedding| {
+                                embedding.to_vec().into_iter().map(f64::from).collect()
+                            }),
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, VectorStoreError>>()?;
+
+                Ok(search::apply_options(
+                    &prompt_embedding.vec,
+                    candidates,
+                    n,
+                    options,
+                ))
+            },
+        )
+        .await
+    }
+
+    /// Implement the `top_n_ids` method of the `VectorStoreIndex` trait for `PostgresVectorIndex`.
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::retrieval_span("postgres", query, n),
+            async move {
+                let prompt_embedding = self.model.embed_text(query).await?;
+
+                Ok(ids(self
+                    .search(&prompt_embedding, n, None, false, false)
+                    .await?))
+            },
+        )
+        .await
+    }
+}
+
+impl<M: EmbeddingModel + Sync + Send> VectorSearchIndex for PostgresVectorIndex<M> {
+    type Model = M;
+
+    fn embedding_model(&self) -> &M {
+        &self.model
+    }
+
+    /// Implement the `top_n_by_vector` method of the `VectorSearchIndex` trait for `PostgresVectorIndex`.
+    async fn top_n_by_vector<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        vector: &[f64],
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::vector_retrieval_span("postgres", vector.len(), n),
+            async move {
+                documents(
+                    self.search(&query_embedding(&self.model, vector)?, n, None, true, false)
+                        .await?,
+                )
+            },
+        )
+        .await
+    }
+
+    /// Implement the `top_n_ids_by_vector` method of the `VectorSearchIndex` trait for `PostgresVectorIndex`.
+    async fn top_n_ids_by_vector(
+        &self,
+        vector: &[f64],
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::vector_retrieval_span("postgres", vector.len(), n),
+            async move {
+                Ok(ids(self
+                    .search(
+                        &query_embedding(&self.model, vector)?,
+                        n,
+                        None,
+                        false,
+                        false,
+                    )
+                    .await?))
+            },
+        )
+        .await
+    }
+}
+
+/// Deserialize the documents of the results of a search
+fn documents<T: for<'a> Deserialize<'a>>(
+    results: Vec<SearchResult>,
+) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+    results
+        .into_iter()
+        .map(|result| {
+            Ok((
+                result.score,
+                result.id,
+                serde_json::from_value(result.document.unwrap_or_default())?,
+            ))
+        })
+        .collect()
+}
+
+fn ids(results: Vec<SearchResult>) -> Vec<(f64, String)> {
+    results
+        .into_iter()
+        .map(|result| (result.score, result.id))
+        .collect()
+}
+
+/// Embedding of a query given as a vector, which must have the dimensions of the embeddings
+/// of the `model`
+fn query_embedding(
+    model: &impl EmbeddingModel,
+    vector: &[f64],
+) -> Result<Embedding, VectorStoreError> {
+    check_query_dimensions(vector, model.ndims())?;
+    Ok(Embedding {
+        document: String::new(),
+        vec: vector.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_to_sql() {
+        let filter = Filter::eq("metadata.chain", "solana")
+            .and((!Filter::gt("price", 100)).or(Filter::one_of("symbol", ["SOL", "JUP"])));
+
+        let (predicate, params) = filter_to_sql(&filter, "document", 3);
+        assert_eq!(
+            predicate,
+            "(COALESCE(jsonb_typeof(document #> $3) = jsonb_typeof($4::jsonb) AND document #> $3 = $4::jsonb, false) \
+            AND (NOT COALESCE(jsonb_typeof(document #> $5) = jsonb_typeof($6::jsonb) AND document #> $5 > $6::jsonb, false) \
+            OR (COALESCE(jsonb_typeof(document #> $7) = jsonb_typeof($8::jsonb) AND document #> $7 = $8::jsonb, false) \
+            OR COALESCE(jsonb_typeof(document #> $9) = jsonb_typeof($10::jsonb) AND document #> $9 = $10::jsonb, false))))"
+        );
+        assert_eq!(params.len(), 8);
+
+        let (predicate, params) =
+            filter_to_sql(&Filter::eq("tags", serde_json::Value::Null), "document", 1);
+        assert_eq!(predicate, "false");
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn test_distance_scores() {
+        assert_eq!(DistanceOperator::Cosine.score(0.25), 0.75);
+        assert_eq!(DistanceOperator::L2.score(1.0), 0.5);
+        assert_eq!(DistanceOperator::InnerProduct.score(-0.5), 0.5);
+    }
+}
@@ -0,0 +1,313 @@
+use rig::{
+    embeddings::{Embedding, EmbeddingsBuilder},
+    providers::openai,
+    vector_store::{
+        filter::Filter, Mmr, SearchOptions, VectorSearchIndex, VectorStoreIndex,
+        WritableVectorStore,
+    },
+    Embed, OneOrMany,
+};
+use rig_postgres::{DistanceOperator, HnswParams, PostgresVectorIndex};
+use serde_json::json;
+use testcontainers::{
+    core::{IntoContainerPort, WaitFor},
+    runners::AsyncRunner,
+    GenericImage, ImageExt,
+};
+use tokio::time::{sleep, Duration};
+
+#[derive(Embed, Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+struct Word {
+    id: String,
+    #[embed]
+    definition: String,
+    planet: String,
+}
+
+const POSTGRES_PORT: u16 = 5432;
+const TABLE_NAME: &str = "words";
+const DATABASE_NAME: &str = "rig";
+const USERNAME: &str = "riguser";
+const PASSWORD: &str = "rigpassword";
+
+/// Embedding mostly along the axis `i`, so that each word is closest to its own query
+fn axis(i: usize) -> Vec<f64> {
+    let mut vec = vec![0.01; 1536];
+    vec[i] = 1.0;
+    vec
+}
+
+fn words() -> Vec<Word> {
+    vec![
+        Word {
+            id: "doc0".to_string(),
+            definition: "Definition of a *flurbo*: A flurbo is a green alien that lives on cold planets".to_string(),
+            planet: "Gazorpazorp".to_string(),
+        },
+        Word {
+            id: "doc1".to_string(),
+            definition: "Definition of a *glarb-glarb*: A glarb-glarb is a ancient tool used by the ancestors of the inhabitants of planet Jiro to farm the land.".to_string(),
+            planet: "Jiro".to_string(),
+        },
+        Word {
+            id: "doc2".to_string(),
+            definition: "Definition of a *linglingdong*: A term used by inhabitants of the far side of the moon to describe humans.".to_string(),
+            planet: "Moon".to_string(),
+        },
+    ]
+}
+
+#[tokio::test]
+async fn vector_search_test() {
+    // Setup mock openai API
+    let server = httpmock::MockServer::start();
+
+    server.mock(|when, then| {
+        when.method(httpmock::Method::POST)
+            .path("/embeddings")
+            .header("Authorization", "Bearer TEST")
+            .json_body(json!({
+                "input": words().into_iter().map(|word| word.definition).collect::<Vec<_>>(),
+                "model": "text-embedding-ada-002",
+            }));
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                    "object": "list",
+                    "data": [
+                      {
+                        "object": "embedding",
+                        "embedding": axis(0),
+                        "index": 0
+                      },
+                      {
+                        "object": "embedding",
+                        "embedding": axis(1),
+                        "index": 1
+                      },
+                      {
+                        "object": "embedding",
+                        "embedding": axis(2),
+                        "index": 2
+                      }
+                    ],
+                    "model": "text-embedding-ada-002",
+                    "usage": {
+                      "prompt_tokens": 8,
+                      "total_tokens": 8
+                    }
+                }
+            ));
+    });
+    server.mock(|when, then| {
+        when.method(httpmock::Method::POST)
+            .path("/embeddings")
+            .header("Authorization", "Bearer TEST")
+            .json_body(json!({
+                "input": [
+                    "What is a linglingdong?"
+                ],
+                "model": "text-embedding-ada-002",
+            }));
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                    "object": "list",
+                    "data": [
+                      {
+                        "object": "embedding",
+                        "embedding": axis(2),
+                        "index": 0
+                      }
+                    ],
+                    "model": "text-embedding-ada-002",
+                    "usage": {
+                      "prompt_tokens": 8,
+                      "total_tokens": 8
+                    }
+                }
+            ));
+    });
+
+    // Initialize OpenAI client
+    let openai_client = openai::Client::from_url("TEST", &server.base_url());
+
+    // Select the embedding model and generate our embeddings
+    let model = openai_client.embedding_model(openai::TEXT_EMBEDDING_ADA_002);
+
+    // Setup a local PostgreSQL container with pgvector for testing. NOTE: docker service must be running.
+    let container = GenericImage::new("pgvector/pgvector", "pg16")
+        .with_exposed_port(POSTGRES_PORT.tcp())
+        .with_wait_for(WaitFor::message_on_stderr(
+            "database system is ready to accept connections",
+        ))
+        .with_env_var("POSTGRES_DB", DATABASE_NAME)
+        .with_env_var("POSTGRES_USER", USERNAME)
+        .with_env_var("POSTGRES_PASSWORD", PASSWORD)
+        .start()
+        .await
+        .expect("Failed to start PostgreSQL container");
+
+    let port = container.get_host_port_ipv4(POSTGRES_PORT).await.unwrap();
+    let host = container.get_host().await.unwrap().to_string();
+
+    let client = connect(&host, port).await;
+
+    // Create the table and an HNSW index of its embeddings
+    let mut index = PostgresVectorIndex::new(client, model.clone(), TABLE_NAME)
+        .unwrap()
+        .with_distance(DistanceOperator::Cosine);
+    index.create_table().await.unwrap();
+    index
+        .create_hnsw_index(HnswParams::new().m(16).ef_construction(64))
+        .await
+        .unwrap();
+
+    // Documents are written with a bulk COPY
+    let embeddings = EmbeddingsBuilder::new(model)
+        .documents(words())
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    index
+        .upsert_documents(embeddings, |word| word.id.clone())
+        .await
+        .unwrap();
+
+    // Query the index
+    let results = index
+        .top_n::<Word>("What is a linglingdong?", 1)
+        .await
+        .unwrap();
+    let (score, id, word) = &results[0];
+    assert_eq!(id, "doc2");
+    assert_eq!(word, &words()[2]);
+    assert!((score - 1.0).abs() < 1e-3);
+
+    // Scores are at most 1, so no document passes the cutoff
+    let results = index
+        .top_n_with_options::<Word>(
+            "What is a linglingdong?",
+            1,
+            &SearchOptions::new().min_score(1.01),
+        )
+        .await
+        .unwrap();
+    assert!(results.is_empty());
+
+    // Embeddings are fetched for MMR
+    let results = index
+        .top_n_with_options::<Word>(
+            "What is a linglingdong?",
+            2,
+            &SearchOptions::new().mmr(Mmr::new(0.5)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].1, "doc2");
+
+    // The filter is applied before the vector search, so that the next best document is returned
+    let results = index
+        .top_n_with_options::<Word>(
+            "What is a linglingdong?",
+            3,
+            &SearchOptions::new().filter(Filter::ne("planet", "Moon")),
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|(_, id, _)| id != "doc2"));
+
+    // Searching with the embedding of the query gives the same results as the query
+    let expected = index.top_n_ids("What is a linglingdong?", 1).await.unwrap();
+    let results = index.top_n_ids_by_vector(&axis(2), 1).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].1, expected[0].1);
+    let results = index.top_n_by_vector::<Word>(&axis(0), 1).await.unwrap();
+    assert_eq!(results[0].2.id, "doc0");
+
+    // Documents are replaced by id, and deleted by id or filter
+    let word = |id: &str, planet: &str, embeddings: &[usize]| {
+        (
+            id.to_string(),
+            Word {
+                id: id.to_string(),
+                definition: format!("Definition of a *{id}*"),
+                planet: planet.to_string(),
+            },
+            OneOrMany::many(embeddings.iter().map(|&i| Embedding {
+                document: format!("Definition of a *{id}*"),
+                vec: axis(i),
+            }))
+            .unwrap(),
+        )
+    };
+    index
+        .upsert_documents_with_ids(vec![
+            word("doc3", "Earth", &[3]),
+            word("doc4", "Mars", &[4]),
+        ])
+        .await
+        .unwrap();
+    // Documents with several embeddings are returned once, with their best matching embedding.
+    // The last copy of documents upserted several times in a batch is kept
+    index
+        .upsert_documents_with_ids(vec![
+            word("doc4", "Jupiter", &[4]),
+            word("doc4", "Venus", &[5, 6]),
+        ])
+        .await
+        .unwrap();
+    let results = index.top_n_by_vector::<Word>(&axis(6), 5).await.unwrap();
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0].1, "doc4");
+    assert_eq!(results[0].2.planet, "Venus");
+    assert!((results[0].0 - 1.0).abs() < 1e-3);
+
+    assert_eq!(
+        index
+            .delete_documents_by_filter(&Filter::one_of("planet", ["Venus", "Jupiter"]))
+            .await
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        index
+            .delete_documents(vec!["doc3".to_string(), "doc4".to_string()])
+            .await
+            .unwrap(),
+        1
+    );
+    let results = index.top_n_ids_by_vector(&axis(3), 5).await.unwrap();
+    assert_eq!(results.len(), 3);
+}
+
+async fn connect(host: &str, port: u16) -> tokio_postgres::Client {
+    let max_attempts = 5;
+
+    // The server restarts once its initialization scripts have run
+    for attempt in 0..max_attempts {
+        match tokio_postgres::connect(
+            &format!("host={host} port={port} user={USERNAME} password={PASSWORD} dbname={DATABASE_NAME}"),
+            tokio_postgres::NoTls,
+        )
+        .await
+        {
+            Ok((client, connection)) => {
+                tokio::spawn(connection);
+                return client;
+            }
+            Err(_) => {
+                println!(
+                    "Waiting for PostgreSQL... {} attempts remaining",
+                    max_attempts - attempt - 1
+                );
+                sleep(Duration::from_secs(2)).await;
+            }
+        }
+    }
+
+    panic!("Failed to connect to PostgreSQL after {max_attempts} attempts");
+}
@@ -0,0 +1,84 @@
+use std::env;
+
+use rig::{
+    embeddings::EmbeddingsBuilder,
+    providers::openai::{Client, TEXT_EMBEDDING_ADA_002},
+    vector_store::{filter::Filter, SearchOptions, VectorStoreIndex, WritableVectorStore},
+    Embed,
+};
+use rig_sqlite::{Connection, SqliteVectorIndex};
+use serde::{Deserialize, Serialize};
+
+// Shape of data that needs to be RAG'ed.
+// The definition field will be used to generate embeddings.
+#[derive(Embed, Clone, Serialize, Deserialize, Debug)]
+struct Word {
+    id: String,
+    #[embed]
+    definition: String,
+    planet: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    // Initialize OpenAI client
+    let openai_api_key = env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY not set");
+    let openai_client = Client::new(&openai_api_key);
+
+    // Open (or create) the SQLite database
+    let connection = Connection::open("vector_store.db").await?;
+
+    // Select the embedding model and generate our embeddings
+    let model = openai_client.embedding_model(TEXT_EMBEDDING_ADA_002);
+
+    let words = vec![
+        Word {
+            id: "doc0".to_string(),
+            definition: "Definition of a *flurbo*: A flurbo is a green alien that lives on cold planets".to_string(),
+            planet: "Gazorpazorp".to_string(),
+        },
+        Word {
+            id: "doc1".to_string(),
+            definition: "Definition of a *glarb-glarb*: A glarb-glarb is a ancient tool used by the ancestors of the inhabitants of planet Jiro to farm the land.".to_string(),
+            planet: "Jiro".to_string(),
+        },
+        Word {
+            id: "doc2".to_string(),
+            definition: "Definition of a *linglingdong*: A term used by inhabitants of the far side of the moon to describe humans.".to_string(),
+            planet: "Moon".to_string(),
+        }
+    ];
+
+    let embeddings = EmbeddingsBuilder::new(model.clone())
+        .documents(words)?
+        .build()
+        .await?;
+
+    // Create a vector index on the "words" table (created if needed).
+    // IMPORTANT: Reuse the same model that was used to generate the embeddings
+    let mut index = SqliteVectorIndex::new(connection, model, "words").await?;
+
+    // Add the documents and their embeddings to the table (replacing the documents with the same id)
+    index
+        .upsert_documents(embeddings, |word| word.id.clone())
+        .await?;
+    println!("Documents added successfully");
+
+    // Query the index
+    let results = index.top_n::<Word>("What is a linglingdong?", 1).await?;
+
+    println!("Results: {:?}", results);
+
+    // Query the words of the other planets only
+    let options = SearchOptions::new().filter(Filter::ne("planet", "Moon"));
+    let id_results = index
+        .top_n_with_options::<Word>("What is a linglingdong?", 1, &options)
+        .await?
+        .into_iter()
+        .map(|(score, id, _)| (score, id))
+        .collect::<Vec<_>>();
+
+    println!("Filtered results: {:?}", id_results);
+
+    Ok(())
+}
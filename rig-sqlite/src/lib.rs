@@ -0,0 +1,835 @@
+//! SQLite implementation of a Rig vector store.
+//!
+//! Documents are stored in a local SQLite database (a file, or in memory), which needs no
+//! outside service. Each index uses two tables:
+//! - `{table}`: the documents, serialized to JSON, by id. The fields of the documents are the
+//!   metadata the searches can be filtered on (see [filter_to_sql]).
+//! - `{table}_embeddings`: the embeddings of the documents, one row per embedding, with the text
+//!   it was generated from and its vector (little-endian `f32` values).
+//!
+//! The tables are created, and migrated to the schema of the current version of the crate, when
+//! the index is created (see [SqliteVectorIndex::new]).
+//!
+//! Searches compare the query with every embedding (filtered in SQL beforehand): in Rust by
+//! default, or with the distance functions of the [sqlite-vec](https://github.com/asg017/sqlite-vec)
+//! extension with the `sqlite-vec` feature (see [SqliteVectorIndex::with_sqlite_vec]).
+//!
+//! # Example
+//! ```rust,ignore
+//! use rig::{embeddings::EmbeddingsBuilder, vector_store::{VectorStoreIndex, WritableVectorStore}};
+//! use rig_sqlite::{Connection, SqliteVectorIndex};
+//!
+//! let connection = Connection::open("vector_store.db").await?;
+//! let mut index = SqliteVectorIndex::new(connection, model.clone(), "words").await?;
+//!
+//! let embeddings = EmbeddingsBuilder::new(model).documents(words)?.build().await?;
+//! index.upsert_documents(embeddings, |word| word.id.clone()).await?;
+//!
+//! let results = index.top_n::<Word>("What is a linglingdong?", 1).await?;
+//! ```
+use std::collections::HashMap;
+
+use rig::{
+    embeddings::{distance::DistanceMetric, Embedding, EmbeddingModel},
+    telemetry,
+    vector_store::{
+        check_query_dimensions, filter::Filter, search, SearchOptions, VectorSearchIndex,
+        VectorStoreError, VectorStoreIndex, WritableVectorStore,
+    },
+    OneOrMany,
+};
+use rusqlite::types::Value as SqlValue;
+use serde::{Deserialize, Serialize};
+
+pub use tokio_rusqlite::Connection;
+
+/// Migrations of the schema of the tables of an index, applied in order. `{table}` is replaced
+/// by the name of the table of the index.
+const MIGRATIONS: &[&str] = &[
+    // 1: documents and their embeddings
+    "CREATE TABLE {table} (
+        id TEXT PRIMARY KEY,
+        document TEXT NOT NULL
+    );
+    CREATE TABLE {table}_embeddings (
+        id TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        content TEXT NOT NULL,
+        embedding BLOB NOT NULL,
+        PRIMARY KEY (id, position)
+    );",
+];
+
+fn sqlite_to_rig_error(e: rusqlite::Error) -> VectorStoreError {
+    VectorStoreError::DatastoreError(Box::new(e))
+}
+
+/// Apply the migrations of the tables of `table` not applied yet, recording the version of the
+/// schema of each table in the `rig_migrations` table.
+fn migrate(conn: &mut rusqlite::Connection, table: &str) -> Result<(), VectorStoreError> {
+    let tx = conn.transaction().map_err(sqlite_to_rig_error)?;
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS rig_migrations (
+            name TEXT PRIMARY KEY,
+            version INTEGER NOT NULL
+        );",
+    )
+    .map_err(sqlite_to_rig_error)?;
+
+    let version = tx
+        .query_row(
+            "SELECT version FROM rig_migrations WHERE name = ?1",
+            [table],
+            |row| row.get::<_, usize>(0),
+        )
+        .or_else(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => Ok(0),
+            e => Err(e),
+        })
+        .map_err(sqlite_to_rig_error)?;
+
+    if version > MIGRATIONS.len() {
+        return Err(VectorStoreError::DatastoreError(
+            format!("Table {table} was created by a newer version of rig-sqlite").into(),
+        ));
+    }
+
+    for migration in &MIGRATIONS[version..] {
+        tx.execute_batch(&migration.replace("{table}", table))
+            .map_err(sqlite_to_rig_error)?;
+    }
+    tx.execute(
+        "INSERT INTO rig_migrations (name, version) VALUES (?1, ?2)
+        ON CONFLICT (name) DO UPDATE SET version = excluded.version",
+        rusqlite::params![table, MIGRATIONS.len()],
+    )
+    .map_err(sqlite_to_rig_error)?;
+
+    tx.commit().map_err(sqlite_to_rig_error)
+}
+
+/// Translate a [Filter] to a SQL predicate on the JSON documents of the `column` (e.g.:
+/// `"document"`), returning the predicate and its parameters (bound to its `?` placeholders,
+/// in order).
+///
+/// Comparisons are only true between values of the same JSON type, and comparisons on missing
+/// fields evaluate to false rather than null, so that negations match the documents missing the
+/// field. Arrays and objects are compared as JSON text.
+pub fn filter_to_sql(filter: &Filter, column: &str) -> (String, Vec<SqlValue>) {
+    fn json_path(field: &str) -> String {
+        field.split('.').fold("$".to_string(), |path, key| {
+            format!("{path}.\"{}\"", key.replace('"', "\\\""))
+        })
+    }
+
+    fn comparison(
+        column: &str,
+        field: &str,
+        operator: &str,
+        value: &serde_json::Value,
+        params: &mut Vec<SqlValue>,
+    ) -> String {
+        let (types, value) = match value {
+            serde_json::Value::Number(n) => (
+                "'integer', 'real'",
+                n.as_i64()
+                    .map(SqlValue::Integer)
+                    .unwrap_or_else(|| SqlValue::Real(n.as_f64().unwrap_or(f64::NAN))),
+            ),
+            serde_json::Value::String(s) => ("'text'", SqlValue::Text(s.clone())),
+            serde_json::Value::Bool(b) => ("'true', 'false'", SqlValue::Integer(*b as i64)),
+            // Null fields are considered missing
+            serde_json::Value::Null => return "0".to_string(),
+            value => ("'array', 'object'", SqlValue::Text(value.to_string())),
+        };
+
+        let path = json_path(field);
+        params.extend([SqlValue::Text(path.clone()), SqlValue::Text(path), value]);
+        format!(
+            "COALESCE(json_type({column}, ?) IN ({types}) AND json_extract({column}, ?) {operator} ?, 0)"
+        )
+    }
+
+    fn translate(filter: &Filter, column: &str, params: &mut Vec<SqlValue>) -> String {
+        let mut join = |filters: &[Filter], operator: &str, empty: &str| {
+            if filters.is_empty() {
+                return empty.to_string();
+            }
+            let filters = filters
+                .iter()
+                .map(|filter| translate(filter, column, params))
+                .collect::<Vec<_>>();
+            format!("({})", filters.join(operator))
+        };
+
+        match filter {
+            Filter::Eq { field, value } => comparison(column, field, "=", value, params),
+            Filter::Ne { field, value } => {
+                format!("NOT {}", comparison(column, field, "=", value, params))
+            }
+            Filter::Gt { field, value } => comparison(column, field, ">", value, params),
+            Filter::Gte { field, value } => comparison(column, field, ">=", value, params),
+            Filter::Lt { field, value } => comparison(column, field, "<", value, params),
+            Filter::Lte { field, value } => comparison(column, field, "<=", value, params),
+            Filter::In { field, values } => join(
+                &values
+                    .iter()
+                    .map(|value| Filter::eq(field.clone(), value.clone()))
+                    .collect::<Vec<_>>(),
+                " OR ",
+                "0",
+            ),
+            Filter::And(filters) => join(filters, " AND ", "1"),
+            Filter::Or(filters) => join(filters, " OR ", "0"),
+            Filter::Not(filter) => format!("NOT {}", translate(filter, column, params)),
+        }
+    }
+
+    let mut params = Vec::new();
+    let predicate = translate(filter, column, &mut params);
+    (predicate, params)
+}
+
+/// Encode an embedding as little-endian `f32` values
+fn encode_embedding(vec: &[f64]) -> Vec<u8> {
+    vec.iter().flat_map(|&x| (x as f32).to_le_bytes()).collect()
+}
+
+fn decode_embedding(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+/// Document returned by a search, with the embedding of the document best matching the query
+struct SearchResult {
+    score: f64,
+    id: String,
+    document: serde_json::Value,
+    embedding: Vec<f32>,
+}
+
+/// A vector index for documents stored in a SQLite database.
+/// # Example
+/// ```rust,ignore
+/// use rig::{providers::openai, vector_store::VectorStoreIndex};
+/// use rig_sqlite::{Connection, SqliteVectorIndex};
+///
+/// #[derive(serde::Deserialize, serde::Serialize, Debug)]
+/// struct WordDefinition {
+///     id: String,
+///     definition: String,
+/// }
+///
+/// let openai_client = openai::Client::from_env();
+/// let model = openai_client.embedding_model(openai::TEXT_EMBEDDING_ADA_002); // <-- replace with your embedding model.
+///
+/// let connection = Connection::open("vector_store.db").await?; // <-- replace with the path of your database.
+/// let index = SqliteVectorIndex::new(connection, model, "definitions").await?;
+///
+/// // Query the index
+/// let definitions = index
+///     .top_n::<WordDefinition>("My boss says I zindle too much, what does that mean?", 1)
+///     .await?;
+/// ```
+pub struct SqliteVectorIndex<M: EmbeddingModel> {
+    conn: Connection,
+    model: M,
+    table: String,
+    metric: DistanceMetric,
+    #[cfg(feature = "sqlite-vec")]
+    sqlite_vec: bool,
+}
+
+impl<M: EmbeddingModel> SqliteVectorIndex<M> {
+    /// Create a new `SqliteVectorIndex` storing its documents in the table `table` (and their
+    /// embeddings in the table `{table}_embeddings`) of the database of `conn`.
+    ///
+    /// The tables are created if they do not exist, and migrated to the current schema otherwise.
+    pub async fn new(conn: Connection, model: M, table: &str) -> Result<Self, VectorStoreError> {
+        let valid = table
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(VectorStoreError::DatastoreError(
+                format!("Invalid table name: {table}").into(),
+            ));
+        }
+
+        let name = table.to_string();
+        conn.call(move |conn| migrate(conn, &name))
+            .await
+            .map_err(call_to_rig_error)?;
+
+        Ok(Self {
+            conn,
+            model,
+            table: table.to_string(),
+            metric: DistanceMetric::default(),
+            #[cfg(feature = "sqlite-vec")]
+            sqlite_vec: false,
+        })
+    }
+
+    /// Rank the documents with the given `metric` (cosine similarity by default). Scores of
+    /// distance metrics are converted so that higher scores are closer (see
+    /// [DistanceMetric::score_of]).
+    pub fn with_metric(mut self, metric: DistanceMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Compute the distances with the functions of the sqlite-vec extension, which must be
+    /// registered (see [register_sqlite_vec]) before opening the connection of the index.
+    /// Only the cosine, euclidean and manhattan metrics are supported by the extension: the
+    /// other metrics are still computed in Rust.
+    #[cfg(feature = "sqlite-vec")]
+    pub async fn with_sqlite_vec(mut self) -> Result<Self, VectorStoreError> {
+        self.conn
+            .call(|conn| {
+                conn.query_row("SELECT vec_version()", [], |row| row.get::<_, String>(0))
+                    .map_err(sqlite_to_rig_error)
+            })
+            .await
+            .map_err(call_to_rig_error)?;
+
+        self.sqlite_vec = true;
+        Ok(self)
+    }
+
+    /// Name of the sqlite-vec function computing the metric of the index, if the extension is
+    /// used and supports the metric.
+    fn distance_function(&self) -> Option<&'static str> {
+        #[cfg(feature = "sqlite-vec")]
+        if self.sqlite_vec {
+            return match self.metric {
+                DistanceMetric::Cosine => Some("vec_distance_cosine"),
+                DistanceMetric::Euclidean => Some("vec_distance_l2"),
+                DistanceMetric::Manhattan => Some("vec_distance_l1"),
+                _ => None,
+            };
+        }
+        None
+    }
+
+    /// Get the `n` documents closest to `prompt_embedding` among the documents matching
+    /// `filter`, with their best matching embedding.
+    /// To be used by implementations of the methods of VectorStoreIndex and VectorSearchIndex.
+    async fn search(
+        &self,
+        prompt_embedding: &Embedding,
+        n: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<SearchResult>, VectorStoreError> {
+        let table = self.table.clone();
+        let metric = self.metric;
+        let function = self.distance_function();
+        let query = prompt_embedding
+            .vec
+            .iter()
+            .map(|&x| x as f32)
+            .collect::<Vec<_>>();
+        let blob = encode_embedding(&prompt_embedding.vec);
+        let (predicate, filter_params) = match filter {
+            Some(filter) => filter_to_sql(filter, "d.document"),
+            None => ("1".to_string(), vec![]),
+        };
+
+        let results = self
+            .conn
+            .call(move |conn| {
+                let candidates = match function {
+                    // Best embedding of each document, ranked by the extension
+                    Some(function) => {
+                        let mut stmt = conn
+                            .prepare(&format!(
+                                "SELECT e.id, e.embedding, MIN({function}(e.embedding, ?)) AS distance
+                                FROM {table}_embeddings e JOIN {table} d ON d.id = e.id
+                                WHERE {predicate}
+                                GROUP BY e.id ORDER BY distance, e.id LIMIT ?"
+                            ))
+                            .map_err(sqlite_to_rig_error)?;
+                        let params = std::iter::once(SqlValue::Blob(blob))
+                            .chain(filter_params)
+                            .chain(std::iter::once(SqlValue::Integer(n as i64)));
+
+                        let rows = stmt
+                            .query_map(rusqlite::params_from_iter(params), |row| {
+                                Ok((
+                                    row.get::<_, f64>(2)?,
+                                    row.get::<_, String>(0)?,
+                                    decode_embedding(&row.get::<_, Vec<u8>>(1)?),
+                                ))
+                            })
+                            .map_err(sqlite_to_rig_error)?;
+                        rows.map(|row| {
+                            let (distance, id, embedding) = row.map_err(sqlite_to_rig_error)?;
+                            let score = match metric {
+                                DistanceMetric::Cosine => 1.0 - distance,
+                                metric => metric.score_of(distance),
+                            };
+                            Ok((score, id, embedding))
+                        })
+                        .collect::<Result<Vec<_>, VectorStoreError>>()?
+                    }
+                    // Brute-force comparison of the query with every embedding
+                    None => {
+                        let mut stmt = conn
+                            .prepare(&format!(
+                                "SELECT e.id, e.embedding
+                                FROM {table}_embeddings e JOIN {table} d ON d.id = e.id
+                                WHERE {predicate}"
+                            ))
+                            .map_err(sqlite_to_rig_error)?;
+                        let mut rows = stmt
+                            .query(rusqlite::params_from_iter(filter_params))
+                            .map_err(sqlite_to_rig_error)?;
+
+                        let mut best = HashMap::<String, (f64, Vec<f32>)>::new();
+                        while let Some(row) = rows.next().map_err(sqlite_to_rig_error)? {
+                            let id = row.get::<_, String>(0).map_err(sqlite_to_rig_error)?;
+                            let embedding = decode_embedding(
+                                &row.get::<_, Vec<u8>>(1).map_err(sqlite_to_rig_error)?,
+                            );
+                            if embedding.len() != query.len() {
+                                continue;
+                            }
+                            let score = metric.score_of(metric.compute_slices(&embedding, &query));
+                            if best.get(&id).is_none_or(|(best, _)| *best < score) {
+                                best.insert(id, (score, embedding));
+                            }
+                        }
+
+                        let mut candidates = best
+                            .into_iter()
+                            .map(|(id, (score, embedding))| (score, id, embedding))
+                            .collect::<Vec<_>>();
+                        candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+                        candidates.truncate(n);
+                        candidates
+                    }
+                };
+
+                let mut stmt = conn
+                    .prepare_cached(&format!("SELECT document FROM {table} WHERE id = ?1"))
+                    .map_err(sqlite_to_rig_error)?;
+                candidates
+                    .into_iter()
+                    .map(|(score, id, embedding)| {
+                        let document = stmt
+                            .query_row([&id], |row| row.get::<_, String>(0))
+                            .map_err(sqlite_to_rig_error)?;
+                        Ok(SearchResult {
+                            score,
+                            id,
+                            document: serde_json::from_str(&document)?,
+                            embedding,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, VectorStoreError>>()
+            })
+            .await
+            .map_err(call_to_rig_error)?;
+
+        tracing::info!(target: "rig",
+            "Selected documents: {}",
+            results.iter()
+                .map(|result| format!("{} ({})", result.id, result.score))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+
+        Ok(results)
+    }
+}
+
+fn call_to_rig_error(e: tokio_rusqlite::Error<VectorStoreError>) -> VectorStoreError {
+    match e {
+        tokio_rusqlite::Error::Error(e) => e,
+        e => VectorStoreError::DatastoreError(Box::new(e)),
+    }
+}
+
+/// Register the sqlite-vec extension, so that it is loaded by the connections opened afterwards
+/// (see [SqliteVectorIndex::with_sqlite_vec]).
+#[cfg(feature = "sqlite-vec")]
+pub fn register_sqlite_vec() {
+    type EntryPoint = unsafe extern "C" fn(
+        *mut rusqlite::ffi::sqlite3,
+        *mut *mut std::os::raw::c_char,
+        *const rusqlite::ffi::sqlite3_api_routines,
+    ) -> std::os::raw::c_int;
+
+    // SAFETY: `sqlite3_vec_init` is the entry point of the extension, declared without its
+    // arguments by the sqlite-vec crate
+    unsafe {
+        rusqlite::ffi::sqlite3_auto_extension(Some(std::mem::transmute::<*const (), EntryPoint>(
+            sqlite_vec::sqlite3_vec_init as *const (),
+        )));
+    }
+}
+
+/// Documents are written to the table of the index as JSON, replacing the document with the
+/// same id and its embeddings.
+impl<M: EmbeddingModel + Sync + Send> WritableVectorStore for SqliteVectorIndex<M> {
+    async fn upsert_documents_with_ids<T: Serialize + Send>(
+        &mut self,
+        documents: Vec<(String, T, OneOrMany<Embedding>)>,
+    ) -> Result<(), VectorStoreError> {
+        let table = self.table.clone();
+        let documents = documents
+            .into_iter()
+            .map(|(id, doc, embeddings)| Ok((id, serde_json::to_string(&doc)?, embeddings)))
+            .collect::<Result<Vec<_>, VectorStoreError>>()?;
+
+        self.conn
+            .call(move |conn| {
+                let tx = conn.transaction().map_err(sqlite_to_rig_error)?;
+                {
+                    let mut upsert = tx
+                        .prepare(&format!(
+                            "INSERT INTO {table} (id, document) VALUES (?1, ?2)
+                            ON CONFLICT (id) DO UPDATE SET document = excluded.document"
+                        ))
+                        .map_err(sqlite_to_rig_error)?;
+                    let mut clear = tx
+                        .prepare(&format!("DELETE FROM {table}_embeddings WHERE id = ?1"))
+                        .map_err(sqlite_to_rig_error)?;
+                    let mut insert = tx
+                        .prepare(&format!(
+                            "INSERT INTO {table}_embeddings (id, position, content, embedding)
+                            VALUES (?1, ?2, ?3, ?4)"
+                        ))
+                        .map_err(sqlite_to_rig_error)?;
+
+                    for (id, document, embeddings) in documents {
+                        upsert
+                            .execute(rusqlite::params![id, document])
+                            .map_err(sqlite_to_rig_error)?;
+                        clear.execute([&id]).map_err(sqlite_to_rig_error)?;
+                        for (position, embedding) in embeddings.iter().enumerate() {
+                            insert
+                                .execute(rusqlite::params![
+                                    id,
+                                    position,
+                                    embedding.document,
+                                    encode_embedding(&embedding.vec)
+                                ])
+                                .map_err(sqlite_to_rig_error)?;
+                        }
+                    }
+                }
+                tx.commit().map_err(sqlite_to_rig_error)
+            })
+            .await
+            .map_err(call_to_rig_error)
+    }
+
+    async fn delete_documents(&mut self, ids: Vec<String>) -> Result<usize, VectorStoreError> {
+        let table = self.table.clone();
+
+        self.conn
+            .call(move |conn| {
+                let tx = conn.transaction().map_err(sqlite_to_rig_error)?;
+                let mut deleted = 0;
+                for id in &ids {
+                    tx.execute(
+                        &format!("DELETE FROM {table}_embeddings WHERE id = ?1"),
+                        [id],
+                    )
+                    .map_err(sqlite_to_rig_error)?;
+                    deleted += tx
+                        .execute(&format!("DELETE FROM {table} WHERE id = ?1"), [id])
+                        .map_err(sqlite_to_rig_error)?;
+                }
+                tx.commit().map_err(sqlite_to_rig_error)?;
+                Ok(deleted)
+            })
+            .await
+            .map_err(call_to_rig_error)
+    }
+
+    async fn delete_documents_by_filter(
+        &mut self,
+        filter: &Filter,
+    ) -> Result<usize, VectorStoreError> {
+        let table = self.table.clone();
+        let (predicate, params) = filter_to_sql(filter, "document");
+
+        self.conn
+            .call(move |conn| {
+                let tx = conn.transaction().map_err(sqlite_to_rig_error)?;
+                tx.execute(
+                    &format!(
+                        "DELETE FROM {table}_embeddings
+                        WHERE id IN (SELECT id FROM {table} WHERE {predicate})"
+                    ),
+                    rusqlite::params_from_iter(params.iter()),
+                )
+                .map_err(sqlite_to_rig_error)?;
+                let deleted = tx
+                    .execute(
+                        &format!("DELETE FROM {table} WHERE {predicate}"),
+                        rusqlite::params_from_iter(params.iter()),
+                    )
+                    .map_err(sqlite_to_rig_error)?;
+                tx.commit().map_err(sqlite_to_rig_error)?;
+                Ok(deleted)
+            })
+            .await
+            .map_err(call_to_rig_error)
+    }
+}
+
+impl<M: EmbeddingModel + Sync + Send> VectorStoreIndex for SqliteVectorIndex<M> {
+    /// Implement the `top_n` method of the `VectorStoreIndex` trait for `SqliteVectorIndex`.
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        telemetry::instrument_retrieval(telemetry::retrieval_span("sqlite", query, n), async move {
+            let prompt_embedding = self.model.embed_text(query).await?;
+
+            documents(self.search(&prompt_embedding, n, None).await?)
+        })
+        .await
+    }
+
+    /// Implement the `top_n_with_options` method of the `VectorStoreIndex` trait for `SqliteVectorIndex`.
+    /// The filter is translated to SQL (see [filter_to_sql]) and applied before ranking, and the
+    /// other search options are applied to the results: the minimum score filters the
+    /// candidates, and MMR selects among them using their best matching embeddings.
+    async fn top_n_with_options<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        telemetry::instrument_retrieval(telemetry::retrieval_span("sqlite", query, n), async move {
+            let prompt_embedding = self.model.embed_text(query).await?;
+
+            let candidates = self
+                .search(
+                    &prompt_embedding,
+                    options.candidates(n),
+                    options.filter.as_ref(),
+                )
+                .await?
+                .into_iter()
+                .map(|result| {
+                    Ok((
+                        result.score,
+                        result.id,
+                        serde_json::from_value(result.document)?,
+                        result.embedding.into_iter().map(f64::from).collect(),
+                    ))
+                })
+                .collect::<Result<Vec<_>, VectorStoreError>>()?;
+
+            Ok(search::apply_options(
+                &prompt_embedding.vec,
+                candidates,
+                n,
+                options,
+            ))
+        })
+        .await
+    }
+
+    /// Implement the `top_n_ids` method of the `VectorStoreIndex` trait for `SqliteVectorIndex`.
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        telemetry::instrument_retrieval(telemetry::retrieval_span("sqlite", query, n), async move {
+            let prompt_embedding = self.model.embed_text(query).await?;
+
+            Ok(ids(self.search(&prompt_embedding, n, None).await?))
+        })
+        .await
+    }
+}
+
+impl<M: EmbeddingModel + Sync + Send> VectorSearchIndex for SqliteVectorIndex<M> {
+    type Model = M;
+
+    fn embedding_model(&self) -> &M {
+        &self.model
+    }
+
+    /// Implement the `top_n_by_vector` method of the `VectorSearchIndex` trait for `SqliteVectorIndex`.
+    async fn top_n_by_vector<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        vector: &[f64],
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::vector_retrieval_span("sqlite", vector.len(), n),
+            async move {
+                documents(
+                    self.search(&query_embedding(&self.model, vector)?, n, None)
+                        .await?,
+                )
+            },
+        )
+        .await
+    }
+
+    /// Implement the `top_n_ids_by_vector` method of the `VectorSearchIndex` trait for `SqliteVectorIndex`.
+    async fn top_n_ids_by_vector(
+        &self,
+        vector: &[f64],
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        telemetry::instrument_retrieval(
+            telemetry::vector_retrieval_span("sqlite", vector.len(), n),
+            async move {
+                Ok(ids(self
+                    .search(&query_embedding(&self.model, vector)?, n, None)
+                    .await?))
+            },
+        )
+        .await
+    }
+}
+
+/// Deserialize the documents of the results of a search
+fn documents<T: for<'a> Deserialize<'a>>(
+    results: Vec<SearchResult>,
+) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+    results
+        .into_iter()
+        .map(|result| {
+            Ok((
+                result.score,
+                result.id,
+                serde_json::from_value(result.document)?,
+            ))
+        })
+        .collect()
+}
+
+fn ids(results: Vec<SearchResult>) -> Vec<(f64, String)> {
+    results
+        .into_iter()
+        .map(|result| (result.score, result.id))
+        .collect()
+}
+
+/// Embedding of a query given as a vector, which must have the dimensions of the embeddings
+/// of the `model`
+fn query_embedding(
+    model: &impl EmbeddingModel,
+    vector: &[f64],
+) -> Result<Embedding, VectorStoreError> {
+    check_query_dimensions(vector, model.ndims())?;
+    Ok(Embedding {
+        document: String::new(),
+        vec: vector.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Positions of the `documents` selected by the translation of `filter` to SQL
+    fn select(filter: &Filter, documents: &[serde_json::Value]) -> Vec<usize> {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE docs (position INTEGER, document TEXT)")
+            .unwrap();
+        for (position, document) in documents.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO docs VALUES (?1, ?2)",
+                rusqlite::params![position, document.to_string()],
+            )
+            .unwrap();
+        }
+
+        let (predicate, params) = filter_to_sql(filter, "document");
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT position FROM docs WHERE {predicate} ORDER BY position"
+            ))
+            .unwrap();
+        stmt.query_map(rusqlite::params_from_iter(params), |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_filter_to_sql() {
+        let documents = [
+            json!({ "symbol": "SOL", "price": 142.5, "metadata": { "chain": "solana", "verified": true } }),
+            json!({ "symbol": "JUP", "price": 1, "metadata": { "chain": "solana" } }),
+            json!({ "symbol": "ETH", "price": "3000", "metadata": { "chain": "ethereum" }, "tags": null }),
+            json!({ "symbol": "it's", "tags": ["meme"] }),
+        ];
+
+        let filters = [
+            Filter::eq("metadata.chain", "solana"),
+            Filter::gt("price", 100),
+            Filter::eq("price", 1.0),
+            Filter::lte("symbol", "JUP"),
+            Filter::eq("metadata.verified", true),
+            Filter::ne("metadata.chain", "solana"),
+            Filter::eq("tags", serde_json::Value::Null),
+            Filter::eq("tags", json!(["meme"])),
+            Filter::eq("symbol", "it's"),
+            Filter::one_of("symbol", ["SOL", "ETH"]),
+            !Filter::gte("price", 0),
+            Filter::eq("metadata.chain", "solana").and(!Filter::gt("price", 100)),
+            Filter::eq("symbol", "ETH").or(Filter::between("price", 1, 200)),
+            Filter::And(vec![]),
+            Filter::Or(vec![]),
+        ];
+
+        for filter in filters {
+            let expected = documents
+                .iter()
+                .enumerate()
+                .filter(|(_, document)| filter.matches(document))
+                .map(|(position, _)| position)
+                .collect::<Vec<_>>();
+            assert_eq!(select(&filter, &documents), expected, "{filter:?}");
+        }
+    }
+
+    #[test]
+    fn test_migrations() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        migrate(&mut conn, "words").unwrap();
+        // Migrations are applied once
+        migrate(&mut conn, "words").unwrap();
+        migrate(&mut conn, "tokens").unwrap();
+
+        let version: usize = conn
+            .query_row(
+                "SELECT version FROM rig_migrations WHERE name = 'words'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+
+        conn.execute("UPDATE rig_migrations SET version = 99", [])
+            .unwrap();
+        assert!(migrate(&mut conn, "words").is_err());
+    }
+
+    #[test]
+    fn test_embedding_encoding() {
+        let vec = vec![0.5, -1.25, 3.0];
+        assert_eq!(
+            decode_embedding(&encode_embedding(&vec)),
+            vec![0.5, -1.25, 3.0]
+        );
+    }
+}
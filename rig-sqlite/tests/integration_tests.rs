@@ -0,0 +1,235 @@
+use rig::{
+    embeddings::{Embedding, EmbeddingError, EmbeddingModel, EmbeddingsBuilder},
+    vector_store::{
+        filter::Filter, SearchOptions, VectorSearchIndex, VectorStoreIndex, WritableVectorStore,
+    },
+    Embed,
+};
+use rig_sqlite::{Connection, SqliteVectorIndex};
+
+#[derive(Embed, Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+struct Word {
+    id: String,
+    #[embed]
+    definition: String,
+    planet: String,
+}
+
+/// Embedding model embedding each word of the definitions along its own axis
+#[derive(Clone)]
+struct MockModel;
+
+impl EmbeddingModel for MockModel {
+    const MAX_DOCUMENTS: usize = 16;
+
+    fn ndims(&self) -> usize {
+        4
+    }
+
+    async fn embed_texts(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<Embedding>, EmbeddingError> {
+        Ok(texts
+            .into_iter()
+            .map(|text| {
+                let vec = ["flurbo", "glarb", "linglingdong"]
+                    .iter()
+                    .map(|word| if text.contains(word) { 1.0 } else { 0.0 })
+                    .chain([0.1])
+                    .collect();
+                Embedding {
+                    document: text,
+                    vec,
+                }
+            })
+            .collect())
+    }
+}
+
+fn words() -> Vec<Word> {
+    vec![
+        Word {
+            id: "doc0".to_string(),
+            definition: "Definition of a *flurbo*: A flurbo is a green alien that lives on cold planets".to_string(),
+            planet: "Gazorpazorp".to_string(),
+        },
+        Word {
+            id: "doc1".to_string(),
+            definition: "Definition of a *glarb-glarb*: A glarb-glarb is a ancient tool used by the ancestors of the inhabitants of planet Jiro to farm the land.".to_string(),
+            planet: "Jiro".to_string(),
+        },
+        Word {
+            id: "doc2".to_string(),
+            definition: "Definition of a *linglingdong*: A term used by inhabitants of the far side of the moon to describe humans.".to_string(),
+            planet: "Moon".to_string(),
+        },
+    ]
+}
+
+async fn create_index(path: &std::path::Path) -> SqliteVectorIndex<MockModel> {
+    let connection = Connection::open(path).await.expect("");
+    SqliteVectorIndex::new(connection, MockModel, "words")
+        .await
+        .expect("")
+}
+
+#[tokio::test]
+async fn vector_search_test() {
+    let dir = tempfile::tempdir().expect("");
+    let path = dir.path().join("vector_store.db");
+
+    let embeddings = EmbeddingsBuilder::new(MockModel)
+        .documents(words())
+        .unwrap()
+        .build()
+        .await
+        .expect("");
+
+    let mut index = create_index(&path).await;
+    index
+        .upsert_documents(embeddings, |word| word.id.clone())
+        .await
+        .expect("");
+
+    // Query the index
+    let results = index
+        .top_n::<Word>("What is a linglingdong?", 1)
+        .await
+        .expect("");
+    let (score, id, word) = &results[0];
+    assert_eq!(id, "doc2");
+    assert_eq!(word, &words()[2]);
+    assert!(*score > 0.9);
+
+    let results = index
+        .top_n_ids("What is a glarb-glarb?", 3)
+        .await
+        .expect("");
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].1, "doc1");
+
+    // Filters are applied before ranking
+    let options = SearchOptions::new().filter(Filter::ne("planet", "Jiro"));
+    let results = index
+        .top_n_with_options::<Word>("What is a glarb-glarb?", 3, &options)
+        .await
+        .expect("");
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|(_, id, _)| id != "doc1"));
+
+    // Searching with the embedding of the query gives the same results as the query
+    let expected = index.top_n_ids("What is a flurbo?", 2).await.expect("");
+    let results = index
+        .top_n_ids_by_vector(&[1.0, 0.0, 0.0, 0.1], 2)
+        .await
+        .expect("");
+    assert_eq!(results, expected);
+
+    // Documents are replaced by id, and deleted by id or filter
+    let mut word = words()[0].clone();
+    word.planet = "Earth".to_string();
+    index
+        .upsert_documents(
+            EmbeddingsBuilder::new(MockModel)
+                .document(word.clone())
+                .unwrap()
+                .build()
+                .await
+                .expect(""),
+            |word| word.id.clone(),
+        )
+        .await
+        .expect("");
+    let results = index.top_n::<Word>("What is a flurbo?", 1).await.expect("");
+    assert_eq!(results[0].2, word);
+
+    assert_eq!(
+        index
+            .delete_documents(vec!["doc2".to_string(), "doc9".to_string()])
+            .await
+            .expect(""),
+        1
+    );
+    assert_eq!(
+        index
+            .delete_documents_by_filter(&Filter::eq("planet", "Earth"))
+            .await
+            .expect(""),
+        1
+    );
+
+    // Documents are persisted in the database file
+    drop(index);
+    let index = create_index(&path).await;
+    let results = index.top_n_ids("What is a flurbo?", 3).await.expect("");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].1, "doc1");
+}
+
+#[tokio::test]
+async fn multiple_embeddings_test() {
+    let connection = Connection::open_in_memory().await.expect("");
+    let mut index = SqliteVectorIndex::new(connection, MockModel, "words")
+        .await
+        .expect("");
+
+    // Documents with several embeddings are ranked by their best matching embedding
+    let documents = ["flurbo", "glarb flurbo", "linglingdong"]
+        .into_iter()
+        .map(|text| text.to_string())
+        .collect::<Vec<_>>();
+    let embeddings = EmbeddingsBuilder::new(MockModel)
+        .document(documents)
+        .unwrap()
+        .build()
+        .await
+        .expect("");
+    index
+        .upsert_documents(embeddings, |_| "doc0".to_string())
+        .await
+        .expect("");
+
+    let results = index.top_n_ids("linglingdong", 5).await.expect("");
+    assert_eq!(results.len(), 1);
+    assert!((results[0].0 - 1.0).abs() < 1e-3);
+}
+
+#[cfg(feature = "sqlite-vec")]
+#[tokio::test]
+async fn sqlite_vec_test() {
+    rig_sqlite::register_sqlite_vec();
+
+    let connection = Connection::open_in_memory().await.expect("");
+    let mut index = SqliteVectorIndex::new(connection, MockModel, "words")
+        .await
+        .expect("");
+    let embeddings = EmbeddingsBuilder::new(MockModel)
+        .documents(words())
+        .unwrap()
+        .build()
+        .await
+        .expect("");
+    index
+        .upsert_documents(embeddings, |word| word.id.clone())
+        .await
+        .expect("");
+
+    let options = SearchOptions::new().filter(Filter::ne("planet", "Jiro"));
+    let expected = index
+        .top_n_with_options::<Word>("What is a flurbo?", 3, &options)
+        .await
+        .expect("");
+
+    // The extension computes the same scores
+    let index = index.with_sqlite_vec().await.expect("");
+    let results = index
+        .top_n_with_options::<Word>("What is a flurbo?", 3, &options)
+        .await
+        .expect("");
+    assert_eq!(results.len(), expected.len());
+    for ((score, id, _), (expected_score, expected_id, _)) in results.iter().zip(&expected) {
+        assert_eq!(id, expected_id);
+        assert!((score - expected_score).abs() < 1e-6);
+    }
+}